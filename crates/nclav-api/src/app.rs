@@ -3,21 +3,68 @@ use std::sync::Arc;
 use axum::middleware;
 use axum::routing::{delete, get, post};
 use axum::Router;
-use nclav_driver::DriverRegistry;
-use nclav_store::StateStore;
+use nclav_driver::{BackendTls, DriverRegistry};
+use nclav_graph::CidrAllocator;
+use nclav_policy::Policy;
+use nclav_store::{BroadcastStore, EventBus, RetentionPolicy, StateStore};
 use tower_http::trace::TraceLayer;
 
 use crate::auth::require_bearer_token;
 use crate::handlers;
+use crate::oidc::OidcVerifier;
+use crate::otel;
 use crate::state::AppState;
 
-pub fn build_app(
-    store: Arc<dyn StateStore>,
-    registry: Arc<DriverRegistry>,
-    auth_token: Arc<String>,
-    api_base: String,
-) -> Router {
-    let state = AppState { store, registry, auth_token, api_base: Arc::new(api_base) };
+/// Server settings for [`build_app`]. Start from [`AppConfig::new`] and set
+/// the optional features needed.
+pub struct AppConfig {
+    pub registry: Arc<DriverRegistry>,
+    /// The operator token, accepted with every scope.
+    pub auth_token: Arc<String>,
+    /// Base URL Terraform uses to reach the state backend.
+    pub api_base: String,
+    pub backend_tls: BackendTls,
+    /// Assigns a `vpc_cidr` to enclaves whose YAML leaves it out.
+    pub vpc_allocator: Option<CidrAllocator>,
+    pub policy: Option<Arc<Policy>>,
+    /// Terraform state versions kept per partition.
+    pub tf_state_versions: usize,
+    pub retention: RetentionPolicy,
+    pub oidc: Option<Arc<OidcVerifier>>,
+}
+
+impl AppConfig {
+    /// Settings with every optional feature off and 10 state versions kept.
+    pub fn new(registry: Arc<DriverRegistry>, auth_token: Arc<String>, api_base: String) -> Self {
+        Self {
+            registry,
+            auth_token,
+            api_base,
+            backend_tls: BackendTls::default(),
+            vpc_allocator: None,
+            policy: None,
+            tf_state_versions: 10,
+            retention: RetentionPolicy::default(),
+            oidc: None,
+        }
+    }
+}
+
+/// Build the API router. Events appended through it are published on `events`.
+pub fn build_app(store: Arc<dyn StateStore>, events: EventBus, config: AppConfig) -> Router {
+    let state = AppState {
+        store: Arc::new(BroadcastStore::new(store, events.clone())),
+        events,
+        registry: config.registry,
+        auth_token: config.auth_token,
+        api_base: Arc::new(config.api_base),
+        backend_tls: config.backend_tls,
+        vpc_allocator: config.vpc_allocator,
+        policy: config.policy,
+        tf_state_versions: config.tf_state_versions,
+        retention: config.retention,
+        oidc: config.oidc,
+    };
 
     Router::new()
         // Health
@@ -50,17 +97,37 @@ pub fn build_app(
             "/terraform/state/:enc/:part/lock",
             post(handlers::lock_tf_state).delete(handlers::unlock_tf_state),
         )
+        .route("/terraform/locks", get(handlers::list_tf_locks))
+        .route(
+            "/terraform/locks/:enc/:part/force-unlock",
+            post(handlers::force_unlock_tf_state),
+        )
+        .route("/terraform/state/:enc/:part/versions", get(handlers::list_tf_state_versions))
+        .route(
+            "/terraform/state/:enc/:part/versions/:version/restore",
+            post(handlers::restore_tf_state_version),
+        )
         // Graphs
         .route("/graph", get(handlers::get_system_graph))
+        .route("/graph/impact", get(handlers::get_graph_impact))
         // Events
         .route("/events", get(handlers::list_events))
+        .route("/events/stream", get(handlers::stream_events))
+        .route("/webhooks/dead-letters", get(handlers::list_dead_letters))
         // Status
         .route("/status", get(handlers::status))
+        // Metrics
+        .route("/metrics", get(handlers::get_metrics))
         // Orphan detection
         .route("/orphans", get(handlers::list_orphans))
+        // Retention
+        .route("/admin/prune", post(handlers::prune))
+        // API tokens
+        .route("/tokens", get(handlers::list_api_tokens).post(handlers::create_api_token))
+        .route("/tokens/:name", delete(handlers::revoke_api_token))
         // Auth middleware applies to all routes above
         .route_layer(middleware::from_fn_with_state(state.clone(), require_bearer_token))
-        .layer(TraceLayer::new_for_http())
+        .layer(TraceLayer::new_for_http().make_span_with(otel::request_span))
         .with_state(state)
 }
 
@@ -78,12 +145,22 @@ mod tests {
     const TEST_TOKEN: &str = "test-token";
 
     fn test_app() -> Router {
-        let store = Arc::new(InMemoryStore::new());
+        test_app_with_store(Arc::new(InMemoryStore::new()))
+    }
+
+    fn test_app_with_store(store: Arc<InMemoryStore>) -> Router {
         let driver = Arc::new(LocalDriver::new());
         let mut registry = DriverRegistry::new(CloudTarget::Local);
         registry.register(CloudTarget::Local, driver);
         let registry = Arc::new(registry);
-        build_app(store, registry, Arc::new(TEST_TOKEN.to_string()), "http://127.0.0.1:8080".into())
+        build_app(store, EventBus::default(), test_config(registry))
+    }
+
+    fn test_config(registry: Arc<DriverRegistry>) -> AppConfig {
+        AppConfig {
+            tf_state_versions: 3,
+            ..AppConfig::new(registry, Arc::new(TEST_TOKEN.to_string()), "http://127.0.0.1:8080".into())
+        }
     }
 
     fn authed(req: axum::http::request::Builder) -> axum::http::request::Builder {
@@ -216,6 +293,38 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn graph_renders_requested_format() {
+        let cases = [("mermaid", "text/plain"), ("html", "text/html"), ("dot", "text/vnd.graphviz")];
+        for (format, content_type) in cases {
+            let resp = test_app()
+                .oneshot(
+                    authed(Request::builder().uri(format!("/graph?format={format}")))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK, "{format}");
+            let ct = resp.headers()["content-type"].to_str().unwrap();
+            assert!(ct.starts_with(content_type), "{format}: {ct}");
+        }
+    }
+
+    #[tokio::test]
+    async fn graph_impact_of_unknown_enclave_is_422() {
+        let app = test_app();
+        let resp = app
+            .oneshot(
+                authed(Request::builder().uri("/graph/impact?target=nonexistent/db"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
     #[tokio::test]
     async fn events_returns_200() {
         let app = test_app();
@@ -498,4 +607,593 @@ mod tests {
             .unwrap();
         assert_eq!(other_resp.status(), StatusCode::NO_CONTENT);
     }
+
+    async fn post_state(app: &Router, blob: serde_json::Value) -> StatusCode {
+        post_state_to(app, STATE_URL, blob).await
+    }
+
+    async fn post_state_to(app: &Router, uri: &str, blob: serde_json::Value) -> StatusCode {
+        app.clone()
+            .oneshot(
+                authed(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri(uri)
+                        .header("content-type", "application/json"),
+                )
+                .body(Body::from(blob.to_string()))
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    async fn get_json(app: &Router, uri: &str) -> serde_json::Value {
+        let resp = app
+            .clone()
+            .oneshot(authed(Request::builder().uri(uri)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn tf_state_post_rejects_other_lineage_and_older_serial() {
+        let app = test_app();
+        let state = |serial: u64, lineage: &str| {
+            serde_json::json!({ "version": 4, "serial": serial, "lineage": lineage })
+        };
+        assert_eq!(post_state(&app, state(5, "abc")).await, StatusCode::OK);
+        assert_eq!(post_state(&app, state(4, "abc")).await, StatusCode::CONFLICT);
+        assert_eq!(post_state(&app, state(6, "other")).await, StatusCode::CONFLICT);
+        assert_eq!(post_state(&app, state(5, "abc")).await, StatusCode::OK);
+
+        let current = get_json(&app, STATE_URL).await;
+        assert_eq!(current["serial"], 5);
+        assert_eq!(current["lineage"], "abc");
+    }
+
+    #[tokio::test]
+    async fn tf_state_post_requires_the_held_lock_id() {
+        let app = test_app();
+        let blob = |serial: u64| serde_json::json!({ "version": 4, "serial": serial, "lineage": "abc" });
+        let lock = |id: &str| {
+            authed(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(LOCK_URL)
+                    .header("content-type", "application/json"),
+            )
+            .body(Body::from(lock_info(id).to_string()))
+            .unwrap()
+        };
+        assert_eq!(app.clone().oneshot(lock("lock-1")).await.unwrap().status(), StatusCode::OK);
+
+        let with_id = |id: &str| format!("{}?ID={}", STATE_URL, id);
+        assert_eq!(post_state(&app, blob(1)).await, StatusCode::CONFLICT);
+        assert_eq!(post_state_to(&app, &with_id("lock-2"), blob(1)).await, StatusCode::CONFLICT);
+        assert_eq!(post_state_to(&app, &with_id("lock-1"), blob(1)).await, StatusCode::OK);
+
+        let unlock = authed(Request::builder().method(Method::DELETE).uri(LOCK_URL))
+            .body(Body::from(lock_info("lock-1").to_string()))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(unlock).await.unwrap().status(), StatusCode::OK);
+        // The lock is gone: a write still claiming it is stale.
+        assert_eq!(post_state_to(&app, &with_id("lock-1"), blob(2)).await, StatusCode::CONFLICT);
+        assert_eq!(post_state(&app, blob(2)).await, StatusCode::OK);
+        assert_eq!(get_json(&app, STATE_URL).await["serial"], 2);
+    }
+
+    #[tokio::test]
+    async fn tf_state_versions_are_listed_and_restorable() {
+        let app = test_app();
+        for serial in 1..=4 {
+            let blob = serde_json::json!({ "serial": serial, "lineage": "abc", "resources": [serial] });
+            assert_eq!(post_state(&app, blob).await, StatusCode::OK);
+        }
+
+        // test_app keeps 3 versions.
+        let versions = get_json(&app, "/terraform/state/enc/part/versions").await;
+        let numbers: Vec<u64> = versions.as_array().unwrap().iter().map(|v| v["version"].as_u64().unwrap()).collect();
+        assert_eq!(numbers, vec![4, 3, 2]);
+
+        let restore = |v: u64| {
+            authed(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/terraform/state/enc/part/versions/{v}/restore")),
+            )
+            .body(Body::empty())
+            .unwrap()
+        };
+        assert_eq!(app.clone().oneshot(restore(1)).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        let resp = app.clone().oneshot(restore(2)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(created["version"], 5);
+        assert_eq!(created["restored_from"], 2);
+
+        // Restored content, with the serial moved past the state it replaced.
+        let current = get_json(&app, STATE_URL).await;
+        assert_eq!(current["resources"], serde_json::json!([2]));
+        assert_eq!(current["serial"], 5);
+
+        // A held lock blocks restore.
+        app.clone()
+            .oneshot(
+                authed(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri(LOCK_URL)
+                        .header("content-type", "application/json"),
+                )
+                .body(Body::from(lock_info("tf-lock").to_string()))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(app.oneshot(restore(4)).await.unwrap().status(), StatusCode::CONFLICT);
+    }
+
+    // ── Terraform lock inspection ─────────────────────────────────────────────
+
+    async fn send(app: &Router, method: Method, uri: &str, body: serde_json::Value) -> StatusCode {
+        app.clone()
+            .oneshot(
+                authed(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("content-type", "application/json"),
+                )
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    fn iac_run(status: nclav_store::IacRunStatus) -> nclav_store::IacRun {
+        nclav_store::IacRun {
+            id: uuid::Uuid::new_v4(),
+            enclave_id: nclav_domain::EnclaveId::new("enc"),
+            partition_id: nclav_domain::PartitionId::new("part"),
+            operation: nclav_store::IacOperation::Provision,
+            started_at: chrono::Utc::now(),
+            finished_at: None,
+            status,
+            exit_code: None,
+            log: String::new(),
+            reconcile_run_id: None,
+            trace_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn tf_unlock_without_lock_id_is_rejected() {
+        let app = test_app();
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l1")).await, StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(
+                authed(Request::builder().method(Method::DELETE).uri(LOCK_URL))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(get_json(&app, "/terraform/locks").await.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn tf_lock_of_finished_run_is_listed_stale_and_expired() {
+        let store = Arc::new(InMemoryStore::new());
+        let app = test_app_with_store(store.clone());
+        let mut run = iac_run(nclav_store::IacRunStatus::Running);
+        store.upsert_iac_run(&run).await.unwrap();
+
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l1")).await, StatusCode::OK);
+        let locks = get_json(&app, "/terraform/locks").await;
+        assert_eq!(locks[0]["key"], "enc/part");
+        assert_eq!(locks[0]["holder"], "tester");
+        assert_eq!(locks[0]["operation"], "OperationTypeApply");
+        assert_eq!(locks[0]["run_id"], run.id.to_string());
+        assert_eq!(locks[0]["stale"], false);
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l2")).await, StatusCode::CONFLICT);
+
+        run.status = nclav_store::IacRunStatus::Failed;
+        store.upsert_iac_run(&run).await.unwrap();
+        assert_eq!(get_json(&app, "/terraform/locks").await[0]["stale"], true);
+
+        // The next lock request clears the stale lock and succeeds.
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l2")).await, StatusCode::OK);
+        assert_eq!(get_json(&app, "/terraform/locks").await[0]["lock_id"], "l2");
+        let events = &get_json(&app, "/events").await["events"];
+        assert_eq!(events[0]["kind"], "TfLockExpired");
+        assert_eq!(events[0]["lock_id"], "l1");
+    }
+
+    #[tokio::test]
+    async fn tf_force_unlock_is_audited() {
+        let app = test_app();
+        let url = "/terraform/locks/enc/part/force-unlock";
+        let note = serde_json::json!({ "note": "alice@laptop" });
+        assert_eq!(send(&app, Method::POST, url, note.clone()).await, StatusCode::NOT_FOUND);
+
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l1")).await, StatusCode::OK);
+        let wrong_id = serde_json::json!({ "lock_id": "other" });
+        assert_eq!(send(&app, Method::POST, url, wrong_id).await, StatusCode::CONFLICT);
+        assert_eq!(send(&app, Method::POST, url, note).await, StatusCode::OK);
+
+        assert!(get_json(&app, "/terraform/locks").await.as_array().unwrap().is_empty());
+        let events = &get_json(&app, "/events").await["events"];
+        assert_eq!(events[0]["kind"], "TfLockForceUnlocked");
+        assert_eq!(events[0]["by"], "admin");
+        assert_eq!(events[0]["note"], "alice@laptop");
+        assert_eq!(events[0]["holder"], "tester");
+
+        // `by` is whoever authenticated, whatever the client claims.
+        let ops = create_token(&app, serde_json::json!({ "name": "ops", "scopes": ["admin"] })).await;
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l2")).await, StatusCode::OK);
+        let claim = serde_json::json!({ "by": "root@prod" });
+        assert_eq!(send_as(&app, &ops, Method::POST, url, claim).await.0, StatusCode::OK);
+        let events = &get_json(&app, "/events?kind=TfLockForceUnlocked").await["events"];
+        assert_eq!(events[1]["by"], "ops");
+        assert_eq!(events[1]["note"], "root@prod");
+    }
+
+    #[tokio::test]
+    async fn event_stream_sends_matching_events_as_they_are_appended() {
+        let app = test_app();
+        let resp = app
+            .clone()
+            .oneshot(
+                authed(Request::builder().uri("/events/stream?kind=TfLockForceUnlocked"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        let mut body = resp.into_body().into_data_stream();
+
+        // Not matched by the filter, so never streamed.
+        assert_eq!(post_state(&app, serde_json::json!({ "serial": 1, "lineage": "abc" })).await, StatusCode::OK);
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l1")).await, StatusCode::OK);
+        let note = serde_json::json!({ "note": "alice@laptop" });
+        assert_eq!(send(&app, Method::POST, "/terraform/locks/enc/part/force-unlock", note).await, StatusCode::OK);
+
+        let frame = futures::StreamExt::next(&mut body).await.unwrap().unwrap();
+        let frame = String::from_utf8(frame.to_vec()).unwrap();
+        assert!(frame.contains("event: TfLockForceUnlocked\n"), "{frame}");
+        assert!(frame.contains("\"note\":\"alice@laptop\""), "{frame}");
+
+        assert_eq!(get_json(&app, "/webhooks/dead-letters").await, serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn admin_prune_dry_run_reports_without_deleting() {
+        let store = Arc::new(InMemoryStore::new());
+        for _ in 0..3 {
+            store.upsert_iac_run(&iac_run(nclav_store::IacRunStatus::Succeeded)).await.unwrap();
+        }
+        let mut registry = DriverRegistry::new(CloudTarget::Local);
+        registry.register(CloudTarget::Local, Arc::new(LocalDriver::new()));
+        let retention = RetentionPolicy { iac_runs_per_partition: Some(1), ..Default::default() };
+        let config = AppConfig { retention, ..test_config(Arc::new(registry)) };
+        let app = build_app(store.clone(), EventBus::default(), config);
+
+        let resp = app
+            .clone()
+            .oneshot(
+                authed(Request::builder().method(Method::POST).uri("/admin/prune?dry_run=true"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(report["iac_runs"], 2);
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(store.list_all_iac_runs().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn events_carry_request_context_and_page_by_kind() {
+        let app = test_app();
+        for serial in 1..=3 {
+            let resp = app
+                .clone()
+                .oneshot(
+                    authed(
+                        Request::builder()
+                            .method(Method::POST)
+                            .uri(STATE_URL)
+                            .header("x-nclav-client", "cli")
+                            .header("x-request-id", format!("req-{serial}")),
+                    )
+                    .body(Body::from(serde_json::json!({ "serial": serial, "lineage": "abc" }).to_string()))
+                    .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        assert_eq!(send(&app, Method::POST, LOCK_URL, lock_info("l1")).await, StatusCode::OK);
+        let body = serde_json::json!({});
+        assert_eq!(send(&app, Method::POST, "/terraform/locks/enc/part/force-unlock", body).await, StatusCode::OK);
+
+        let page = get_json(&app, "/events?kind=TfStateUploaded&limit=2").await;
+        let events = page["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["serial"], 2);
+        assert_eq!(events[1]["serial"], 3);
+        assert_eq!(events[1]["actor"], "admin");
+        assert_eq!(events[1]["source"], "cli");
+        assert_eq!(events[1]["request_id"], "req-3");
+
+        let cursor = page["next_cursor"].as_u64().unwrap();
+        let older = get_json(&app, &format!("/events?kind=TfStateUploaded&limit=2&before={cursor}")).await;
+        assert_eq!(older["events"][0]["serial"], 1);
+        assert!(older["next_cursor"].is_null());
+
+        // Requests without the CLI header are attributed to the API, with a generated request ID.
+        let unlocked = &get_json(&app, "/events?kind=TfLockForceUnlocked").await["events"][0];
+        assert_eq!(unlocked["source"], "api");
+        assert!(unlocked["request_id"].is_string());
+    }
+
+    // ── API tokens and authorization ──────────────────────────────────────────
+
+    async fn send_as(
+        app: &Router,
+        authorization: &str,
+        method: Method,
+        uri: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("Authorization", authorization)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null))
+    }
+
+    async fn create_token(app: &Router, body: serde_json::Value) -> String {
+        let admin = format!("Bearer {}", TEST_TOKEN);
+        let (status, created) = send_as(app, &admin, Method::POST, "/tokens", body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created.get("token_hash").is_none());
+        format!("Bearer {}", created["token"].as_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn api_tokens_are_limited_to_their_scopes_and_enclaves() {
+        let app = test_app();
+        let none = serde_json::Value::Null;
+        let reader = create_token(&app, serde_json::json!({ "name": "ci", "scopes": ["read"] })).await;
+        assert_eq!(send_as(&app, &reader, Method::GET, "/enclaves", none.clone()).await.0, StatusCode::OK);
+        let dry_run = serde_json::json!({ "enclaves_dir": "/nonexistent" });
+        assert_eq!(
+            send_as(&app, &reader, Method::POST, "/reconcile/dry-run", dry_run).await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(send_as(&app, &reader, Method::GET, "/tokens", none.clone()).await.0, StatusCode::FORBIDDEN);
+
+        let deployer = create_token(
+            &app,
+            serde_json::json!({ "name": "deployer", "scopes": ["apply"], "enclaves": ["enc"] }),
+        )
+        .await;
+        assert_eq!(send_as(&app, &deployer, Method::POST, STATE_URL, tf_state_blob()).await.0, StatusCode::OK);
+        let other = "/terraform/state/other/part";
+        assert_eq!(send_as(&app, &deployer, Method::POST, other, tf_state_blob()).await.0, StatusCode::FORBIDDEN);
+        assert_eq!(send_as(&app, &deployer, Method::DELETE, "/enclaves/enc", none.clone()).await.0, StatusCode::FORBIDDEN);
+        let events = &get_json(&app, "/events?kind=TfStateUploaded").await["events"];
+        assert_eq!(events[0]["actor"], "deployer");
+
+        // Names are unique, and revoked tokens stop working.
+        let dup = serde_json::json!({ "name": "ci", "scopes": ["read"] });
+        assert_eq!(send(&app, Method::POST, "/tokens", dup).await, StatusCode::CONFLICT);
+        assert_eq!(get_json(&app, "/tokens").await.as_array().unwrap().len(), 2);
+        assert_eq!(send(&app, Method::DELETE, "/tokens/ci", none.clone()).await, StatusCode::NO_CONTENT);
+        assert_eq!(send(&app, Method::DELETE, "/tokens/ci", none.clone()).await, StatusCode::NOT_FOUND);
+        assert_eq!(send_as(&app, &reader, Method::GET, "/enclaves", none).await.0, StatusCode::UNAUTHORIZED);
+        let kinds: Vec<_> = get_json(&app, "/events").await["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["kind"].as_str().unwrap().to_string())
+            .collect();
+        assert!(kinds.contains(&"ApiTokenCreated".to_string()));
+        assert!(kinds.contains(&"ApiTokenRevoked".to_string()));
+    }
+
+    #[tokio::test]
+    async fn enclave_limited_tokens_only_see_their_enclaves() {
+        let app = test_app();
+        let none = serde_json::Value::Null;
+        let token = create_token(
+            &app,
+            serde_json::json!({ "name": "team", "scopes": ["read"], "enclaves": ["enc"] }),
+        )
+        .await;
+
+        // Global routes would show every enclave.
+        let global = ["/enclaves", "/graph", "/events", "/events/stream", "/terraform/locks", "/status", "/orphans"];
+        for uri in global {
+            let (status, _) = send_as(&app, &token, Method::GET, uri, none.clone()).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+        }
+        let other = "/events?enclave_id=other";
+        assert_eq!(send_as(&app, &token, Method::GET, other, none.clone()).await.0, StatusCode::FORBIDDEN);
+
+        let (status, page) = send_as(&app, &token, Method::GET, "/events?enclave_id=enc", none.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(page["events"].is_array());
+        assert_eq!(send_as(&app, &token, Method::GET, "/health", none.clone()).await.0, StatusCode::OK);
+        assert_eq!(send_as(&app, &token, Method::GET, "/enclaves/enc", none).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn reading_raw_tf_state_needs_apply() {
+        let app = test_app();
+        let none = serde_json::Value::Null;
+        assert_eq!(post_state(&app, tf_state_blob()).await, StatusCode::OK);
+        let reader = create_token(&app, serde_json::json!({ "name": "reader", "scopes": ["read"] })).await;
+        let deployer = create_token(&app, serde_json::json!({ "name": "deployer", "scopes": ["apply"] })).await;
+
+        assert_eq!(send_as(&app, &reader, Method::GET, STATE_URL, none.clone()).await.0, StatusCode::FORBIDDEN);
+        let versions = "/terraform/state/enc/part/versions";
+        assert_eq!(send_as(&app, &reader, Method::GET, versions, none.clone()).await.0, StatusCode::OK);
+        let (status, state) = send_as(&app, &deployer, Method::GET, STATE_URL, none).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state["lineage"], "abc");
+    }
+
+    #[tokio::test]
+    async fn reserved_token_names_are_rejected() {
+        let app = test_app();
+        for name in ["admin", "terraform:enc/part", "has space", ""] {
+            let body = serde_json::json!({ "name": name, "scopes": ["read"] });
+            assert_eq!(send(&app, Method::POST, "/tokens", body).await, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn state_backend_token_only_grants_its_own_state() {
+        let app = test_app();
+        let none = serde_json::Value::Null;
+        let token = nclav_store::sign_state_token(
+            TEST_TOKEN,
+            "enc/part",
+            chrono::Utc::now() + nclav_store::STATE_TOKEN_TTL,
+        );
+        let basic = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("nclav:{token}"))
+        );
+
+        assert_eq!(send_as(&app, &basic, Method::POST, STATE_URL, tf_state_blob()).await.0, StatusCode::OK);
+        assert_eq!(send_as(&app, &basic, Method::POST, LOCK_URL, lock_info("l1")).await.0, StatusCode::OK);
+        let other = "/terraform/state/enc/other";
+        assert_eq!(send_as(&app, &basic, Method::GET, other, none.clone()).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(send_as(&app, &basic, Method::GET, "/enclaves", none.clone()).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(send_as(&app, &basic, Method::DELETE, STATE_URL, none).await.0, StatusCode::FORBIDDEN);
+
+        let events = &get_json(&app, "/events?kind=TfStateUploaded").await["events"];
+        assert_eq!(events[0]["actor"], "terraform:enc/part");
+    }
+
+    #[tokio::test]
+    async fn oidc_tokens_get_their_roles_and_are_recorded_by_subject() {
+        use crate::oidc::test_support::TestIssuer;
+
+        let issuer = TestIssuer::new();
+        let verifier = issuer.verifier(
+            "  - name: deploy\n    claims: { repository: acme/infra, ref: refs/heads/main }\n    scopes: [apply]\n    enclaves: [enc]\n",
+        );
+        let mut registry = DriverRegistry::new(CloudTarget::Local);
+        registry.register(CloudTarget::Local, Arc::new(LocalDriver::new()));
+        let config = AppConfig { oidc: Some(Arc::new(verifier)), ..test_config(Arc::new(registry)) };
+        let app = build_app(Arc::new(InMemoryStore::new()), EventBus::default(), config);
+        let none = serde_json::Value::Null;
+
+        let main = format!(
+            "Bearer {}",
+            issuer.sign(serde_json::json!({ "repository": "acme/infra", "ref": "refs/heads/main" }))
+        );
+        assert_eq!(send_as(&app, &main, Method::POST, STATE_URL, tf_state_blob()).await.0, StatusCode::OK);
+        let other = "/terraform/state/other/part";
+        assert_eq!(send_as(&app, &main, Method::POST, other, tf_state_blob()).await.0, StatusCode::FORBIDDEN);
+        let events = &get_json(&app, "/events?kind=TfStateUploaded").await["events"];
+        assert_eq!(events[0]["actor"], "oidc:repo:acme/infra:ref:refs/heads/main");
+
+        // A valid token matching no role authenticates but may do nothing.
+        let fork = format!("Bearer {}", issuer.sign(serde_json::json!({ "repository": "mallory/infra" })));
+        assert_eq!(send_as(&app, &fork, Method::GET, "/enclaves", none.clone()).await.0, StatusCode::FORBIDDEN);
+
+        let expired = issuer.sign(serde_json::json!({ "exp": chrono::Utc::now().timestamp() - 3600 }));
+        let expired = format!("Bearer {expired}");
+        assert_eq!(send_as(&app, &expired, Method::GET, "/enclaves", none).await.0, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn metrics_report_reconciles_and_state_locks() {
+        let app = test_app();
+        let reconcile = serde_json::json!({ "enclaves_dir": "/no/such/path" });
+        assert!(!send(&app, Method::POST, "/reconcile", reconcile).await.is_success());
+        let mut info = lock_info("metrics-lock");
+        info["Created"] = serde_json::json!(chrono::Utc::now());
+        assert_eq!(send(&app, Method::POST, LOCK_URL, info.clone()).await, StatusCode::OK);
+        assert_eq!(send(&app, Method::DELETE, LOCK_URL, info).await, StatusCode::OK);
+
+        let resp = app
+            .oneshot(authed(Request::builder().uri("/metrics")).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers()["content-type"].to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains(r#"nclav_reconcile_duration_seconds_count{outcome="error",dry_run="false"}"#));
+        assert!(text.contains("nclav_tf_lock_wait_seconds_count "));
+        assert!(text.contains(r#"nclav_tf_lock_hold_seconds_count{released="unlock"}"#));
+        assert!(text.contains("# TYPE nclav_enclaves gauge"));
+    }
+
+    #[tokio::test]
+    async fn traceparent_is_continued_and_recorded_on_events() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (layer, mut spans) = crate::otel::test_support::capturing_layer();
+        let _tracing = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+        let app = test_app();
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let resp = app
+            .clone()
+            .oneshot(
+                authed(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri(STATE_URL)
+                        .header("content-type", "application/json")
+                        .header("traceparent", format!("00-{trace_id}-00f067aa0ba902b7-01")),
+                )
+                .body(Body::from(tf_state_blob().to_string()))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        // The request span ends with the response body.
+        drop(resp);
+        let request = spans.try_recv().unwrap();
+        assert_eq!(request["name"], "request");
+        assert_eq!(request["traceId"], trace_id);
+        assert_eq!(request["parentSpanId"], "00f067aa0ba902b7");
+
+        let events = &get_json(&app, "/events?kind=TfStateUploaded").await["events"];
+        assert_eq!(events[0]["trace_id"], trace_id);
+    }
 }
@@ -1,25 +1,46 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
 use axum::{
-    extract::{Request, State},
-    http::StatusCode,
+    async_trait,
+    extract::{FromRequestParts, MatchedPath, Query, Request, State},
+    http::{request::Parts, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use base64::Engine as _;
+use chrono::Utc;
+use nclav_domain::EnclaveId;
+use nclav_store::{
+    hash_token, verify_state_token, EventContext, EventSource, Scope, StoreError,
+    STATE_TOKEN_PREFIX,
+};
+use uuid::Uuid;
 
+use crate::oidc::looks_like_jwt;
 use crate::state::AppState;
+use crate::tls::ClientCertificate;
 
-/// Axum middleware that requires a valid `Authorization` header on every request.
+/// Axum middleware that requires a valid `Authorization` header on every request
+/// and checks that the caller may use the matched route.
 ///
 /// Accepts two formats:
 ///   - `Bearer <token>` — used by the nclav CLI and API clients
 ///   - `Basic base64(<user>:<token>)` — used by Terraform's HTTP state backend,
 ///     which sends the token as the Basic auth password (username is ignored)
 ///
-/// Returns 401 for missing, malformed, or incorrect tokens.
-/// Applied to all routes — no public endpoints.
+/// The token is the server's own token, a named [`nclav_store::ApiToken`], an
+/// OIDC ID token from a configured issuer (see [`crate::oidc`]), or a
+/// state-backend token minted for one partition's Terraform runs. Without a
+/// header, a client certificate verified during the TLS handshake (see
+/// [`crate::tls`]) authenticates the request instead.
+/// Returns 401 for missing, malformed, or unknown tokens and 403 when the
+/// token lacks the scope or enclave the route needs (see [`authorize`]).
+/// Applied to all routes — no public endpoints. On success the caller's
+/// [`Principal`] is attached to the request for handlers to attribute events to.
 pub async fn require_bearer_token(
     State(state): State<AppState>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
     let header = request
@@ -43,9 +64,260 @@ pub async fn require_bearer_token(
         }
         None
     });
+    let route = RouteInfo::of(&request);
+    let principal = match token {
+        Some(token) => match authenticate(&state, &token, &route).await {
+            Ok(Some(principal)) => principal,
+            Ok(None) => return unauthorized(),
+            Err(e) => {
+                tracing::error!(error = %e, "token lookup failed");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error\n").into_response();
+            }
+        },
+        None => match request.extensions().get::<ClientCertificate>() {
+            Some(ClientCertificate(principal)) => principal.clone(),
+            None => return unauthorized(),
+        },
+    };
+    if let Err(reason) = authorize(&principal.grant, request.method(), &route) {
+        return (StatusCode::FORBIDDEN, format!("Forbidden: {reason}\n")).into_response();
+    }
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, "Unauthorized\n").into_response()
+}
+
+/// Resolve `token` to the principal it identifies, if any.
+async fn authenticate(
+    state: &AppState,
+    token: &str,
+    route: &RouteInfo,
+) -> Result<Option<Principal>, StoreError> {
+    if token == state.auth_token.as_str() {
+        return Ok(Some(Principal { actor: ADMIN_ACTOR.to_string(), grant: Grant::Server }));
+    }
+    if let Some(oidc) = state.oidc.as_ref().filter(|_| looks_like_jwt(token)) {
+        return match oidc.verify(token).await {
+            Ok(identity) => Ok(Some(Principal {
+                actor: identity.actor,
+                grant: Grant::Any(
+                    identity
+                        .roles
+                        .into_iter()
+                        .map(|r| Grant::Token { scopes: r.scopes, enclaves: r.enclaves })
+                        .collect(),
+                ),
+            })),
+            Err(e) => {
+                tracing::debug!(error = %e, "rejected JWT");
+                Ok(None)
+            }
+        };
+    }
+    if token.starts_with(STATE_TOKEN_PREFIX) {
+        // Signed for one state key, so only checkable on that key's routes.
+        let Some(key) = route.state_key() else { return Ok(None) };
+        if !verify_state_token(&state.auth_token, &key, token, Utc::now()) {
+            return Ok(None);
+        }
+        return Ok(Some(Principal {
+            actor: format!("{STATE_ACTOR_PREFIX}{key}"),
+            grant: Grant::State { key },
+        }));
+    }
+    Ok(state.store.find_api_token(&hash_token(token)).await?.map(|t| Principal {
+        actor: t.name,
+        grant: Grant::Token { scopes: t.scopes, enclaves: t.enclaves },
+    }))
+}
+
+/// Check that `grant` allows `method` on `route`, or say why not.
+///
+/// Routes need `read` for `GET`, `diff` for `/reconcile/dry-run`, `apply` to
+/// reconcile and write Terraform state and locks, `destroy` to delete enclaves,
+/// partitions and Terraform state, and `admin` for everything else. Raw
+/// Terraform state holds secrets, so reading it needs `apply` rather than
+/// `read`. A token with an enclave allow-list may only use routes naming an
+/// allowed enclave, the health checks, and the event routes filtered to an
+/// allowed enclave: every other global route shows all enclaves.
+pub fn authorize(grant: &Grant, method: &Method, route: &RouteInfo) -> Result<(), String> {
+    match grant {
+        Grant::Server => Ok(()),
+        Grant::State { key } => {
+            let allowed = route.state_key().as_ref() == Some(key)
+                && matches!(
+                    (route.path.as_str(), method.as_str()),
+                    ("/terraform/state/:enc/:part", "GET" | "POST")
+                        | ("/terraform/state/:enc/:part/lock", "POST" | "DELETE")
+                );
+            if allowed {
+                Ok(())
+            } else {
+                Err(format!("state-backend token only grants the state of {key}"))
+            }
+        }
+        Grant::Any(grants) => {
+            let mut reason = "no role matches the token's claims".to_string();
+            for grant in grants {
+                match authorize(grant, method, route) {
+                    Ok(()) => return Ok(()),
+                    Err(e) => reason = e,
+                }
+            }
+            Err(reason)
+        }
+        Grant::Token { scopes, enclaves } => {
+            let required = required_scope(method, &route.path);
+            if !scopes.iter().any(|s| s.grants(required)) {
+                return Err(format!("token lacks the {required} scope"));
+            }
+            if enclaves.is_empty() {
+                return Ok(());
+            }
+            match route.enclave_id() {
+                Some(id) if enclaves.contains(&id) => Ok(()),
+                Some(id) => Err(format!("token is not allowed on enclave {id}")),
+                None if matches!(route.path.as_str(), "/health" | "/ready") => Ok(()),
+                None => match route.event_filter_enclave() {
+                    Some(id) if enclaves.contains(&id) => Ok(()),
+                    Some(id) => Err(format!("token is not allowed on enclave {id}")),
+                    None => Err("token is limited to specific enclaves".into()),
+                },
+            }
+        }
+    }
+}
+
+/// The scope a named token needs for `method` on the route pattern `path`.
+fn required_scope(method: &Method, path: &str) -> Scope {
+    match (method.as_str(), path) {
+        (_, "/tokens" | "/tokens/:name" | "/admin/prune")
+        | (_, "/terraform/locks/:enc/:part/force-unlock") => Scope::Admin,
+        ("GET", "/terraform/state/:enc/:part") => Scope::Apply,
+        ("GET", _) => Scope::Read,
+        ("POST", "/reconcile/dry-run") => Scope::Diff,
+        ("POST", "/reconcile")
+        | ("POST", "/terraform/state/:enc/:part")
+        | ("POST" | "DELETE", "/terraform/state/:enc/:part/lock")
+        | ("POST", "/terraform/state/:enc/:part/versions/:version/restore") => Scope::Apply,
+        ("DELETE", "/enclaves/:id")
+        | ("DELETE", "/enclaves/:id/partitions/:part")
+        | ("DELETE", "/terraform/state/:enc/:part") => Scope::Destroy,
+        _ => Scope::Admin,
+    }
+}
+
+/// The matched route pattern of a request and its path parameters.
+#[derive(Debug, Clone, Default)]
+pub struct RouteInfo {
+    /// Route pattern, e.g. `/enclaves/:id`.
+    pub path: String,
+    pub params: HashMap<String, String>,
+    /// Query string parameters.
+    pub query: HashMap<String, String>,
+}
+
+impl RouteInfo {
+    fn of(request: &Request) -> Self {
+        let Some(matched) = request.extensions().get::<MatchedPath>() else {
+            return Self::default();
+        };
+        let query = Query::<HashMap<String, String>>::try_from_uri(request.uri())
+            .map(|Query(q)| q)
+            .unwrap_or_default();
+        Self { query, ..Self::new(matched.as_str(), request.uri().path()) }
+    }
+
+    /// Pair up the `:name` segments of `pattern` with the segments of `path`.
+    pub fn new(pattern: &str, path: &str) -> Self {
+        let params = pattern
+            .split('/')
+            .zip(path.split('/'))
+            .filter_map(|(p, v)| Some((p.strip_prefix(':')?.to_string(), v.to_string())))
+            .collect();
+        Self { path: pattern.to_string(), params, query: HashMap::new() }
+    }
+
+    fn enclave_id(&self) -> Option<EnclaveId> {
+        self.params.get("id").or_else(|| self.params.get("enc")).map(|s| EnclaveId::new(s.as_str()))
+    }
+
+    /// The enclave the event routes are filtered to, if any.
+    fn event_filter_enclave(&self) -> Option<EnclaveId> {
+        if !matches!(self.path.as_str(), "/events" | "/events/stream") {
+            return None;
+        }
+        self.query.get("enclave_id").map(|s| EnclaveId::new(s.as_str()))
+    }
+
+    fn state_key(&self) -> Option<String> {
+        if !self.path.starts_with("/terraform/state/") {
+            return None;
+        }
+        Some(format!("{}/{}", self.params.get("enc")?, self.params.get("part")?))
+    }
+}
+
+/// Actor name recorded for requests made with the server's API token.
+pub const ADMIN_ACTOR: &str = "admin";
+
+/// Header the nclav CLI sets so its requests are recorded with source `cli`.
+pub const CLIENT_HEADER: &str = "x-nclav-client";
+
+/// Header carrying a caller-chosen request ID; one is generated when absent.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Actor prefix for state-backend tokens, followed by the state key.
+pub const STATE_ACTOR_PREFIX: &str = "terraform:";
+
+/// The authenticated caller, inserted by [`require_bearer_token`].
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub actor: String,
+    pub grant: Grant,
+}
+
+/// What the caller's token allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grant {
+    /// The server's own token: everything.
+    Server,
+    /// A named API token.
+    Token { scopes: Vec<Scope>, enclaves: Vec<EnclaveId> },
+    /// A state-backend token: only the state and lock routes of `key`.
+    State { key: String },
+    /// A verified OIDC token: whatever any of its matched roles allows.
+    Any(Vec<Grant>),
+}
+
+/// Audit context for the current request: the authenticated actor, whether
+/// the call came from the nclav CLI or another client, and its request ID.
+#[derive(Debug, Clone)]
+pub struct AuditContext(pub EventContext);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = Infallible;
 
-    match token {
-        Some(t) if t == state.auth_token.as_str() => next.run(request).await,
-        _ => (StatusCode::UNAUTHORIZED, "Unauthorized\n").into_response(),
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+        let source = match header(CLIENT_HEADER) {
+            Some("cli") => EventSource::Cli,
+            _ => EventSource::Api,
+        };
+        let request_id = header(REQUEST_ID_HEADER)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        Ok(Self(EventContext {
+            actor: parts.extensions.get::<Principal>().map(|p| p.actor.clone()),
+            source: Some(source),
+            run_id: None,
+            request_id: Some(request_id),
+            trace_id: nclav_telemetry::trace::current_trace_id(),
+        }))
     }
 }
@@ -17,6 +17,10 @@ impl ApiError {
         ApiError { status: StatusCode::UNPROCESSABLE_ENTITY, message: msg.into() }
     }
 
+    pub fn conflict(msg: impl Into<String>) -> Self {
+        ApiError { status: StatusCode::CONFLICT, message: msg.into() }
+    }
+
     pub fn not_found(msg: impl Into<String>) -> Self {
         ApiError { status: StatusCode::NOT_FOUND, message: msg.into() }
     }
@@ -48,6 +52,9 @@ impl From<nclav_reconciler::ReconcileError> for ApiError {
 
 impl From<nclav_store::StoreError> for ApiError {
     fn from(e: nclav_store::StoreError) -> Self {
-        ApiError::internal(e.to_string())
+        match e {
+            nclav_store::StoreError::Conflict(_) => ApiError::conflict(e.to_string()),
+            _ => ApiError::internal(e.to_string()),
+        }
     }
 }
@@ -1,20 +1,34 @@
 
 use axum::body::Bytes;
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::Json;
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use futures::Stream;
+use chrono::Utc;
 use nclav_domain::{EnclaveId, PartitionId};
 use nclav_driver::TerraformBackend;
-use nclav_reconciler::{reconcile, ReconcileRequest};
-use nclav_store::StoreError;
+use nclav_graph::{impact, validate, GraphError, ImpactTarget};
+use nclav_reconciler::{reconcile, teardown_enclave, teardown_partition, ReconcileRequest};
+use nclav_telemetry::metrics;
+use nclav_store::{
+    expire_stale_lock, ApiToken, AuditEvent, EnclaveState, EventContext, EventQuery, IacRun, IacRunStatus,
+    observe_lock_granted, observe_lock_released, Scope, StoreError, TfLockStatus, TfStateGuard,
+    TfStateHeader, TfStateWrite, LOCK_ACQUIRED_FIELD, LOCK_RUN_FIELD,
+};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::warn;
 use uuid::Uuid;
 
+use crate::auth::{AuditContext, Principal, ADMIN_ACTOR, STATE_ACTOR_PREFIX};
 use crate::error::ApiError;
+use crate::prune::Pruner;
+use crate::render;
 use crate::state::AppState;
 
 // ── Health ────────────────────────────────────────────────────────────────────
@@ -39,6 +53,7 @@ pub struct ReconcileBody {
 
 pub async fn post_reconcile(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Json(body): Json<ReconcileBody>,
 ) -> Result<Json<Value>, ApiError> {
     let req = ReconcileRequest {
@@ -46,8 +61,12 @@ pub async fn post_reconcile(
         dry_run: false,
         api_base: (*state.api_base).clone(),
         auth_token: state.auth_token.clone(),
+        backend_tls: state.backend_tls.clone(),
         test_mode: false,
         resources_only: body.resources_only,
+        vpc_allocator: state.vpc_allocator.clone(),
+        policy: state.policy.clone(),
+        context,
     };
     let report = reconcile(req, state.store, state.registry).await?;
     Ok(Json(json!(report)))
@@ -55,6 +74,7 @@ pub async fn post_reconcile(
 
 pub async fn post_reconcile_dry_run(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Json(body): Json<ReconcileBody>,
 ) -> Result<Json<Value>, ApiError> {
     let req = ReconcileRequest {
@@ -62,8 +82,12 @@ pub async fn post_reconcile_dry_run(
         dry_run: true,
         api_base: (*state.api_base).clone(),
         auth_token: state.auth_token.clone(),
+        backend_tls: state.backend_tls.clone(),
         test_mode: false,
         resources_only: body.resources_only,
+        vpc_allocator: state.vpc_allocator.clone(),
+        policy: state.policy.clone(),
+        context,
     };
     let report = reconcile(req, state.store, state.registry).await?;
     Ok(Json(json!(report)))
@@ -97,6 +121,7 @@ pub struct DeleteEnclaveQuery {
 
 pub async fn delete_enclave(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Path(id): Path<String>,
     Query(query): Query<DeleteEnclaveQuery>,
 ) -> Result<Json<Value>, ApiError> {
@@ -120,54 +145,63 @@ pub async fn delete_enclave(
     let tf_backend = TerraformBackend {
         api_base: (*state.api_base).clone(),
         auth_token: state.auth_token.clone(),
+        tls: state.backend_tls.clone(),
         store: state.store.clone(),
         test_mode: false,
         workspace_root: None,
     };
 
-    let mut errors: Vec<String> = Vec::new();
-
-    if let Some(enc_handle) = &existing.enclave_handle {
-        // Teardown IaC partitions first
-        let auth_env = driver.auth_env(&existing.desired, enc_handle);
-        for (part_id, part_state) in &existing.partitions {
-            if let Err(e) = tf_backend
-                .teardown(&existing.desired, &part_state.desired, &auth_env, None)
-                .await
-            {
-                warn!(enclave_id = %id, partition_id = %part_id, error = %e, "IaC teardown failed");
-                errors.push(format!("teardown {}/{}: {}", id, part_id, e));
-            }
-            // Clean up partition SA after IaC teardown
-            if let Some(handle) = &part_state.partition_handle {
-                if let Err(e) = driver
-                    .teardown_partition(&existing.desired, &part_state.desired, handle)
-                    .await
-                {
-                    warn!(enclave_id = %id, partition_id = %part_id, error = %e, "partition SA cleanup failed");
-                    errors.push(format!("SA cleanup {}/{}: {}", id, part_id, e));
-                }
-            }
-        }
+    let context = destroy_requested(&state, context, &eid, None, query.resources_only).await?;
 
-        // Teardown the enclave itself (skip project deletion if resources_only)
-        if query.resources_only {
-            warn!(enclave_id = %id, "resources_only: skipping project deletion");
-        } else if let Err(e) = driver.teardown_enclave(&existing.desired, enc_handle).await {
-            warn!(enclave_id = %id, error = %e, "enclave teardown failed");
-            errors.push(format!("enclave teardown {}: {}", id, e));
-        }
-    }
+    // Partitions first, then the enclave; on any failure the enclave stays in
+    // the store as a tombstone for `nclav destroy --retry` or the next reconcile.
+    let outcome = teardown_enclave(
+        state.store.as_ref(),
+        driver.as_ref(),
+        &tf_backend,
+        existing,
+        query.resources_only,
+        &context,
+    )
+    .await?;
 
-    state.store.delete_enclave(&eid).await?;
+    Ok(Json(json!({
+        "destroyed":           id,
+        "errors":              outcome.errors,
+        "remaining_resources": outcome.remaining_resources,
+        "purged":              outcome.purged,
+    })))
+}
 
-    Ok(Json(json!({ "destroyed": id, "errors": errors })))
+/// Start a destroy run: record `DestroyRequested` and return the context the
+/// teardown's own events are recorded under.
+async fn destroy_requested(
+    state: &AppState,
+    context: EventContext,
+    enclave_id: &EnclaveId,
+    partition_id: Option<&PartitionId>,
+    resources_only: bool,
+) -> Result<EventContext, ApiError> {
+    let context = context.with_run(Uuid::new_v4());
+    state
+        .store
+        .append_event(&AuditEvent::DestroyRequested {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: context.clone(),
+            enclave_id: enclave_id.clone(),
+            partition_id: partition_id.cloned(),
+            resources_only,
+        })
+        .await?;
+    Ok(context)
 }
 
 // ── delete_partition ──────────────────────────────────────────────────────────
 
 pub async fn delete_partition(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Path((enc_id, part_id)): Path<(String, String)>,
 ) -> Result<Json<Value>, ApiError> {
     let eid = EnclaveId::new(&enc_id);
@@ -179,13 +213,11 @@ pub async fn delete_partition(
         .await?
         .ok_or_else(|| ApiError::not_found(format!("enclave '{}' not found", enc_id)))?;
 
-    let part_state = existing
-        .partitions
-        .get(&pid)
-        .cloned()
-        .ok_or_else(|| ApiError::not_found(
+    if !existing.partitions.contains_key(&pid) {
+        return Err(ApiError::not_found(
             format!("partition '{}' not found in enclave '{}'", part_id, enc_id)
-        ))?;
+        ));
+    }
 
     let cloud = existing
         .resolved_cloud
@@ -197,56 +229,35 @@ pub async fn delete_partition(
         .for_cloud(cloud)
         .map_err(|e| ApiError::internal(e.to_string()))?;
 
-    let mut errors: Vec<String> = Vec::new();
-
     let tf_backend = TerraformBackend {
         api_base:       (*state.api_base).clone(),
         auth_token:     state.auth_token.clone(),
+        tls:            state.backend_tls.clone(),
         store:          state.store.clone(),
         test_mode:      false,
         workspace_root: None,
     };
-    let auth_env = existing
-        .enclave_handle
-        .as_ref()
-        .map(|h| driver.auth_env(&existing.desired, h))
-        .unwrap_or_default();
-    if let Err(e) = tf_backend
-        .teardown(&existing.desired, &part_state.desired, &auth_env, None)
-        .await
-    {
-        warn!(enclave_id = %enc_id, partition_id = %part_id, error = %e, "IaC partition teardown failed");
-        errors.push(format!("{}", e));
-    }
-    // Clean up partition SA
-    if let Some(handle) = &part_state.partition_handle {
-        if let Err(e) = driver
-            .teardown_partition(&existing.desired, &part_state.desired, handle)
-            .await
-        {
-            warn!(enclave_id = %enc_id, partition_id = %part_id, error = %e, "partition SA cleanup failed");
-            errors.push(format!("SA cleanup: {}", e));
-        }
-    }
 
-    // Remove partition from store regardless of teardown errors so the operator
-    // can fix things manually and re-apply.
-    state.store.delete_partition(&eid, &pid).await?;
-
-    // Post-destroy CAI check: list any resources still labeled to this partition.
-    let remaining_resources: Vec<String> = if let Some(enc_handle) = &existing.enclave_handle {
-        driver
-            .list_partition_resources(&existing.desired, enc_handle, &part_state.desired)
-            .await
-            .unwrap_or_default()
-    } else {
-        vec![]
-    };
+    let context = destroy_requested(&state, context, &eid, Some(&pid), false).await?;
+
+    // The partition is only removed from the store once terraform destroy, SA
+    // cleanup and the post-destroy resource check all succeeded.
+    let mut enc_state = existing;
+    let outcome = teardown_partition(
+        state.store.as_ref(),
+        driver.as_ref(),
+        &tf_backend,
+        &mut enc_state,
+        &pid,
+        &context,
+    )
+    .await?;
 
     Ok(Json(json!({
         "destroyed":           format!("{}/{}", enc_id, part_id),
-        "errors":              errors,
-        "remaining_resources": remaining_resources,
+        "errors":              outcome.errors,
+        "remaining_resources": outcome.remaining_resources,
+        "purged":              outcome.purged,
     })))
 }
 
@@ -300,9 +311,42 @@ pub async fn get_enclave_graph(
     })))
 }
 
-pub async fn get_system_graph(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Text,
+    Dot,
+    Mermaid,
+    Html,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQuery {
+    #[serde(default)]
+    pub format: GraphFormat,
+    /// Limit rendered (non-JSON) formats to one enclave.
+    pub enclave: Option<String>,
+}
+
+pub async fn get_system_graph(
+    State(state): State<AppState>,
+    Query(q): Query<GraphQuery>,
+) -> Result<Response, ApiError> {
     let all = state.store.list_enclaves().await?;
+    let filter = q.enclave.as_deref();
+    let (content_type, body) = match q.format {
+        GraphFormat::Json => return Ok(Json(system_graph_json(&all)).into_response()),
+        GraphFormat::Text => ("text/plain; charset=utf-8", render::render_graph_text_live(&all, filter)),
+        GraphFormat::Dot => ("text/vnd.graphviz; charset=utf-8", render::render_dot_live(&all, filter)),
+        GraphFormat::Mermaid => ("text/plain; charset=utf-8", render::render_mermaid_live(&all, filter)),
+        GraphFormat::Html => ("text/html; charset=utf-8", render::render_html_live(&all, filter)),
+    };
+    Ok(([(header::CONTENT_TYPE, content_type)], body).into_response())
+}
 
+fn system_graph_json(all: &[EnclaveState]) -> Value {
     let nodes: Vec<Value> = all
         .iter()
         .map(|s| {
@@ -332,7 +376,7 @@ pub async fn get_system_graph(State(state): State<AppState>) -> Result<Json<Valu
         .collect();
 
     let mut edges: Vec<Value> = Vec::new();
-    for s in &all {
+    for s in all {
         for import in &s.desired.imports {
             edges.push(json!({
                 "from": import.from,
@@ -354,7 +398,35 @@ pub async fn get_system_graph(State(state): State<AppState>) -> Result<Json<Valu
         }
     }
 
-    Ok(Json(json!({ "nodes": nodes, "edges": edges })))
+    json!({ "nodes": nodes, "edges": edges })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpactQuery {
+    /// `<enclave>`, `<enclave>/<partition>` or `<enclave>:<export>`.
+    pub target: String,
+}
+
+pub async fn get_graph_impact(
+    State(state): State<AppState>,
+    Query(q): Query<ImpactQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let target: ImpactTarget = q
+        .target
+        .parse()
+        .map_err(|e: GraphError| ApiError::bad_request(e.to_string()))?;
+    let enclaves: Vec<_> = state
+        .store
+        .list_enclaves()
+        .await?
+        .into_iter()
+        .map(|s| s.desired)
+        .collect();
+    let resolved = validate(&enclaves).map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    let dependents = impact(&enclaves, &resolved, &target)
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(json!({ "target": target, "dependents": dependents })))
 }
 
 // ── Events ────────────────────────────────────────────────────────────────────
@@ -362,16 +434,88 @@ pub async fn get_system_graph(State(state): State<AppState>) -> Result<Json<Valu
 #[derive(Debug, Deserialize)]
 pub struct EventsQuery {
     pub enclave_id: Option<String>,
+    pub partition_id: Option<String>,
+    /// Event kind, e.g. `PartitionDeleted`.
+    pub kind: Option<String>,
+    /// Reconcile or destroy run ID.
+    pub run_id: Option<Uuid>,
+    /// RFC 3339 lower bound, inclusive.
+    pub since: Option<chrono::DateTime<Utc>>,
+    /// RFC 3339 upper bound, exclusive.
+    pub until: Option<chrono::DateTime<Utc>>,
+    /// `next_cursor` from the previous page.
+    pub before: Option<u64>,
     pub limit: Option<u32>,
 }
 
+impl From<EventsQuery> for EventQuery {
+    fn from(q: EventsQuery) -> Self {
+        EventQuery {
+            enclave_id: q.enclave_id.as_deref().map(EnclaveId::new),
+            partition_id: q.partition_id.as_deref().map(PartitionId::new),
+            kind: q.kind,
+            run_id: q.run_id,
+            since: q.since,
+            until: q.until,
+            before: q.before,
+            limit: q.limit.unwrap_or(100),
+        }
+    }
+}
+
+/// One page of audit events, oldest first. Follow `next_cursor` (as `before`)
+/// to walk back through older events.
 pub async fn list_events(
     State(state): State<AppState>,
     Query(q): Query<EventsQuery>,
 ) -> Result<Json<Value>, ApiError> {
-    let eid = q.enclave_id.as_deref().map(EnclaveId::new);
-    let events = state.store.list_events(eid.as_ref(), q.limit.unwrap_or(100)).await?;
-    Ok(Json(json!(events)))
+    let page = state.store.query_events(&q.into()).await?;
+    Ok(Json(json!(page)))
+}
+
+/// Server-sent events: every audit event appended from now on that matches
+/// the same filters as `GET /events` (`before` and `limit` are ignored). Each
+/// SSE message has the event's `id`, its kind as the SSE event name and the
+/// event JSON as data. A `lagged` message reports events this client missed
+/// because it read too slowly.
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Query(q): Query<EventsQuery>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let query: EventQuery = q.into();
+    let rx = state.events.subscribe();
+    let stream = futures::stream::unfold((rx, query), |(mut rx, query)| async move {
+        loop {
+            let message = match rx.recv().await {
+                Ok(event) if query.matches_event(&event) => SseEvent::default()
+                    .id(event.id().to_string())
+                    .event(event.kind())
+                    .json_data(&event)
+                    .expect("audit events serialize"),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    SseEvent::default().event("lagged").data(missed.to_string())
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(message), (rx, query)));
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLettersQuery {
+    pub limit: Option<u32>,
+}
+
+/// Webhook deliveries that failed after every retry, newest first.
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Query(q): Query<DeadLettersQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let letters = state.store.list_dead_letters(q.limit.unwrap_or(100)).await?;
+    Ok(Json(json!(letters)))
 }
 
 // ── Terraform HTTP state backend ──────────────────────────────────────────────
@@ -388,60 +532,324 @@ pub async fn get_tf_state(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TfStateWriteQuery {
+    /// Lock ID Terraform sends when it writes under a lock.
+    #[serde(rename = "ID")]
+    pub id: Option<String>,
+}
+
 pub async fn put_tf_state(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Path((enc, part)): Path<(String, String)>,
+    Query(q): Query<TfStateWriteQuery>,
     body: Bytes,
 ) -> Result<StatusCode, ApiError> {
     let key = format!("{}/{}", enc, part);
-    state.store.put_tf_state(&key, body.to_vec()).await?;
+    let run = running_iac_run(&state, &enc, &part).await?;
+    // The store refuses a state from another lineage or an older serial, and a
+    // write made without the held lock, atomically with the write — a stale or
+    // misdirected apply would otherwise silently replace the good state.
+    let write = TfStateWrite {
+        run_id: run.as_ref().map(|r| r.id),
+        restored_from: None,
+        keep_versions: state.tf_state_versions,
+        guard: Some(TfStateGuard { lock_id: q.id }),
+        ..Default::default()
+    };
+    let version = match state.store.put_tf_state(&key, body.to_vec(), &write).await {
+        Ok(version) => version,
+        Err(StoreError::StaleState(reason)) => return Err(ApiError::conflict(reason)),
+        Err(StoreError::LockConflict { holder }) => {
+            return Err(ApiError::conflict(format!(
+                "state {} is locked by {} (lock {})",
+                key,
+                holder.get("Who").and_then(|v| v.as_str()).unwrap_or("unknown"),
+                holder.get("ID").and_then(|v| v.as_str()).unwrap_or("?"),
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    state
+        .store
+        .append_event(&AuditEvent::TfStateUploaded {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            // Terraform's own requests carry no trace; the run it belongs to does.
+            context: EventContext {
+                run_id: run.as_ref().and_then(|r| r.reconcile_run_id),
+                trace_id: run.and_then(|r| r.trace_id).or(context.trace_id),
+                ..context
+            },
+            enclave_id: EnclaveId::new(&enc),
+            partition_id: PartitionId::new(&part),
+            version: version.version,
+            serial: version.serial,
+            lineage: version.lineage,
+            iac_run_id: write.run_id,
+        })
+        .await?;
     Ok(StatusCode::OK)
 }
 
+/// The partition's IaC run still marked `running`, if any. State writes made
+/// through the HTTP backend are attributed to it.
+async fn running_iac_run(state: &AppState, enc: &str, part: &str) -> Result<Option<IacRun>, ApiError> {
+    let runs = state
+        .store
+        .list_iac_runs(&EnclaveId::new(enc), &PartitionId::new(part))
+        .await?;
+    Ok(runs.into_iter().find(|r| r.status == IacRunStatus::Running))
+}
+
+pub async fn list_tf_state_versions(
+    State(state): State<AppState>,
+    Path((enc, part)): Path<(String, String)>,
+) -> Result<Json<Value>, ApiError> {
+    let key = format!("{}/{}", enc, part);
+    let versions = state.store.list_tf_state_versions(&key).await?;
+    Ok(Json(json!(versions)))
+}
+
+/// Make a retained version the current state again.
+///
+/// The restored blob is written as a new version with its `serial` bumped past
+/// the current one, so Terraform accepts it on the next run. Refused with 409
+/// while the state is locked.
+pub async fn restore_tf_state_version(
+    State(state): State<AppState>,
+    AuditContext(context): AuditContext,
+    Path((enc, part, version)): Path<(String, String, u64)>,
+) -> Result<Json<Value>, ApiError> {
+    let key = format!("{}/{}", enc, part);
+    let blob = state
+        .store
+        .get_tf_state_version(&key, version)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("no version {} of state {}", version, key)))?;
+    let mut restored: Value = serde_json::from_slice(&blob).map_err(|e| {
+        ApiError::unprocessable(format!("version {} is not a JSON Terraform state: {}", version, e))
+    })?;
+    let current = match state.store.get_tf_state(&key).await? {
+        Some(bytes) => TfStateHeader::parse(&bytes),
+        None => TfStateHeader::default(),
+    };
+    let serial = current.serial.max(TfStateHeader::parse(&blob).serial).map_or(1, |s| s + 1);
+    restored["serial"] = json!(serial);
+    let bytes = serde_json::to_vec_pretty(&restored).map_err(|e| ApiError::internal(e.to_string()))?;
+
+    let lock_id = Uuid::new_v4().to_string();
+    let lock_info = json!({
+        "ID": lock_id,
+        "Operation": "nclav-restore",
+        "Who": "nclav",
+        "Info": format!("restore version {}", version),
+        "Created": Utc::now(),
+    });
+    let lock_info = match acquire_tf_lock(&state, &key, lock_info, &context).await {
+        Ok(info) => info,
+        Err(StoreError::LockConflict { holder }) => {
+            return Err(ApiError::conflict(format!(
+                "state {} is locked by {} (lock {}); retry once it is released",
+                key,
+                holder.get("Who").and_then(|v| v.as_str()).unwrap_or("unknown"),
+                holder.get("ID").and_then(|v| v.as_str()).unwrap_or("?"),
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let write = TfStateWrite {
+        run_id: None,
+        restored_from: Some(version),
+        keep_versions: state.tf_state_versions,
+        ..Default::default()
+    };
+    // Release the lock even if the write fails.
+    let result = state.store.put_tf_state(&key, bytes, &write).await;
+    state.store.unlock_tf_state(&key, &lock_id).await?;
+    observe_lock_released(&lock_info, "unlock");
+    let written = result?;
+    state
+        .store
+        .append_event(&AuditEvent::TfStateRestored {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context,
+            enclave_id: EnclaveId::new(&enc),
+            partition_id: PartitionId::new(&part),
+            version,
+        })
+        .await?;
+    Ok(Json(json!(written)))
+}
+
 pub async fn delete_tf_state(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Path((enc, part)): Path<(String, String)>,
 ) -> Result<StatusCode, ApiError> {
     let key = format!("{}/{}", enc, part);
     state.store.delete_tf_state(&key).await?;
+    state
+        .store
+        .append_event(&AuditEvent::TfStateDeleted {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context,
+            enclave_id: EnclaveId::new(&enc),
+            partition_id: PartitionId::new(&part),
+        })
+        .await?;
     Ok(StatusCode::OK)
 }
 
 pub async fn lock_tf_state(
     State(state): State<AppState>,
+    AuditContext(context): AuditContext,
     Path((enc, part)): Path<(String, String)>,
-    Json(lock_info): Json<Value>,
-) -> impl IntoResponse {
+    Json(mut lock_info): Json<Value>,
+) -> Result<Response, ApiError> {
     let key = format!("{}/{}", enc, part);
-    match state.store.lock_tf_state(&key, lock_info).await {
-        Ok(()) => StatusCode::OK.into_response(),
+    // Link the lock to the partition's running IaC run so it can be expired
+    // once that run is over.
+    if let Some(run) = running_iac_run(&state, &enc, &part).await? {
+        if let Some(obj) = lock_info.as_object_mut() {
+            obj.insert(LOCK_RUN_FIELD.into(), json!(run.id));
+        }
+    }
+    match acquire_tf_lock(&state, &key, lock_info, &context).await {
+        Ok(_) => Ok(StatusCode::OK.into_response()),
         // Terraform's HTTP backend expects the response body to be the existing
         // lock info JSON directly (not wrapped) — it uses it to show the lock owner.
-        Err(StoreError::LockConflict { holder }) => (
+        Err(StoreError::LockConflict { holder }) => Ok((
             StatusCode::CONFLICT,
             Json(holder),
         )
-            .into_response(),
-        Err(e) => ApiError::internal(e.to_string()).into_response(),
+            .into_response()),
+        Err(e) => Err(e.into()),
     }
 }
 
+/// Take the lock on `key`, returning the lock info as stored. If it is held by
+/// an IaC run that has already finished, expire that lock and try once more.
+async fn acquire_tf_lock(
+    state: &AppState,
+    key: &str,
+    mut lock_info: Value,
+    context: &EventContext,
+) -> Result<Value, StoreError> {
+    if let Some(obj) = lock_info.as_object_mut() {
+        obj.insert(LOCK_ACQUIRED_FIELD.into(), json!(Utc::now()));
+    }
+    let result = match state.store.lock_tf_state(key, lock_info.clone()).await {
+        Err(StoreError::LockConflict { .. })
+            if expire_stale_lock(state.store.as_ref(), key, context).await? =>
+        {
+            warn!(key = %key, "expired state lock held by a finished IaC run");
+            state.store.lock_tf_state(key, lock_info.clone()).await
+        }
+        other => other,
+    };
+    result?;
+    observe_lock_granted(&lock_info);
+    Ok(lock_info)
+}
+
 pub async fn unlock_tf_state(
     State(state): State<AppState>,
     Path((enc, part)): Path<(String, String)>,
     body: axum::body::Bytes,
 ) -> Result<StatusCode, ApiError> {
     let key = format!("{}/{}", enc, part);
-    // Parse the lock ID from the body if present (Terraform's normal unlock path).
-    // An absent or empty body means force-unlock — clear the lock regardless of ID.
+    // Terraform sends the lock info it was granted. Breaking someone else's
+    // lock goes through force_unlock_tf_state instead, so it is audited.
     let lock_id = serde_json::from_slice::<Value>(&body)
         .ok()
         .and_then(|v| v.get("ID").and_then(|v| v.as_str()).map(String::from))
         .unwrap_or_default();
+    if lock_id.is_empty() {
+        return Err(ApiError::bad_request(
+            "unlock requires the lock ID; break a lock with `nclav iac unlock --force`",
+        ));
+    }
+    let held = state.store.list_tf_locks().await?.into_iter().find(|(k, info)| {
+        *k == key && info.get("ID").and_then(|v| v.as_str()) == Some(lock_id.as_str())
+    });
     state.store.unlock_tf_state(&key, &lock_id).await?;
+    if let Some((_, info)) = held {
+        observe_lock_released(&info, "unlock");
+    }
     Ok(StatusCode::OK)
 }
 
+pub async fn list_tf_locks(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let mut locks = Vec::new();
+    for (key, info) in state.store.list_tf_locks().await? {
+        locks.push(TfLockStatus::inspect(state.store.as_ref(), &key, &info).await?);
+    }
+    Ok(Json(json!(locks)))
+}
+
+#[derive(Deserialize)]
+pub struct ForceUnlockBody {
+    /// Free-text note recorded in the audit log, e.g. the client's `user@host`.
+    /// Not verified; the event's `by` is the authenticated caller.
+    #[serde(alias = "by")]
+    pub note: Option<String>,
+    /// Only break the lock if it still has this ID.
+    pub lock_id: Option<String>,
+}
+
+/// Break the lock on a partition's state regardless of holder, recording a
+/// `TfLockForceUnlocked` audit event. Returns the lock that was broken.
+pub async fn force_unlock_tf_state(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    AuditContext(context): AuditContext,
+    Path((enc, part)): Path<(String, String)>,
+    Json(body): Json<ForceUnlockBody>,
+) -> Result<Json<Value>, ApiError> {
+    let key = format!("{}/{}", enc, part);
+    let info = state
+        .store
+        .list_tf_locks()
+        .await?
+        .into_iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, info)| info)
+        .ok_or_else(|| ApiError::not_found(format!("state {} is not locked", key)))?;
+    let lock = TfLockStatus::inspect(state.store.as_ref(), &key, &info).await?;
+    if let Some(expected) = &body.lock_id {
+        if *expected != lock.lock_id {
+            return Err(ApiError::conflict(format!(
+                "state {} is now locked with ID {}, not {}",
+                key, lock.lock_id, expected
+            )));
+        }
+    }
+
+    state.store.unlock_tf_state(&key, &lock.lock_id).await?;
+    observe_lock_released(&info, "force_unlock");
+    state
+        .store
+        .append_event(&AuditEvent::TfLockForceUnlocked {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context,
+            enclave_id: EnclaveId::new(&enc),
+            partition_id: PartitionId::new(&part),
+            lock_id: lock.lock_id.clone(),
+            holder: lock.holder.clone(),
+            by: principal.actor.clone(),
+            note: body.note.filter(|n| !n.trim().is_empty()),
+        })
+        .await?;
+    warn!(key = %key, lock_id = %lock.lock_id, by = %principal.actor, "state lock force-unlocked");
+    Ok(Json(json!(lock)))
+}
+
 // ── IaC run logs ──────────────────────────────────────────────────────────────
 
 pub async fn list_iac_runs(
@@ -530,6 +938,29 @@ pub async fn status(State(state): State<AppState>) -> Result<Json<Value>, ApiErr
     })))
 }
 
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+/// Prometheus metrics. Enclave and partition gauges are computed from the
+/// store on each scrape; everything else is recorded as the work happens.
+pub async fn get_metrics(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let mut enclaves: HashMap<String, f64> = HashMap::new();
+    let mut partitions: HashMap<String, f64> = HashMap::new();
+    for s in state.store.list_enclaves().await? {
+        *enclaves.entry(s.meta.status.to_string()).or_default() += 1.0;
+        for ps in s.partitions.values() {
+            *partitions.entry(ps.meta.status.to_string()).or_default() += 1.0;
+        }
+    }
+    let m = metrics();
+    m.enclaves.replace(enclaves.iter().map(|(status, n)| (vec![status.as_str()], *n)));
+    m.partitions.replace(partitions.iter().map(|(status, n)| (vec![status.as_str()], *n)));
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        m.render(),
+    )
+        .into_response())
+}
+
 // ── Orphans ────────────────────────────────────────────────────────────────────
 
 pub async fn list_orphans(
@@ -537,6 +968,7 @@ pub async fn list_orphans(
 ) -> Result<Json<Value>, ApiError> {
     let enclaves = state.store.list_enclaves().await?;
     let mut all_orphans: Vec<Value> = Vec::new();
+    let mut counts: Vec<(&str, f64)> = Vec::new();
 
     for enc_state in &enclaves {
         let Some(enc_handle) = &enc_state.enclave_handle else { continue };
@@ -552,6 +984,7 @@ pub async fn list_orphans(
             .list_orphaned_resources(&enc_state.desired, enc_handle, &known)
             .await
         {
+            counts.push((enc_state.desired.id.as_str(), orphans.len() as f64));
             for o in orphans {
                 all_orphans.push(json!({
                     "enclave":         enc_state.desired.id.as_str(),
@@ -563,5 +996,117 @@ pub async fn list_orphans(
         }
     }
 
+    metrics().orphaned_resources.replace(counts.into_iter().map(|(enclave, n)| (vec![enclave], n)));
+    metrics().orphan_scan_timestamp.replace([(vec![], Utc::now().timestamp() as f64)]);
+
     Ok(Json(json!({ "orphans": all_orphans })))
 }
+
+// ── Retention ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct PruneQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Apply the server's retention policy now and prune orphaned workspaces.
+/// With `?dry_run=true`, report what would be removed without removing it.
+pub async fn prune(
+    State(state): State<AppState>,
+    Query(q): Query<PruneQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let pruner = Pruner::new(state.store.clone(), state.retention.clone());
+    let report = pruner.run(q.dry_run).await?;
+    Ok(Json(json!(report)))
+}
+
+// ── API tokens ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenBody {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Enclaves the token may act on; empty or omitted means all of them.
+    #[serde(default)]
+    pub enclaves: Vec<EnclaveId>,
+}
+
+/// A token as listed by the API: everything but the hash.
+fn token_json(token: &ApiToken) -> Value {
+    json!({
+        "name": token.name,
+        "scopes": token.scopes,
+        "enclaves": token.enclaves,
+        "created_at": token.created_at,
+        "created_by": token.created_by,
+    })
+}
+
+/// Create a named API token. The response is the only time its secret is shown.
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    AuditContext(context): AuditContext,
+    Json(body): Json<CreateTokenBody>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let name = body.name.trim();
+    if name.is_empty()
+        || name.len() > 64
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(ApiError::bad_request(
+            "token name must be 1-64 characters of letters, digits, '-', '_' or '.'",
+        ));
+    }
+    if name == ADMIN_ACTOR || name.starts_with(STATE_ACTOR_PREFIX) {
+        return Err(ApiError::bad_request(format!("token name '{}' is reserved", name)));
+    }
+    if body.scopes.is_empty() {
+        return Err(ApiError::bad_request("a token needs at least one scope"));
+    }
+
+    let (token, secret) =
+        ApiToken::generate(name, body.scopes, body.enclaves, context.actor.clone());
+    state.store.create_api_token(&token).await?;
+    state
+        .store
+        .append_event(&AuditEvent::ApiTokenCreated {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context,
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            enclaves: token.enclaves.clone(),
+        })
+        .await?;
+
+    let mut response = token_json(&token);
+    response["token"] = json!(secret);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub async fn list_api_tokens(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let tokens = state.store.list_api_tokens().await?;
+    Ok(Json(Value::Array(tokens.iter().map(token_json).collect())))
+}
+
+/// Revoke a named API token. Requests made with it fail from then on.
+pub async fn revoke_api_token(
+    State(state): State<AppState>,
+    AuditContext(context): AuditContext,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.store.delete_api_token(&name).await? {
+        return Err(ApiError::not_found(format!("token '{}' not found", name)));
+    }
+    state
+        .store
+        .append_event(&AuditEvent::ApiTokenRevoked {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context,
+            name,
+        })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -2,7 +2,13 @@ pub mod app;
 pub mod auth;
 pub mod error;
 pub mod handlers;
+pub mod oidc;
+pub mod otel;
+pub mod prune;
+pub mod render;
 pub mod state;
+pub mod tls;
+pub mod webhooks;
 
-pub use app::build_app;
+pub use app::{build_app, AppConfig};
 pub use state::AppState;
@@ -0,0 +1,641 @@
+//! OpenID Connect ID tokens as API credentials.
+//!
+//! CI systems such as GitHub Actions and GitLab mint short-lived JWTs signed
+//! by their issuer. With an `--oidc` file the server accepts them as bearer
+//! tokens: the signature is checked against the issuer's JWKS, `iss`, `aud`,
+//! `exp` and `nbf` are validated, and the token's claims are matched against
+//! the file's roles to decide what it may do.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use nclav_domain::EnclaveId;
+use nclav_store::Scope;
+use ring::signature::{self, UnparsedPublicKey};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Clock skew tolerated on `exp` and `nbf`.
+const LEEWAY_SECS: i64 = 60;
+
+/// How long a JWKS fetched from a URL is used before it is fetched again.
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+
+/// Shortest wait between two fetches of an issuer's JWKS, so tokens with
+/// unknown key IDs can't make the server hammer the issuer.
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+/// Timeout for fetching a JWKS.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prefix of the actor recorded for requests authenticated with a JWT.
+pub const OIDC_ACTOR_PREFIX: &str = "oidc:";
+
+#[derive(Debug, Error)]
+pub enum OidcError {
+    #[error("io error reading {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("yaml parse error in {path}: {source}")]
+    YamlParse {
+        path: String,
+        #[source]
+        source: serde_yaml::Error,
+    },
+
+    #[error("invalid OIDC config in {path}: {message}")]
+    Invalid { path: String, message: String },
+
+    #[error("fetching JWKS of {issuer}: {message}")]
+    Jwks { issuer: String, message: String },
+
+    #[error("invalid token: {0}")]
+    Token(String),
+}
+
+/// Top level of the `--oidc` file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OidcFile {
+    issuers: Vec<IssuerSpec>,
+    #[serde(default)]
+    roles: Vec<RoleSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IssuerSpec {
+    issuer: String,
+    audiences: Vec<String>,
+    jwks_url: Option<String>,
+    /// JWKS read once at startup, for air-gapped servers and tests.
+    jwks_file: Option<String>,
+    #[serde(default = "default_actor_claim")]
+    actor_claim: String,
+}
+
+fn default_actor_claim() -> String {
+    "sub".into()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleSpec {
+    name: String,
+    issuer: Option<String>,
+    #[serde(default)]
+    claims: BTreeMap<String, String>,
+    scopes: Vec<Scope>,
+    #[serde(default)]
+    enclaves: Vec<EnclaveId>,
+}
+
+/// A trusted token issuer.
+#[derive(Debug, Clone)]
+pub struct OidcIssuer {
+    /// Must equal the token's `iss` claim.
+    pub issuer: String,
+    /// The token's `aud` must contain one of these.
+    pub audiences: Vec<String>,
+    pub jwks: JwksSource,
+    /// Claim whose value, prefixed with [`OIDC_ACTOR_PREFIX`], is the actor.
+    pub actor_claim: String,
+}
+
+/// Where an issuer's signing keys come from.
+#[derive(Debug, Clone)]
+pub enum JwksSource {
+    /// Fetched on first use and refreshed hourly, or sooner when a token
+    /// names an unknown key.
+    Url(reqwest::Url),
+    /// Read from a file when the config was loaded.
+    Static(Vec<Jwk>),
+}
+
+/// Grants given to tokens whose claims match.
+#[derive(Debug, Clone)]
+pub struct OidcRole {
+    pub name: String,
+    /// Only match tokens from this issuer; `None` matches any configured issuer.
+    pub issuer: Option<String>,
+    /// Claim name to pattern; `*` matches any run of characters. Every entry
+    /// must match. Array claims match if any element does.
+    pub claims: BTreeMap<String, String>,
+    pub scopes: Vec<Scope>,
+    /// Enclaves the role may act on; empty means all of them.
+    pub enclaves: Vec<EnclaveId>,
+}
+
+impl OidcRole {
+    /// Whether the verified `claims` of a token from `issuer` match this role.
+    pub fn matches(&self, issuer: &str, claims: &Map<String, Value>) -> bool {
+        self.issuer.as_deref().is_none_or(|i| i == issuer)
+            && self.claims.iter().all(|(name, pattern)| match claims.get(name) {
+                Some(Value::Array(values)) => values.iter().any(|v| claim_matches(pattern, v)),
+                Some(value) => claim_matches(pattern, value),
+                None => false,
+            })
+    }
+}
+
+fn claim_matches(pattern: &str, value: &Value) -> bool {
+    match value {
+        Value::String(s) => glob_match(pattern, s),
+        Value::Bool(_) | Value::Number(_) => glob_match(pattern, &value.to_string()),
+        _ => false,
+    }
+}
+
+/// Match `value` against `pattern`, where `*` matches any run of characters.
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let [first, middle @ .., last] = parts.as_slice() else { return pattern == value };
+    if value.len() < first.len() + last.len() || !value.starts_with(first) || !value.ends_with(last) {
+        return false;
+    }
+    let mut rest = &value[first.len()..value.len() - last.len()];
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// A public key from a JWKS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jwk {
+    pub kid: Option<String>,
+    pub key: PublicKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    /// `point` is the uncompressed SEC1 point, `0x04 || x || y`.
+    Ec { crv: String, point: Vec<u8> },
+}
+
+#[derive(Debug, Deserialize)]
+struct RawJwks {
+    keys: Vec<RawJwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawJwk {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    use_: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    crv: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+/// The signature keys of a JWKS document. Encryption keys and key types
+/// nclav can't verify with are skipped.
+fn parse_jwks(body: &str) -> Result<Vec<Jwk>, String> {
+    let raw: RawJwks = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let decode = |field: Option<String>| field.and_then(|f| URL_SAFE_NO_PAD.decode(f).ok());
+    let keys = raw
+        .keys
+        .into_iter()
+        .filter(|k| k.use_.as_deref().is_none_or(|u| u == "sig"))
+        .filter_map(|k| {
+            let key = match k.kty.as_str() {
+                "RSA" => PublicKey::Rsa { n: decode(k.n)?, e: decode(k.e)? },
+                "EC" => {
+                    let mut point = vec![0x04];
+                    point.extend(decode(k.x)?);
+                    point.extend(decode(k.y)?);
+                    PublicKey::Ec { crv: k.crv?, point }
+                }
+                _ => return None,
+            };
+            Some(Jwk { kid: k.kid, key })
+        })
+        .collect();
+    Ok(keys)
+}
+
+/// Whether `sig` is a valid `alg` signature of `message` under `key`.
+fn verify_signature(alg: &str, key: &PublicKey, message: &[u8], sig: &[u8]) -> bool {
+    match (alg, key) {
+        ("RS256" | "RS384" | "RS512", PublicKey::Rsa { n, e }) => {
+            let params = match alg {
+                "RS256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+                "RS384" => &signature::RSA_PKCS1_2048_8192_SHA384,
+                _ => &signature::RSA_PKCS1_2048_8192_SHA512,
+            };
+            signature::RsaPublicKeyComponents { n, e }.verify(params, message, sig).is_ok()
+        }
+        ("ES256", PublicKey::Ec { crv, point }) if crv == "P-256" => {
+            UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                .verify(message, sig)
+                .is_ok()
+        }
+        ("ES384", PublicKey::Ec { crv, point }) if crv == "P-384" => {
+            UnparsedPublicKey::new(&signature::ECDSA_P384_SHA384_FIXED, point)
+                .verify(message, sig)
+                .is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Whether `token` has the shape of a JWT rather than an opaque token.
+pub fn looks_like_jwt(token: &str) -> bool {
+    token.starts_with("eyJ") && token.split('.').count() == 3
+}
+
+/// Read and validate an OIDC config file, loading any `jwks_file`s.
+pub fn load_oidc(path: &Path) -> Result<OidcVerifier, OidcError> {
+    let display = path.display().to_string();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| OidcError::Io { path: display.clone(), source: e })?;
+    let file: OidcFile = serde_yaml::from_str(&content)
+        .map_err(|e| OidcError::YamlParse { path: display.clone(), source: e })?;
+    let invalid = |message: String| OidcError::Invalid { path: display.clone(), message };
+
+    let mut issuers = Vec::new();
+    for spec in file.issuers {
+        if issuers.iter().any(|i: &OidcIssuer| i.issuer == spec.issuer) {
+            return Err(invalid(format!("duplicate issuer '{}'", spec.issuer)));
+        }
+        if spec.audiences.is_empty() {
+            return Err(invalid(format!("issuer '{}': audiences must not be empty", spec.issuer)));
+        }
+        let jwks = match (spec.jwks_url, spec.jwks_file) {
+            (Some(url), None) => JwksSource::Url(
+                reqwest::Url::parse(&url)
+                    .map_err(|e| invalid(format!("issuer '{}': bad jwks_url: {}", spec.issuer, e)))?,
+            ),
+            (None, Some(file)) => {
+                // Relative to the config file, like the paths in enclave YAML.
+                let file = path.parent().unwrap_or(Path::new(".")).join(file);
+                let body = std::fs::read_to_string(&file).map_err(|e| OidcError::Io {
+                    path: file.display().to_string(),
+                    source: e,
+                })?;
+                let keys = parse_jwks(&body).map_err(|e| {
+                    invalid(format!("issuer '{}': bad JWKS in {}: {}", spec.issuer, file.display(), e))
+                })?;
+                JwksSource::Static(keys)
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "issuer '{}': set exactly one of 'jwks_url' and 'jwks_file'",
+                    spec.issuer
+                )))
+            }
+        };
+        issuers.push(OidcIssuer {
+            issuer: spec.issuer,
+            audiences: spec.audiences,
+            jwks,
+            actor_claim: spec.actor_claim,
+        });
+    }
+    if issuers.is_empty() {
+        return Err(invalid("at least one issuer is required".into()));
+    }
+
+    let mut seen = HashSet::new();
+    let mut roles = Vec::new();
+    for spec in file.roles {
+        if !seen.insert(spec.name.clone()) {
+            return Err(invalid(format!("duplicate role name '{}'", spec.name)));
+        }
+        if let Some(issuer) = &spec.issuer {
+            if !issuers.iter().any(|i| &i.issuer == issuer) {
+                return Err(invalid(format!("role '{}': unknown issuer '{}'", spec.name, issuer)));
+            }
+        }
+        if spec.scopes.is_empty() {
+            return Err(invalid(format!("role '{}': scopes must not be empty", spec.name)));
+        }
+        roles.push(OidcRole {
+            name: spec.name,
+            issuer: spec.issuer,
+            claims: spec.claims,
+            scopes: spec.scopes,
+            enclaves: spec.enclaves,
+        });
+    }
+
+    Ok(OidcVerifier::new(issuers, roles))
+}
+
+/// A JWT that passed [`OidcVerifier::verify`].
+#[derive(Debug, Clone)]
+pub struct OidcIdentity {
+    /// [`OIDC_ACTOR_PREFIX`] followed by the issuer's actor claim.
+    pub actor: String,
+    pub issuer: String,
+    /// Roles whose claim patterns the token matched; may be empty.
+    pub roles: Vec<OidcRole>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedJwks {
+    keys: Vec<Jwk>,
+    fetched_at: Instant,
+}
+
+/// Validates JWTs against the configured issuers and maps them to roles.
+pub struct OidcVerifier {
+    issuers: Vec<OidcIssuer>,
+    roles: Vec<OidcRole>,
+    client: reqwest::Client,
+    /// JWKS fetched from `jwks_url`s, by issuer.
+    fetched: Mutex<HashMap<String, CachedJwks>>,
+}
+
+impl OidcVerifier {
+    pub fn new(issuers: Vec<OidcIssuer>, roles: Vec<OidcRole>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build HTTP client");
+        Self { issuers, roles, client, fetched: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn issuers(&self) -> &[OidcIssuer] {
+        &self.issuers
+    }
+
+    pub fn roles(&self) -> &[OidcRole] {
+        &self.roles
+    }
+
+    /// Check `token`'s signature and registered claims, and find its roles.
+    pub async fn verify(&self, token: &str) -> Result<OidcIdentity, OidcError> {
+        let bad = |msg: &str| OidcError::Token(msg.to_string());
+        let mut segments = token.split('.');
+        let (Some(header), Some(payload), Some(sig), None) =
+            (segments.next(), segments.next(), segments.next(), segments.next())
+        else {
+            return Err(bad("not a JWT"));
+        };
+        let decode_json = |segment: &str| -> Result<Map<String, Value>, OidcError> {
+            let bytes = URL_SAFE_NO_PAD.decode(segment).map_err(|_| bad("bad base64"))?;
+            serde_json::from_slice(&bytes).map_err(|_| bad("bad JSON"))
+        };
+        let header = decode_json(header)?;
+        let claims = decode_json(payload)?;
+        let sig = URL_SAFE_NO_PAD.decode(sig).map_err(|_| bad("bad signature encoding"))?;
+
+        let iss = claims.get("iss").and_then(Value::as_str).ok_or_else(|| bad("no iss claim"))?;
+        let issuer = self
+            .issuers
+            .iter()
+            .find(|i| i.issuer == iss)
+            .ok_or_else(|| OidcError::Token(format!("untrusted issuer '{iss}'")))?;
+
+        let aud_ok = match claims.get("aud") {
+            Some(Value::String(aud)) => issuer.audiences.contains(aud),
+            Some(Value::Array(auds)) => auds
+                .iter()
+                .any(|a| a.as_str().is_some_and(|a| issuer.audiences.iter().any(|x| x == a))),
+            _ => false,
+        };
+        if !aud_ok {
+            return Err(bad("audience not accepted"));
+        }
+
+        let now = Utc::now().timestamp();
+        let exp = claims.get("exp").and_then(Value::as_i64).ok_or_else(|| bad("no exp claim"))?;
+        if now >= exp + LEEWAY_SECS {
+            return Err(bad("token expired"));
+        }
+        if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64) {
+            if now + LEEWAY_SECS < nbf {
+                return Err(bad("token not yet valid"));
+            }
+        }
+
+        let alg = header.get("alg").and_then(Value::as_str).unwrap_or_default();
+        let kid = header.get("kid").and_then(Value::as_str);
+        let message = &token.as_bytes()[..token.rfind('.').expect("three segments")];
+        if !self.check_signature(issuer, alg, kid, message, &sig).await? {
+            return Err(bad("signature does not verify"));
+        }
+
+        let subject = claims
+            .get(&issuer.actor_claim)
+            .and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .ok_or_else(|| OidcError::Token(format!("no {} claim", issuer.actor_claim)))?;
+        let roles = self.roles.iter().filter(|r| r.matches(iss, &claims)).cloned().collect();
+        Ok(OidcIdentity {
+            actor: format!("{OIDC_ACTOR_PREFIX}{subject}"),
+            issuer: issuer.issuer.clone(),
+            roles,
+        })
+    }
+
+    /// Verify against the issuer's keys, refetching a URL JWKS once if no
+    /// key verifies and the cached set may be out of date.
+    async fn check_signature(
+        &self,
+        issuer: &OidcIssuer,
+        alg: &str,
+        kid: Option<&str>,
+        message: &[u8],
+        sig: &[u8],
+    ) -> Result<bool, OidcError> {
+        let verifies = |keys: &[Jwk]| {
+            keys.iter()
+                .filter(|k| kid.is_none() || k.kid.as_deref() == kid)
+                .any(|k| verify_signature(alg, &k.key, message, sig))
+        };
+        let url = match &issuer.jwks {
+            JwksSource::Static(keys) => return Ok(verifies(keys)),
+            JwksSource::Url(url) => url,
+        };
+
+        let cached = self.fetched.lock().unwrap().get(&issuer.issuer).cloned();
+        if let Some(cached) = &cached {
+            let age = cached.fetched_at.elapsed();
+            if age < JWKS_TTL && (verifies(&cached.keys) || age < JWKS_MIN_REFRESH) {
+                return Ok(verifies(&cached.keys));
+            }
+        }
+        let keys = self.fetch_jwks(issuer, url).await?;
+        let ok = verifies(&keys);
+        self.fetched
+            .lock()
+            .unwrap()
+            .insert(issuer.issuer.clone(), CachedJwks { keys, fetched_at: Instant::now() });
+        Ok(ok)
+    }
+
+    async fn fetch_jwks(&self, issuer: &OidcIssuer, url: &reqwest::Url) -> Result<Vec<Jwk>, OidcError> {
+        let err = |message: String| OidcError::Jwks { issuer: issuer.issuer.clone(), message };
+        let body = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| err(e.to_string()))?
+            .text()
+            .await
+            .map_err(|e| err(e.to_string()))?;
+        parse_jwks(&body).map_err(err)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use serde_json::json;
+
+    pub const ISSUER: &str = "https://ci.example.com";
+    pub const AUDIENCE: &str = "nclav";
+
+    /// An ES256 issuer whose JWKS is written to a temp dir.
+    pub struct TestIssuer {
+        key: EcdsaKeyPair,
+        rng: SystemRandom,
+        pub dir: tempfile::TempDir,
+    }
+
+    impl TestIssuer {
+        pub fn new() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+            let key =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+            let point = key.public_key().as_ref();
+            let jwks = json!({ "keys": [{
+                "kty": "EC", "crv": "P-256", "kid": "k1", "use": "sig",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+            }]});
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("jwks.json"), jwks.to_string()).unwrap();
+            Self { key, rng, dir }
+        }
+
+        /// Load a config trusting this issuer with the given `roles:` YAML.
+        pub fn verifier(&self, roles: &str) -> OidcVerifier {
+            let config = format!(
+                "issuers:\n  - issuer: {ISSUER}\n    audiences: [{AUDIENCE}]\n    jwks_file: jwks.json\nroles:\n{roles}"
+            );
+            let path = self.dir.path().join("oidc.yaml");
+            std::fs::write(&path, config).unwrap();
+            load_oidc(&path).unwrap()
+        }
+
+        /// A signed token with the standard claims plus `extra`.
+        pub fn sign(&self, extra: Value) -> String {
+            let mut claims = json!({
+                "iss": ISSUER,
+                "aud": AUDIENCE,
+                "sub": "repo:acme/infra:ref:refs/heads/main",
+                "exp": Utc::now().timestamp() + 300,
+            });
+            for (k, v) in extra.as_object().unwrap() {
+                claims[k] = v.clone();
+            }
+            let header = json!({ "alg": "ES256", "kid": "k1", "typ": "JWT" });
+            let signing_input = format!(
+                "{}.{}",
+                URL_SAFE_NO_PAD.encode(header.to_string()),
+                URL_SAFE_NO_PAD.encode(claims.to_string())
+            );
+            let sig = self.key.sign(&self.rng, signing_input.as_bytes()).unwrap();
+            format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(sig.as_ref()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::*;
+    use super::*;
+    use serde_json::json;
+
+    const ROLES: &str = "  - name: infra-main
+    claims: { repository: acme/infra, ref: refs/heads/main }
+    scopes: [apply]
+    enclaves: [prod]
+  - name: any-acme
+    claims: { repository: 'acme/*' }
+    scopes: [read]
+";
+
+    #[tokio::test]
+    async fn valid_token_maps_claims_to_roles() {
+        let issuer = TestIssuer::new();
+        let verifier = issuer.verifier(ROLES);
+        let token = issuer.sign(json!({ "repository": "acme/infra", "ref": "refs/heads/main" }));
+        assert!(looks_like_jwt(&token));
+
+        let identity = verifier.verify(&token).await.unwrap();
+        assert_eq!(identity.actor, "oidc:repo:acme/infra:ref:refs/heads/main");
+        let names: Vec<_> = identity.roles.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["infra-main", "any-acme"]);
+
+        let branch = issuer.sign(json!({ "repository": "acme/infra", "ref": "refs/heads/dev" }));
+        let identity = verifier.verify(&branch).await.unwrap();
+        assert_eq!(identity.roles.len(), 1);
+        assert_eq!(identity.roles[0].name, "any-acme");
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_audience_issuer_expiry_and_signature() {
+        let issuer = TestIssuer::new();
+        let verifier = issuer.verifier(ROLES);
+        let past = Utc::now().timestamp() - 3600;
+        for token in [
+            issuer.sign(json!({ "aud": "someone-else" })),
+            issuer.sign(json!({ "iss": "https://evil.example.com" })),
+            issuer.sign(json!({ "exp": past })),
+            issuer.sign(json!({ "nbf": Utc::now().timestamp() + 3600 })),
+        ] {
+            assert!(verifier.verify(&token).await.is_err(), "accepted {token}");
+        }
+
+        // Swap in another payload under the original signature.
+        let good = issuer.sign(json!({}));
+        let forged = issuer.sign(json!({ "repository": "acme/infra" }));
+        let parts: Vec<&str> = good.split('.').collect();
+        let forged_payload = forged.split('.').nth(1).unwrap();
+        let tampered = format!("{}.{}.{}", parts[0], forged_payload, parts[2]);
+        assert!(verifier.verify(&tampered).await.is_err());
+
+        // A different key with the same kid doesn't verify either.
+        let other = TestIssuer::new();
+        assert!(verifier.verify(&other.sign(json!({}))).await.is_err());
+    }
+
+    #[test]
+    fn glob_patterns_match_runs_of_characters() {
+        assert!(glob_match("refs/heads/*", "refs/heads/main"));
+        assert!(glob_match("acme/*-infra", "acme/team-infra"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("acme/*", "acme"));
+        assert!(!glob_match("refs/tags/v*", "refs/heads/v1"));
+        assert!(glob_match("exact", "exact"));
+    }
+}
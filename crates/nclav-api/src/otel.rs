@@ -0,0 +1,357 @@
+//! OpenTelemetry trace export.
+//!
+//! [`OtlpLayer`] is a `tracing` layer that turns INFO-and-above spans into
+//! OTLP spans and sends them in batches to an OTLP/HTTP collector, JSON
+//! encoded, at `{endpoint}/v1/traces`. It attaches a [`TraceContext`] to every
+//! span it records, which [`nclav_telemetry::trace`] reads back to propagate the
+//! trace into Terraform and cloud API requests. A span with a `traceparent`
+//! field continues that remote trace; an `error` field marks it failed.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::http::Request;
+use nclav_telemetry::TraceContext;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{info_span, warn, Span, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use uuid::Uuid;
+
+/// Spans sent in one export request at most.
+const BATCH_SIZE: usize = 512;
+
+/// How often a partial batch is exported.
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Finished spans waiting for export; further spans are dropped.
+const QUEUE_CAPACITY: usize = 8192;
+
+/// Timeout for a single export request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Span field naming a remote parent.
+const TRACEPARENT_FIELD: &str = "traceparent";
+
+/// Span field marking the span failed, with the error as status message.
+const ERROR_FIELD: &str = "error";
+
+#[derive(Debug, Error)]
+pub enum OtelError {
+    #[error("invalid OTLP endpoint {endpoint}: {message}")]
+    Endpoint { endpoint: String, message: String },
+
+    #[error("invalid OTLP header {0:?}: expected key=value")]
+    Header(String),
+}
+
+/// Where and how to export traces.
+#[derive(Debug, Clone)]
+pub struct OtlpOptions {
+    /// Collector base URL, e.g. `http://localhost:4318`.
+    pub endpoint: String,
+    /// `key=value` headers sent with every export, e.g. for collector auth.
+    pub headers: Vec<String>,
+    /// `service.name` resource attribute.
+    pub service_name: String,
+}
+
+/// A `tracing` layer exporting spans over OTLP/HTTP.
+pub struct OtlpLayer {
+    spans: mpsc::Sender<Value>,
+}
+
+impl OtlpLayer {
+    /// Validate `options` and start the exporter task. Must be called within
+    /// a Tokio runtime.
+    pub fn new(options: OtlpOptions) -> Result<Self, OtelError> {
+        let url = format!("{}/v1/traces", options.endpoint.trim_end_matches('/'));
+        let invalid = |message: String| OtelError::Endpoint { endpoint: options.endpoint.clone(), message };
+        let url = reqwest::Url::parse(&url).map_err(|e| invalid(e.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid("expected an http:// or https:// URL".into()));
+        }
+        let headers = options
+            .headers
+            .iter()
+            .map(|h| match h.split_once('=') {
+                Some((k, v)) if !k.trim().is_empty() => Ok((k.trim().to_string(), v.trim().to_string())),
+                _ => Err(OtelError::Header(h.clone())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let resource = json!({
+            "attributes": [attribute("service.name", json!({ "stringValue": options.service_name }))],
+        });
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(export(rx, url, headers, resource));
+        Ok(Self { spans: tx })
+    }
+}
+
+/// What the layer keeps on a span until it closes.
+struct SpanRecord {
+    parent_span_id: Option<[u8; 8]>,
+    start: SystemTime,
+    attributes: Vec<Value>,
+    error: Option<String>,
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+
+        let remote = fields.traceparent.as_deref().and_then(TraceContext::parse_traceparent);
+        let local = || span.parent().and_then(|p| p.extensions().get::<TraceContext>().copied());
+        let parent = remote.or_else(local);
+        let context = TraceContext {
+            trace_id: parent.map_or_else(|| Uuid::new_v4().into_bytes(), |p| p.trace_id),
+            span_id: new_span_id(),
+        };
+
+        let mut extensions = span.extensions_mut();
+        extensions.insert(context);
+        extensions.insert(SpanRecord {
+            parent_span_id: parent.map(|p| p.span_id),
+            start: SystemTime::now(),
+            attributes: fields.attributes,
+            error: fields.error,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut fields = FieldVisitor::default();
+        values.record(&mut fields);
+        let mut extensions = span.extensions_mut();
+        if let Some(record) = extensions.get_mut::<SpanRecord>() {
+            record.attributes.extend(fields.attributes);
+            record.error = fields.error.or(record.error.take());
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let mut extensions = span.extensions_mut();
+        let (Some(record), Some(&mut context)) =
+            (extensions.remove::<SpanRecord>(), extensions.get_mut::<TraceContext>())
+        else {
+            return;
+        };
+
+        let mut otlp = json!({
+            "traceId": context.trace_id_hex(),
+            "spanId": context.span_id_hex(),
+            "name": span.name(),
+            "kind": 1,
+            "startTimeUnixNano": unix_nanos(record.start),
+            "endTimeUnixNano": unix_nanos(SystemTime::now()),
+            "attributes": record.attributes,
+        });
+        if let Some(parent) = record.parent_span_id {
+            otlp["parentSpanId"] = json!(hex(&parent));
+        }
+        if let Some(message) = record.error {
+            otlp["status"] = json!({ "code": 2, "message": message });
+        }
+        // Dropping spans beats blocking the traced code on a slow collector.
+        let _ = self.spans.try_send(otlp);
+    }
+}
+
+/// Span for one API request, continuing the caller's trace when the request
+/// carries a `traceparent` header.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let method = request.method();
+    let uri = request.uri();
+    match request.headers().get(TRACEPARENT_FIELD).and_then(|v| v.to_str().ok()) {
+        Some(traceparent) => info_span!("request", %method, %uri, traceparent),
+        None => info_span!("request", %method, %uri),
+    }
+}
+
+/// Batch finished spans and POST them to `url` until the layer is dropped.
+async fn export(
+    mut spans: mpsc::Receiver<Value>,
+    url: reqwest::Url,
+    headers: Vec<(String, String)>,
+    resource: Value,
+) {
+    let client = reqwest::Client::new();
+    let mut batch = Vec::new();
+    let mut ticker = tokio::time::interval(EXPORT_INTERVAL);
+    loop {
+        let open = tokio::select! {
+            span = spans.recv() => match span {
+                Some(span) => {
+                    batch.push(span);
+                    if batch.len() < BATCH_SIZE {
+                        continue;
+                    }
+                    true
+                }
+                None => false,
+            },
+            _ = ticker.tick() => true,
+        };
+        if !batch.is_empty() {
+            let body = export_body(&resource, std::mem::take(&mut batch));
+            let mut request = client.post(url.clone()).json(&body).timeout(REQUEST_TIMEOUT);
+            for (key, value) in &headers {
+                request = request.header(key, value);
+            }
+            match request.send().await {
+                Ok(resp) if resp.status().is_success() => {}
+                Ok(resp) => warn!(status = %resp.status(), "OTLP trace export rejected"),
+                Err(e) => warn!(error = %e, "OTLP trace export failed"),
+            }
+        }
+        if !open {
+            return;
+        }
+    }
+}
+
+/// An OTLP `ExportTraceServiceRequest` carrying `spans`.
+fn export_body(resource: &Value, spans: Vec<Value>) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": resource,
+            "scopeSpans": [{ "scope": { "name": "nclav" }, "spans": spans }],
+        }],
+    })
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    attributes: Vec<Value>,
+    traceparent: Option<String>,
+    error: Option<String>,
+}
+
+impl FieldVisitor {
+    fn push(&mut self, field: &Field, value: Value) {
+        self.attributes.push(attribute(field.name(), value));
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            TRACEPARENT_FIELD => self.traceparent = Some(value.to_string()),
+            ERROR_FIELD => self.error = Some(value.to_string()),
+            _ => self.push(field, json!({ "stringValue": value })),
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        // OTLP/JSON encodes 64-bit integers as strings.
+        self.push(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, json!({ "boolValue": value }));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, json!({ "doubleValue": value }));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+fn new_span_id() -> [u8; 8] {
+    let mut id = [0; 8];
+    id.copy_from_slice(&Uuid::new_v4().as_bytes()[..8]);
+    id
+}
+
+fn unix_nanos(at: SystemTime) -> String {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    /// A layer whose finished spans are returned by the receiver instead of
+    /// being exported.
+    pub fn capturing_layer() -> (OtlpLayer, mpsc::Receiver<Value>) {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        (OtlpLayer { spans: tx }, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::capturing_layer;
+    use super::*;
+    use nclav_telemetry::trace;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const REMOTE: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn spans_continue_remote_traces_and_nest() {
+        let (layer, mut spans) = capturing_layer();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let root = info_span!("request", traceparent = REMOTE);
+            root.in_scope(|| {
+                let call = info_span!("driver_call", method = "provision_enclave", error = tracing::field::Empty);
+                call.in_scope(|| {
+                    let current = trace::current().unwrap();
+                    assert_eq!(current.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+                });
+                call.record("error", "quota exceeded");
+            });
+        });
+
+        let call = spans.try_recv().unwrap();
+        let root = spans.try_recv().unwrap();
+        assert_eq!(root["name"], "request");
+        assert_eq!(root["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(root["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(call["traceId"], root["traceId"]);
+        assert_eq!(call["parentSpanId"], root["spanId"]);
+        assert_eq!(call["attributes"][0], json!({ "key": "method", "value": { "stringValue": "provision_enclave" } }));
+        assert_eq!(call["status"], json!({ "code": 2, "message": "quota exceeded" }));
+        assert!(root.get("status").is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_options() {
+        let options = |endpoint: &str, header: &str| OtlpOptions {
+            endpoint: endpoint.into(),
+            headers: vec![header.into()],
+            service_name: "nclav".into(),
+        };
+        assert!(OtlpLayer::new(options("http://localhost:4318", "x-api-key=secret")).is_ok());
+        assert!(matches!(OtlpLayer::new(options("localhost", "a=b")), Err(OtelError::Endpoint { .. })));
+        assert!(matches!(OtlpLayer::new(options("localhost:4318", "a=b")), Err(OtelError::Endpoint { .. })));
+        assert!(matches!(OtlpLayer::new(options("http://localhost:4318", "novalue")), Err(OtelError::Header(_))));
+    }
+}
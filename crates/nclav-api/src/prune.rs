@@ -0,0 +1,180 @@
+//! Retention for the audit log, IaC run logs and Terraform workspaces.
+//!
+//! `nclav serve` runs a [`Pruner`] on an interval; `POST /admin/prune` runs one
+//! pass on demand, optionally as a dry run. Workspaces are pruned regardless of
+//! the [`RetentionPolicy`]: a workspace is orphaned once its partition is gone
+//! from the store and nothing is running in it.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use chrono::Utc;
+use nclav_driver::{list_workspaces, workspaces_dir, Workspace};
+use nclav_store::{prune_store, IacRunStatus, PruneSummary, RetentionPolicy, StateStore, StoreError};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Workspaces modified more recently than this are never pruned. Provisioning
+/// creates the workspace before the partition is stored or its run recorded.
+pub const WORKSPACE_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// What one pruning pass removed, or with `dry_run` would remove.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneReport {
+    #[serde(flatten)]
+    pub store: PruneSummary,
+    /// Orphaned workspace directories.
+    pub workspaces: Vec<PathBuf>,
+}
+
+pub struct Pruner {
+    pub store: Arc<dyn StateStore>,
+    pub policy: RetentionPolicy,
+    /// Root passed to [`workspaces_dir`]; `None` uses `~/.nclav`.
+    pub workspace_root: Option<PathBuf>,
+    pub workspace_grace: Duration,
+}
+
+impl Pruner {
+    pub fn new(store: Arc<dyn StateStore>, policy: RetentionPolicy) -> Self {
+        Self { store, policy, workspace_root: None, workspace_grace: WORKSPACE_GRACE }
+    }
+
+    /// Run one pruning pass.
+    pub async fn run(&self, dry_run: bool) -> Result<PruneReport, StoreError> {
+        let store = prune_store(self.store.as_ref(), &self.policy, Utc::now(), dry_run).await?;
+        let mut workspaces = Vec::new();
+        for ws in self.orphaned_workspaces().await? {
+            if !dry_run {
+                if let Err(e) = tokio::fs::remove_dir_all(&ws.path).await {
+                    warn!(path = %ws.path.display(), error = %e, "failed to remove orphaned workspace");
+                    continue;
+                }
+                // Drop the enclave directory too once its last workspace is gone.
+                if let Some(parent) = ws.path.parent() {
+                    let _ = tokio::fs::remove_dir(parent).await;
+                }
+            }
+            workspaces.push(ws.path);
+        }
+        Ok(PruneReport { store, workspaces })
+    }
+
+    /// Workspaces whose partition is not in the store, with no running IaC run
+    /// and untouched for at least `workspace_grace`.
+    async fn orphaned_workspaces(&self) -> Result<Vec<Workspace>, StoreError> {
+        let dir = workspaces_dir(self.workspace_root.as_deref());
+        let found = match list_workspaces(&dir).await {
+            Ok(found) => found,
+            Err(e) => {
+                warn!(dir = %dir.display(), error = %e, "failed to list workspaces");
+                return Ok(Vec::new());
+            }
+        };
+        if found.is_empty() {
+            return Ok(found);
+        }
+
+        let mut live: HashSet<(String, String)> = HashSet::new();
+        for enclave in self.store.list_enclaves().await? {
+            for partition_id in enclave.partitions.keys() {
+                live.insert((enclave.desired.id.to_string(), partition_id.to_string()));
+            }
+        }
+        for run in self.store.list_iac_run_summaries().await? {
+            if run.status == IacRunStatus::Running {
+                live.insert((run.enclave_id.to_string(), run.partition_id.to_string()));
+            }
+        }
+
+        let now = SystemTime::now();
+        Ok(found
+            .into_iter()
+            .filter(|ws| !live.contains(&(ws.enclave_id.clone(), ws.partition_id.clone())))
+            .filter(|ws| {
+                ws.modified
+                    .and_then(|m| now.duration_since(m).ok())
+                    .is_some_and(|age| age >= self.workspace_grace)
+            })
+            .collect())
+    }
+
+    /// Run a pass every `interval`, starting now, until the runtime shuts down.
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        info!(?interval, policy = ?self.policy, "pruner enabled");
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.run(false).await {
+                    Ok(report) => {
+                        let PruneSummary { events, iac_runs, .. } = report.store;
+                        if events + iac_runs > 0 || !report.workspaces.is_empty() {
+                            info!(events, iac_runs, workspaces = report.workspaces.len(), "pruned");
+                        }
+                    }
+                    Err(e) => warn!(error = %e, "pruning failed"),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nclav_domain::{Enclave, EnclaveId, Partition, PartitionBackend, PartitionId};
+    use nclav_store::{EnclaveState, InMemoryStore, PartitionState};
+    use tempfile::TempDir;
+
+    fn enclave_with_partition(enc: &str, part: &str) -> EnclaveState {
+        let mut state = EnclaveState::new(Enclave {
+            id: EnclaveId::new(enc),
+            name: enc.into(),
+            region: "local".into(),
+            ..Default::default()
+        });
+        let partition = PartitionState::new(Partition {
+            id: PartitionId::new(part),
+            name: part.into(),
+            produces: None,
+            imports: vec![],
+            exports: vec![],
+            inputs: Default::default(),
+            declared_outputs: vec![],
+            backend: PartitionBackend::default(),
+        });
+        state.partitions.insert(PartitionId::new(part), partition);
+        state
+    }
+
+    #[tokio::test]
+    async fn removes_only_workspaces_of_partitions_gone_from_the_store() {
+        let root = TempDir::new().unwrap();
+        let dir = workspaces_dir(Some(root.path()));
+        for ws in ["live/db", "gone/db", "live/old"] {
+            std::fs::create_dir_all(dir.join(ws)).unwrap();
+        }
+        let store = Arc::new(InMemoryStore::new());
+        store.upsert_enclave(&enclave_with_partition("live", "db")).await.unwrap();
+
+        let mut pruner = Pruner::new(store, RetentionPolicy::default());
+        pruner.workspace_root = Some(root.path().to_path_buf());
+
+        // Inside the grace period nothing is touched.
+        assert!(pruner.run(false).await.unwrap().workspaces.is_empty());
+
+        pruner.workspace_grace = Duration::ZERO;
+        let preview = pruner.run(true).await.unwrap();
+        assert_eq!(preview.workspaces, vec![dir.join("gone/db"), dir.join("live/old")]);
+        assert!(dir.join("gone/db").exists());
+
+        pruner.run(false).await.unwrap();
+        assert!(dir.join("live/db").exists());
+        assert!(!dir.join("live/old").exists());
+        assert!(!dir.join("gone").exists());
+    }
+}
@@ -0,0 +1,363 @@
+//! Graph renderers shared by `GET /graph?format=…` and `nclav graph --output …`.
+
+use nclav_store::EnclaveState;
+use serde_json::{json, Value};
+
+/// Render the graph as Graphviz DOT from live store state, nodes coloured by status.
+pub fn render_dot_live(states: &[EnclaveState], filter_enclave: Option<&str>) -> String {
+    let mut out = String::from(
+        "digraph nclav {\n  rankdir=LR;\n  node [shape=box, style=filled];\n\n",
+    );
+
+    for s in states {
+        if let Some(f) = filter_enclave {
+            if s.desired.id.as_str() != f {
+                continue;
+            }
+        }
+        let enc = &s.desired;
+        let cloud_tag = s.resolved_cloud.as_ref().map(|c| format!(" [{}]", c)).unwrap_or_default();
+        out.push_str(&format!(
+            "  subgraph cluster_{} {{\n    label=\"{}{} [{}]\";\n",
+            sanitize(&enc.id.0), enc.name, cloud_tag, s.meta.status,
+        ));
+        for part in &enc.partitions {
+            let pstatus = s.partitions.get(&part.id)
+                .map(|ps| ps.meta.status.to_string())
+                .unwrap_or_else(|| "pending".to_string());
+            out.push_str(&format!(
+                "    \"{}:{}\" [label=\"{} [{}]\", fillcolor=\"{}\"];\n",
+                enc.id.as_str(), part.id.as_str(), part.name, pstatus, status_color(&pstatus),
+            ));
+        }
+        out.push_str("  }\n\n");
+    }
+
+    for s in states {
+        let enc = &s.desired;
+        for import in &enc.imports {
+            out.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                import.from.as_str(), enc.id.as_str(), import.export_name,
+            ));
+        }
+        for part in &enc.partitions {
+            for import in &part.imports {
+                out.push_str(&format!(
+                    "  \"{}\" -> \"{}:{}\" [label=\"{}\"];\n",
+                    import.from.as_str(), enc.id.as_str(), part.id.as_str(), import.export_name,
+                ));
+            }
+        }
+    }
+
+    out.push('}');
+    out
+}
+
+/// Render graph text from live store state, with status and resolved outputs.
+pub fn render_graph_text_live(states: &[EnclaveState], filter_enclave: Option<&str>) -> String {
+    let mut out = String::new();
+    for s in states {
+        if let Some(f) = filter_enclave {
+            if s.desired.id.as_str() != f {
+                continue;
+            }
+        }
+        let enc = &s.desired;
+        let cloud_tag = s.resolved_cloud.as_ref().map(|c| format!(" [{}]", c)).unwrap_or_default();
+        out.push_str(&format!(
+            "Enclave: {}{} ({}) [{}]\n",
+            enc.name, cloud_tag, enc.id, s.meta.status
+        ));
+        if let Some(err) = &s.meta.last_error {
+            out.push_str(&format!("  ! error: {}\n", err.message));
+        }
+        for part in &enc.partitions {
+            let pstatus = s.partitions.get(&part.id)
+                .map(|ps| ps.meta.status.to_string())
+                .unwrap_or_else(|| "pending".to_string());
+            out.push_str(&format!(
+                "  Partition: {} ({}) [{}]\n",
+                part.name, part.id, pstatus
+            ));
+            if let Some(ps) = s.partitions.get(&part.id) {
+                if let Some(err) = &ps.meta.last_error {
+                    out.push_str(&format!("    ! error: {}\n", err.message));
+                }
+                if let Some(p) = &part.produces {
+                    out.push_str(&format!("    produces: {}\n", p));
+                }
+                for (k, v) in &ps.resolved_outputs {
+                    let shown = match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    out.push_str(&format!("    output {}: {}\n", k, shown));
+                }
+            }
+            for imp in &part.imports {
+                out.push_str(&format!(
+                    "    imports: {}.{} as {}\n",
+                    imp.from, imp.export_name, imp.alias
+                ));
+            }
+        }
+        for exp in &enc.exports {
+            out.push_str(&format!(
+                "  Export: {} ({:?}) -> {:?}\n",
+                exp.name, exp.export_type, exp.to
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Render the graph as a Mermaid flowchart, nodes coloured by status.
+/// Paste-able into Markdown on GitHub/GitLab and most wikis.
+pub fn render_mermaid_live(states: &[EnclaveState], filter_enclave: Option<&str>) -> String {
+    let mut out = String::from("flowchart LR\n");
+
+    for s in states {
+        if let Some(f) = filter_enclave {
+            if s.desired.id.as_str() != f {
+                continue;
+            }
+        }
+        let enc = &s.desired;
+        let cloud_tag = s.resolved_cloud.as_ref().map(|c| format!(" [{}]", c)).unwrap_or_default();
+        out.push_str(&format!(
+            "  subgraph enc_{}[\"{}{} [{}]\"]\n",
+            sanitize(&enc.id.0), mermaid_label(&enc.name), cloud_tag, s.meta.status,
+        ));
+        for part in &enc.partitions {
+            let pstatus = s.partitions.get(&part.id)
+                .map(|ps| ps.meta.status.to_string())
+                .unwrap_or_else(|| "pending".to_string());
+            out.push_str(&format!(
+                "    part_{}__{}[\"{} [{}]\"]:::{}\n",
+                sanitize(&enc.id.0), sanitize(&part.id.0), mermaid_label(&part.name), pstatus, pstatus,
+            ));
+        }
+        out.push_str("  end\n");
+    }
+
+    for s in states {
+        let enc = &s.desired;
+        for import in &enc.imports {
+            out.push_str(&format!(
+                "  enc_{} -->|{}| enc_{}\n",
+                sanitize(&import.from.0), mermaid_label(&import.export_name), sanitize(&enc.id.0),
+            ));
+        }
+        for part in &enc.partitions {
+            for import in &part.imports {
+                out.push_str(&format!(
+                    "  enc_{} -->|{}| part_{}__{}\n",
+                    sanitize(&import.from.0), mermaid_label(&import.export_name),
+                    sanitize(&enc.id.0), sanitize(&part.id.0),
+                ));
+            }
+        }
+    }
+
+    for status in STATUSES {
+        out.push_str(&format!("  classDef {} fill:{},stroke:#666\n", status, status_color(status)));
+    }
+    out
+}
+
+/// Render a self-contained HTML page: enclave data is embedded as JSON, with a
+/// search box, collapsible enclaves, and a detail pane showing a clicked
+/// partition's resolved outputs and last error. No external assets.
+pub fn render_html_live(states: &[EnclaveState], filter_enclave: Option<&str>) -> String {
+    let enclaves: Vec<Value> = states
+        .iter()
+        .filter(|s| filter_enclave.is_none_or(|f| s.desired.id.as_str() == f))
+        .map(|s| {
+            let enc = &s.desired;
+            let partitions: Vec<Value> = enc.partitions.iter().map(|part| {
+                let ps = s.partitions.get(&part.id);
+                let pstatus = ps.map(|ps| ps.meta.status.to_string())
+                    .unwrap_or_else(|| "pending".to_string());
+                let imports: Vec<Value> = part.imports.iter()
+                    .map(|i| json!({ "from": i.from, "export": i.export_name, "alias": i.alias }))
+                    .collect();
+                json!({
+                    "id": part.id,
+                    "name": part.name,
+                    "produces": part.produces,
+                    "status": pstatus,
+                    "color": status_color(&pstatus),
+                    "outputs": ps.map(|ps| json!(ps.resolved_outputs)).unwrap_or(json!({})),
+                    "last_error": ps.and_then(|ps| ps.meta.last_error.as_ref()).map(|e| &e.message),
+                    "imports": imports,
+                })
+            }).collect();
+            let imports: Vec<Value> = enc.imports.iter()
+                .map(|i| json!({ "from": i.from, "export": i.export_name, "alias": i.alias }))
+                .collect();
+            let status = s.meta.status.to_string();
+            json!({
+                "id": enc.id,
+                "name": enc.name,
+                "cloud": s.resolved_cloud,
+                "status": status,
+                "color": status_color(&status),
+                "last_error": s.meta.last_error.as_ref().map(|e| &e.message),
+                "partitions": partitions,
+                "imports": imports,
+            })
+        })
+        .collect();
+
+    // `</` inside the JSON would end the <script> element early.
+    let data = Value::Array(enclaves).to_string().replace("</", "<\\/");
+    HTML_TEMPLATE.replace("__NCLAV_DATA__", &data)
+}
+
+const STATUSES: [&str; 8] =
+    ["pending", "provisioning", "active", "updating", "degraded", "error", "deleting", "deleted"];
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>nclav graph</title>
+<style>
+  body { font-family: system-ui, sans-serif; margin: 0; display: flex; height: 100vh; }
+  #main { flex: 1; overflow: auto; padding: 1rem; }
+  #detail { width: 28rem; border-left: 1px solid #ccc; padding: 1rem; overflow: auto; background: #fafafa; }
+  #search { width: 100%; padding: .4rem; margin-bottom: 1rem; box-sizing: border-box; }
+  details { border: 1px solid #ccc; border-radius: 4px; margin-bottom: .6rem; padding: .3rem .6rem; }
+  summary { cursor: pointer; font-weight: 600; padding: .2rem; border-radius: 3px; }
+  .part { display: inline-block; margin: .3rem; padding: .3rem .6rem; border: 1px solid #999;
+          border-radius: 3px; cursor: pointer; }
+  .imports { color: #555; font-size: .85rem; margin: .3rem 0; }
+  .error { color: #b71c1c; white-space: pre-wrap; }
+  pre { background: #fff; border: 1px solid #ddd; padding: .5rem; overflow: auto; }
+</style>
+</head>
+<body>
+<div id="main">
+  <input id="search" type="search" placeholder="Filter enclaves and partitions…">
+  <div id="graph"></div>
+</div>
+<div id="detail"><em>Click a partition to see its outputs and last error.</em></div>
+<script id="nclav-data" type="application/json">__NCLAV_DATA__</script>
+<script>
+const enclaves = JSON.parse(document.getElementById("nclav-data").textContent);
+const graph = document.getElementById("graph");
+const detail = document.getElementById("detail");
+
+function el(tag, props, ...children) {
+  const e = Object.assign(document.createElement(tag), props);
+  for (const c of children) e.append(c);
+  return e;
+}
+
+function show(enc, part) {
+  detail.replaceChildren(
+    el("h3", { textContent: enc.id + "/" + part.id }),
+    el("p", { textContent: "status: " + part.status + (part.produces ? " · produces " + part.produces : "") }),
+    part.last_error ? el("p", { className: "error", textContent: part.last_error }) : "",
+    el("h4", { textContent: "Resolved outputs" }),
+    el("pre", { textContent: JSON.stringify(part.outputs, null, 2) }),
+  );
+}
+
+function render(query) {
+  const q = query.trim().toLowerCase();
+  graph.replaceChildren();
+  for (const enc of enclaves) {
+    const encMatch = !q || enc.id.toLowerCase().includes(q) || enc.name.toLowerCase().includes(q);
+    const parts = enc.partitions.filter(p => encMatch || p.id.toLowerCase().includes(q)
+      || p.name.toLowerCase().includes(q));
+    if (!encMatch && parts.length === 0) continue;
+    const summary = el("summary", {
+      textContent: enc.name + " (" + enc.id + ")" + (enc.cloud ? " [" + enc.cloud + "]" : "")
+        + " [" + enc.status + "]",
+    });
+    summary.style.background = enc.color;
+    const box = el("details", { open: true }, summary);
+    if (enc.last_error) box.append(el("div", { className: "error", textContent: "! " + enc.last_error }));
+    for (const i of enc.imports) {
+      box.append(el("div", { className: "imports", textContent: "imports " + i.from + "." + i.export + " as " + i.alias }));
+    }
+    for (const part of parts) {
+      const node = el("span", { className: "part", textContent: part.name + " [" + part.status + "]" });
+      node.style.background = part.color;
+      node.title = part.imports.map(i => "imports " + i.from + "." + i.export + " as " + i.alias).join("\n");
+      node.onclick = () => show(enc, part);
+      box.append(node);
+    }
+    graph.append(box);
+  }
+}
+
+document.getElementById("search").addEventListener("input", e => render(e.target.value));
+render("");
+</script>
+</body>
+</html>
+"#;
+
+fn mermaid_label(s: &str) -> String {
+    s.replace('"', "#quot;").replace('|', "#124;")
+}
+
+fn sanitize(s: &str) -> String {
+    s.replace(['-', '.'], "_")
+}
+
+fn status_color(status: &str) -> &'static str {
+    match status {
+        "active"                      => "#c8e6c9", // light green
+        "error"                       => "#ffcdd2", // light red
+        "degraded"                    => "#ffe0b2", // light orange
+        "provisioning" | "updating"   => "#fff9c4", // light yellow
+        "deleting" | "deleted"        => "#f5f5f5", // light grey
+        _                             => "#ffffff", // white (pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nclav_domain::{Enclave, EnclaveId, Import};
+
+    fn state(id: &str, name: &str) -> EnclaveState {
+        EnclaveState::new(Enclave {
+            id: EnclaveId::new(id),
+            name: name.into(),
+            region: "local".into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn mermaid_has_subgraphs_edges_and_status_classes() {
+        let mut web = state("web-dev", "Web \"dev\"");
+        web.desired.imports.push(Import {
+            from: EnclaveId::new("api-dev"),
+            export_name: "api-http".into(),
+            alias: "api".into(),
+            import_type: None,
+        });
+        let out = render_mermaid_live(&[state("api-dev", "API"), web], None);
+
+        assert!(out.starts_with("flowchart LR\n"));
+        assert!(out.contains("subgraph enc_web_dev[\"Web #quot;dev#quot; [pending]\"]"), "{out}");
+        assert!(out.contains("enc_api_dev -->|api-http| enc_web_dev"), "{out}");
+        assert!(out.contains("classDef active fill:#c8e6c9"), "{out}");
+    }
+
+    #[test]
+    fn html_embeds_data_without_closing_the_script() {
+        let out = render_html_live(&[state("a", "</script><b>")], None);
+        assert!(!out.contains("__NCLAV_DATA__"));
+        assert!(out.contains(r#""name":"<\/script><b>""#), "data not escaped");
+        assert_eq!(out.matches("</script>").count(), 2);
+    }
+}
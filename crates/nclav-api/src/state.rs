@@ -1,14 +1,33 @@
 use std::sync::Arc;
-use nclav_driver::DriverRegistry;
-use nclav_store::StateStore;
+use nclav_driver::{BackendTls, DriverRegistry};
+use nclav_graph::CidrAllocator;
+use nclav_policy::Policy;
+use nclav_store::{EventBus, RetentionPolicy, StateStore};
+
+use crate::oidc::OidcVerifier;
 
 #[derive(Clone)]
 pub struct AppState {
+    /// Publishes every appended event on `events`.
     pub store: Arc<dyn StateStore>,
+    /// Live audit events, for `GET /events/stream`.
+    pub events: EventBus,
     pub registry: Arc<DriverRegistry>,
     /// Bearer token required on every request.
     pub auth_token: Arc<String>,
     /// Base URL of this API server (e.g. "http://127.0.0.1:8080").
     /// Passed to the reconciler so IaC partitions can configure their TF HTTP backend.
     pub api_base: Arc<String>,
+    /// CA bundle and client certificate IaC subprocesses use to reach `api_base`.
+    pub backend_tls: BackendTls,
+    /// Assigns omitted `vpc_cidr`s during reconcile; `None` leaves them unset.
+    pub vpc_allocator: Option<CidrAllocator>,
+    /// Organization policy checked on every reconcile; `None` disables it.
+    pub policy: Option<Arc<Policy>>,
+    /// Terraform state versions retained per partition.
+    pub tf_state_versions: usize,
+    /// Applied by `POST /admin/prune`.
+    pub retention: RetentionPolicy,
+    /// Accepts OIDC ID tokens as credentials; `None` disables JWT auth.
+    pub oidc: Option<Arc<OidcVerifier>>,
 }
@@ -0,0 +1,354 @@
+//! HTTPS serving for `nclav serve --tls-cert/--tls-key`.
+//!
+//! The certificate and key are reloaded when either file changes, so renewed
+//! certificates are picked up without a restart. With a client CA bundle,
+//! clients may authenticate with a certificate it signed instead of a bearer
+//! token; requests without a client certificate still need a token.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::extract::Request;
+use axum::Router;
+use der::asn1::{AnyRef, ObjectIdentifier};
+use der::{Reader, SliceReader, Tag, Tagged};
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use nclav_store::Scope;
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::{RootCertStore, ServerConfig};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+use crate::auth::{Grant, Principal};
+
+/// How often the certificate and key files are checked for changes.
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Prefix of the actor recorded for requests authenticated by a client
+/// certificate, followed by the certificate's subject common name.
+pub const CLIENT_CERT_ACTOR_PREFIX: &str = "cert:";
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("io error reading {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid PEM in {path}: {message}")]
+    Pem { path: String, message: String },
+
+    #[error("TLS configuration error: {0}")]
+    Config(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsOptions {
+    /// PEM certificate chain, leaf first.
+    pub cert: PathBuf,
+    /// PEM private key of the leaf certificate.
+    pub key: PathBuf,
+    /// Verify client certificates against this PEM CA bundle. Clients that
+    /// present none must still send a bearer token.
+    pub client_ca: Option<PathBuf>,
+    /// Scopes granted to requests authenticated by a client certificate.
+    pub client_scopes: Vec<Scope>,
+}
+
+/// The principal of a request whose client certificate was verified during
+/// the handshake. Used by the auth middleware when no token is sent.
+#[derive(Debug, Clone)]
+pub struct ClientCertificate(pub Principal);
+
+/// The server certificate, swapped when its files change.
+#[derive(Debug)]
+pub struct ReloadingCert {
+    cert: PathBuf,
+    key: PathBuf,
+    current: RwLock<(Arc<CertifiedKey>, Option<[SystemTime; 2]>)>,
+}
+
+impl ReloadingCert {
+    pub fn load(cert: &Path, key: &Path) -> Result<Self, TlsError> {
+        let stamp = modified(cert, key);
+        let certified = load_certified_key(cert, key)?;
+        Ok(Self {
+            cert: cert.to_path_buf(),
+            key: key.to_path_buf(),
+            current: RwLock::new((Arc::new(certified), stamp)),
+        })
+    }
+
+    /// Reload the certificate if either file changed since it was last
+    /// loaded. On error the previous certificate stays in use.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let stamp = modified(&self.cert, &self.key);
+        if stamp.is_none() || stamp == self.current.read().unwrap().1 {
+            return Ok(false);
+        }
+        let certified = load_certified_key(&self.cert, &self.key)?;
+        *self.current.write().unwrap() = (Arc::new(certified), stamp);
+        Ok(true)
+    }
+
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        self.current.read().unwrap().0.clone()
+    }
+}
+
+impl ResolvesServerCert for ReloadingCert {
+    fn resolve(&self, _hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+fn modified(cert: &Path, key: &Path) -> Option<[SystemTime; 2]> {
+    let mtime = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    Some([mtime(cert)?, mtime(key)?])
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let display = path.display().to_string();
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| pem_error(path, e))?;
+    if certs.is_empty() {
+        return Err(TlsError::Pem { path: display, message: "no certificates found".into() });
+    }
+    Ok(certs)
+}
+
+fn pem_error(path: &Path, e: rustls_pki_types::pem::Error) -> TlsError {
+    let path = path.display().to_string();
+    match e {
+        rustls_pki_types::pem::Error::Io(source) => TlsError::Io { path, source },
+        other => TlsError::Pem { path, message: other.to_string() },
+    }
+}
+
+fn load_certified_key(cert: &Path, key: &Path) -> Result<CertifiedKey, TlsError> {
+    let certs = read_certs(cert)?;
+    let key_der = PrivateKeyDer::from_pem_file(key).map_err(|e| pem_error(key, e))?;
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(&key_der)
+        .map_err(|e| TlsError::Pem { path: key.display().to_string(), message: e.to_string() })?;
+    let certified = CertifiedKey::new(certs, signing_key);
+    certified.keys_match().map_err(|e| {
+        TlsError::Config(format!("{} does not match {}: {}", key.display(), cert.display(), e))
+    })?;
+    Ok(certified)
+}
+
+/// A rustls server config serving `cert` and, with a client CA, asking
+/// clients for a certificate.
+pub fn server_config(
+    options: &TlsOptions,
+    cert: Arc<ReloadingCert>,
+) -> Result<Arc<ServerConfig>, TlsError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| TlsError::Config(e.to_string()))?;
+    let builder = match &options.client_ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            for ca_cert in read_certs(ca)? {
+                roots.add(ca_cert).map_err(|e| TlsError::Pem {
+                    path: ca.display().to_string(),
+                    message: e.to_string(),
+                })?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .allow_unauthenticated()
+                .build()
+                .map_err(|e| TlsError::Config(e.to_string()))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder.with_cert_resolver(cert);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// The principal of a verified client certificate: `cert:<common name>`, or
+/// `cert:sha256:<fingerprint prefix>` when the subject has no common name.
+pub fn client_principal(cert: &CertificateDer<'_>, scopes: &[Scope]) -> Principal {
+    let name = subject_common_name(cert).unwrap_or_else(|| {
+        let digest: String =
+            Sha256::digest(cert.as_ref()).iter().take(8).map(|b| format!("{:02x}", b)).collect();
+        format!("sha256:{digest}")
+    });
+    Principal {
+        actor: format!("{CLIENT_CERT_ACTOR_PREFIX}{name}"),
+        grant: Grant::Token { scopes: scopes.to_vec(), enclaves: Vec::new() },
+    }
+}
+
+/// The subject common name of a certificate, if it has one.
+fn subject_common_name(cert: &CertificateDer<'_>) -> Option<String> {
+    const COMMON_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+    let cert = webpki::EndEntityCert::try_from(cert).ok()?;
+    // Name ::= SEQUENCE OF SET OF SEQUENCE { type OID, value }; webpki hands
+    // back the contents of the outer SEQUENCE.
+    let mut rdns = SliceReader::new(cert.subject()).ok()?;
+    while !rdns.is_finished() {
+        let rdn: AnyRef<'_> = rdns.decode().ok()?;
+        rdn.tag().assert_eq(Tag::Set).ok()?;
+        let mut attributes = SliceReader::new(rdn.value()).ok()?;
+        while !attributes.is_finished() {
+            let (oid, value) = attributes
+                .decode::<AnyRef<'_>>()
+                .and_then(|attribute| {
+                    attribute.sequence(|a| Ok((a.decode::<ObjectIdentifier>()?, a.decode::<AnyRef<'_>>()?)))
+                })
+                .ok()?;
+            if oid == COMMON_NAME {
+                return match value.tag() {
+                    Tag::Utf8String | Tag::PrintableString | Tag::Ia5String => {
+                        std::str::from_utf8(value.value()).ok().map(str::to_string)
+                    }
+                    _ => None,
+                };
+            }
+        }
+    }
+    None
+}
+
+/// Serve `app` over TLS on `listener`, reloading the certificate every
+/// [`RELOAD_INTERVAL`] when its files change. Runs until the process exits.
+pub async fn serve_tls(listener: TcpListener, app: Router, options: TlsOptions) -> Result<(), TlsError> {
+    let cert = Arc::new(ReloadingCert::load(&options.cert, &options.key)?);
+    let acceptor = TlsAcceptor::from(server_config(&options, cert.clone())?);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match cert.reload_if_changed() {
+                Ok(true) => info!(cert = %cert.cert.display(), "reloaded TLS certificate"),
+                Ok(false) => {}
+                Err(e) => warn!(error = %e, "failed to reload TLS certificate; keeping the previous one"),
+            }
+        }
+    });
+
+    loop {
+        let (tcp, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // Usually out of file descriptors; back off rather than spin.
+                warn!(error = %e, "failed to accept connection");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        let client_scopes = options.client_scopes.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(tcp).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    debug!(%peer, error = %e, "TLS handshake failed");
+                    return;
+                }
+            };
+            let client = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| ClientCertificate(client_principal(cert, &client_scopes)));
+            let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+                if let Some(client) = &client {
+                    request.extensions_mut().insert(client.clone());
+                }
+                app.clone().oneshot(request)
+            });
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(%peer, error = %e, "connection closed with error");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls").join(name)
+    }
+
+    #[test]
+    fn client_principal_is_named_after_the_subject_common_name() {
+        let cert = read_certs(&fixture("client.pem")).unwrap().remove(0);
+        let principal = client_principal(&cert, &[Scope::Apply]);
+        assert_eq!(principal.actor, "cert:ci-runner");
+        assert_eq!(principal.grant, Grant::Token { scopes: vec![Scope::Apply], enclaves: vec![] });
+    }
+
+    #[test]
+    fn client_principal_falls_back_to_the_fingerprint() {
+        let cert = read_certs(&fixture("no-cn.pem")).unwrap().remove(0);
+        let digest: String = Sha256::digest(cert.as_ref()).iter().take(8).map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(client_principal(&cert, &[]).actor, format!("cert:sha256:{digest}"));
+    }
+
+    #[test]
+    fn malformed_certificates_have_no_common_name() {
+        let der = read_certs(&fixture("client.pem")).unwrap().remove(0).as_ref().to_vec();
+        assert_eq!(subject_common_name(&CertificateDer::from(der.clone())).as_deref(), Some("ci-runner"));
+
+        let truncated = der[..der.len() / 2].to_vec();
+        let mut trailing = der.clone();
+        trailing.push(0);
+        let mut wrong_tag = der.clone();
+        wrong_tag[0] = 0x31;
+        // A long-form length claiming far more bytes than the input holds.
+        let overlong = vec![0x30, 0x84, 0xff, 0xff, 0xff, 0xff, 0x30, 0x00];
+        for bad in [vec![], vec![0x30], truncated, trailing, wrong_tag, overlong] {
+            assert_eq!(subject_common_name(&CertificateDer::from(bad.clone())), None, "{bad:02x?}");
+        }
+    }
+
+    #[test]
+    fn certificate_is_reloaded_when_its_files_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = (dir.path().join("tls.pem"), dir.path().join("tls.key"));
+        std::fs::copy(fixture("server.pem"), &cert).unwrap();
+        std::fs::copy(fixture("server.key"), &key).unwrap();
+        let reloading = ReloadingCert::load(&cert, &key).unwrap();
+        let before = reloading.current();
+        assert!(!reloading.reload_if_changed().unwrap());
+
+        // A mismatched pair is refused and the old certificate kept.
+        std::fs::copy(fixture("client.pem"), &cert).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options().write(true).open(&cert).unwrap().set_modified(later).unwrap();
+        assert!(reloading.reload_if_changed().is_err());
+        assert_eq!(reloading.current().cert, before.cert);
+
+        std::fs::copy(fixture("client.key"), &key).unwrap();
+        std::fs::File::options().write(true).open(&key).unwrap().set_modified(later).unwrap();
+        assert!(reloading.reload_if_changed().unwrap());
+        assert_ne!(reloading.current().cert, before.cert);
+    }
+}
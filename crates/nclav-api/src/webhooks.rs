@@ -0,0 +1,554 @@
+//! Outbound webhooks for audit events.
+//!
+//! Each sink in the `--webhooks` file gets its own subscriber on the
+//! [`EventBus`], so a slow or failing endpoint never holds up the others.
+//! Events are POSTed one at a time, in order, as CloudEvents 1.0 structured
+//! JSON signed with HMAC-SHA256. A delivery that still fails after
+//! `max_attempts` is recorded as a [`DeadLetter`] in the store. A sink that
+//! falls more than the bus capacity behind replays the events it missed from
+//! the store before carrying on.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use nclav_domain::EnclaveId;
+use nclav_store::{AuditEvent, DeadLetter, EventBus, EventQuery, StateStore};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body>`.
+pub const SIGNATURE_HEADER: &str = "x-nclav-signature";
+
+/// Content type of a structured-mode CloudEvent.
+pub const CLOUDEVENTS_CONTENT_TYPE: &str = "application/cloudevents+json";
+
+/// Prefix of the CloudEvents `type` attribute; the event kind is appended.
+pub const EVENT_TYPE_PREFIX: &str = "dev.nclav.audit.";
+
+/// Longest wait between two attempts, however many have failed.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Timeout for a single delivery attempt.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How far past either end of a gap a replay looks for missed events, since
+/// events are not always recorded in timestamp order.
+const REPLAY_SLACK: TimeDelta = TimeDelta::seconds(5);
+
+/// Events per store query while replaying.
+const REPLAY_PAGE: u32 = 500;
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("io error reading {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("yaml parse error in {path}: {source}")]
+    YamlParse {
+        path: String,
+        #[source]
+        source: serde_yaml::Error,
+    },
+
+    #[error("invalid webhooks in {path}: {message}")]
+    Invalid { path: String, message: String },
+}
+
+/// Top level of the `--webhooks` file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookFile {
+    webhooks: Vec<WebhookSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookSpec {
+    name: String,
+    url: String,
+    /// Signing secret inline. Prefer `secret_env`.
+    secret: Option<String>,
+    /// Environment variable holding the signing secret.
+    secret_env: Option<String>,
+    #[serde(default)]
+    kinds: Vec<String>,
+    #[serde(default)]
+    enclaves: Vec<EnclaveId>,
+    #[serde(default = "default_max_attempts")]
+    max_attempts: u32,
+    #[serde(default = "default_backoff_ms")]
+    backoff_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_backoff_ms() -> u64 {
+    1000
+}
+
+/// A configured webhook sink.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub name: String,
+    pub url: reqwest::Url,
+    pub secret: String,
+    /// Event kinds to deliver; empty means all.
+    pub kinds: Vec<String>,
+    /// Enclaves to deliver events for; empty means all. Events that belong to
+    /// no enclave (e.g. `ReconcileStarted`) only go to sinks without this filter.
+    pub enclaves: Vec<EnclaveId>,
+    /// Attempts per event before it is dead-lettered.
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled after each failed attempt.
+    pub backoff: Duration,
+}
+
+impl Webhook {
+    /// Whether `event` passes this sink's kind and enclave filters.
+    pub fn accepts(&self, event: &AuditEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.iter().any(|k| k == event.kind()))
+            && (self.enclaves.is_empty()
+                || event.enclave_id().is_some_and(|id| self.enclaves.contains(id)))
+    }
+}
+
+/// Read and validate a webhooks file, resolving `secret_env` secrets.
+pub fn load_webhooks(path: &Path) -> Result<Vec<Webhook>, WebhookError> {
+    let display = path.display().to_string();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| WebhookError::Io { path: display.clone(), source: e })?;
+    let file: WebhookFile = serde_yaml::from_str(&content)
+        .map_err(|e| WebhookError::YamlParse { path: display.clone(), source: e })?;
+    let invalid = |message: String| WebhookError::Invalid { path: display.clone(), message };
+
+    let mut seen = HashSet::new();
+    let mut webhooks = Vec::new();
+    for spec in file.webhooks {
+        if !seen.insert(spec.name.clone()) {
+            return Err(invalid(format!("duplicate webhook name '{}'", spec.name)));
+        }
+        let url = reqwest::Url::parse(&spec.url)
+            .map_err(|e| invalid(format!("webhook '{}': bad url: {}", spec.name, e)))?;
+        let secret = match (spec.secret, spec.secret_env) {
+            (Some(secret), None) => secret,
+            (None, Some(var)) => std::env::var(&var).map_err(|_| {
+                invalid(format!("webhook '{}': environment variable {} is not set", spec.name, var))
+            })?,
+            _ => {
+                return Err(invalid(format!(
+                    "webhook '{}': set exactly one of 'secret' and 'secret_env'",
+                    spec.name
+                )))
+            }
+        };
+        if secret.is_empty() {
+            return Err(invalid(format!("webhook '{}': secret is empty", spec.name)));
+        }
+        if spec.max_attempts == 0 {
+            return Err(invalid(format!("webhook '{}': max_attempts must be at least 1", spec.name)));
+        }
+        webhooks.push(Webhook {
+            name: spec.name,
+            url,
+            secret,
+            kinds: spec.kinds,
+            enclaves: spec.enclaves,
+            max_attempts: spec.max_attempts,
+            backoff: Duration::from_millis(spec.backoff_ms),
+        });
+    }
+    Ok(webhooks)
+}
+
+/// Wrap `event` in a CloudEvents 1.0 structured-mode envelope. `source` is
+/// the URL of this server.
+pub fn cloud_event(source: &str, event: &AuditEvent) -> Value {
+    let subject = match (event.enclave_id(), event.partition_id()) {
+        (Some(e), Some(p)) => Some(format!("{}/{}", e, p)),
+        (Some(e), None) => Some(e.to_string()),
+        _ => None,
+    };
+    let mut envelope = json!({
+        "specversion": "1.0",
+        "id": event.id(),
+        "source": source,
+        "type": format!("{}{}", EVENT_TYPE_PREFIX, event.kind()),
+        "time": event.at(),
+        "datacontenttype": "application/json",
+        "data": event,
+    });
+    if let Some(subject) = subject {
+        envelope["subject"] = json!(subject);
+    }
+    envelope
+}
+
+/// `sha256=<hex>` HMAC of `body` under `secret`, as sent in [`SIGNATURE_HEADER`].
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", digest)
+}
+
+/// Start one delivery task per webhook. Each runs until the bus is dropped.
+pub fn spawn_webhooks(
+    webhooks: Vec<Webhook>,
+    bus: &EventBus,
+    store: Arc<dyn StateStore>,
+    source: String,
+) -> Vec<JoinHandle<()>> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("failed to build HTTP client");
+    webhooks
+        .into_iter()
+        .map(|hook| {
+            let mut rx = bus.subscribe();
+            let client = client.clone();
+            let store = store.clone();
+            let source = source.clone();
+            info!(webhook = %hook.name, url = %hook.url, "webhook sink enabled");
+            tokio::spawn(async move {
+                let mut seen = Seen::default();
+                let started = Utc::now();
+                // Set while events have been skipped: the newest event time handled before the gap.
+                let mut gap_after: Option<DateTime<Utc>> = None;
+                loop {
+                    match rx.recv().await {
+                        Ok(event) => {
+                            if let Some(after) = gap_after.take() {
+                                let sink = Sink { client: &client, hook: &hook, store: store.as_ref(), source: &source };
+                                sink.replay(&mut seen, after, event.at()).await;
+                            }
+                            if seen.insert(&event) && hook.accepts(&event) {
+                                deliver(&client, &hook, store.as_ref(), &source, &event).await;
+                            }
+                        }
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!(webhook = %hook.name, missed, "webhook fell behind; replaying missed events from the store");
+                            gap_after.get_or_insert(seen.newest.unwrap_or(started));
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// Events a sink has already handled, kept for twice [`REPLAY_SLACK`] behind
+/// the newest so that neither a replay nor the bus hands one over twice.
+#[derive(Default)]
+struct Seen {
+    recent: VecDeque<(DateTime<Utc>, Uuid)>,
+    newest: Option<DateTime<Utc>>,
+}
+
+impl Seen {
+    /// Record `event`; `false` if it was handled already.
+    fn insert(&mut self, event: &AuditEvent) -> bool {
+        if self.recent.iter().any(|(_, id)| *id == event.id()) {
+            return false;
+        }
+        let newest = self.newest.map_or(event.at(), |n| n.max(event.at()));
+        self.newest = Some(newest);
+        self.recent.push_back((event.at(), event.id()));
+        while self.recent.front().is_some_and(|(at, _)| *at < newest - REPLAY_SLACK * 2) {
+            self.recent.pop_front();
+        }
+        true
+    }
+}
+
+/// What a replay needs to deliver events.
+struct Sink<'a> {
+    client: &'a reqwest::Client,
+    hook: &'a Webhook,
+    store: &'a dyn StateStore,
+    source: &'a str,
+}
+
+impl Sink<'_> {
+    /// Deliver, in the order they were recorded, the stored events between
+    /// `after` and `until` that this sink has not handled yet.
+    async fn replay(&self, seen: &mut Seen, after: DateTime<Utc>, until: DateTime<Utc>) {
+        let mut query = EventQuery {
+            since: Some(after - REPLAY_SLACK),
+            until: Some(until + REPLAY_SLACK),
+            limit: REPLAY_PAGE,
+            ..Default::default()
+        };
+        // Pages run backwards in time; collect them all, then go forwards.
+        let mut pages = Vec::new();
+        loop {
+            match self.store.query_events(&query).await {
+                Ok(page) => {
+                    pages.push(page.events);
+                    match page.next_cursor {
+                        Some(cursor) => query.before = Some(cursor),
+                        None => break,
+                    }
+                }
+                Err(e) => {
+                    warn!(webhook = %self.hook.name, error = %e, "failed to read missed events; some were not delivered");
+                    break;
+                }
+            }
+        }
+        for event in pages.into_iter().rev().flatten() {
+            if seen.insert(&event) && self.hook.accepts(&event) {
+                deliver(self.client, self.hook, self.store, self.source, &event).await;
+            }
+        }
+    }
+}
+
+/// POST `event` to `hook`, retrying with exponential backoff. Records a dead
+/// letter once the attempts are used up.
+async fn deliver(
+    client: &reqwest::Client,
+    hook: &Webhook,
+    store: &dyn StateStore,
+    source: &str,
+    event: &AuditEvent,
+) {
+    let body = serde_json::to_vec(&cloud_event(source, event)).expect("audit events serialize");
+    let signature = sign(&hook.secret, &body);
+    let mut backoff = hook.backoff;
+    let mut last_error = String::new();
+    for attempt in 1..=hook.max_attempts {
+        let result = client
+            .post(hook.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, CLOUDEVENTS_CONTENT_TYPE)
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) => last_error = format!("HTTP {}", resp.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < hook.max_attempts {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    warn!(webhook = %hook.name, event_id = %event.id(), error = %last_error, "webhook delivery failed; dead-lettered");
+    let letter = DeadLetter {
+        id: Uuid::new_v4(),
+        webhook: hook.name.clone(),
+        url: hook.url.to_string(),
+        event: event.clone(),
+        attempts: hook.max_attempts,
+        last_error,
+        failed_at: Utc::now(),
+    };
+    if let Err(e) = store.append_dead_letter(&letter).await {
+        warn!(webhook = %hook.name, error = %e, "failed to record webhook dead letter");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use nclav_domain::PartitionId;
+    use nclav_store::{BroadcastStore, EventContext, InMemoryStore};
+
+    /// Headers and body of one delivered request.
+    type Delivery = (HeaderMap, Vec<u8>);
+
+    /// Local receiver that fails the first `fail_first` requests.
+    #[derive(Clone, Default)]
+    struct Receiver {
+        fail_first: usize,
+        calls: Arc<AtomicUsize>,
+        received: Arc<Mutex<Vec<Delivery>>>,
+    }
+
+    async fn receive(State(rx): State<Receiver>, headers: HeaderMap, body: axum::body::Bytes) -> StatusCode {
+        if rx.calls.fetch_add(1, Ordering::SeqCst) < rx.fail_first {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        rx.received.lock().unwrap().push((headers, body.to_vec()));
+        StatusCode::NO_CONTENT
+    }
+
+    async fn start_receiver(rx: Receiver) -> reqwest::Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/hook", post(receive)).with_state(rx);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        reqwest::Url::parse(&format!("http://{addr}/hook")).unwrap()
+    }
+
+    fn webhook(url: reqwest::Url, kinds: &[&str]) -> Webhook {
+        Webhook {
+            name: "chat".into(),
+            url,
+            secret: "s3cret".into(),
+            kinds: kinds.iter().map(|k| k.to_string()).collect(),
+            enclaves: vec![],
+            max_attempts: 3,
+            backoff: Duration::from_millis(5),
+        }
+    }
+
+    fn partition_error(enclave: &str) -> AuditEvent {
+        AuditEvent::PartitionError {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: EventContext::controller(),
+            enclave_id: EnclaveId::new(enclave),
+            partition_id: PartitionId::new("db"),
+            message: "boom".into(),
+        }
+    }
+
+    async fn wait_for(mut done: impl FnMut() -> bool) {
+        for _ in 0..200 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("timed out waiting for webhook delivery");
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_cloud_events_after_retrying() {
+        let rx = Receiver { fail_first: 2, ..Default::default() };
+        let url = start_receiver(rx.clone()).await;
+        let bus = EventBus::default();
+        let store = Arc::new(BroadcastStore::new(Arc::new(InMemoryStore::new()), bus.clone()));
+        spawn_webhooks(vec![webhook(url, &["PartitionError"])], &bus, store.clone(), "http://nclav".into());
+
+        let event = partition_error("enc");
+        store.append_event(&AuditEvent::EnclaveDeleted {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: EventContext::controller(),
+            enclave_id: EnclaveId::new("enc"),
+        })
+        .await
+        .unwrap();
+        store.append_event(&event).await.unwrap();
+
+        wait_for(|| !rx.received.lock().unwrap().is_empty()).await;
+        assert_eq!(rx.calls.load(Ordering::SeqCst), 3);
+        let (headers, body) = rx.received.lock().unwrap()[0].clone();
+        assert_eq!(headers[reqwest::header::CONTENT_TYPE.as_str()], CLOUDEVENTS_CONTENT_TYPE);
+        assert_eq!(headers[SIGNATURE_HEADER].to_str().unwrap(), sign("s3cret", &body));
+
+        let envelope: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(envelope["specversion"], "1.0");
+        assert_eq!(envelope["type"], "dev.nclav.audit.PartitionError");
+        assert_eq!(envelope["id"], event.id().to_string());
+        assert_eq!(envelope["subject"], "enc/db");
+        assert_eq!(envelope["data"]["message"], "boom");
+        assert!(store.list_dead_letters(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_are_dead_lettered() {
+        let rx = Receiver { fail_first: usize::MAX, ..Default::default() };
+        let url = start_receiver(rx.clone()).await;
+        let bus = EventBus::default();
+        let store = Arc::new(BroadcastStore::new(Arc::new(InMemoryStore::new()), bus.clone()));
+        let mut hook = webhook(url, &[]);
+        hook.enclaves = vec![EnclaveId::new("prod")];
+        spawn_webhooks(vec![hook], &bus, store.clone(), "http://nclav".into());
+
+        store.append_event(&partition_error("dev")).await.unwrap();
+        store.append_event(&partition_error("prod")).await.unwrap();
+
+        for _ in 0..200 {
+            if !store.list_dead_letters(10).await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let letters = store.list_dead_letters(10).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].webhook, "chat");
+        assert_eq!(letters[0].attempts, 3);
+        assert_eq!(letters[0].last_error, "HTTP 503 Service Unavailable");
+        assert_eq!(letters[0].event.enclave_id(), Some(&EnclaveId::new("prod")));
+        // The dev event was filtered out, not attempted.
+        assert_eq!(rx.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn events_missed_while_lagging_are_replayed_from_the_store() {
+        let rx = Receiver::default();
+        let url = start_receiver(rx.clone()).await;
+        let bus = EventBus::new(2);
+        let store = Arc::new(BroadcastStore::new(Arc::new(InMemoryStore::new()), bus.clone()));
+        spawn_webhooks(vec![webhook(url, &[])], &bus, store.clone(), "http://nclav".into());
+
+        // Published before the sink task first runs, so all but two overflow the bus.
+        let events: Vec<AuditEvent> = (0..10).map(|i| partition_error(&format!("enc-{i}"))).collect();
+        for event in &events {
+            store.append_event(event).await.unwrap();
+        }
+
+        wait_for(|| rx.received.lock().unwrap().len() >= events.len()).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let delivered: Vec<String> = rx
+            .received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_slice::<Value>(body).unwrap()["id"].as_str().unwrap().to_string())
+            .collect();
+        let expected: Vec<String> = events.iter().map(|e| e.id().to_string()).collect();
+        assert_eq!(delivered, expected);
+        assert!(store.list_dead_letters(10).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn load_rejects_missing_secret() {
+        let dir = std::env::temp_dir().join(format!("nclav-webhooks-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("webhooks.yml");
+        std::fs::write(&path, "webhooks:\n  - name: chat\n    url: https://hooks.example.com/x\n").unwrap();
+        let err = load_webhooks(&path).unwrap_err().to_string();
+        assert!(err.contains("exactly one of 'secret' and 'secret_env'"), "{err}");
+
+        std::fs::write(
+            &path,
+            "webhooks:\n  - name: chat\n    url: https://hooks.example.com/x\n    secret: abc\n    kinds: [PartitionError]\n",
+        )
+        .unwrap();
+        let hooks = load_webhooks(&path).unwrap();
+        assert_eq!(hooks[0].kinds, vec!["PartitionError".to_string()]);
+        assert_eq!(hooks[0].max_attempts, 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use nclav_api::{build_app, AppConfig};
+use nclav_api::tls::{serve_tls, TlsOptions};
+use nclav_domain::CloudTarget;
+use nclav_driver::{DriverRegistry, LocalDriver};
+use nclav_store::{EventBus, InMemoryStore, Scope};
+use reqwest::StatusCode;
+
+const TOKEN: &str = "test-token";
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls").join(name)
+}
+
+/// Start an HTTPS server asking for client certificates; returns its base URL.
+async fn start_server() -> String {
+    let mut registry = DriverRegistry::new(CloudTarget::Local);
+    registry.register(CloudTarget::Local, Arc::new(LocalDriver::new()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("https://localhost:{}", listener.local_addr().unwrap().port());
+    let app = build_app(
+        Arc::new(InMemoryStore::new()),
+        EventBus::default(),
+        AppConfig::new(Arc::new(registry), Arc::new(TOKEN.to_string()), base.clone()),
+    );
+    let options = TlsOptions {
+        cert: fixture("server.pem"),
+        key: fixture("server.key"),
+        client_ca: Some(fixture("ca.pem")),
+        client_scopes: vec![Scope::Apply],
+    };
+    tokio::spawn(serve_tls(listener, app, options));
+    base
+}
+
+fn client(identity: bool) -> reqwest::Client {
+    let ca = std::fs::read(fixture("ca.pem")).unwrap();
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let mut builder = reqwest::Client::builder()
+        .use_rustls_tls()
+        .add_root_certificate(reqwest::Certificate::from_pem(&ca).unwrap());
+    if identity {
+        let mut pem = std::fs::read(fixture("client.pem")).unwrap();
+        pem.extend(std::fs::read(fixture("client.key")).unwrap());
+        builder = builder.identity(reqwest::Identity::from_pem(&pem).unwrap());
+    }
+    builder.build().unwrap()
+}
+
+#[tokio::test]
+async fn tokens_and_client_certificates_authenticate_over_tls() {
+    let base = start_server().await;
+
+    let anonymous = client(false);
+    let resp = anonymous.get(format!("{base}/health")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let resp = anonymous.get(format!("{base}/health")).bearer_auth(TOKEN).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The client certificate stands in for a token, with the configured scopes.
+    let with_cert = client(true);
+    let state = serde_json::json!({ "version": 4, "serial": 1, "lineage": "abc" });
+    let resp = with_cert
+        .post(format!("{base}/terraform/state/enc/part"))
+        .json(&state)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = with_cert.delete(format!("{base}/enclaves/enc")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let events: serde_json::Value = with_cert
+        .get(format!("{base}/events?kind=TfStateUploaded"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(events["events"][0]["actor"], "cert:ci-runner");
+}
@@ -1,52 +1,119 @@
 use std::io::{self, BufRead, Write as IoWrite};
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 use anyhow::{Context, Result};
-use nclav_domain::CloudTarget;
-use nclav_driver::{AwsDriver, AwsDriverConfig, AzureDriver, AzureDriverConfig, DriverRegistry, GcpDriver, GcpDriverConfig, LocalDriver};
-use nclav_store::{EnclaveState, InMemoryStore, PostgresStore, RedbStore, StateStore};
+use nclav_api::prune::{PruneReport, Pruner};
+use nclav_api::AppConfig;
+use nclav_api::render;
+use nclav_api::tls::TlsOptions;
+use nclav_config::{ApiVersion, MigrationOutcome};
+use nclav_domain::{CloudTarget, ProducesType};
+use nclav_graph::{CidrAllocator, Dependent, GraphError};
+use nclav_driver::{check_tf_contracts, AwsDriver, BackendTls, AwsDriverConfig, AzureDriver, AzureDriverConfig, DriverRegistry, GcpDriver, GcpDriverConfig, LocalDriver};
+use nclav_store::{
+    EnclaveState, EncryptedStore, EventBus, ImportSummary, InMemoryStore, Keyring, LocalKek, PostgresStore,
+    RedbStore, RetentionPolicy, Scope, SqliteStore, StateArchive, StateStore, StoreError, TfLockStatus, TfStateVersion,
+};
 use uuid::Uuid;
 
-use crate::cli::{CloudArg, GraphOutput};
+use crate::cli::{CloudArg, EventsArgs, GraphOutput, ImpactOutput, ProducesArg, ScopeArg, ServeArgs};
 use crate::output;
+use crate::scaffold;
 
 // ── Serve ─────────────────────────────────────────────────────────────────────
 
-pub async fn serve(
-    cloud: CloudArg,
-    enable_cloud: Vec<CloudArg>,
-    remote: Option<String>,
-    ephemeral: bool,
-    rotate_token: bool,
-    store_path: Option<String>,
-    postgres_url: Option<String>,
-    mut gcp_parent: Option<String>,
-    mut gcp_billing_account: Option<String>,
-    gcp_default_region: String,
-    gcp_project_prefix: Option<String>,
-    mut azure_tenant_id: Option<String>,
-    mut azure_management_group_id: Option<String>,
-    mut azure_billing_account_name: Option<String>,
-    mut azure_billing_profile_name: Option<String>,
-    mut azure_invoice_section_name: Option<String>,
-    azure_default_location: String,
-    azure_subscription_prefix: Option<String>,
-    azure_client_id: Option<String>,
-    azure_client_secret: Option<String>,
-    mut aws_org_unit_id: Option<String>,
-    mut aws_email_domain: Option<String>,
-    aws_default_region: String,
-    aws_account_prefix: Option<String>,
-    aws_cross_account_role: String,
-    aws_role_arn: Option<String>,
-    port: u16,
-    bind: String,
-) -> Result<()> {
+pub async fn serve(args: ServeArgs, remote: Option<String>, ca_cert: Option<PathBuf>) -> Result<()> {
+    let ServeArgs {
+        cloud,
+        enable_cloud,
+        ephemeral,
+        rotate_token,
+        store_path,
+        postgres_url,
+        sqlite_path,
+        encryption_key_file,
+        previous_encryption_key_file,
+        mut gcp_parent,
+        mut gcp_billing_account,
+        gcp_default_region,
+        gcp_project_prefix,
+        mut azure_tenant_id,
+        mut azure_management_group_id,
+        mut azure_billing_account_name,
+        mut azure_billing_profile_name,
+        mut azure_invoice_section_name,
+        azure_default_location,
+        azure_subscription_prefix,
+        azure_client_id,
+        azure_client_secret,
+        mut aws_org_unit_id,
+        mut aws_email_domain,
+        aws_default_region,
+        aws_account_prefix,
+        aws_cross_account_role,
+        aws_role_arn,
+        vpc_supernet,
+        vpc_prefix_len,
+        policy,
+        tf_state_versions,
+        webhooks,
+        oidc,
+        event_retention_days,
+        iac_run_retention_days,
+        iac_runs_per_partition,
+        prune_interval_mins,
+        tls_cert,
+        tls_key,
+        tls_client_ca,
+        tls_client_scopes,
+        otlp_endpoint: _,
+        otlp_headers: _,
+        otel_service_name: _,
+        port,
+        bind,
+    } = args;
+    let retention = RetentionPolicy {
+        event_max_age_days: event_retention_days,
+        iac_run_max_age_days: iac_run_retention_days,
+        iac_runs_per_partition,
+    };
+    let tls = tls_cert.zip(tls_key).map(|(cert, key)| TlsOptions {
+        cert,
+        key,
+        client_ca: tls_client_ca,
+        client_scopes: tls_client_scopes.iter().map(scope_arg_to_scope).collect(),
+    });
+    // Only the CA: the operator's client identity never reaches IaC subprocesses.
+    let mut backend_tls = BackendTls { ca_cert };
+
     if remote.is_some() {
         anyhow::bail!("serve does not support --remote; run the server locally");
     }
 
+    let vpc_allocator = vpc_supernet
+        .map(|supernet| CidrAllocator::new(&supernet, vpc_prefix_len))
+        .transpose()
+        .context("Invalid --vpc-supernet")?;
+
+    let policy = policy
+        .map(|path| nclav_policy::load_policy(&path))
+        .transpose()
+        .context("Failed to load --policy")?
+        .map(Arc::new);
+
+    let webhooks = webhooks
+        .map(|path| nclav_api::webhooks::load_webhooks(&path))
+        .transpose()
+        .context("Failed to load --webhooks")?
+        .unwrap_or_default();
+
+    let oidc = oidc
+        .map(|path| nclav_api::oidc::load_oidc(&path))
+        .transpose()
+        .context("Failed to load --oidc")?
+        .map(Arc::new);
+
     // When running in a managed environment (e.g. Cloud Run), the token is
     // injected via Secret Manager rather than stored in a local file.
     // NCLAV_TOKEN takes priority over file-based token resolution.
@@ -94,6 +161,14 @@ pub async fn serve(
                 .await
                 .context("Failed to connect to PostgreSQL")?,
         )
+    } else if let Some(path) = sqlite_path {
+        let path = PathBuf::from(path);
+        println!("Using SQLite store at {}", path.display());
+        Arc::new(
+            SqliteStore::open(&path)
+                .await
+                .with_context(|| format!("Failed to open SQLite store at {}", path.display()))?,
+        )
     } else if ephemeral {
         println!("Using in-memory (ephemeral) store — state will be lost on server stop");
         Arc::new(InMemoryStore::new())
@@ -106,6 +181,15 @@ pub async fn serve(
         )
     };
 
+    let store: Arc<dyn StateStore> =
+        match load_keyring(encryption_key_file.as_deref(), &previous_encryption_key_file)? {
+            Some(keys) => {
+                println!("Encrypting tf state and driver handles at rest (key {})", keys.primary_id());
+                Arc::new(EncryptedStore::new(store, keys))
+            }
+            None => store,
+        };
+
     // Build the ordered, deduplicated list of clouds to register.
     // The default cloud comes first; --enable-cloud entries follow.
     let mut clouds: Vec<CloudArg> = vec![cloud.clone()];
@@ -207,22 +291,81 @@ pub async fn serve(
     let registry = Arc::new(registry);
 
     let addr = format!("{bind}:{port}");
+    let scheme = if tls.is_some() { "https" } else { "http" };
     println!(
-        "Starting nclav API server on http://{addr} (default: {default_target}, drivers: {drivers})",
+        "Starting nclav API server on {scheme}://{addr} (default: {default_target}, drivers: {drivers})",
         default_target = default_target,
         drivers = active.join(", "),
     );
 
-    let api_base = format!("http://{addr}");
-    let app = nclav_api::build_app(store, registry, Arc::new(token), api_base);
+    // Terraform reaches the state backend at api_base, so it must trust the
+    // server's certificate: --ca-cert if given, else the certificate itself.
+    let api_base = format!("{scheme}://{addr}");
+    if let Some(tls) = &tls {
+        backend_tls.ca_cert.get_or_insert_with(|| tls.cert.clone());
+        if tls.client_ca.is_some() {
+            let scopes: Vec<String> = tls.client_scopes.iter().map(|s| s.to_string()).collect();
+            println!("Accepting client certificates with scope(s) {}", scopes.join(", "));
+        }
+    }
+    let events = EventBus::default();
+    if !webhooks.is_empty() {
+        println!("Delivering audit events to {} webhook(s)", webhooks.len());
+        nclav_api::webhooks::spawn_webhooks(webhooks, &events, store.clone(), api_base.clone());
+    }
+    if let Some(oidc) = &oidc {
+        let issuers: Vec<&str> = oidc.issuers().iter().map(|i| i.issuer.as_str()).collect();
+        println!(
+            "Accepting OIDC tokens from {} ({} role(s))",
+            issuers.join(", "),
+            oidc.roles().len()
+        );
+    }
+    if !retention.is_empty() {
+        println!("Pruning every {prune_interval_mins} min with {}", describe_retention(&retention));
+    }
+    Pruner::new(store.clone(), retention.clone())
+        .spawn(std::time::Duration::from_secs(prune_interval_mins * 60));
+    let config = AppConfig {
+        backend_tls,
+        vpc_allocator,
+        policy,
+        tf_state_versions: tf_state_versions as usize,
+        retention,
+        oidc,
+        ..AppConfig::new(registry, Arc::new(token), api_base)
+    };
+    let app = nclav_api::build_app(store, events, config);
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .with_context(|| format!("Failed to bind to {addr}"))?;
-    axum::serve(listener, app).await.context("Server error")?;
+    match tls {
+        Some(tls) => nclav_api::tls::serve_tls(listener, app, tls).await.context("Server error")?,
+        None => axum::serve(listener, app).await.context("Server error")?,
+    }
 
     Ok(())
 }
 
+/// e.g. "events 90d, IaC runs 30d / 20 per partition".
+fn describe_retention(policy: &RetentionPolicy) -> String {
+    let mut parts = Vec::new();
+    if let Some(days) = policy.event_max_age_days {
+        parts.push(format!("events {days}d"));
+    }
+    let runs: Vec<String> = [
+        policy.iac_run_max_age_days.map(|d| format!("{d}d")),
+        policy.iac_runs_per_partition.map(|n| format!("{n} per partition")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !runs.is_empty() {
+        parts.push(format!("IaC runs {}", runs.join(" / ")));
+    }
+    parts.join(", ")
+}
+
 fn cloud_arg_to_target(arg: &CloudArg) -> CloudTarget {
     match arg {
         CloudArg::Local => CloudTarget::Local,
@@ -241,7 +384,7 @@ pub async fn apply(
     token: Option<String>,
 ) -> Result<()> {
     let token = resolve_token(token)?;
-    api_reconcile(&server_url(remote), &enclaves_dir, false, resources_only, &token).await
+    api_reconcile(&server_url(remote), &enclaves_dir, false, resources_only, token.as_deref()).await
 }
 
 // ── Diff ──────────────────────────────────────────────────────────────────────
@@ -252,7 +395,170 @@ pub async fn diff(
     token: Option<String>,
 ) -> Result<()> {
     let token = resolve_token(token)?;
-    api_reconcile(&server_url(remote), &enclaves_dir, true, false, &token).await
+    api_reconcile(&server_url(remote), &enclaves_dir, true, false, token.as_deref()).await
+}
+
+// ── Validate ──────────────────────────────────────────────────────────────────
+
+pub fn validate(enclaves_dir: PathBuf, policy: Option<PathBuf>) -> Result<()> {
+    let enclaves = nclav_config::load_enclaves(&enclaves_dir)
+        .with_context(|| format!("Failed to load enclaves from {}", enclaves_dir.display()))?;
+
+    let mut problems: Vec<String> = Vec::new();
+    match nclav_graph::validate(&enclaves) {
+        Ok(_) => {}
+        Err(GraphError::Multiple(errs)) => problems.extend(errs.iter().map(|e| e.to_string())),
+        Err(e) => problems.push(e.to_string()),
+    }
+    problems.extend(check_tf_contracts(&enclaves).iter().map(|v| v.to_string()));
+
+    let mut warnings: Vec<String> = Vec::new();
+    if let Some(path) = policy {
+        let policy = nclav_policy::load_policy(&path)
+            .with_context(|| format!("Failed to load policy from {}", path.display()))?;
+        // Offline: the server's default cloud is unknown, so enclaves without
+        // `cloud:` skip cloud-specific rules here.
+        for v in nclav_policy::evaluate(&policy, &enclaves, None) {
+            if v.is_blocking() {
+                problems.push(v.to_string());
+            } else {
+                warnings.push(v.to_string());
+            }
+        }
+    }
+    for w in &warnings {
+        eprintln!("  ~ {}", w);
+    }
+
+    if problems.is_empty() {
+        let n_parts: usize = enclaves.iter().map(|e| e.partitions.len()).sum();
+        println!("{} enclave(s), {} partition(s): OK", enclaves.len(), n_parts);
+        return Ok(());
+    }
+
+    for p in &problems {
+        eprintln!("  ! {}", p);
+    }
+    anyhow::bail!("{} problem(s) found", problems.len());
+}
+
+// ── Migrate config ────────────────────────────────────────────────────────────
+
+pub fn migrate_config(dir: PathBuf) -> Result<()> {
+    let files = nclav_config::migrate_config_dir(&dir)
+        .with_context(|| format!("Failed to migrate configs under {}", dir.display()))?;
+
+    let mut changed = 0;
+    for file in &files {
+        let path = file.path.strip_prefix(&dir).unwrap_or(&file.path).display();
+        match &file.outcome {
+            MigrationOutcome::UpToDate => println!("  = {}  (up to date)", path),
+            MigrationOutcome::Skipped => println!("  - {}  (not an nclav config; skipped)", path),
+            MigrationOutcome::Rewritten { from } => {
+                changed += 1;
+                println!("  ~ {}  ({} -> {})", path, from, ApiVersion::CURRENT);
+            }
+            MigrationOutcome::Reserialized { from } => {
+                changed += 1;
+                println!(
+                    "  ~ {}  ({} -> {}; re-serialized, comments not preserved)",
+                    path, from, ApiVersion::CURRENT
+                );
+            }
+        }
+    }
+    println!("{} of {} file(s) migrated to {}", changed, files.len(), ApiVersion::CURRENT);
+    Ok(())
+}
+
+// ── New ───────────────────────────────────────────────────────────────────────
+
+pub fn new_enclave(
+    id: String,
+    cloud: CloudArg,
+    region: Option<String>,
+    dir: PathBuf,
+) -> Result<()> {
+    let cloud = cloud_arg_to_target(&cloud);
+    let region = region.unwrap_or_else(|| scaffold::default_region(&cloud).to_string());
+    let enc_dir = dir.join(&id);
+
+    write_new_files(&enc_dir, &[("config.yml", scaffold::enclave_config(&id, &cloud, &region))])?;
+    println!("Created enclave '{}' at {}", id, enc_dir.display());
+    Ok(())
+}
+
+pub fn new_partition(
+    enclave: String,
+    id: String,
+    produces: Option<ProducesArg>,
+    module: Option<String>,
+    cloud: Option<CloudArg>,
+    dir: PathBuf,
+) -> Result<()> {
+    let enc_dir = dir.join(&enclave);
+    let enc_config = enc_dir.join("config.yml");
+    let raw: serde_yaml::Value = serde_yaml::from_str(
+        &std::fs::read_to_string(&enc_config)
+            .with_context(|| format!("No enclave config at {}", enc_config.display()))?,
+    )
+    .with_context(|| format!("Failed to parse {}", enc_config.display()))?;
+
+    let cloud = match cloud {
+        Some(c) => cloud_arg_to_target(&c),
+        None => match raw.get("cloud").and_then(|c| c.as_str()) {
+            Some("gcp") => CloudTarget::Gcp,
+            Some("azure") => CloudTarget::Azure,
+            Some("aws") => CloudTarget::Aws,
+            _ => CloudTarget::Local,
+        },
+    };
+    let produces = produces.map(|p| match p {
+        ProducesArg::Http  => ProducesType::Http,
+        ProducesArg::Tcp   => ProducesType::Tcp,
+        ProducesArg::Queue => ProducesType::Queue,
+    });
+
+    let mut files = vec![(
+        "config.yml",
+        scaffold::partition_config(&id, &cloud, produces.as_ref(), module.as_deref()),
+    )];
+    if module.is_none() {
+        files.push(("main.tf", scaffold::partition_main_tf(&id, &cloud)));
+        files.push(("variables.tf", scaffold::partition_variables_tf(&cloud)));
+        files.push(("outputs.tf", scaffold::partition_outputs_tf(produces.as_ref())));
+    }
+
+    let part_dir = enc_dir.join(&id);
+    write_new_files(&part_dir, &files)?;
+    println!("Created partition '{}' at {}", id, part_dir.display());
+
+    // An explicit partitions: list disables subdirectory discovery.
+    if let Some(list) = raw.get("partitions").and_then(|p| p.as_sequence()) {
+        if !list.iter().any(|p| p.as_str() == Some(id.as_str())) {
+            println!("Note: add '{}' to partitions: in {}", id, enc_config.display());
+        }
+    }
+    Ok(())
+}
+
+/// Create `dir` and write each `(name, content)` into it, refusing to overwrite.
+fn write_new_files(dir: &std::path::Path, files: &[(&str, String)]) -> Result<()> {
+    for (name, _) in files {
+        let path = dir.join(name);
+        if path.exists() {
+            anyhow::bail!("{} already exists; refusing to overwrite", path.display());
+        }
+    }
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    for (name, content) in files {
+        let path = dir.join(name);
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("  wrote {}", path.display());
+    }
+    Ok(())
 }
 
 // ── Status ────────────────────────────────────────────────────────────────────
@@ -261,7 +567,7 @@ pub async fn status(remote: Option<String>, token: Option<String>) -> Result<()>
     let token = resolve_token(token)?;
     let url = server_url(remote);
     let body: serde_json::Value = expect_success(
-        authed_client(&token)
+        authed_client(token.as_deref())
             .get(format!("{}/status", url.trim_end_matches('/')))
             .send()
             .await
@@ -295,7 +601,7 @@ pub async fn graph(
 ) -> Result<()> {
     let token = resolve_token(token)?;
     let url = server_url(remote);
-    let client = authed_client(&token);
+    let client = authed_client(token.as_deref());
     let filter = filter_enclave.as_deref();
 
     match output_format {
@@ -317,7 +623,7 @@ pub async fn graph(
             .await?;
             println!("{}", serde_json::to_string_pretty(&body)?);
         }
-        GraphOutput::Text | GraphOutput::Dot => {
+        GraphOutput::Text | GraphOutput::Dot | GraphOutput::Mermaid | GraphOutput::Html => {
             let states: Vec<EnclaveState> = expect_success(
                 client
                     .get(format!("{}/enclaves", url.trim_end_matches('/')))
@@ -330,8 +636,10 @@ pub async fn graph(
             .await
             .context("Failed to deserialize enclave states")?;
             match output_format {
-                GraphOutput::Text => print!("{}", output::render_graph_text_live(&states, filter)),
-                GraphOutput::Dot => println!("{}", output::render_dot_live(&states, filter)),
+                GraphOutput::Text => print!("{}", render::render_graph_text_live(&states, filter)),
+                GraphOutput::Dot => println!("{}", render::render_dot_live(&states, filter)),
+                GraphOutput::Mermaid => print!("{}", render::render_mermaid_live(&states, filter)),
+                GraphOutput::Html => print!("{}", render::render_html_live(&states, filter)),
                 GraphOutput::Json => unreachable!(),
             }
         }
@@ -340,6 +648,41 @@ pub async fn graph(
     Ok(())
 }
 
+// ── Impact ────────────────────────────────────────────────────────────────────
+
+pub async fn impact(
+    target: String,
+    output_format: ImpactOutput,
+    remote: Option<String>,
+    token: Option<String>,
+) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url = server_url(remote);
+    let body: serde_json::Value = expect_success(
+        authed_client(token.as_deref())
+            .get(format!("{}/graph/impact", url.trim_end_matches('/')))
+            .query(&[("target", &target)])
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await?;
+
+    match output_format {
+        ImpactOutput::Json => println!("{}", serde_json::to_string_pretty(&body)?),
+        ImpactOutput::Text => {
+            let dependents: Vec<Dependent> =
+                serde_json::from_value(body["dependents"].clone())
+                    .context("Failed to deserialize impact response")?;
+            println!("{}: {} dependent(s)", target, dependents.len());
+            print!("{}", output::render_impact_text(&dependents));
+        }
+    }
+    Ok(())
+}
+
 // ── Destroy ───────────────────────────────────────────────────────────────────
 
 /// Prompt the user to type `expected` to confirm a destructive action.
@@ -369,7 +712,7 @@ pub async fn destroy(
 ) -> Result<()> {
     let token  = resolve_token(token)?;
     let url    = server_url(remote);
-    let client = authed_client(&token);
+    let client = authed_client(token.as_deref());
     let base   = url.trim_end_matches('/');
 
     // ── Partition destroy ─────────────────────────────────────────────────────
@@ -392,23 +735,7 @@ pub async fn destroy(
             .await
             .with_context(|| format!("Failed to reach server at {url}"))?;
 
-        let status = resp.status();
-        let body: serde_json::Value = resp.json().await.unwrap_or(serde_json::Value::Null);
-
-        if status.is_success() {
-            let errors = body["errors"].as_array().cloned().unwrap_or_default();
-            if errors.is_empty() {
-                println!("done.");
-            } else {
-                println!("done (with errors):");
-                for e in &errors {
-                    println!("  ! {}", e.as_str().unwrap_or(&e.to_string()));
-                }
-                anyhow::bail!("partition destroy completed with errors");
-            }
-        } else {
-            let msg = body["error"].as_str().unwrap_or("unknown error");
-            println!("failed: {} — {}", status, msg);
+        if !print_destroy_outcome(resp).await {
             anyhow::bail!("partition destroy failed");
         }
         return Ok(());
@@ -473,33 +800,349 @@ pub async fn destroy(
             .await
             .with_context(|| format!("Failed to reach server at {url}"))?;
 
-        let status = resp.status();
-        let body: serde_json::Value = resp.json().await.unwrap_or(serde_json::Value::Null);
+        if !print_destroy_outcome(resp).await {
+            any_error = true;
+        }
+    }
 
-        if status.is_success() {
-            let errors = body.get("errors").and_then(|v| v.as_array()).cloned().unwrap_or_default();
-            if errors.is_empty() {
-                println!("done.");
-            } else {
-                println!("done (with errors):");
-                for e in &errors {
-                    println!("  ! {}", e.as_str().unwrap_or(&e.to_string()));
-                }
-                any_error = true;
+    if any_error {
+        anyhow::bail!("one or more enclave destroys failed");
+    }
+    Ok(())
+}
+
+/// Retry teardowns that failed earlier: tombstoned enclaves are destroyed
+/// again, and so are tombstoned partitions of enclaves that are otherwise live.
+pub async fn destroy_retry(
+    enclave_ids: Vec<String>,
+    resources_only: bool,
+    remote: Option<String>,
+    token: Option<String>,
+) -> Result<()> {
+    let token  = resolve_token(token)?;
+    let url    = server_url(remote);
+    let client = authed_client(token.as_deref());
+    let base   = url.trim_end_matches('/');
+
+    let states: Vec<EnclaveState> = expect_success(
+        client
+            .get(format!("{}/enclaves", base))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse enclave list")?;
+
+    // (label, DELETE URL) for every outstanding teardown
+    let mut targets: Vec<(String, String)> = Vec::new();
+    for state in &states {
+        let id = &state.desired.id;
+        if !enclave_ids.is_empty() && !enclave_ids.contains(&id.0) {
+            continue;
+        }
+        if state.meta.is_tombstone() {
+            let mut target = format!("{}/enclaves/{}", base, id);
+            if resources_only {
+                target.push_str("?resources_only=true");
             }
-        } else {
-            let msg = body.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
-            println!("failed: {} — {}", status, msg);
+            targets.push((id.to_string(), target));
+            continue;
+        }
+        let mut part_ids: Vec<_> = state
+            .partitions
+            .iter()
+            .filter(|(_, ps)| ps.meta.is_tombstone())
+            .map(|(pid, _)| pid.to_string())
+            .collect();
+        part_ids.sort();
+        for pid in part_ids {
+            targets.push((
+                format!("{}/{}", id, pid),
+                format!("{}/enclaves/{}/partitions/{}", base, id, pid),
+            ));
+        }
+    }
+
+    if targets.is_empty() {
+        println!("No outstanding teardowns.");
+        return Ok(());
+    }
+
+    let mut any_error = false;
+    for (label, target) in &targets {
+        print!("Retrying teardown of {}… ", label);
+        let resp = client
+            .delete(target)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?;
+        if !print_destroy_outcome(resp).await {
             any_error = true;
         }
     }
 
     if any_error {
-        anyhow::bail!("one or more enclave destroys failed");
+        anyhow::bail!("one or more teardowns are still outstanding");
+    }
+    Ok(())
+}
+
+/// Print the result of an enclave or partition DELETE. Returns whether the
+/// teardown completed and the server purged the record.
+async fn print_destroy_outcome(resp: reqwest::Response) -> bool {
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or(serde_json::Value::Null);
+
+    if !status.is_success() {
+        let msg = body.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+        println!("failed: {} — {}", status, msg);
+        return false;
+    }
+
+    let errors = body.get("errors").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    if errors.is_empty() {
+        println!("done.");
+        return true;
+    }
+    println!("incomplete:");
+    for e in &errors {
+        println!("  ! {}", e.as_str().unwrap_or(&e.to_string()));
+    }
+    if body.get("purged").and_then(|v| v.as_bool()) == Some(false) {
+        println!("  Kept in the 'deleting' state. Fix the cause and run 'nclav destroy --retry'.");
+    }
+    false
+}
+
+// ── State ─────────────────────────────────────────────────────────────────────
+
+pub async fn state_export(store: Option<String>, output: Option<PathBuf>) -> Result<()> {
+    let source = open_store_spec(store.as_deref()).await?;
+    let archive = StateArchive::export(source.as_ref())
+        .await
+        .context("Failed to read state")?;
+
+    let json = serde_json::to_vec_pretty(&archive)?;
+    match &output {
+        Some(path) => {
+            std::fs::write(path, &json)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "Exported {} enclave(s), {} tf state blob(s), {} IaC run(s) to {}",
+                archive.enclaves.len(),
+                archive.tf_state.len(),
+                archive.iac_runs.len(),
+                path.display()
+            );
+        }
+        None => io::stdout().write_all(&json)?,
     }
     Ok(())
 }
 
+pub async fn state_import(file: PathBuf, store: Option<String>, force: bool) -> Result<()> {
+    let bytes = std::fs::read(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let archive = StateArchive::from_slice(&bytes)
+        .with_context(|| format!("Refusing to import {}", file.display()))?;
+    let target = open_store_spec(store.as_deref()).await?;
+    let summary = import_archive(&archive, target.as_ref(), force).await?;
+    println!("Imported {}", summary);
+    Ok(())
+}
+
+pub async fn state_migrate(from: String, to: String, force: bool) -> Result<()> {
+    let source = open_store_spec(Some(&from)).await?;
+    let target = open_store_spec(Some(&to)).await?;
+    let archive = StateArchive::export(source.as_ref())
+        .await
+        .context("Failed to read source state")?;
+    let summary = import_archive(&archive, target.as_ref(), force).await?;
+    println!("Migrated {}", summary);
+    Ok(())
+}
+
+pub async fn state_rotate_key(
+    store: Option<String>,
+    key_file: Option<PathBuf>,
+    previous_key_file: Vec<PathBuf>,
+) -> Result<()> {
+    let keys = load_keyring(key_file.as_deref(), &previous_key_file)?
+        .context("No new key given: pass --key-file or set NCLAV_ENCRYPTION_KEY")?;
+    let primary = keys.primary_id().to_string();
+    let inner = open_store_spec(store.as_deref()).await?;
+    let summary = EncryptedStore::new(inner, keys)
+        .reencrypt_all()
+        .await
+        .context("Re-encryption failed; pass the old key with --previous-key-file")?;
+    println!("Re-encrypted {} under key {}", summary, primary);
+    Ok(())
+}
+
+/// The store encryption keys: the primary key from `key_file`, or else from
+/// NCLAV_ENCRYPTION_KEY, plus retired keys for reading. `None` when neither is set.
+fn load_keyring(key_file: Option<&Path>, previous: &[PathBuf]) -> Result<Option<Keyring>> {
+    let primary = match key_file {
+        Some(path) => LocalKek::from_file(path)?,
+        None => match std::env::var("NCLAV_ENCRYPTION_KEY") {
+            Ok(key) if !key.is_empty() => {
+                LocalKek::from_base64(&key).context("Invalid NCLAV_ENCRYPTION_KEY")?
+            }
+            _ if !previous.is_empty() => {
+                anyhow::bail!("previous encryption keys were given without a current key")
+            }
+            _ => return Ok(None),
+        },
+    };
+    let mut keys = Keyring::new(Arc::new(primary));
+    for path in previous {
+        keys = keys.with_previous(Arc::new(LocalKek::from_file(path)?));
+    }
+    Ok(Some(keys))
+}
+
+async fn import_archive(
+    archive: &StateArchive,
+    target: &dyn StateStore,
+    force: bool,
+) -> Result<ImportSummary> {
+    match archive.import_into(target, force).await {
+        Ok(summary) => Ok(summary),
+        Err(e @ StoreError::TargetNotEmpty(_)) => {
+            anyhow::bail!("{}; pass --force to import anyway", e)
+        }
+        Err(e) => Err(e).context("Import failed"),
+    }
+}
+
+// ── Events ────────────────────────────────────────────────────────────────────
+
+pub async fn events(args: EventsArgs, remote: Option<String>, token: Option<String>) -> Result<()> {
+    let EventsArgs { enclave, partition, kind, run, limit, follow } = args;
+    let token = resolve_token(token)?;
+    let url = server_url(remote);
+    let base = url.trim_end_matches('/');
+    let client = authed_client(token.as_deref());
+
+    let filters: Vec<(&str, String)> = [
+        ("enclave_id", enclave),
+        ("partition_id", partition),
+        ("kind", kind),
+        ("run_id", run),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|v| (name, v)))
+    .collect();
+
+    // Subscribe before fetching the history so nothing recorded in between is lost.
+    let stream = if follow {
+        let resp = client
+            .get(format!("{base}/events/stream"))
+            .query(&filters)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?;
+        Some(expect_success(resp).await?)
+    } else {
+        None
+    };
+
+    let mut query = filters.clone();
+    query.push(("limit", limit.to_string()));
+    let page: serde_json::Value = expect_success(
+        client
+            .get(format!("{base}/events"))
+            .query(&query)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse events response")?;
+
+    let history = page["events"].as_array().cloned().unwrap_or_default();
+    let seen: std::collections::HashSet<String> = history
+        .iter()
+        .filter_map(|e| e["id"].as_str().map(String::from))
+        .collect();
+    for event in &history {
+        println!("{}", event_line(event));
+    }
+
+    let Some(mut stream) = stream else {
+        if history.is_empty() {
+            println!("No events found");
+        }
+        return Ok(());
+    };
+
+    // Server-sent events: messages are separated by a blank line; keep-alive
+    // messages are comments without an `event:` field.
+    let mut buf: Vec<u8> = Vec::new();
+    while let Some(chunk) = stream.chunk().await.context("Event stream interrupted")? {
+        buf.extend_from_slice(&chunk);
+        while let Some(end) = buf.windows(2).position(|w| w == b"\n\n") {
+            let message: Vec<u8> = buf.drain(..end + 2).collect();
+            let message = String::from_utf8_lossy(&message);
+            let mut name = None;
+            let mut data = String::new();
+            for line in message.lines() {
+                if let Some(v) = line.strip_prefix("event:") {
+                    name = Some(v.trim().to_string());
+                } else if let Some(v) = line.strip_prefix("data:") {
+                    data.push_str(v.trim_start());
+                }
+            }
+            match name.as_deref() {
+                None => {}
+                Some("lagged") => eprintln!("warning: missed {} event(s); the stream fell behind", data),
+                Some(_) => {
+                    let event: serde_json::Value =
+                        serde_json::from_str(&data).context("Failed to parse streamed event")?;
+                    if !event["id"].as_str().is_some_and(|id| seen.contains(id)) {
+                        println!("{}", event_line(&event));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One audit event as a log line: time, kind, subject, actor and detail.
+fn event_line(event: &serde_json::Value) -> String {
+    let str_field = |name: &str| event.get(name).and_then(|v| v.as_str());
+    let at = str_field("at").unwrap_or("-");
+    let at = if at.len() >= 19 { &at[..19] } else { at };
+    let subject = match (str_field("enclave_id"), str_field("partition_id")) {
+        (Some(e), Some(p)) => format!("{}/{}", e, p),
+        (Some(e), None) => e.to_string(),
+        _ => "-".into(),
+    };
+    let actor = match (str_field("actor"), str_field("source")) {
+        (Some(a), Some(s)) => format!("{} ({})", a, s),
+        (Some(a), None) => a.to_string(),
+        (None, Some(s)) => s.to_string(),
+        (None, None) => "-".into(),
+    };
+    let mut line = format!(
+        "{:<20} {:<24} {:<32} {}",
+        at,
+        str_field("kind").unwrap_or("-"),
+        subject,
+        actor
+    );
+    if let Some(message) = str_field("message") {
+        line.push_str(&format!("  {}", message));
+    }
+    line
+}
+
 // ── Iac ───────────────────────────────────────────────────────────────────────
 
 pub async fn iac_runs(
@@ -517,7 +1160,7 @@ pub async fn iac_runs(
         partition_id,
     );
     let runs: serde_json::Value = expect_success(
-        authed_client(&token)
+        authed_client(token.as_deref())
             .get(&endpoint)
             .send()
             .await
@@ -544,8 +1187,8 @@ pub async fn iac_runs(
 
     // Table header
     println!(
-        "{:<38} {:<12} {:<12} {:<22} {}",
-        "ID", "OPERATION", "STATUS", "STARTED", "EXIT"
+        "{:<38} {:<12} {:<12} {:<22} EXIT",
+        "ID", "OPERATION", "STATUS", "STARTED"
     );
     println!("{}", "-".repeat(90));
 
@@ -595,7 +1238,7 @@ pub async fn iac_logs(
         ),
     };
 
-    let resp = authed_client(&token)
+    let resp = authed_client(token.as_deref())
         .get(&endpoint)
         .send()
         .await
@@ -632,6 +1275,222 @@ pub async fn iac_logs(
     Ok(())
 }
 
+pub async fn iac_locks(remote: Option<String>, token: Option<String>) -> Result<()> {
+    let locks = fetch_tf_locks(remote, token).await?;
+    if locks.is_empty() {
+        println!("No Terraform state locks held");
+        return Ok(());
+    }
+
+    println!(
+        "{:<32} {:<38} {:<24} {:<22} {:<26} RUN",
+        "STATE", "LOCK ID", "HOLDER", "OPERATION", "CREATED"
+    );
+    println!("{}", "-".repeat(160));
+    for lock in &locks {
+        let run = match (lock.run_id, &lock.run_status) {
+            (Some(id), Some(status)) => format!("{} ({})", id, status),
+            (Some(id), None) => format!("{} (missing)", id),
+            (None, _) => "-".into(),
+        };
+        println!(
+            "{:<32} {:<38} {:<24} {:<22} {:<26} {}{}",
+            lock.key,
+            lock.lock_id,
+            lock.holder.as_deref().unwrap_or("-"),
+            lock.operation.as_deref().unwrap_or("-"),
+            lock.created.as_deref().map(|c| c.get(..19).unwrap_or(c)).unwrap_or("-"),
+            run,
+            if lock.stale { "  STALE" } else { "" },
+        );
+    }
+    Ok(())
+}
+
+pub async fn iac_unlock(
+    enclave_id: String,
+    partition_id: String,
+    force: bool,
+    lock_id: Option<String>,
+    remote: Option<String>,
+    token: Option<String>,
+) -> Result<()> {
+    let key = format!("{}/{}", enclave_id, partition_id);
+    let held = fetch_tf_locks(remote.clone(), token.clone())
+        .await?
+        .into_iter()
+        .find(|l| l.key == key);
+    let Some(lock) = held else {
+        println!("State {} is not locked", key);
+        return Ok(());
+    };
+    if !force {
+        anyhow::bail!(
+            "state {} is locked by {} (lock {}, {}); pass --force to break the lock",
+            key,
+            lock.holder.as_deref().unwrap_or("unknown"),
+            lock.lock_id,
+            lock.operation.as_deref().unwrap_or("unknown operation"),
+        );
+    }
+
+    let token = resolve_token(token)?;
+    let url = server_url(remote);
+    let endpoint = format!(
+        "{}/terraform/locks/{}/{}/force-unlock",
+        url.trim_end_matches('/'),
+        enclave_id,
+        partition_id,
+    );
+    let broken: TfLockStatus = expect_success(
+        authed_client(token.as_deref())
+            .post(&endpoint)
+            .json(&serde_json::json!({ "note": whoami(), "lock_id": lock_id }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse force-unlock response")?;
+
+    println!(
+        "Broke lock {} on {} (held by {})",
+        broken.lock_id,
+        key,
+        broken.holder.as_deref().unwrap_or("unknown")
+    );
+    Ok(())
+}
+
+async fn fetch_tf_locks(remote: Option<String>, token: Option<String>) -> Result<Vec<TfLockStatus>> {
+    let token = resolve_token(token)?;
+    let url = server_url(remote);
+    let endpoint = format!("{}/terraform/locks", url.trim_end_matches('/'));
+    expect_success(
+        authed_client(token.as_deref())
+            .get(&endpoint)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse locks response")
+}
+
+/// `user@host` noted on audit records, in the same form Terraform uses for lock holders.
+fn whoami() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".into());
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".into());
+    format!("{}@{}", user, host)
+}
+
+pub async fn iac_state_versions(
+    enclave_id: String,
+    partition_id: String,
+    remote: Option<String>,
+    token: Option<String>,
+) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url = server_url(remote);
+    let endpoint = format!(
+        "{}/terraform/state/{}/{}/versions",
+        url.trim_end_matches('/'),
+        enclave_id,
+        partition_id,
+    );
+    let versions: Vec<TfStateVersion> = expect_success(
+        authed_client(token.as_deref())
+            .get(&endpoint)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse state versions response")?;
+
+    if versions.is_empty() {
+        println!("No Terraform state stored for {}/{}", enclave_id, partition_id);
+        return Ok(());
+    }
+
+    println!(
+        "{:<8} {:<8} {:<22} {:<10} {:<38} NOTE",
+        "VERSION", "SERIAL", "CREATED", "SIZE", "RUN"
+    );
+    println!("{}", "-".repeat(100));
+    for v in &versions {
+        let serial = v.serial.map(|s| s.to_string()).unwrap_or_else(|| "-".into());
+        let run = v.run_id.map(|id| id.to_string()).unwrap_or_else(|| "-".into());
+        let note = v
+            .restored_from
+            .map(|from| format!("restored from {}", from))
+            .unwrap_or_default();
+        println!(
+            "{:<8} {:<8} {:<22} {:<10} {:<38} {}",
+            v.version,
+            serial,
+            v.created_at.format("%Y-%m-%dT%H:%M:%S"),
+            v.size,
+            run,
+            note
+        );
+    }
+    Ok(())
+}
+
+pub async fn iac_state_restore(
+    enclave_id: String,
+    partition_id: String,
+    version: u64,
+    remote: Option<String>,
+    token: Option<String>,
+) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url = server_url(remote);
+    let endpoint = format!(
+        "{}/terraform/state/{}/{}/versions/{}/restore",
+        url.trim_end_matches('/'),
+        enclave_id,
+        partition_id,
+        version,
+    );
+    let created: TfStateVersion = expect_success(
+        authed_client(token.as_deref())
+            .post(&endpoint)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse restore response")?;
+
+    println!(
+        "Restored version {} of {}/{} as version {} (serial {})",
+        version,
+        enclave_id,
+        partition_id,
+        created.version,
+        created.serial.map(|s| s.to_string()).unwrap_or_else(|| "-".into()),
+    );
+    println!("Run `nclav diff` / `nclav apply` to reconcile infrastructure against it.");
+    Ok(())
+}
+
 // ── Orphans ───────────────────────────────────────────────────────────────────
 
 pub async fn orphans(
@@ -642,7 +1501,7 @@ pub async fn orphans(
     let token = resolve_token(token)?;
     let url   = server_url(remote);
     let body: serde_json::Value = expect_success(
-        authed_client(&token)
+        authed_client(token.as_deref())
             .get(format!("{}/orphans", url.trim_end_matches('/')))
             .send()
             .await
@@ -662,7 +1521,7 @@ pub async fn orphans(
         .filter(|o| {
             filter_enclave
                 .as_deref()
-                .map_or(true, |f| o["enclave"].as_str() == Some(f))
+                .is_none_or(|f| o["enclave"].as_str() == Some(f))
         })
         .collect();
 
@@ -673,8 +1532,8 @@ pub async fn orphans(
 
     // Print as an ASCII table
     println!(
-        "{:<22} {:<16} {:<28} {}",
-        "ENCLAVE", "PARTITION", "RESOURCE TYPE", "RESOURCE NAME"
+        "{:<22} {:<16} {:<28} RESOURCE NAME",
+        "ENCLAVE", "PARTITION", "RESOURCE TYPE"
     );
     println!("{}", "─".repeat(100));
 
@@ -690,6 +1549,140 @@ pub async fn orphans(
     anyhow::bail!("{} orphaned resource(s) found", filtered.len());
 }
 
+// ── Admin ─────────────────────────────────────────────────────────────────────
+
+pub async fn admin_prune(dry_run: bool, remote: Option<String>, token: Option<String>) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url   = server_url(remote);
+    let report: PruneReport = expect_success(
+        authed_client(token.as_deref())
+            .post(format!("{}/admin/prune", url.trim_end_matches('/')))
+            .query(&[("dry_run", dry_run)])
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse prune response")?;
+
+    println!(
+        "{} {} audit event(s), {} IaC run(s), {} workspace(s)",
+        if dry_run { "Would remove" } else { "Removed" },
+        report.store.events,
+        report.store.iac_runs,
+        report.workspaces.len(),
+    );
+    for path in &report.workspaces {
+        println!("  {}", path.display());
+    }
+    Ok(())
+}
+
+// ── API tokens ────────────────────────────────────────────────────────────────
+
+fn scope_arg_to_scope(arg: &ScopeArg) -> Scope {
+    match arg {
+        ScopeArg::Read    => Scope::Read,
+        ScopeArg::Diff    => Scope::Diff,
+        ScopeArg::Apply   => Scope::Apply,
+        ScopeArg::Destroy => Scope::Destroy,
+        ScopeArg::Admin   => Scope::Admin,
+    }
+}
+
+/// An API token as returned by `GET /tokens` and `POST /tokens`.
+#[derive(serde::Deserialize)]
+struct TokenInfo {
+    name: String,
+    scopes: Vec<Scope>,
+    enclaves: Vec<String>,
+    created_at: String,
+    created_by: Option<String>,
+    /// Only present in the response to `POST /tokens`.
+    token: Option<String>,
+}
+
+pub async fn token_create(
+    name: String,
+    scopes: Vec<ScopeArg>,
+    enclaves: Vec<String>,
+    remote: Option<String>,
+    token: Option<String>,
+) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url   = server_url(remote);
+    let scopes: Vec<Scope> = scopes.iter().map(scope_arg_to_scope).collect();
+    let created: TokenInfo = expect_success(
+        authed_client(token.as_deref())
+            .post(format!("{}/tokens", url.trim_end_matches('/')))
+            .json(&serde_json::json!({ "name": name, "scopes": scopes, "enclaves": enclaves }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse token response")?;
+
+    println!("Created token '{}'. Store it now; it will not be shown again:", created.name);
+    println!();
+    println!("  {}", created.token.unwrap_or_default());
+    Ok(())
+}
+
+pub async fn token_list(remote: Option<String>, token: Option<String>) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url   = server_url(remote);
+    let tokens: Vec<TokenInfo> = expect_success(
+        authed_client(token.as_deref())
+            .get(format!("{}/tokens", url.trim_end_matches('/')))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?
+    .json()
+    .await
+    .context("Failed to parse token list")?;
+
+    if tokens.is_empty() {
+        println!("No API tokens");
+        return Ok(());
+    }
+    println!("{:<24} {:<28} {:<24} {:<20} BY", "NAME", "SCOPES", "ENCLAVES", "CREATED");
+    println!("{}", "-".repeat(110));
+    for t in &tokens {
+        let scopes: Vec<String> = t.scopes.iter().map(|s| s.to_string()).collect();
+        println!(
+            "{:<24} {:<28} {:<24} {:<20} {}",
+            t.name,
+            scopes.join(","),
+            if t.enclaves.is_empty() { "*".to_string() } else { t.enclaves.join(",") },
+            t.created_at.get(..19).unwrap_or(&t.created_at),
+            t.created_by.as_deref().unwrap_or("-"),
+        );
+    }
+    Ok(())
+}
+
+pub async fn token_revoke(name: String, remote: Option<String>, token: Option<String>) -> Result<()> {
+    let token = resolve_token(token)?;
+    let url   = server_url(remote);
+    expect_success(
+        authed_client(token.as_deref())
+            .delete(format!("{}/tokens/{}", url.trim_end_matches('/'), name))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach server at {url}"))?,
+    )
+    .await?;
+    println!("Revoked token '{}'", name);
+    Ok(())
+}
+
 // ── Token helpers ─────────────────────────────────────────────────────────────
 
 /// Generate a cryptographically random token as a 64-character hex string.
@@ -701,14 +1694,20 @@ fn generate_token() -> String {
 
 /// Resolve the token to use for API calls.
 ///
-/// Priority: explicit value (from --token / NCLAV_TOKEN) → ~/.nclav/token file
-fn resolve_token(explicit: Option<String>) -> Result<String> {
+/// Priority: explicit value (from --token / NCLAV_TOKEN) → ~/.nclav/token file.
+/// `None` when a client certificate is configured and there is no token file:
+/// the certificate authenticates the request on its own.
+fn resolve_token(explicit: Option<String>) -> Result<Option<String>> {
     if let Some(t) = explicit {
-        return Ok(t);
+        return Ok(Some(t));
     }
     let path = default_token_path();
+    let has_client_cert = CLIENT_TLS.get().is_some_and(|tls| tls.identity.is_some());
+    if has_client_cert && !path.exists() {
+        return Ok(None);
+    }
     std::fs::read_to_string(&path)
-        .map(|s| s.trim().to_string())
+        .map(|s| Some(s.trim().to_string()))
         .with_context(|| {
             format!(
                 "No token provided and could not read token file at {}. \
@@ -750,19 +1749,73 @@ fn default_gcp_credentials_path() -> PathBuf {
     PathBuf::from(home).join(".nclav").join("gcp-credentials.json")
 }
 
-/// Build a reqwest Client with the Authorization header pre-configured.
-fn authed_client(token: &str) -> reqwest::Client {
+/// CA bundle and client identity from the global TLS flags, shared by every
+/// client [`authed_client`] builds. Set once by [`init_client_tls`].
+#[derive(Default)]
+struct ClientTls {
+    ca_certs: Vec<reqwest::Certificate>,
+    identity: Option<reqwest::Identity>,
+}
+
+static CLIENT_TLS: OnceLock<ClientTls> = OnceLock::new();
+
+/// Load the global `--ca-cert`, `--client-cert` and `--client-key` files.
+pub fn init_client_tls(
+    ca_cert: Option<&Path>,
+    client_cert: Option<&Path>,
+    client_key: Option<&Path>,
+) -> Result<()> {
+    let read = |path: &Path| {
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+    };
+    // reqwest's rustls backend uses the process-wide provider.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let mut tls = ClientTls::default();
+    if let Some(path) = ca_cert {
+        tls.ca_certs = reqwest::Certificate::from_pem_bundle(&read(path)?)
+            .with_context(|| format!("Invalid CA bundle {}", path.display()))?;
+    }
+    if let (Some(cert), Some(key)) = (client_cert, client_key) {
+        // rustls takes the certificate chain and its key as one PEM buffer.
+        let mut pem = read(cert)?;
+        pem.push(b'\n');
+        pem.extend(read(key)?);
+        tls.identity = Some(
+            reqwest::Identity::from_pem(&pem)
+                .with_context(|| format!("Invalid client certificate {}", cert.display()))?,
+        );
+    }
+    let _ = CLIENT_TLS.set(tls);
+    Ok(())
+}
+
+/// Build a reqwest Client with the Authorization header pre-configured. Without
+/// a token no header is sent, for servers that accept the client certificate.
+fn authed_client(token: Option<&str>) -> reqwest::Client {
     let mut headers = reqwest::header::HeaderMap::new();
-    let bearer = format!("Bearer {}", token);
+    if let Some(token) = token {
+        let bearer = format!("Bearer {}", token);
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            reqwest::header::HeaderValue::from_str(&bearer)
+                .expect("token contains invalid header characters"),
+        );
+    }
+    // Lets the server record audit events as coming from the CLI.
     headers.insert(
-        reqwest::header::AUTHORIZATION,
-        reqwest::header::HeaderValue::from_str(&bearer)
-            .expect("token contains invalid header characters"),
+        nclav_api::auth::CLIENT_HEADER,
+        reqwest::header::HeaderValue::from_static("cli"),
     );
-    reqwest::Client::builder()
-        .default_headers(headers)
-        .build()
-        .expect("failed to build HTTP client")
+    let mut builder = reqwest::Client::builder().default_headers(headers).use_rustls_tls();
+    if let Some(tls) = CLIENT_TLS.get() {
+        for cert in &tls.ca_certs {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if let Some(identity) = &tls.identity {
+            builder = builder.identity(identity.clone());
+        }
+    }
+    builder.build().expect("failed to build HTTP client")
 }
 
 /// Check that a response has a success status code, returning a clear error
@@ -795,12 +1848,54 @@ fn resolve_store_path(store_path: Option<String>) -> PathBuf {
     PathBuf::from(home).join(".nclav").join("state.redb")
 }
 
+/// Open the store named by a `nclav state` spec: `redb:<path>`, `sqlite:<path>`
+/// or a `postgres://` URL. `None` means the default redb file.
+async fn open_store_spec(spec: Option<&str>) -> Result<Arc<dyn StateStore>> {
+    let Some(spec) = spec else {
+        let path = resolve_store_path(None);
+        return Ok(Arc::new(
+            RedbStore::open(&path)
+                .with_context(|| format!("Failed to open store at {}", path.display()))?,
+        ));
+    };
+    if spec.starts_with("postgres://") || spec.starts_with("postgresql://") {
+        return Ok(Arc::new(
+            PostgresStore::connect(spec)
+                .await
+                .context("Failed to connect to PostgreSQL")?,
+        ));
+    }
+    let expand = |p: &str| match p.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into())).join(rest),
+        None => PathBuf::from(p),
+    };
+    if let Some(path) = spec.strip_prefix("redb:") {
+        let path = expand(path);
+        Ok(Arc::new(
+            RedbStore::open(&path)
+                .with_context(|| format!("Failed to open store at {}", path.display()))?,
+        ))
+    } else if let Some(path) = spec.strip_prefix("sqlite:") {
+        let path = expand(path);
+        Ok(Arc::new(
+            SqliteStore::open(&path)
+                .await
+                .with_context(|| format!("Failed to open SQLite store at {}", path.display()))?,
+        ))
+    } else {
+        anyhow::bail!(
+            "unrecognised store '{}': expected redb:<path>, sqlite:<path> or postgres://…",
+            spec
+        )
+    }
+}
+
 async fn api_reconcile(
     url: &str,
-    enclaves_dir: &PathBuf,
+    enclaves_dir: &Path,
     dry_run: bool,
     resources_only: bool,
-    token: &str,
+    token: Option<&str>,
 ) -> Result<()> {
     let endpoint = if dry_run {
         format!("{}/reconcile/dry-run", url.trim_end_matches('/'))
@@ -831,6 +1926,16 @@ async fn api_reconcile(
         }
     }
 
+    // Dry runs list who depends on each updated enclave or partition.
+    if let Some(impact) = report.get("impact").and_then(|i| i.as_array()) {
+        for entry in impact {
+            let dependents: Vec<Dependent> =
+                serde_json::from_value(entry["dependents"].clone()).unwrap_or_default();
+            println!("{} affects:", entry["change"]);
+            print!("{}", output::render_impact_text(&dependents));
+        }
+    }
+
     let n_changes = report
         .get("changes")
         .and_then(|c| c.as_array())
@@ -851,5 +1956,14 @@ async fn api_reconcile(
         }
     }
 
+    if let Some(warnings) = report.get("warnings").and_then(|w| w.as_array()) {
+        if !warnings.is_empty() {
+            eprintln!("\n{} warning(s):", warnings.len());
+            for w in warnings {
+                eprintln!("  ~ {}", w);
+            }
+        }
+    }
+
     Ok(())
 }
@@ -1,99 +1,100 @@
 mod cli;
 mod commands;
 mod output;
+mod scaffold;
 
 use anyhow::Result;
-use cli::{Cli, Command, IacCommand};
+use cli::{
+    AdminCommand, Cli, Command, IacCommand, IacStateCommand, NewCommand, StateCommand, TokenCommand,
+};
 use clap::Parser;
-use tracing_subscriber::EnvFilter;
+use nclav_api::otel::{OtlpLayer, OtlpOptions};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_target(false)
+    let cli = Cli::parse();
+
+    // Log per RUST_LOG; when serving with an OTLP endpoint, also export spans.
+    let otlp = match &cli.command {
+        Command::Serve(args) => match &args.otlp_endpoint {
+            Some(endpoint) => Some(OtlpLayer::new(OtlpOptions {
+                endpoint: endpoint.clone(),
+                headers: args.otlp_headers.clone(),
+                service_name: args.otel_service_name.clone(),
+            })?),
+            None => None,
+        },
+        _ => None,
+    };
+    let spans = |meta: &tracing::Metadata<'_>| meta.is_span() && *meta.level() <= tracing::Level::INFO;
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_target(false).with_filter(EnvFilter::from_default_env()))
+        .with(otlp.map(|layer| layer.with_filter(filter_fn(spans))))
         .init();
 
-    let cli = Cli::parse();
+    commands::init_client_tls(cli.ca_cert.as_deref(), cli.client_cert.as_deref(), cli.client_key.as_deref())?;
 
     match cli.command {
-        Command::Serve {
-            cloud,
-            enable_cloud,
-            ephemeral,
-            rotate_token,
-            store_path,
-            postgres_url,
-            gcp_parent,
-            gcp_billing_account,
-            gcp_default_region,
-            gcp_project_prefix,
-            azure_tenant_id,
-            azure_management_group_id,
-            azure_billing_account_name,
-            azure_billing_profile_name,
-            azure_invoice_section_name,
-            azure_default_location,
-            azure_subscription_prefix,
-            azure_client_id,
-            azure_client_secret,
-            aws_org_unit_id,
-            aws_email_domain,
-            aws_default_region,
-            aws_account_prefix,
-            aws_cross_account_role,
-            aws_role_arn,
-            port,
-            bind,
-        } => {
-            commands::serve(
-                cloud,
-                enable_cloud,
-                cli.remote,
-                ephemeral,
-                rotate_token,
-                store_path,
-                postgres_url,
-                gcp_parent,
-                gcp_billing_account,
-                gcp_default_region,
-                gcp_project_prefix,
-                azure_tenant_id,
-                azure_management_group_id,
-                azure_billing_account_name,
-                azure_billing_profile_name,
-                azure_invoice_section_name,
-                azure_default_location,
-                azure_subscription_prefix,
-                azure_client_id,
-                azure_client_secret,
-                aws_org_unit_id,
-                aws_email_domain,
-                aws_default_region,
-                aws_account_prefix,
-                aws_cross_account_role,
-                aws_role_arn,
-                port,
-                bind,
-            )
-            .await
-        }
+        Command::Serve(args) => commands::serve(*args, cli.remote, cli.ca_cert).await,
         Command::Apply { enclaves_dir, resources_only } => {
             commands::apply(enclaves_dir, resources_only, cli.remote, cli.token).await
         }
         Command::Diff { enclaves_dir } => {
             commands::diff(enclaves_dir, cli.remote, cli.token).await
         }
+        Command::Validate { enclaves_dir, policy } => commands::validate(enclaves_dir, policy),
+        Command::MigrateConfig { dir } => commands::migrate_config(dir),
+        Command::New { command } => match command {
+            NewCommand::Enclave { id, cloud, region, dir } => {
+                commands::new_enclave(id, cloud, region, dir)
+            }
+            NewCommand::Partition { enclave, id, produces, module, cloud, dir } => {
+                commands::new_partition(enclave, id, produces, module, cloud, dir)
+            }
+        },
         Command::Status => commands::status(cli.remote, cli.token).await,
         Command::Graph { output, enclave } => {
             commands::graph(output, enclave, cli.remote, cli.token).await
         }
+        Command::Impact { target, output } => {
+            commands::impact(target, output, cli.remote, cli.token).await
+        }
         Command::Orphans { enclave } => {
             commands::orphans(enclave, cli.remote, cli.token).await
         }
-        Command::Destroy { enclave_ids, all, partition, yes, resources_only } => {
-            commands::destroy(enclave_ids, all, partition, yes, resources_only, cli.remote, cli.token).await
+        Command::Destroy { enclave_ids, all, partition, yes, resources_only, retry } => {
+            if retry {
+                commands::destroy_retry(enclave_ids, resources_only, cli.remote, cli.token).await
+            } else {
+                commands::destroy(enclave_ids, all, partition, yes, resources_only, cli.remote, cli.token).await
+            }
         }
+        Command::Events(args) => commands::events(args, cli.remote, cli.token).await,
+        Command::State { command } => match command {
+            StateCommand::Export { store, output } => commands::state_export(store, output).await,
+            StateCommand::Import { file, store, force } => {
+                commands::state_import(file, store, force).await
+            }
+            StateCommand::Migrate { from, to, force } => {
+                commands::state_migrate(from, to, force).await
+            }
+            StateCommand::RotateKey { store, key_file, previous_key_file } => {
+                commands::state_rotate_key(store, key_file, previous_key_file).await
+            }
+        },
+        Command::Admin { command } => match command {
+            AdminCommand::Prune { dry_run } => commands::admin_prune(dry_run, cli.remote, cli.token).await,
+        },
+        Command::Token { command } => match command {
+            TokenCommand::Create { name, scopes, enclaves } => {
+                commands::token_create(name, scopes, enclaves, cli.remote, cli.token).await
+            }
+            TokenCommand::List => commands::token_list(cli.remote, cli.token).await,
+            TokenCommand::Revoke { name } => commands::token_revoke(name, cli.remote, cli.token).await,
+        },
         Command::Iac { command } => match command {
             IacCommand::Runs { enclave_id, partition_id } => {
                 commands::iac_runs(enclave_id, partition_id, cli.remote, cli.token).await
@@ -101,6 +102,20 @@ async fn main() -> Result<()> {
             IacCommand::Logs { enclave_id, partition_id, run_id } => {
                 commands::iac_logs(enclave_id, partition_id, run_id, cli.remote, cli.token).await
             }
+            IacCommand::Locks => commands::iac_locks(cli.remote, cli.token).await,
+            IacCommand::Unlock { enclave_id, partition_id, force, lock_id } => {
+                commands::iac_unlock(enclave_id, partition_id, force, lock_id, cli.remote, cli.token)
+                    .await
+            }
+            IacCommand::State { command } => match command {
+                IacStateCommand::Versions { enclave_id, partition_id } => {
+                    commands::iac_state_versions(enclave_id, partition_id, cli.remote, cli.token).await
+                }
+                IacStateCommand::Restore { enclave_id, partition_id, version } => {
+                    commands::iac_state_restore(enclave_id, partition_id, version, cli.remote, cli.token)
+                        .await
+                }
+            },
         },
     }
 }
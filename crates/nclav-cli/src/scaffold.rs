@@ -0,0 +1,193 @@
+//! File templates for `nclav new enclave` and `nclav new partition`.
+//!
+//! Every generated partition satisfies the static contract checked by
+//! `nclav validate`: each `inputs:` key has a `variable`, each required variable is
+//! supplied, and `declared_outputs` matches both the `output` blocks and
+//! `ProducesType::required_outputs`.
+
+use nclav_config::ApiVersion;
+use nclav_domain::{CloudTarget, ProducesType};
+use nclav_driver::{context_var_names, PREAMBLE_VARS};
+
+/// Region written into a new enclave's `config.yml` when `--region` is not given.
+pub fn default_region(cloud: &CloudTarget) -> &'static str {
+    match cloud {
+        CloudTarget::Local => "local",
+        CloudTarget::Gcp => "us-central1",
+        CloudTarget::Azure => "eastus2",
+        CloudTarget::Aws => "us-east-1",
+    }
+}
+
+/// `config.yml` for a new enclave with no partitions, exports or imports.
+pub fn enclave_config(id: &str, cloud: &CloudTarget, region: &str) -> String {
+    let api_version = ApiVersion::CURRENT;
+    format!(
+        "apiVersion: {api_version}\n\
+         id: {id}\n\
+         name: {id}\n\
+         cloud: {cloud}\n\
+         region: {region}\n\
+         \n\
+         # Partitions are discovered from subdirectories; add one with\n\
+         #   nclav new partition {id} <partition-id>\n\
+         \n\
+         # network:\n\
+         #   vpc_cidr: \"10.0.0.0/16\"\n\
+         #   subnets:\n\
+         #     - \"10.0.1.0/24\"\n\
+         \n\
+         exports: []\n\
+         imports: []\n"
+    )
+}
+
+/// Context vars that must be mapped through `inputs:` — the driver's tokens minus
+/// those the tfvars preamble already writes.
+fn input_vars(cloud: &CloudTarget) -> Vec<&'static str> {
+    context_var_names(cloud)
+        .iter()
+        .copied()
+        .filter(|v| !PREAMBLE_VARS.contains(v))
+        .collect()
+}
+
+/// `config.yml` for a new partition. `module` selects a module-backed partition.
+pub fn partition_config(
+    id: &str,
+    cloud: &CloudTarget,
+    produces: Option<&ProducesType>,
+    module: Option<&str>,
+) -> String {
+    let mut out = format!("apiVersion: {}\nid: {id}\nname: {id}\n", ApiVersion::CURRENT);
+    if let Some(p) = produces {
+        out.push_str(&format!("produces: {p}\n"));
+    }
+    out.push_str("backend: terraform\n");
+    if let Some(source) = module {
+        out.push_str(&format!("terraform:\n  source: {source:?}\n"));
+    }
+
+    let vars = input_vars(cloud);
+    if vars.is_empty() {
+        out.push_str("\ninputs: {}\n");
+    } else {
+        out.push_str("\ninputs:\n");
+        for v in vars {
+            out.push_str(&format!("  {v}: \"{{{{ {v} }}}}\"\n"));
+        }
+    }
+
+    match produces {
+        Some(p) => {
+            out.push_str("\ndeclared_outputs:\n");
+            for key in p.required_outputs() {
+                out.push_str(&format!("  - {key}\n"));
+            }
+        }
+        None => out.push_str("\ndeclared_outputs: []\n"),
+    }
+    out
+}
+
+const GCP_PROVIDER: &str = r#"terraform {
+  required_providers {
+    google = {
+      source  = "hashicorp/google"
+      version = "~> 5.0"
+    }
+  }
+}
+
+provider "google" {
+  project = var.nclav_project_id
+  region  = var.nclav_region
+}
+
+"#;
+
+const AZURE_PROVIDER: &str = r#"terraform {
+  required_providers {
+    azurerm = {
+      source  = "hashicorp/azurerm"
+      version = "~> 4.0"
+    }
+  }
+}
+
+provider "azurerm" {
+  features {}
+  subscription_id = var.nclav_subscription_id
+}
+
+"#;
+
+const AWS_PROVIDER: &str = r#"terraform {
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = "~> 5.0"
+    }
+  }
+}
+
+provider "aws" {
+  region = var.nclav_region
+}
+
+"#;
+
+/// `main.tf` for a new raw-Terraform partition: provider and the labeling locals.
+pub fn partition_main_tf(id: &str, cloud: &CloudTarget) -> String {
+    let provider = match cloud {
+        CloudTarget::Local => "",
+        CloudTarget::Gcp => GCP_PROVIDER,
+        CloudTarget::Azure => AZURE_PROVIDER,
+        CloudTarget::Aws => AWS_PROVIDER,
+    };
+    format!(
+        "# Partition {id}.\n\
+         #\n\
+         # Variables are supplied by nclav via nclav_context.auto.tfvars.json; see\n\
+         # variables.tf and inputs: in config.yml.\n\
+         \n\
+         {provider}\
+         locals {{\n  \
+           nclav_labels = {{\n    \
+             \"nclav-enclave\"   = var.nclav_enclave\n    \
+             \"nclav-partition\" = var.nclav_partition\n    \
+             \"nclav-managed\"   = \"true\"\n  \
+           }}\n\
+         }}\n\
+         \n\
+         # Add resources here and apply local.nclav_labels to each of them.\n"
+    )
+}
+
+/// `variables.tf` for a new raw-Terraform partition: the preamble vars plus every
+/// context var the cloud's driver provides.
+pub fn partition_variables_tf(cloud: &CloudTarget) -> String {
+    let mut out = String::from("# Always injected by nclav.\n");
+    for v in PREAMBLE_VARS {
+        out.push_str(&format!("variable \"{v}\" {{ default = \"\" }}\n"));
+    }
+    let vars = input_vars(cloud);
+    if !vars.is_empty() {
+        out.push_str(&format!("\n# Context from the {cloud} driver, mapped via inputs: in config.yml.\n"));
+        for v in vars {
+            out.push_str(&format!("variable \"{v}\" {{}}\n"));
+        }
+    }
+    out
+}
+
+/// `outputs.tf` for a new raw-Terraform partition, one block per required output.
+pub fn partition_outputs_tf(produces: Option<&ProducesType>) -> String {
+    let mut out = String::from("# Must match declared_outputs in config.yml.\n");
+    for key in produces.map(|p| p.required_outputs()).unwrap_or(&[]) {
+        out.push_str(&format!(
+            "\noutput \"{key}\" {{\n  value = \"\" # TODO: set from your resources\n}}\n"
+        ));
+    }
+    out
+}
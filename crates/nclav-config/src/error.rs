@@ -16,6 +16,9 @@ pub enum ConfigError {
         source: serde_yaml::Error,
     },
 
+    #[error("unsupported apiVersion '{version}' in {path}; expected nclav.dev/v1alpha1 or nclav.dev/v1")]
+    UnsupportedApiVersion { path: String, version: String },
+
     #[error("conversion error in {path}: {message}")]
     Conversion { path: String, message: String },
 
@@ -1,6 +1,8 @@
 mod raw;
 mod loader;
+mod migrate;
 pub mod error;
 
 pub use loader::load_enclaves;
 pub use error::ConfigError;
+pub use migrate::{migrate_config_dir, ApiVersion, MigratedFile, MigrationOutcome};
@@ -2,11 +2,13 @@ use std::path::Path;
 
 use nclav_domain::{
     AuthType, CloudTarget, DnsConfig, Enclave, EnclaveId, Export, ExportTarget, ExportType, Import,
-    NetworkConfig, Partition, PartitionBackend, PartitionId, ProducesType, TerraformConfig,
+    NetworkConfig, Partition, PartitionBackend, PartitionId, PolicyException, ProducesType,
+    TerraformConfig,
 };
 use tracing::debug;
 
 use crate::error::ConfigError;
+use crate::migrate::{self, parse_enclave, parse_partition};
 use crate::raw::{RawEnclave, RawExport, RawExportTarget, RawImport, RawPartition};
 
 /// Walk `dir` and load every enclave found.
@@ -55,11 +57,15 @@ fn collect_enclaves(dir: &Path, out: &mut Vec<Enclave>) -> Result<(), ConfigErro
         })?;
         // Heuristic: a file is an enclave config if it has the required `id` field
         // (cloud is optional — absent means inherit the API's default cloud)
-        if let Ok(raw) = serde_yaml::from_str::<RawEnclave>(&content) {
-            debug!("Loading enclave from {}", config_path.display());
-            let enclave = convert_enclave(raw, dir, &config_path)?;
-            out.push(enclave);
-            return Ok(());
+        match parse_enclave(&content, &config_path) {
+            Ok(raw) => {
+                debug!("Loading enclave from {}", config_path.display());
+                let enclave = convert_enclave(raw, dir, &config_path)?;
+                out.push(enclave);
+                return Ok(());
+            }
+            Err(e) if migrate::is_unsupported_nclav_version(&e) => return Err(e),
+            Err(_) => {}
         }
     }
 
@@ -90,7 +96,7 @@ fn convert_enclave(
     let exports = raw
         .exports
         .into_iter()
-        .map(|e| convert_export(e, config_path))
+        .map(|e| convert_export(e, None, config_path))
         .collect::<Result<Vec<_>, _>>()?;
 
     // Load partitions: each name in raw.partitions is a subdirectory of dir
@@ -109,11 +115,7 @@ fn convert_enclave(
                 path: part_config.display().to_string(),
                 source: e,
             })?;
-        let raw_part: RawPartition =
-            serde_yaml::from_str(&content).map_err(|e| ConfigError::YamlParse {
-                path: part_config.display().to_string(),
-                source: e,
-            })?;
+        let raw_part = parse_partition(&content, &part_config)?;
         partitions.push(convert_partition(raw_part, &part_config)?);
     }
 
@@ -135,14 +137,18 @@ fn convert_enclave(
                         source: e,
                     })?;
                 // Try to parse as partition
-                if let Ok(raw_part) = serde_yaml::from_str::<RawPartition>(&content) {
-                    if raw_part.produces.is_some()
-                        || !raw_part.imports.is_empty()
-                        || !raw_part.exports.is_empty()
-                        || !raw_part.declared_outputs.is_empty()
-                    {
-                        partitions.push(convert_partition(raw_part, &part_config)?);
-                    }
+                let raw_part = match parse_partition(&content, &part_config) {
+                    Ok(raw_part) => raw_part,
+                    Err(e @ ConfigError::Conversion { .. }) => return Err(e),
+                    Err(e) if migrate::declares_nclav_version(&content) => return Err(e),
+                    Err(_) => continue,
+                };
+                if raw_part.produces.is_some()
+                    || !raw_part.imports.is_empty()
+                    || !raw_part.exports.is_empty()
+                    || !raw_part.declared_outputs.is_empty()
+                {
+                    partitions.push(convert_partition(raw_part, &part_config)?);
                 }
             }
         }
@@ -155,6 +161,20 @@ fn convert_enclave(
 
     let dns = raw.dns.map(|d| DnsConfig { zone: d.zone });
 
+    let policy_exceptions = raw
+        .policy_exceptions
+        .into_iter()
+        .map(|e| {
+            if e.reason.trim().is_empty() {
+                return Err(ConfigError::Conversion {
+                    path: config_path.display().to_string(),
+                    message: format!("policy exception for rule '{}' needs a reason", e.rule),
+                });
+            }
+            Ok(PolicyException { rule: e.rule, reason: e.reason })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     Ok(Enclave {
         id: EnclaveId::new(&raw.id),
         name: raw.name,
@@ -166,6 +186,9 @@ fn convert_enclave(
         imports,
         exports,
         partitions,
+        labels: raw.labels,
+        policy_exceptions,
+        source: Some(config_path.to_path_buf()),
     })
 }
 
@@ -179,14 +202,14 @@ fn convert_partition(raw: RawPartition, path: &Path) -> Result<Partition, Config
     let exports = raw
         .exports
         .into_iter()
-        .map(|e| convert_export(e, path))
+        .map(|e| convert_export(e, Some(&raw.id), path))
         .collect::<Result<Vec<_>, _>>()?;
 
     // The partition directory is the parent of config.yml.
     let dir = path.parent().unwrap_or(path).to_path_buf();
 
     let backend = match raw.backend.as_str() {
-        "" | "terraform" => PartitionBackend::Terraform(TerraformConfig {
+        "terraform" => PartitionBackend::Terraform(TerraformConfig {
             tool: raw.terraform.as_ref().and_then(|t| t.tool.clone()),
             source: raw.terraform.as_ref().and_then(|t| t.source.clone()),
             dir,
@@ -196,12 +219,6 @@ fn convert_partition(raw: RawPartition, path: &Path) -> Result<Partition, Config
             source: raw.terraform.as_ref().and_then(|t| t.source.clone()),
             dir,
         }),
-        "managed" => {
-            return Err(ConfigError::Conversion {
-                path: path.display().to_string(),
-                message: "backend 'managed' is no longer supported; use 'terraform' or 'opentofu' with a main.tf in the partition directory".to_string(),
-            });
-        }
         other => {
             return Err(ConfigError::Conversion {
                 path: path.display().to_string(),
@@ -222,22 +239,46 @@ fn convert_partition(raw: RawPartition, path: &Path) -> Result<Partition, Config
     })
 }
 
-fn convert_import(raw: RawImport, _path: &Path) -> Result<Import, ConfigError> {
+fn convert_import(raw: RawImport, path: &Path) -> Result<Import, ConfigError> {
+    let import_type = raw.import_type.as_deref().map(|s| parse_export_type(s, path)).transpose()?;
     Ok(Import {
         from: EnclaveId::new(&raw.from),
         export_name: raw.export_name,
         alias: raw.alias,
+        import_type,
     })
 }
 
-fn convert_export(raw: RawExport, path: &Path) -> Result<Export, ConfigError> {
+/// `owner` is the declaring partition for partition-level exports, `None` for
+/// enclave-level ones. A partition may only export itself.
+fn convert_export(raw: RawExport, owner: Option<&str>, path: &Path) -> Result<Export, ConfigError> {
     let export_type = parse_export_type(&raw.export_type, path)?;
     let auth = parse_auth(&raw.auth, path)?;
     let to = convert_export_target(raw.to, path)?;
 
+    let target_partition = match (owner, raw.target_partition.as_str()) {
+        (None, "") => {
+            return Err(ConfigError::Conversion {
+                path: path.display().to_string(),
+                message: format!("export '{}' is missing target_partition", raw.name),
+            });
+        }
+        (Some(owner), "") => owner.to_string(),
+        (Some(owner), target) if target != owner => {
+            return Err(ConfigError::Conversion {
+                path: path.display().to_string(),
+                message: format!(
+                    "partition export '{}' targets '{}'; a partition can only export itself",
+                    raw.name, target
+                ),
+            });
+        }
+        (_, target) => target.to_string(),
+    };
+
     Ok(Export {
         name: raw.name,
-        target_partition: PartitionId::new(&raw.target_partition),
+        target_partition: PartitionId::new(target_partition),
         export_type,
         to,
         auth,
@@ -250,7 +291,7 @@ fn convert_export_target(raw: RawExportTarget, path: &Path) -> Result<ExportTarg
     match raw {
         RawExportTarget::Simple(s) => match s.as_str() {
             "public" => Ok(ExportTarget::Public),
-            "any_enclave" | "any-enclave" => Ok(ExportTarget::AnyEnclave),
+            "any_enclave" => Ok(ExportTarget::AnyEnclave),
             "vpn" => Ok(ExportTarget::Vpn),
             other => Err(ConfigError::Conversion {
                 path: path.display().to_string(),
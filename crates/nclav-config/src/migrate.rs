@@ -0,0 +1,359 @@
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::error::ConfigError;
+use crate::raw::{v1alpha1, RawEnclave, RawExport, RawExportTarget, RawPartition};
+
+// ── Schema versions ───────────────────────────────────────────────────────────
+
+/// Config schema version, declared by `apiVersion:` at the top of a config file.
+/// Files without `apiVersion:` are `nclav.dev/v1alpha1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ApiVersion {
+    V1Alpha1,
+    V1,
+}
+
+impl ApiVersion {
+    /// The version written by `nclav migrate-config` and `nclav new`.
+    pub const CURRENT: ApiVersion = ApiVersion::V1;
+
+    const ALL: [ApiVersion; 2] = [ApiVersion::V1Alpha1, ApiVersion::V1];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1Alpha1 => "nclav.dev/v1alpha1",
+            ApiVersion::V1 => "nclav.dev/v1",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.as_str() == s)
+    }
+}
+
+impl std::fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigKind {
+    Enclave,
+    Partition,
+}
+
+fn yaml<T: DeserializeOwned>(content: &str, path: &Path) -> Result<T, ConfigError> {
+    serde_yaml::from_str(content).map_err(|e| ConfigError::YamlParse {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+/// `true` for an `apiVersion` this build does not know that is nonetheless in the
+/// `nclav.dev/` group. Other config.yml files (e.g. Kubernetes manifests) are not ours.
+pub(crate) fn is_unsupported_nclav_version(err: &ConfigError) -> bool {
+    matches!(err, ConfigError::UnsupportedApiVersion { version, .. } if version.starts_with(API_GROUP))
+}
+
+/// `true` if the file declares an `apiVersion` in the `nclav.dev/` group, known
+/// or not. Such a file is ours, so failing to parse it is an error, not a skip.
+pub(crate) fn declares_nclav_version(content: &str) -> bool {
+    serde_yaml::from_str::<serde_yaml::Value>(content)
+        .ok()
+        .and_then(|doc| doc.get("apiVersion")?.as_str().map(|v| v.starts_with(API_GROUP)))
+        .unwrap_or(false)
+}
+
+const API_GROUP: &str = "nclav.dev/";
+
+fn detect_version(content: &str, path: &Path) -> Result<ApiVersion, ConfigError> {
+    let doc: serde_yaml::Value = yaml(content, path)?;
+    match doc.get("apiVersion") {
+        None => Ok(ApiVersion::V1Alpha1),
+        Some(v) => {
+            let s = v.as_str().unwrap_or_default();
+            ApiVersion::parse(s).ok_or_else(|| ConfigError::UnsupportedApiVersion {
+                path: path.display().to_string(),
+                version: s.to_string(),
+            })
+        }
+    }
+}
+
+// ── Versioned parsing ─────────────────────────────────────────────────────────
+
+/// Parse an enclave config of any supported version into the current `RawEnclave`.
+pub(crate) fn parse_enclave(content: &str, path: &Path) -> Result<RawEnclave, ConfigError> {
+    match detect_version(content, path)? {
+        ApiVersion::V1Alpha1 => Ok(enclave_from_v1alpha1(yaml(content, path)?)),
+        ApiVersion::V1 => yaml(content, path),
+    }
+}
+
+/// Parse a partition config of any supported version into the current `RawPartition`.
+pub(crate) fn parse_partition(content: &str, path: &Path) -> Result<RawPartition, ConfigError> {
+    match detect_version(content, path)? {
+        ApiVersion::V1Alpha1 => partition_from_v1alpha1(yaml(content, path)?, path),
+        ApiVersion::V1 => yaml(content, path),
+    }
+}
+
+fn enclave_from_v1alpha1(raw: v1alpha1::RawEnclave) -> RawEnclave {
+    RawEnclave {
+        id: raw.id,
+        name: raw.name,
+        cloud: raw.cloud,
+        region: raw.region,
+        identity: raw.identity,
+        network: raw.network,
+        dns: raw.dns,
+        imports: raw.imports,
+        exports: raw.exports.into_iter().map(export_from_v1alpha1).collect(),
+        partitions: raw.partitions,
+        labels: raw.labels,
+        policy_exceptions: raw.policy_exceptions,
+    }
+}
+
+fn export_from_v1alpha1(mut raw: RawExport) -> RawExport {
+    if let RawExportTarget::Simple(s) = &mut raw.to {
+        if s == "any-enclave" {
+            *s = "any_enclave".into();
+        }
+    }
+    raw
+}
+
+fn partition_from_v1alpha1(
+    raw: v1alpha1::RawPartition,
+    path: &Path,
+) -> Result<RawPartition, ConfigError> {
+    let backend = match raw.backend.as_deref() {
+        None | Some("") => "terraform".to_string(),
+        Some("managed") => {
+            return Err(ConfigError::Conversion {
+                path: path.display().to_string(),
+                message: "backend 'managed' is no longer supported; use 'terraform' or 'opentofu' with a main.tf in the partition directory".to_string(),
+            });
+        }
+        Some(other) => other.to_string(),
+    };
+    Ok(RawPartition {
+        id: raw.id,
+        name: raw.name,
+        produces: raw.produces,
+        imports: raw.imports,
+        exports: raw.exports.into_iter().map(export_from_v1alpha1).collect(),
+        inputs: raw.inputs,
+        declared_outputs: raw.declared_outputs,
+        backend,
+        terraform: raw.terraform,
+    })
+}
+
+// ── In-place migration ────────────────────────────────────────────────────────
+
+/// What [`migrate_config_dir`] did to a single `config.yml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationOutcome {
+    /// Already at [`ApiVersion::CURRENT`]; left untouched.
+    UpToDate,
+    /// Upgraded by editing individual lines; comments and layout are preserved.
+    Rewritten { from: ApiVersion },
+    /// Upgraded by re-serializing the whole document; comments were lost.
+    Reserialized { from: ApiVersion },
+    /// Not recognisable as an enclave or partition config; left untouched.
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigratedFile {
+    pub path: PathBuf,
+    pub outcome: MigrationOutcome,
+}
+
+/// Upgrade every `config.yml` under `dir` to [`ApiVersion::CURRENT`], in place.
+///
+/// Each file is first rewritten line by line so comments survive. The result is
+/// parsed back and compared with a typed conversion of the original; if the two
+/// disagree (e.g. flow-style YAML the line edits cannot handle), the file is
+/// re-serialized from the typed conversion instead.
+pub fn migrate_config_dir(dir: &Path) -> Result<Vec<MigratedFile>, ConfigError> {
+    let mut paths = Vec::new();
+    collect_config_files(dir, &mut paths)?;
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let outcome = migrate_config_file(&path)?;
+            Ok(MigratedFile { path, outcome })
+        })
+        .collect()
+}
+
+fn collect_config_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), ConfigError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| ConfigError::Io {
+        path: dir.display().to_string(),
+        source: e,
+    })?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_config_files(&path, out)?;
+        } else if path.file_name().is_some_and(|n| n == "config.yml") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn migrate_config_file(path: &Path) -> Result<MigrationOutcome, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(|e| ConfigError::Io {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    let from = match detect_version(&content, path) {
+        Ok(v) => v,
+        Err(e) if is_unsupported_nclav_version(&e) => return Err(e),
+        Err(_) => return Ok(MigrationOutcome::Skipped),
+    };
+    if from == ApiVersion::CURRENT {
+        return Ok(MigrationOutcome::UpToDate);
+    }
+    let Some(kind) = detect_kind(&content) else {
+        return Ok(MigrationOutcome::Skipped);
+    };
+
+    let expected = parse_as_json(kind, &content, path)?;
+    let rewritten = rewrite(&content, from, kind);
+    let (new_content, outcome) = match parse_as_json(kind, &rewritten, path) {
+        Ok(v) if v == expected => (rewritten, MigrationOutcome::Rewritten { from }),
+        _ => (reserialize(kind, &content, path)?, MigrationOutcome::Reserialized { from }),
+    };
+
+    std::fs::write(path, new_content).map_err(|e| ConfigError::Io {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    Ok(outcome)
+}
+
+/// Enclave configs carry `region:`; partition configs do not.
+fn detect_kind(content: &str) -> Option<ConfigKind> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    doc.get("id")?;
+    if doc.get("region").is_some() {
+        Some(ConfigKind::Enclave)
+    } else {
+        Some(ConfigKind::Partition)
+    }
+}
+
+fn parse_as_json(
+    kind: ConfigKind,
+    content: &str,
+    path: &Path,
+) -> Result<serde_json::Value, ConfigError> {
+    let value = match kind {
+        ConfigKind::Enclave => serde_json::to_value(parse_enclave(content, path)?),
+        ConfigKind::Partition => serde_json::to_value(parse_partition(content, path)?),
+    };
+    value.map_err(|e| ConfigError::Conversion {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn reserialize(kind: ConfigKind, content: &str, path: &Path) -> Result<String, ConfigError> {
+    let body = match kind {
+        ConfigKind::Enclave => serde_yaml::to_string(&parse_enclave(content, path)?),
+        ConfigKind::Partition => serde_yaml::to_string(&parse_partition(content, path)?),
+    }
+    .map_err(|e| ConfigError::YamlParse {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    Ok(format!("apiVersion: {}\n{}", ApiVersion::CURRENT, body))
+}
+
+// ── Line-level rewrites ───────────────────────────────────────────────────────
+
+/// Apply every line-level upgrade step after `from`, then stamp the current version.
+fn rewrite(content: &str, from: ApiVersion, kind: ConfigKind) -> String {
+    let mut text = content.to_string();
+    if from <= ApiVersion::V1Alpha1 {
+        text = rewrite_v1alpha1_to_v1(&text, kind);
+    }
+    set_api_version(&text, ApiVersion::CURRENT)
+}
+
+/// The value of a `key: value` line with any trailing comment and quotes removed.
+fn line_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = line.trim_start().trim_start_matches("- ").trim_start();
+    let value = rest.strip_prefix(key)?.strip_prefix(':')?;
+    let value = value.split(" #").next().unwrap_or_default().trim();
+    Some(value.trim_matches(|c| c == '"' || c == '\''))
+}
+
+fn rewrite_v1alpha1_to_v1(content: &str, kind: ConfigKind) -> String {
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if line_value(line, "to") == Some("any-enclave") {
+                line.replacen("any-enclave", "any_enclave", 1)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+
+    if kind == ConfigKind::Partition {
+        let top_level = |l: &String, key: &str| !l.starts_with([' ', '\t']) && line_value(l, key).is_some();
+        match lines.iter().position(|l| top_level(l, "backend")) {
+            Some(i) if line_value(&lines[i], "backend") == Some("") => {
+                lines[i] = "backend: terraform".into();
+            }
+            Some(_) => {}
+            None => {
+                let anchor = lines
+                    .iter()
+                    .position(|l| top_level(l, "produces"))
+                    .or_else(|| lines.iter().position(|l| top_level(l, "name")));
+                let at = anchor.map_or(lines.len(), |i| i + 1);
+                lines.insert(at, "backend: terraform".into());
+            }
+        }
+    }
+
+    let mut out = lines.join("\n");
+    if content.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Replace an existing top-level `apiVersion:` line, or insert one before the first
+/// line of content (after any leading comments and document markers).
+fn set_api_version(content: &str, version: ApiVersion) -> String {
+    let line = format!("apiVersion: {}", version);
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    if let Some(i) = lines.iter().position(|l| l.starts_with("apiVersion:")) {
+        lines[i] = line;
+    } else {
+        let at = lines
+            .iter()
+            .position(|l| {
+                let t = l.trim();
+                !(t.is_empty() || t.starts_with('#') || t == "---")
+            })
+            .unwrap_or(lines.len());
+        lines.insert(at, line);
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
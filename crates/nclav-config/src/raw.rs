@@ -1,3 +1,8 @@
+//! Raw YAML representations of the config files.
+//!
+//! The top-level structs describe the current schema (`nclav.dev/v1`). Older schema
+//! versions live in submodules and are converted forward by [`crate::migrate`].
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -18,6 +23,19 @@ pub struct RawEnclave {
     pub exports: Vec<RawExport>,
     #[serde(default)]
     pub partitions: Vec<String>,
+    /// Free-form labels matched by organization policy rules.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    /// Policy rules this enclave is exempt from, each with a justification.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_exceptions: Vec<RawPolicyException>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RawPolicyException {
+    pub rule: String,
+    #[serde(default)]
+    pub reason: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -43,11 +61,10 @@ pub struct RawPartition {
     #[serde(default)]
     pub exports: Vec<RawExport>,
     #[serde(default)]
-    pub inputs: HashMap<String, String>,
+    pub inputs: HashMap<String, serde_json::Value>,
     #[serde(default)]
     pub declared_outputs: Vec<String>,
-    /// "terraform" (default) or "opentofu".
-    #[serde(default)]
+    /// "terraform" or "opentofu".
     pub backend: String,
     /// Present when `backend` is "terraform" or "opentofu".
     pub terraform: Option<RawTerraformConfig>,
@@ -64,6 +81,8 @@ pub struct RawTerraformConfig {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RawExport {
     pub name: String,
+    /// Required on enclave exports; defaults to the declaring partition on partition exports.
+    #[serde(default)]
     pub target_partition: String,
     #[serde(rename = "type")]
     pub export_type: String,
@@ -91,4 +110,59 @@ pub struct RawImport {
     pub from: String,
     pub export_name: String,
     pub alias: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub import_type: Option<String>,
+}
+
+/// `nclav.dev/v1alpha1` — the original format, used for files without `apiVersion:`.
+///
+/// Differences from v1:
+/// - `backend:` may be omitted (meaning terraform) or set to the removed `managed`.
+/// - export `to:` also accepts the `any-enclave` spelling.
+pub mod v1alpha1 {
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    use super::{RawDns, RawExport, RawImport, RawNetwork, RawPolicyException, RawTerraformConfig};
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct RawEnclave {
+        pub id: String,
+        pub name: String,
+        pub cloud: Option<String>,
+        pub region: String,
+        pub identity: Option<String>,
+        pub network: Option<RawNetwork>,
+        pub dns: Option<RawDns>,
+        #[serde(default)]
+        pub imports: Vec<RawImport>,
+        #[serde(default)]
+        pub exports: Vec<RawExport>,
+        #[serde(default)]
+        pub partitions: Vec<String>,
+        // Not part of v1alpha1, but accepted so a policy label or exception is
+        // never silently dropped from an unmigrated file.
+        #[serde(default)]
+        pub labels: HashMap<String, String>,
+        #[serde(default)]
+        pub policy_exceptions: Vec<RawPolicyException>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct RawPartition {
+        pub id: String,
+        pub name: String,
+        pub produces: Option<String>,
+        #[serde(default)]
+        pub imports: Vec<RawImport>,
+        #[serde(default)]
+        pub exports: Vec<RawExport>,
+        #[serde(default)]
+        pub inputs: HashMap<String, serde_json::Value>,
+        #[serde(default)]
+        pub declared_outputs: Vec<String>,
+        /// "terraform", "opentofu", "managed", or absent (terraform).
+        pub backend: Option<String>,
+        pub terraform: Option<RawTerraformConfig>,
+    }
 }
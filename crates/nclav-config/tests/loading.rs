@@ -1,5 +1,8 @@
-use nclav_config::load_enclaves;
+use nclav_config::{load_enclaves, ConfigError};
+use nclav_domain::{ExportTarget, PartitionId};
+use std::fs;
 use std::path::Path;
+use tempfile::TempDir;
 
 #[test]
 fn load_valid_fixture() {
@@ -12,6 +15,17 @@ fn load_valid_fixture() {
     assert_eq!(enc.cloud, Some(nclav_domain::CloudTarget::Local));
 }
 
+#[test]
+fn partition_inputs_keep_yaml_structure() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let enclaves = load_enclaves(&dir).expect("should load without error");
+    let part = &enclaves[0].partitions[0];
+
+    assert_eq!(part.inputs["replicas"], serde_json::json!(2));
+    assert_eq!(part.inputs["zones"], serde_json::json!(["a", "b"]));
+    assert_eq!(part.inputs["labels"], serde_json::json!({ "tier": "web" }));
+}
+
 #[test]
 fn load_real_enclaves_fixture() {
     // Use the workspace-level enclaves directory
@@ -29,3 +43,63 @@ fn missing_dir_returns_error() {
     let dir = Path::new("/nonexistent/path/does/not/exist");
     assert!(load_enclaves(dir).is_err());
 }
+
+/// An enclave `shop` with one partition `db` exporting `db-tcp` with `extra` appended.
+fn partition_export_tree(extra: &str) -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let enc = tmp.path().join("shop");
+    fs::create_dir_all(enc.join("db")).unwrap();
+    fs::write(enc.join("config.yml"), "id: shop\nname: Shop\nregion: local\n").unwrap();
+    fs::write(
+        enc.join("db/config.yml"),
+        format!(
+            "id: db\nname: Database\nproduces: tcp\nexports:\n  - name: db-tcp\n    type: tcp\n    \
+             to:\n      partition: api\n{}",
+            extra
+        ),
+    )
+    .unwrap();
+    tmp
+}
+
+#[test]
+fn partition_export_targets_its_own_partition() {
+    let tmp = partition_export_tree("");
+    let enclaves = load_enclaves(tmp.path()).expect("should load without error");
+    let export = &enclaves[0].partitions[0].exports[0];
+
+    assert_eq!(export.target_partition.as_str(), "db");
+    assert_eq!(export.to, ExportTarget::Partition(PartitionId::new("api")));
+}
+
+#[test]
+fn partition_export_cannot_target_another_partition() {
+    let tmp = partition_export_tree("    target_partition: api\n");
+    let err = load_enclaves(tmp.path()).unwrap_err();
+    assert!(matches!(err, ConfigError::Conversion { .. }), "got {err:?}");
+}
+
+#[test]
+fn policy_exception_requires_reason() {
+    let tmp = TempDir::new().unwrap();
+    let enc = tmp.path().join("shop");
+    fs::create_dir_all(&enc).unwrap();
+    fs::write(
+        enc.join("config.yml"),
+        "id: shop\nname: Shop\nregion: local\nlabels:\n  env: prod\n\
+         policy_exceptions:\n  - rule: no-public-prod\n",
+    )
+    .unwrap();
+    let err = load_enclaves(tmp.path()).unwrap_err();
+    assert!(matches!(err, ConfigError::Conversion { .. }), "got {err:?}");
+
+    fs::write(
+        enc.join("config.yml"),
+        "id: shop\nname: Shop\nregion: local\nlabels:\n  env: prod\n\
+         policy_exceptions:\n  - rule: no-public-prod\n    reason: public storefront\n",
+    )
+    .unwrap();
+    let enclaves = load_enclaves(tmp.path()).expect("should load without error");
+    assert_eq!(enclaves[0].labels["env"], "prod");
+    assert_eq!(enclaves[0].policy_exceptions[0].reason, "public storefront");
+}
@@ -0,0 +1,139 @@
+use nclav_config::{load_enclaves, migrate_config_dir, ApiVersion, ConfigError, MigrationOutcome};
+use nclav_domain::{ExportTarget, PartitionBackend};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const LEGACY_ENCLAVE: &str = "\
+# Product A, dev.
+id: product-a
+name: Product A
+cloud: local
+region: local
+
+exports:
+  - name: api
+    target_partition: svc
+    type: http
+    to: any-enclave   # anyone may call it
+";
+
+const LEGACY_PARTITION: &str = "\
+id: svc
+name: Service
+produces: http
+
+declared_outputs:
+  - hostname
+  - port
+";
+
+fn write_tree(root: &Path, enclave: &str, partition: &str) {
+    fs::create_dir_all(root.join("a/svc")).unwrap();
+    fs::write(root.join("a/config.yml"), enclave).unwrap();
+    fs::write(root.join("a/svc/config.yml"), partition).unwrap();
+}
+
+#[test]
+fn unversioned_files_load_as_v1alpha1() {
+    let dir = TempDir::new().unwrap();
+    write_tree(dir.path(), LEGACY_ENCLAVE, LEGACY_PARTITION);
+
+    let enclaves = load_enclaves(dir.path()).unwrap();
+    assert_eq!(enclaves[0].exports[0].to, ExportTarget::AnyEnclave);
+    assert!(matches!(enclaves[0].partitions[0].backend, PartitionBackend::Terraform(_)));
+}
+
+#[test]
+fn migration_rewrites_in_place_and_keeps_comments() {
+    let dir = TempDir::new().unwrap();
+    write_tree(dir.path(), LEGACY_ENCLAVE, LEGACY_PARTITION);
+    let before = load_enclaves(dir.path()).unwrap();
+
+    let report = migrate_config_dir(dir.path()).unwrap();
+    assert_eq!(report.len(), 2);
+    for file in &report {
+        assert_eq!(file.outcome, MigrationOutcome::Rewritten { from: ApiVersion::V1Alpha1 });
+    }
+
+    let enclave = fs::read_to_string(dir.path().join("a/config.yml")).unwrap();
+    assert!(enclave.starts_with("# Product A, dev.\napiVersion: nclav.dev/v1\n"));
+    assert!(enclave.contains("to: any_enclave   # anyone may call it"));
+
+    let partition = fs::read_to_string(dir.path().join("a/svc/config.yml")).unwrap();
+    assert!(partition.contains("produces: http\nbackend: terraform\n"));
+
+    let after = load_enclaves(dir.path()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&before).unwrap(),
+        serde_json::to_value(&after).unwrap()
+    );
+}
+
+#[test]
+fn migration_is_idempotent() {
+    let dir = TempDir::new().unwrap();
+    write_tree(dir.path(), LEGACY_ENCLAVE, LEGACY_PARTITION);
+    migrate_config_dir(dir.path()).unwrap();
+
+    let report = migrate_config_dir(dir.path()).unwrap();
+    assert!(report.iter().all(|f| f.outcome == MigrationOutcome::UpToDate));
+}
+
+#[test]
+fn flow_style_yaml_falls_back_to_reserialization() {
+    let dir = TempDir::new().unwrap();
+    let enclave = "{ id: a, name: A, region: local, exports: [{ name: api, target_partition: svc, type: http, to: any-enclave }] }\n";
+    write_tree(dir.path(), enclave, LEGACY_PARTITION);
+
+    let report = migrate_config_dir(dir.path()).unwrap();
+    assert_eq!(report[0].outcome, MigrationOutcome::Reserialized { from: ApiVersion::V1Alpha1 });
+
+    let enclaves = load_enclaves(dir.path()).unwrap();
+    assert_eq!(enclaves[0].exports[0].to, ExportTarget::AnyEnclave);
+}
+
+#[test]
+fn unknown_nclav_api_version_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let enclave = format!("apiVersion: nclav.dev/v9\n{}", LEGACY_ENCLAVE);
+    write_tree(dir.path(), &enclave, LEGACY_PARTITION);
+
+    let err = load_enclaves(dir.path()).unwrap_err();
+    assert!(matches!(err, ConfigError::UnsupportedApiVersion { ref version, .. } if version == "nclav.dev/v9"));
+    assert!(migrate_config_dir(dir.path()).is_err());
+}
+
+#[test]
+fn foreign_config_files_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    write_tree(dir.path(), LEGACY_ENCLAVE, LEGACY_PARTITION);
+    fs::create_dir_all(dir.path().join("k8s")).unwrap();
+    fs::write(dir.path().join("k8s/config.yml"), "apiVersion: v1\nkind: ConfigMap\n").unwrap();
+
+    let report = migrate_config_dir(dir.path()).unwrap();
+    let k8s = report.iter().find(|f| f.path.ends_with("k8s/config.yml")).unwrap();
+    assert_eq!(k8s.outcome, MigrationOutcome::Skipped);
+    assert_eq!(load_enclaves(dir.path()).unwrap().len(), 1);
+}
+
+#[test]
+fn v1_partition_requires_backend() {
+    let dir = TempDir::new().unwrap();
+    let enclave = "apiVersion: nclav.dev/v1\nid: a\nname: A\nregion: local\npartitions: [svc]\n";
+    let partition = format!("apiVersion: nclav.dev/v1\n{}", LEGACY_PARTITION);
+    write_tree(dir.path(), enclave, &partition);
+
+    assert!(matches!(load_enclaves(dir.path()), Err(ConfigError::YamlParse { .. })));
+}
+
+#[test]
+fn scanned_v1_partition_without_backend_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let enclave = "apiVersion: nclav.dev/v1\nid: a\nname: A\nregion: local\n";
+    let partition = format!("apiVersion: nclav.dev/v1\n{}", LEGACY_PARTITION);
+    write_tree(dir.path(), enclave, &partition);
+
+    let err = load_enclaves(dir.path()).unwrap_err();
+    assert!(matches!(err, ConfigError::YamlParse { ref path, .. } if path.ends_with("svc/config.yml")));
+}
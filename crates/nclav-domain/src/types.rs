@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 // ── Identifiers ──────────────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EnclaveId(pub String);
 
 impl EnclaveId {
@@ -265,6 +265,9 @@ pub struct Import {
     pub export_name: String,
     /// Local alias used inside this partition for template substitution.
     pub alias: String,
+    /// Expected type of the export, if declared (`type:` in YAML). Checked during validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_type: Option<ExportType>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -274,8 +277,10 @@ pub struct Partition {
     pub produces: Option<ProducesType>,
     pub imports: Vec<Import>,
     pub exports: Vec<Export>,
-    /// Template-able input values resolved before provisioning.
-    pub inputs: HashMap<String, String>,
+    /// Template-able input values resolved before provisioning. Values may be
+    /// any JSON type (string, number, bool, list, map); templates inside strings
+    /// are resolved recursively.
+    pub inputs: HashMap<String, serde_json::Value>,
     /// Output keys this partition declares it will produce.
     pub declared_outputs: Vec<String>,
     /// How this partition's workload is provisioned. Defaults to `Terraform`.
@@ -294,7 +299,16 @@ pub struct DnsConfig {
     pub zone: Option<String>,
 }
 
+/// A declared exemption from one organization policy rule.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyException {
+    /// The `id` of the policy rule being waived.
+    pub rule: String,
+    /// Why the waiver is justified. Reported alongside the downgraded violation.
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Enclave {
     pub id: EnclaveId,
     pub name: String,
@@ -309,6 +323,15 @@ pub struct Enclave {
     /// Exports this enclave exposes to others.
     pub exports: Vec<Export>,
     pub partitions: Vec<Partition>,
+    /// Free-form key/value labels (e.g. `env: prod`) that policy rules can match on.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    /// Organization policy rules this enclave is exempt from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_exceptions: Vec<PolicyException>,
+    /// The `config.yml` this enclave was loaded from, for diagnostics. Not persisted.
+    #[serde(skip)]
+    pub source: Option<std::path::PathBuf>,
 }
 
 #[cfg(test)]
@@ -10,8 +10,9 @@ use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 
-use crate::driver::{Driver, ObservedState, OrphanedResource, ProvisionResult};
+use crate::driver::{access_port, output_str, Driver, ObservedState, OrphanedResource, ProvisionResult};
 use crate::error::DriverError;
+use crate::propagation::PropagateTrace;
 use crate::Handle;
 
 type HmacSha256 = Hmac<Sha256>;
@@ -422,6 +423,26 @@ fn xml_all_texts(xml: &str, tag: &str) -> Vec<String> {
     result
 }
 
+/// A failed AWS Query-protocol call. `code` is the AWS error code when the
+/// service answered with an error document, `None` for transport failures.
+#[derive(Debug)]
+struct QueryError {
+    code:  Option<String>,
+    error: DriverError,
+}
+
+impl QueryError {
+    fn is(&self, code: &str) -> bool {
+        self.code.as_deref() == Some(code)
+    }
+}
+
+impl From<QueryError> for DriverError {
+    fn from(e: QueryError) -> Self {
+        e.error
+    }
+}
+
 /// Parse the AWS error code from an XML error response.
 fn xml_error_code(xml: &str) -> String {
     xml_text(xml, "Code")
@@ -473,6 +494,15 @@ fn partition_role_name(partition_id: &str) -> String {
     format!("{}{}-{}", prefix, &partition_id[..max_id_len], hash)
 }
 
+/// Keys returned by [`AwsDriver`]'s `context_vars`, usable as `{{ token }}` in `inputs:`.
+pub const CONTEXT_VARS: &[&str] = &[
+    "nclav_project_id",
+    "nclav_region",
+    "nclav_account_id",
+    "nclav_role_arn",
+    "nclav_enclave",
+];
+
 // ── AwsDriver ─────────────────────────────────────────────────────────────────
 
 pub struct AwsDriver {
@@ -561,6 +591,21 @@ impl AwsDriver {
         creds:     &AwsCredentials,
         params:    &[(&str, &str)],
     ) -> Result<String, DriverError> {
+        self.query_api_coded(base_url, region, service, creds, params)
+            .await
+            .map_err(DriverError::from)
+    }
+
+    /// Like [`query_api`](Self::query_api), but keeps the AWS error code so callers
+    /// can treat specific codes (e.g. `InvalidPermission.Duplicate`) as success.
+    async fn query_api_coded(
+        &self,
+        base_url:  &str,
+        region:    &str,
+        service:   &str,
+        creds:     &AwsCredentials,
+        params:    &[(&str, &str)],
+    ) -> Result<String, QueryError> {
         let host = url_host(base_url).to_string();
         let url  = format!("{}/", base_url.trim_end_matches('/'));
 
@@ -587,9 +632,13 @@ impl AwsDriver {
         }
 
         let resp = req
+            .traced()
             .send()
             .await
-            .map_err(|e| DriverError::Internal(format!("POST {} failed: {}", url, e)))?;
+            .map_err(|e| QueryError {
+                code:  None,
+                error: DriverError::Internal(format!("POST {} failed: {}", url, e)),
+            })?;
 
         let status = resp.status().as_u16();
         let text   = resp.text().await.unwrap_or_default();
@@ -597,9 +646,8 @@ impl AwsDriver {
         if status >= 400 {
             let code = xml_error_code(&text);
             let msg  = xml_error_message(&text);
-            return Err(DriverError::ProvisionFailed(format!(
-                "{}: {} — {}", base_url, code, msg
-            )));
+            let error = DriverError::ProvisionFailed(format!("{}: {} — {}", base_url, code, msg));
+            return Err(QueryError { code: Some(code), error });
         }
         Ok(text)
     }
@@ -650,6 +698,7 @@ impl AwsDriver {
         }
 
         let resp = req
+            .traced()
             .send()
             .await
             .map_err(|e| DriverError::Internal(format!("POST {} failed: {}", url, e)))?;
@@ -700,7 +749,7 @@ impl AwsDriver {
             req = req.header(k, v);
         }
 
-        let resp   = req.send().await
+        let resp   = req.traced().send().await
             .map_err(|e| DriverError::Internal(format!("Route53 POST {}: {}", path, e)))?;
         let status = resp.status().as_u16();
         let text   = resp.text().await.unwrap_or_default();
@@ -1020,6 +1069,143 @@ impl AwsDriver {
             .ok_or_else(|| DriverError::ProvisionFailed("EC2 CreateSubnet: no subnetId".into()))
     }
 
+    /// Return the ID of the `nclav-{partition}` security group in `vpc_id`, creating
+    /// it if needed. Partition-scoped access rules reference these groups.
+    async fn ec2_ensure_partition_sg(
+        &self,
+        creds:   &AwsCredentials,
+        region:  &str,
+        vpc_id:  &str,
+        enc_id:  &str,
+        part_id: &str,
+    ) -> Result<String, DriverError> {
+        let group_name = format!("nclav-{}", part_id);
+        info!(group_name, vpc_id, "EC2: CreateSecurityGroup");
+        let created = self.query_api_coded(
+            &self.base.ec2,
+            region,
+            "ec2",
+            creds,
+            &[
+                ("Action", "CreateSecurityGroup"),
+                ("Version", "2016-11-15"),
+                ("GroupName", &group_name),
+                ("GroupDescription", &format!("nclav partition {}", part_id)),
+                ("VpcId", vpc_id),
+                ("TagSpecification.1.ResourceType", "security-group"),
+                ("TagSpecification.1.Tag.1.Key", "nclav-managed"),
+                ("TagSpecification.1.Tag.1.Value", "true"),
+                ("TagSpecification.1.Tag.2.Key", "nclav-enclave"),
+                ("TagSpecification.1.Tag.2.Value", enc_id),
+                ("TagSpecification.1.Tag.3.Key", "nclav-partition"),
+                ("TagSpecification.1.Tag.3.Value", part_id),
+            ],
+        ).await;
+
+        let xml = match created {
+            Ok(xml) => xml,
+            Err(e) if e.is("InvalidGroup.Duplicate") => {
+                info!(group_name, "Security group already exists");
+                self.query_api_with(
+                    &self.base.ec2,
+                    region,
+                    "ec2",
+                    creds,
+                    &[
+                        ("Action", "DescribeSecurityGroups"),
+                        ("Version", "2016-11-15"),
+                        ("Filter.1.Name", "group-name"),
+                        ("Filter.1.Value.1", &group_name),
+                        ("Filter.2.Name", "vpc-id"),
+                        ("Filter.2.Value.1", vpc_id),
+                    ],
+                ).await?
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        xml_text(&xml, "groupId").ok_or_else(|| {
+            DriverError::ProvisionFailed(format!("EC2: no groupId for security group '{}'", group_name))
+        })
+    }
+
+    /// Allow TCP `port` into `target_sg` from members of `source_sg`. Idempotent.
+    async fn ec2_authorize_sg_ingress(
+        &self,
+        creds:     &AwsCredentials,
+        region:    &str,
+        target_sg: &str,
+        source_sg: &str,
+        port:      u16,
+    ) -> Result<(), DriverError> {
+        info!(target_sg, source_sg, port, "EC2: AuthorizeSecurityGroupIngress");
+        let result = self
+            .ec2_sg_ingress("AuthorizeSecurityGroupIngress", creds, region, target_sg, source_sg, port)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) if e.is("InvalidPermission.Duplicate") => {
+                info!(target_sg, source_sg, "Ingress rule already exists");
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove the rule added by [`ec2_authorize_sg_ingress`](Self::ec2_authorize_sg_ingress).
+    /// A rule or group that is already gone counts as removed.
+    async fn ec2_revoke_sg_ingress(
+        &self,
+        creds:     &AwsCredentials,
+        region:    &str,
+        target_sg: &str,
+        source_sg: &str,
+        port:      u16,
+    ) -> Result<(), DriverError> {
+        info!(target_sg, source_sg, port, "EC2: RevokeSecurityGroupIngress");
+        let result = self
+            .ec2_sg_ingress("RevokeSecurityGroupIngress", creds, region, target_sg, source_sg, port)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) if e.is("InvalidPermission.NotFound") || e.is("InvalidGroup.NotFound") => {
+                info!(target_sg, source_sg, "Ingress rule already removed");
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Authorize or revoke a single group-to-group TCP ingress permission.
+    async fn ec2_sg_ingress(
+        &self,
+        action:    &str,
+        creds:     &AwsCredentials,
+        region:    &str,
+        target_sg: &str,
+        source_sg: &str,
+        port:      u16,
+    ) -> Result<String, QueryError> {
+        let port = port.to_string();
+        self.query_api_coded(
+            &self.base.ec2,
+            region,
+            "ec2",
+            creds,
+            &[
+                ("Action", action),
+                ("Version", "2016-11-15"),
+                ("GroupId", target_sg),
+                ("IpPermissions.1.IpProtocol", "tcp"),
+                ("IpPermissions.1.FromPort", &port),
+                ("IpPermissions.1.ToPort", &port),
+                ("IpPermissions.1.Groups.1.GroupId", source_sg),
+            ],
+        ).await
+    }
+
     // ── Route53 helpers ───────────────────────────────────────────────────────
 
     async fn route53_create_hosted_zone(
@@ -1466,7 +1652,7 @@ impl Driver for AwsDriver {
         &self,
         enclave:         &Enclave,
         partition:       &Partition,
-        _resolved_inputs: &HashMap<String, String>,
+        _resolved_inputs: &HashMap<String, Value>,
         existing:        Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         let enc_id  = enclave.id.as_str();
@@ -1483,8 +1669,7 @@ impl Driver for AwsDriver {
         }
 
         // Get enclave account ID from enclave handle (via resolved_inputs injected by reconciler)
-        let enc_handle_str = _resolved_inputs.get("nclav_account_id")
-            .cloned()
+        let enc_handle_str = output_str(_resolved_inputs, "nclav_account_id")
             .unwrap_or_default();
         let account_id = if enc_handle_str.is_empty() {
             return Err(DriverError::ProvisionFailed(format!(
@@ -1576,7 +1761,7 @@ impl Driver for AwsDriver {
         &self,
         _enclave:          &Enclave,
         export:            &Export,
-        partition_outputs: &HashMap<String, String>,
+        partition_outputs: &HashMap<String, Value>,
         existing:          Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         if let Some(h) = existing {
@@ -1588,12 +1773,9 @@ impl Driver for AwsDriver {
         let export_name = &export.name;
         let handle = match &export.export_type {
             ExportType::Http | ExportType::Tcp => {
-                let endpoint_url = partition_outputs
-                    .get("endpoint_url")
-                    .cloned()
+                let endpoint_url = output_str(partition_outputs, "endpoint_url")
                     .unwrap_or_default();
-                let port: u16 = partition_outputs
-                    .get("port")
+                let port: u16 = output_str(partition_outputs, "port")
                     .and_then(|p| p.parse().ok())
                     .unwrap_or(if export.export_type == ExportType::Http { 443 } else { 0 });
                 json!({
@@ -1606,9 +1788,7 @@ impl Driver for AwsDriver {
                 })
             }
             ExportType::Queue => {
-                let queue_url = partition_outputs
-                    .get("queue_url")
-                    .cloned()
+                let queue_url = output_str(partition_outputs, "queue_url")
                     .unwrap_or_default();
                 json!({
                     "driver":     "aws",
@@ -1620,7 +1800,7 @@ impl Driver for AwsDriver {
             }
         };
 
-        let mut outputs = HashMap::new();
+        let mut outputs: HashMap<String, Value> = HashMap::new();
         if let Some(url) = partition_outputs.get("endpoint_url") {
             outputs.insert("endpoint_url".into(), url.clone());
         }
@@ -1680,17 +1860,119 @@ impl Driver for AwsDriver {
             }
         };
 
-        let mut outputs = HashMap::new();
+        let mut outputs: HashMap<String, Value> = HashMap::new();
         if let Some(url) = export_handle["endpoint_url"].as_str() {
-            outputs.insert("endpoint_url".into(), url.to_string());
+            outputs.insert("endpoint_url".into(), url.into());
         }
         if let Some(url) = export_handle["queue_url"].as_str() {
-            outputs.insert("queue_url".into(), url.to_string());
+            outputs.insert("queue_url".into(), url.into());
         }
 
         Ok(ProvisionResult { handle, outputs })
     }
 
+    // ── provision_partition_access ────────────────────────────────────────────
+
+    async fn provision_partition_access(
+        &self,
+        enclave:          &Enclave,
+        enclave_handle:   &Handle,
+        export:           &Export,
+        importer:         &Partition,
+        exporter_outputs: &HashMap<String, Value>,
+        existing:         Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        let enc_id      = enclave.id.as_str();
+        let exporter_id = export.target_partition.as_str();
+        let importer_id = importer.id.as_str();
+        let vpc_id      = enclave_handle["vpc_id"].as_str().unwrap_or("");
+        let mut handle  = json!({
+            "driver":         "aws",
+            "kind":           "partition_access",
+            "export_name":    export.name,
+            "from_partition": importer_id,
+            "to_partition":   exporter_id,
+        });
+
+        // Queues have no network primitive, and without a VPC there is nothing to secure.
+        let port = match access_port(export, exporter_outputs) {
+            Some(port) if !vpc_id.is_empty() => port,
+            _ => {
+                debug!(enc_id, export = %export.name, "No security group rule needed for partition access");
+                if let Some(existing) = existing {
+                    self.teardown_partition_access(enclave, existing).await?;
+                }
+                return Ok(ProvisionResult { handle, outputs: HashMap::new() });
+            }
+        };
+
+        let account_id = enclave_handle["account_id"].as_str().ok_or_else(|| {
+            DriverError::ProvisionFailed(format!(
+                "provision_partition_access for enclave '{}': enclave handle has no account_id",
+                enc_id
+            ))
+        })?;
+        let region = enclave_handle["region"].as_str().unwrap_or(&enclave.region);
+        handle["account_id"] = json!(account_id);
+        handle["region"]     = json!(region);
+
+        // The rule already allows this port: nothing to change.
+        let stored = existing.and_then(|h| {
+            let exporter_sg = h["exporter_security_group"].as_str()?;
+            let importer_sg = h["importer_security_group"].as_str()?;
+            let port        = u16::try_from(h["port"].as_u64()?).ok()?;
+            Some((exporter_sg, importer_sg, port))
+        });
+        if let Some((exporter_sg, importer_sg, stored_port)) = stored {
+            if stored_port == port {
+                handle["exporter_security_group"] = json!(exporter_sg);
+                handle["importer_security_group"] = json!(importer_sg);
+                handle["port"]                    = json!(port);
+                return Ok(ProvisionResult { handle, outputs: HashMap::new() });
+            }
+        }
+
+        let enc_creds   = self.enclave_creds(account_id).await?;
+        let exporter_sg = self.ec2_ensure_partition_sg(&enc_creds, region, vpc_id, enc_id, exporter_id).await?;
+        let importer_sg = self.ec2_ensure_partition_sg(&enc_creds, region, vpc_id, enc_id, importer_id).await?;
+        self.ec2_authorize_sg_ingress(&enc_creds, region, &exporter_sg, &importer_sg, port).await?;
+
+        // The port changed: drop the rule for the old one once the new one is in place.
+        if let Some((old_exporter_sg, old_importer_sg, old_port)) = stored {
+            info!(enc_id, export = %export.name, old_port, port, "Partition access port changed");
+            self.ec2_revoke_sg_ingress(&enc_creds, region, old_exporter_sg, old_importer_sg, old_port).await?;
+        }
+
+        handle["exporter_security_group"] = json!(exporter_sg);
+        handle["importer_security_group"] = json!(importer_sg);
+        handle["port"]                    = json!(port);
+        Ok(ProvisionResult { handle, outputs: HashMap::new() })
+    }
+
+    // ── teardown_partition_access ─────────────────────────────────────────────
+
+    async fn teardown_partition_access(
+        &self,
+        enclave: &Enclave,
+        handle:  &Handle,
+    ) -> Result<(), DriverError> {
+        let (Some(exporter_sg), Some(importer_sg), Some(port)) = (
+            handle["exporter_security_group"].as_str(),
+            handle["importer_security_group"].as_str(),
+            handle["port"].as_u64().and_then(|p| u16::try_from(p).ok()),
+        ) else {
+            return Ok(());
+        };
+        let Some(account_id) = handle["account_id"].as_str() else {
+            warn!(enclave_id = %enclave.id, exporter_sg, importer_sg,
+                  "Partition access handle has no account_id; leaving security group rule in place");
+            return Ok(());
+        };
+        let region    = handle["region"].as_str().unwrap_or(&enclave.region);
+        let enc_creds = self.enclave_creds(account_id).await?;
+        self.ec2_revoke_sg_ingress(&enc_creds, region, exporter_sg, importer_sg, port).await
+    }
+
     // ── observe_enclave ───────────────────────────────────────────────────────
 
     async fn observe_enclave(
@@ -1887,8 +2169,8 @@ mod urlencoding {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nclav_domain::{EnclaveId, NetworkConfig, PartitionId};
-    use wiremock::matchers::{method, path};
+    use nclav_domain::{AuthType, EnclaveId, ExportTarget, NetworkConfig, PartitionId};
+    use wiremock::matchers::{body_string_contains, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     fn test_config() -> AwsDriverConfig {
@@ -1928,15 +2210,11 @@ mod tests {
             name:       "Product A Dev".into(),
             cloud:      Some(nclav_domain::CloudTarget::Aws),
             region:     "us-east-1".into(),
-            identity:   None,
             network:    Some(NetworkConfig {
                 vpc_cidr: Some("10.0.0.0/16".into()),
                 subnets:  vec!["10.0.1.0/24".into()],
             }),
-            dns:        None,
-            imports:    vec![],
-            exports:    vec![],
-            partitions: vec![],
+            ..Default::default()
         }
     }
 
@@ -2108,6 +2386,285 @@ mod tests {
         assert!(result.handle["partition_role_arn"].as_str().unwrap_or("").contains("nclav-partition-api"));
     }
 
+    // ── provision_partition_access ────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn provision_partition_access_authorizes_sg_ingress() {
+        let server = MockServer::start().await;
+
+        let sts_xml = r#"<AssumeRoleResponse><AssumeRoleResult><Credentials>
+          <AccessKeyId>ASIA-ENC</AccessKeyId>
+          <SecretAccessKey>ENC-SECRET</SecretAccessKey>
+          <SessionToken>ENC-TOKEN</SessionToken>
+        </Credentials></AssumeRoleResult></AssumeRoleResponse>"#;
+        Mock::given(method("POST"))
+            .and(path("/sts/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(sts_xml))
+            .mount(&server)
+            .await;
+
+        // Exporter SG is new; importer SG already exists and is looked up.
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=CreateSecurityGroup"))
+            .and(body_string_contains("GroupName=nclav-api"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<CreateSecurityGroupResponse><groupId>sg-api</groupId></CreateSecurityGroupResponse>",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=CreateSecurityGroup"))
+            .and(body_string_contains("GroupName=nclav-web"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                "<Response><Errors><Error><Code>InvalidGroup.Duplicate</Code>\
+                 <Message>already exists</Message></Error></Errors></Response>",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=DescribeSecurityGroups"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<DescribeSecurityGroupsResponse><securityGroupInfo><item>\
+                 <groupId>sg-web</groupId></item></securityGroupInfo></DescribeSecurityGroupsResponse>",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=AuthorizeSecurityGroupIngress"))
+            .and(body_string_contains("GroupId=sg-api"))
+            .and(body_string_contains("IpPermissions.1.Groups.1.GroupId=sg-web"))
+            .and(body_string_contains("IpPermissions.1.FromPort=443"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<AuthorizeSecurityGroupIngressResponse><return>true</return></AuthorizeSecurityGroupIngressResponse>",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let export = Export {
+            name:             "api-http".into(),
+            target_partition: PartitionId::new("api"),
+            export_type:      ExportType::Http,
+            to:               ExportTarget::Partition(PartitionId::new("web")),
+            auth:             AuthType::None,
+            hostname:         None,
+            port:             None,
+        };
+        let importer = Partition { id: PartitionId::new("web"), ..dummy_partition() };
+        let enc_handle = json!({
+            "driver":     "aws",
+            "account_id": "123456789012",
+            "region":     "us-east-1",
+            "vpc_id":     "vpc-123",
+        });
+
+        let d = AwsDriver::with_test_config(test_config(), test_base_urls(&server), test_creds());
+        let result = d
+            .provision_partition_access(&dummy_enclave(), &enc_handle, &export, &importer, &HashMap::new(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["exporter_security_group"], "sg-api");
+        assert_eq!(result.handle["importer_security_group"], "sg-web");
+        assert_eq!(result.handle["port"], 443);
+    }
+
+    async fn mount_enclave_sts(server: &MockServer) {
+        let sts_xml = r#"<AssumeRoleResponse><AssumeRoleResult><Credentials>
+          <AccessKeyId>ASIA-ENC</AccessKeyId>
+          <SecretAccessKey>ENC-SECRET</SecretAccessKey>
+          <SessionToken>ENC-TOKEN</SessionToken>
+        </Credentials></AssumeRoleResult></AssumeRoleResponse>"#;
+        Mock::given(method("POST"))
+            .and(path("/sts/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(sts_xml))
+            .mount(server)
+            .await;
+    }
+
+    fn access_export(port: Option<u16>) -> Export {
+        Export {
+            name:             "api-http".into(),
+            target_partition: PartitionId::new("api"),
+            export_type:      ExportType::Http,
+            to:               ExportTarget::Partition(PartitionId::new("web")),
+            auth:             AuthType::None,
+            hostname:         None,
+            port,
+        }
+    }
+
+    fn access_enclave_handle() -> Value {
+        json!({ "driver": "aws", "account_id": "123456789012", "region": "us-east-1", "vpc_id": "vpc-123" })
+    }
+
+    fn access_handle(port: u16) -> Value {
+        json!({
+            "driver":                  "aws",
+            "kind":                    "partition_access",
+            "account_id":              "123456789012",
+            "region":                  "us-east-1",
+            "exporter_security_group": "sg-api",
+            "importer_security_group": "sg-web",
+            "port":                    port,
+        })
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_with_unchanged_port_makes_no_calls() {
+        // No mocks mounted: any request would fail the call.
+        let server   = MockServer::start().await;
+        let importer = Partition { id: PartitionId::new("web"), ..dummy_partition() };
+        let existing = access_handle(8080);
+
+        let d = AwsDriver::with_test_config(test_config(), test_base_urls(&server), test_creds());
+        let result = d
+            .provision_partition_access(
+                &dummy_enclave(),
+                &access_enclave_handle(),
+                &access_export(Some(8080)),
+                &importer,
+                &HashMap::new(),
+                Some(&existing),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["port"], 8080);
+        assert_eq!(result.handle["exporter_security_group"], "sg-api");
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_replaces_rule_when_port_changes() {
+        let server = MockServer::start().await;
+        mount_enclave_sts(&server).await;
+        for (name, id) in [("nclav-api", "sg-api"), ("nclav-web", "sg-web")] {
+            Mock::given(method("POST"))
+                .and(path("/ec2/"))
+                .and(body_string_contains("Action=CreateSecurityGroup"))
+                .and(body_string_contains(format!("GroupName={}", name)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                    "<CreateSecurityGroupResponse><groupId>{}</groupId></CreateSecurityGroupResponse>",
+                    id
+                )))
+                .mount(&server)
+                .await;
+        }
+        // The new rule already exists; matched on the error code, not the message.
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=AuthorizeSecurityGroupIngress"))
+            .and(body_string_contains("IpPermissions.1.FromPort=9090"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                "<Response><Errors><Error><Code>InvalidPermission.Duplicate</Code>\
+                 <Message>the specified rule is present</Message></Error></Errors></Response>",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=RevokeSecurityGroupIngress"))
+            .and(body_string_contains("GroupId=sg-api"))
+            .and(body_string_contains("IpPermissions.1.Groups.1.GroupId=sg-web"))
+            .and(body_string_contains("IpPermissions.1.FromPort=8080"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<RevokeSecurityGroupIngressResponse><return>true</return></RevokeSecurityGroupIngressResponse>",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let importer = Partition { id: PartitionId::new("web"), ..dummy_partition() };
+        let existing = access_handle(8080);
+        let d = AwsDriver::with_test_config(test_config(), test_base_urls(&server), test_creds());
+        let result = d
+            .provision_partition_access(
+                &dummy_enclave(),
+                &access_enclave_handle(),
+                &access_export(Some(9090)),
+                &importer,
+                &HashMap::new(),
+                Some(&existing),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["port"], 9090);
+        assert_eq!(result.handle["account_id"], "123456789012");
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_surfaces_other_error_codes() {
+        let server = MockServer::start().await;
+        mount_enclave_sts(&server).await;
+        // The message mentions a duplicate, but the code says something else.
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=CreateSecurityGroup"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                "<Response><Errors><Error><Code>UnauthorizedOperation</Code>\
+                 <Message>not allowed to check InvalidGroup.Duplicate</Message></Error></Errors></Response>",
+            ))
+            .mount(&server)
+            .await;
+
+        let importer = Partition { id: PartitionId::new("web"), ..dummy_partition() };
+        let d = AwsDriver::with_test_config(test_config(), test_base_urls(&server), test_creds());
+        let err = d
+            .provision_partition_access(
+                &dummy_enclave(),
+                &access_enclave_handle(),
+                &access_export(None),
+                &importer,
+                &HashMap::new(),
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("UnauthorizedOperation"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn teardown_partition_access_revokes_sg_ingress() {
+        let server = MockServer::start().await;
+        mount_enclave_sts(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=RevokeSecurityGroupIngress"))
+            .and(body_string_contains("IpPermissions.1.FromPort=8080"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<RevokeSecurityGroupIngressResponse><return>true</return></RevokeSecurityGroupIngressResponse>",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // Already gone counts as revoked.
+        Mock::given(method("POST"))
+            .and(path("/ec2/"))
+            .and(body_string_contains("Action=RevokeSecurityGroupIngress"))
+            .and(body_string_contains("IpPermissions.1.FromPort=9090"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                "<Response><Errors><Error><Code>InvalidPermission.NotFound</Code>\
+                 <Message>rule missing</Message></Error></Errors></Response>",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let d   = AwsDriver::with_test_config(test_config(), test_base_urls(&server), test_creds());
+        let enc = dummy_enclave();
+        d.teardown_partition_access(&enc, &access_handle(8080)).await.unwrap();
+        d.teardown_partition_access(&enc, &access_handle(9090)).await.unwrap();
+        // Queue or no-VPC handles carry no rule.
+        d.teardown_partition_access(&enc, &json!({ "kind": "partition_access" })).await.unwrap();
+    }
+
     // ── observe_partition ─────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -2146,6 +2703,12 @@ mod tests {
         assert_eq!(vars.get("nclav_region").map(String::as_str),       Some("us-east-1"));
         assert_eq!(vars.get("nclav_enclave").map(String::as_str),      Some("product-a-dev"));
         assert!(vars.get("nclav_role_arn").map(String::as_str).unwrap_or("").contains("nclav-partition"));
+
+        let mut keys: Vec<&str> = vars.keys().map(String::as_str).collect();
+        keys.sort();
+        let mut expected = CONTEXT_VARS.to_vec();
+        expected.sort();
+        assert_eq!(keys, expected, "CONTEXT_VARS must list every context_vars key");
     }
 
     // ── auth_env ──────────────────────────────────────────────────────────────
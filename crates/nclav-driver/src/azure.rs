@@ -6,11 +6,12 @@ use async_trait::async_trait;
 use nclav_domain::{Enclave, Export, ExportType, Import, Partition};
 use serde_json::{json, Value};
 use tokio::sync::Mutex;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, info_span, warn, Instrument};
 use uuid::Uuid;
 
-use crate::driver::{Driver, ObservedState, OrphanedResource, ProvisionResult};
+use crate::driver::{access_port, output_str, Driver, ObservedState, OrphanedResource, ProvisionResult};
 use crate::error::DriverError;
+use crate::propagation::PropagateTrace;
 use crate::Handle;
 
 // ── Configuration ─────────────────────────────────────────────────────────────
@@ -221,6 +222,17 @@ impl TokenProvider for StaticToken {
     }
 }
 
+/// Keys returned by [`AzureDriver`]'s `context_vars`, usable as `{{ token }}` in `inputs:`.
+pub const CONTEXT_VARS: &[&str] = &[
+    "nclav_project_id",
+    "nclav_region",
+    "nclav_subscription_id",
+    "nclav_resource_group",
+    "nclav_location",
+    "nclav_identity_client_id",
+    "nclav_enclave",
+];
+
 // ── AzureDriver ───────────────────────────────────────────────────────────────
 
 pub struct AzureDriver {
@@ -339,49 +351,54 @@ impl AzureDriver {
     /// This method accepts either and polls until `status == "Succeeded"`.
     /// Backoff: `[1, 2, 4, 8, 16, 30]` cycling, max 120 polls.
     async fn wait_for_operation(&self, op_url: &str) -> Result<Value, DriverError> {
-        let token  = self.bearer().await?;
-        let delays = [1u64, 2, 4, 8, 16, 30];
-        let max_polls = 120;
-
-        for (i, &delay) in delays.iter().cycle().take(max_polls).enumerate() {
-            let resp = self
-                .client
-                .get(op_url)
-                .bearer_auth(&token)
-                .send()
-                .await
-                .map_err(|e| DriverError::Internal(format!("poll {}: {}", op_url, e)))?;
-
-            let body: Value = resp
-                .json()
-                .await
-                .map_err(|e| DriverError::Internal(format!("poll decode {}: {}", op_url, e)))?;
-
-            let status = body["status"].as_str().unwrap_or("Unknown");
-            match status {
-                "Succeeded" => return Ok(body),
-                "Failed" | "Canceled" => {
-                    let msg = Self::parse_arm_error(&body);
-                    return Err(DriverError::ProvisionFailed(
-                        format!("ARM operation failed ({}): {}", status, msg),
-                    ));
+        async {
+            let token  = self.bearer().await?;
+            let delays = [1u64, 2, 4, 8, 16, 30];
+            let max_polls = 120;
+
+            for (i, &delay) in delays.iter().cycle().take(max_polls).enumerate() {
+                let resp = self
+                    .client
+                    .get(op_url)
+                    .bearer_auth(&token)
+                    .traced()
+                    .send()
+                    .await
+                    .map_err(|e| DriverError::Internal(format!("poll {}: {}", op_url, e)))?;
+
+                let body: Value = resp
+                    .json()
+                    .await
+                    .map_err(|e| DriverError::Internal(format!("poll decode {}: {}", op_url, e)))?;
+
+                let status = body["status"].as_str().unwrap_or("Unknown");
+                match status {
+                    "Succeeded" => return Ok(body),
+                    "Failed" | "Canceled" => {
+                        let msg = Self::parse_arm_error(&body);
+                        return Err(DriverError::ProvisionFailed(
+                            format!("ARM operation failed ({}): {}", status, msg),
+                        ));
+                    }
+                    _ => {}
                 }
-                _ => {}
-            }
 
-            let poll = i + 1;
-            if poll % 10 == 0 {
-                info!(poll, op_url, "still waiting for Azure ARM operation");
-            } else {
-                debug!(poll, op_url, delay, "Azure ARM operation pending, waiting");
+                let poll = i + 1;
+                if poll % 10 == 0 {
+                    info!(poll, op_url, "still waiting for Azure ARM operation");
+                } else {
+                    debug!(poll, op_url, delay, "Azure ARM operation pending, waiting");
+                }
+                tokio::time::sleep(Duration::from_secs(delay)).await;
             }
-            tokio::time::sleep(Duration::from_secs(delay)).await;
-        }
 
-        Err(DriverError::ProvisionFailed(format!(
-            "Azure ARM operation timed out after {} polls: {}",
-            max_polls, op_url
-        )))
+            Err(DriverError::ProvisionFailed(format!(
+                "Azure ARM operation timed out after {} polls: {}",
+                max_polls, op_url
+            )))
+        }
+        .instrument(info_span!("wait_for_operation", op_url))
+        .await
     }
 
     // ── ARM HTTP verbs ────────────────────────────────────────────────────────
@@ -393,6 +410,7 @@ impl AzureDriver {
             .client
             .put(url)
             .bearer_auth(&token)
+            .traced()
             .json(body)
             .send()
             .await
@@ -419,6 +437,7 @@ impl AzureDriver {
             .client
             .get(url)
             .bearer_auth(&token)
+            .traced()
             .send()
             .await
             .map_err(|e| DriverError::Internal(format!("GET {}: {}", url, e)))?;
@@ -438,6 +457,7 @@ impl AzureDriver {
             .client
             .delete(url)
             .bearer_auth(&token)
+            .traced()
             .send()
             .await
             .map_err(|e| DriverError::TeardownFailed(format!("DELETE {}: {}", url, e)))?;
@@ -478,6 +498,7 @@ impl AzureDriver {
             .client
             .post(url)
             .bearer_auth(&token)
+            .traced()
             .json(body)
             .send()
             .await
@@ -664,6 +685,43 @@ impl AzureDriver {
         }
     }
 
+    /// Attach the partitions NSG to every subnet of the enclave VNet so its rules
+    /// take effect. Subnets already using it are left alone; a subnet bound to a
+    /// different NSG is an error, since Azure allows only one per subnet.
+    async fn associate_partitions_nsg(&self, vnet_id: &str, nsg_id: &str) -> Result<(), DriverError> {
+        let list_url = format!("{}{}/subnets?api-version=2023-11-01", self.base.management, vnet_id);
+        let (status, list) = self.arm_get(&list_url).await?;
+        if !(200..300).contains(&status) {
+            return Err(DriverError::ProvisionFailed(format!(
+                "list subnets of {}: status {} — {}",
+                vnet_id, status, Self::parse_arm_error(&list)
+            )));
+        }
+
+        for subnet in list["value"].as_array().map(Vec::as_slice).unwrap_or_default() {
+            let subnet_id = subnet["id"].as_str().unwrap_or_default();
+            match subnet["properties"]["networkSecurityGroup"]["id"].as_str() {
+                Some(current) if current.eq_ignore_ascii_case(nsg_id) => continue,
+                Some(current) => {
+                    return Err(DriverError::ProvisionFailed(format!(
+                        "subnet {} already uses NSG {}; nclav needs nclav-partitions-nsg attached \
+                         to enforce partition access rules",
+                        subnet_id, current
+                    )));
+                }
+                None => {}
+            }
+
+            let mut body = json!({ "properties": subnet["properties"].clone() });
+            body["properties"]["networkSecurityGroup"] = json!({ "id": nsg_id });
+            info!(subnet_id, "Associating nclav-partitions-nsg with subnet");
+            let url = format!("{}{}?api-version=2023-11-01", self.base.management, subnet_id);
+            self.arm_put_and_wait(&url, &body).await
+                .map_err(|e| DriverError::ProvisionFailed(format!("associate NSG with {}: {}", subnet_id, e)))?;
+        }
+        Ok(())
+    }
+
     /// Create a user-assigned managed identity in `nclav-rg`.
     async fn create_managed_identity(
         &self,
@@ -785,6 +843,39 @@ fn partition_mi_name(partition_id: &str) -> String {
     format!("pt-{}-{}", truncated, short_hash)
 }
 
+/// Name of the NSG rule letting `importer_id` reach a partition export.
+///
+/// Azure security rule names: 1–80 chars, letters/digits/hyphens/underscores/periods.
+fn partition_rule_name(export_name: &str, importer_id: &str) -> String {
+    format!("nclav-{}-{}", export_name, importer_id)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
+        .take(80)
+        .collect()
+}
+
+/// Priority range nclav uses for partition access rules.
+const PARTITION_RULE_PRIORITIES: std::ops::RangeInclusive<u64> = 1000..=3999;
+
+/// Priority for `rule_name` in `nsg`, a `networkSecurityGroups` GET body.
+///
+/// Priorities must be unique within an NSG. A rule that already exists keeps its
+/// priority; otherwise the lowest priority in [`PARTITION_RULE_PRIORITIES`] not taken
+/// by another rule is used. `None` when the range is exhausted.
+fn partition_rule_priority(nsg: &Value, rule_name: &str) -> Option<u64> {
+    let rules = nsg["properties"]["securityRules"].as_array().map(Vec::as_slice).unwrap_or_default();
+    if let Some(own) = rules.iter().find(|r| r["name"].as_str() == Some(rule_name)) {
+        if let Some(p) = own["properties"]["priority"].as_u64() {
+            return Some(p);
+        }
+    }
+    let taken: std::collections::HashSet<u64> = rules
+        .iter()
+        .filter_map(|r| r["properties"]["priority"].as_u64())
+        .collect();
+    PARTITION_RULE_PRIORITIES.into_iter().find(|p| !taken.contains(p))
+}
+
 // ── Driver impl ───────────────────────────────────────────────────────────────
 
 #[async_trait]
@@ -977,7 +1068,7 @@ impl Driver for AzureDriver {
         &self,
         enclave: &Enclave,
         partition: &Partition,
-        resolved_inputs: &HashMap<String, String>,
+        resolved_inputs: &HashMap<String, Value>,
         existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         // Re-use existing partition handle if already provisioned (idempotency)
@@ -993,13 +1084,10 @@ impl Driver for AzureDriver {
 
         // Subscription ID comes from context_vars injected by the reconciler into resolved_inputs.
         // Falls back to the existing partition handle's subscription_id, then the enclave identity field.
-        let sub_id = resolved_inputs
-            .get("nclav_subscription_id")
-            .map(|s| s.as_str())
-            .or_else(|| existing.and_then(|h| h["subscription_id"].as_str()))
-            .or_else(|| enclave.identity.as_deref())
-            .unwrap_or("")
-            .to_string();
+        let sub_id = output_str(resolved_inputs, "nclav_subscription_id")
+            .or_else(|| existing.and_then(|h| h["subscription_id"].as_str()).map(str::to_string))
+            .or_else(|| enclave.identity.clone())
+            .unwrap_or_default();
 
         if sub_id.is_empty() {
             return Err(DriverError::ProvisionFailed(format!(
@@ -1009,11 +1097,8 @@ impl Driver for AzureDriver {
             )));
         }
 
-        let location   = resolved_inputs
-            .get("nclav_location")
-            .map(|s| s.as_str())
-            .unwrap_or_else(|| self.location(enclave))
-            .to_string();
+        let location   = output_str(resolved_inputs, "nclav_location")
+            .unwrap_or_else(|| self.location(enclave).to_string());
         let enclave_id = enclave.id.as_str();
         let part_id    = partition.id.as_str();
         let mi_name    = partition_mi_name(part_id);
@@ -1099,7 +1184,7 @@ impl Driver for AzureDriver {
         &self,
         enclave: &Enclave,
         export: &Export,
-        partition_outputs: &HashMap<String, String>,
+        partition_outputs: &HashMap<String, Value>,
         existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         if let Some(h) = existing {
@@ -1116,14 +1201,13 @@ impl Driver for AzureDriver {
 
         match &export.export_type {
             ExportType::Http => {
-                let pls_resource_id = partition_outputs.get("pls_id").cloned().unwrap_or_default();
-                let endpoint_url = partition_outputs.get("endpoint_url")
+                let pls_resource_id = output_str(partition_outputs, "pls_id").unwrap_or_default();
+                let endpoint_url = output_str(partition_outputs, "endpoint_url")
                     .ok_or_else(|| DriverError::ProvisionFailed(
                         format!("provision_export '{export_name}': missing Terraform output 'endpoint_url' — \
                                  your .tf must declare output \"endpoint_url\"")
-                    ))?
-                    .clone();
-                let port: u16 = partition_outputs.get("port")
+                    ))?;
+                let port: u16 = output_str(partition_outputs, "port")
                     .and_then(|p| p.parse().ok())
                     .unwrap_or(443);
 
@@ -1139,22 +1223,21 @@ impl Driver for AzureDriver {
                     "port":             port,
                 });
 
-                let mut outputs = HashMap::new();
-                outputs.insert("hostname".into(), extract_url_hostname(&endpoint_url));
-                outputs.insert("port".into(), port.to_string());
+                let mut outputs: HashMap<String, Value> = HashMap::new();
+                outputs.insert("hostname".into(), extract_url_hostname(&endpoint_url).into());
+                outputs.insert("port".into(), port.into());
 
                 info!(enclave_id, export_name, "Azure HTTP export provisioned");
                 Ok(ProvisionResult { handle, outputs })
             }
 
             ExportType::Tcp => {
-                let pls_resource_id = partition_outputs.get("pls_id")
+                let pls_resource_id = output_str(partition_outputs, "pls_id")
                     .ok_or_else(|| DriverError::ProvisionFailed(
                         format!("provision_export '{export_name}': missing Terraform output 'pls_id' — \
                                  your .tf must declare output \"pls_id\"")
-                    ))?
-                    .clone();
-                let port: u16 = partition_outputs.get("port")
+                    ))?;
+                let port: u16 = output_str(partition_outputs, "port")
                     .and_then(|p| p.parse().ok())
                     .unwrap_or(0);
 
@@ -1169,32 +1252,29 @@ impl Driver for AzureDriver {
                     "port":             port,
                 });
 
-                let mut outputs = HashMap::new();
-                outputs.insert("pls_resource_id".into(), pls_resource_id);
-                outputs.insert("port".into(), port.to_string());
+                let mut outputs: HashMap<String, Value> = HashMap::new();
+                outputs.insert("pls_resource_id".into(), pls_resource_id.into());
+                outputs.insert("port".into(), port.into());
 
                 info!(enclave_id, export_name, "Azure TCP export provisioned");
                 Ok(ProvisionResult { handle, outputs })
             }
 
             ExportType::Queue => {
-                let ns_name = partition_outputs.get("service_bus_namespace_name")
+                let ns_name = output_str(partition_outputs, "service_bus_namespace_name")
                     .ok_or_else(|| DriverError::ProvisionFailed(
                         format!("provision_export '{export_name}': missing Terraform output \
                                  'service_bus_namespace_name'")
-                    ))?
-                    .clone();
-                let topic_name = partition_outputs.get("topic_name")
+                    ))?;
+                let topic_name = output_str(partition_outputs, "topic_name")
                     .ok_or_else(|| DriverError::ProvisionFailed(
                         format!("provision_export '{export_name}': missing Terraform output 'topic_name'")
-                    ))?
-                    .clone();
-                let sb_resource_id = partition_outputs.get("service_bus_resource_id")
+                    ))?;
+                let sb_resource_id = output_str(partition_outputs, "service_bus_resource_id")
                     .ok_or_else(|| DriverError::ProvisionFailed(
                         format!("provision_export '{export_name}': missing Terraform output \
                                  'service_bus_resource_id'")
-                    ))?
-                    .clone();
+                    ))?;
 
                 let queue_url = format!("{}.servicebus.windows.net/{}", ns_name, topic_name);
 
@@ -1210,8 +1290,8 @@ impl Driver for AzureDriver {
                     "service_bus_resource_id":      sb_resource_id,
                 });
 
-                let mut outputs = HashMap::new();
-                outputs.insert("queue_url".into(), queue_url);
+                let mut outputs: HashMap<String, Value> = HashMap::new();
+                outputs.insert("queue_url".into(), queue_url.into());
 
                 info!(enclave_id, export_name, "Azure queue export provisioned");
                 Ok(ProvisionResult { handle, outputs })
@@ -1368,9 +1448,9 @@ impl Driver for AzureDriver {
                             "private_ip":               private_ip,
                             "dns_record_name":          alias,
                         });
-                        let mut outputs = HashMap::new();
-                        outputs.insert("hostname".into(), hostname);
-                        outputs.insert("port".into(), port.to_string());
+                        let mut outputs: HashMap<String, Value> = HashMap::new();
+                        outputs.insert("hostname".into(), hostname.into());
+                        outputs.insert("port".into(), port.into());
                         return Ok(ProvisionResult { handle, outputs });
                     }
                 }
@@ -1387,9 +1467,9 @@ impl Driver for AzureDriver {
                     "private_ip":               private_ip,
                     "dns_record_name":          "",
                 });
-                let mut outputs = HashMap::new();
-                outputs.insert("hostname".into(), private_ip);
-                outputs.insert("port".into(), port.to_string());
+                let mut outputs: HashMap<String, Value> = HashMap::new();
+                outputs.insert("hostname".into(), private_ip.into());
+                outputs.insert("port".into(), port.into());
                 Ok(ProvisionResult { handle, outputs })
             }
 
@@ -1466,8 +1546,8 @@ impl Driver for AzureDriver {
                     "resource_group":   "nclav-rg",
                     "alias":            alias,
                 });
-                let mut outputs = HashMap::new();
-                outputs.insert("queue_url".into(), queue_url);
+                let mut outputs: HashMap<String, Value> = HashMap::new();
+                outputs.insert("queue_url".into(), queue_url.into());
                 Ok(ProvisionResult { handle, outputs })
             }
 
@@ -1478,6 +1558,147 @@ impl Driver for AzureDriver {
         }
     }
 
+    // ── provision_partition_access ────────────────────────────────────────────
+
+    async fn provision_partition_access(
+        &self,
+        enclave: &Enclave,
+        enclave_handle: &Handle,
+        export: &Export,
+        importer: &Partition,
+        exporter_outputs: &HashMap<String, Value>,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        let enclave_id  = enclave.id.as_str();
+        let exporter_id = export.target_partition.as_str();
+        let importer_id = importer.id.as_str();
+        let sub_id      = enclave_handle["subscription_id"].as_str().unwrap_or("");
+        let vnet_id     = enclave_handle["vnet_resource_id"].as_str().unwrap_or("");
+        let mut handle  = json!({
+            "driver":          "azure",
+            "kind":            "partition_access",
+            "subscription_id": sub_id,
+            "export_name":     export.name,
+            "from_partition":  importer_id,
+            "to_partition":    exporter_id,
+        });
+
+        // Queues have no network primitive, and without a VNet there is nothing to secure.
+        let port = match access_port(export, exporter_outputs) {
+            Some(port) if !sub_id.is_empty() && !vnet_id.is_empty() => port,
+            _ => {
+                debug!(enclave_id, export = %export.name, "No NSG rule needed for partition access");
+                if let Some(existing) = existing {
+                    self.teardown_partition_access(enclave, existing).await?;
+                }
+                return Ok(ProvisionResult { handle, outputs: HashMap::new() });
+            }
+        };
+        let location = enclave_handle["location"].as_str().unwrap_or(&enclave.region);
+        let network  = format!(
+            "{}/subscriptions/{}/resourceGroups/nclav-rg/providers/Microsoft.Network",
+            self.base.management, sub_id,
+        );
+
+        // One application security group per partition; workloads join them via
+        // their NICs, and the NSG rule references the groups rather than addresses.
+        let mut asg_ids = Vec::with_capacity(2);
+        for part_id in [exporter_id, importer_id] {
+            let asg_url = format!(
+                "{}/applicationSecurityGroups/nclav-asg-{}?api-version=2023-11-01",
+                network, part_id,
+            );
+            let asg_body = json!({
+                "location": location,
+                "tags": { "nclav-managed": "true", "nclav-enclave": enclave_id, "nclav-partition": part_id },
+            });
+            let asg = self.arm_put_and_wait(&asg_url, &asg_body).await
+                .map_err(|e| DriverError::ProvisionFailed(format!("create ASG for '{}': {}", part_id, e)))?;
+            let asg_id = asg["id"].as_str().map(str::to_string).unwrap_or_else(|| format!(
+                "/subscriptions/{}/resourceGroups/nclav-rg/providers/Microsoft.Network/applicationSecurityGroups/nclav-asg-{}",
+                sub_id, part_id,
+            ));
+            asg_ids.push(asg_id);
+        }
+
+        // The shared partitions NSG. PUT replaces the whole rule set, so only create
+        // it when missing; rules are added individually below.
+        let nsg_url = format!(
+            "{}/networkSecurityGroups/nclav-partitions-nsg?api-version=2023-11-01",
+            network,
+        );
+        let (nsg_status, mut nsg) = self.arm_get(&nsg_url).await?;
+        if nsg_status == 404 {
+            info!(enclave_id, sub_id, "Creating NSG nclav-partitions-nsg");
+            let nsg_body = json!({
+                "location": location,
+                "tags": { "nclav-managed": "true", "nclav-enclave": enclave_id },
+            });
+            nsg = self.arm_put_and_wait(&nsg_url, &nsg_body).await
+                .map_err(|e| DriverError::ProvisionFailed(format!("create NSG: {}", e)))?;
+        }
+        let nsg_id = nsg["id"].as_str().map(str::to_string).unwrap_or_else(|| format!(
+            "/subscriptions/{}/resourceGroups/nclav-rg/providers/Microsoft.Network/networkSecurityGroups/nclav-partitions-nsg",
+            sub_id,
+        ));
+        self.associate_partitions_nsg(vnet_id, &nsg_id).await?;
+
+        let rule_name = partition_rule_name(&export.name, importer_id);
+        let priority  = partition_rule_priority(&nsg, &rule_name).ok_or_else(|| {
+            DriverError::ProvisionFailed(format!(
+                "NSG nclav-partitions-nsg has no free priority in {:?} for rule '{}'",
+                PARTITION_RULE_PRIORITIES, rule_name,
+            ))
+        })?;
+        let rule_url  = format!(
+            "{}/networkSecurityGroups/nclav-partitions-nsg/securityRules/{}?api-version=2023-11-01",
+            network, rule_name,
+        );
+        let rule_body = json!({
+            "properties": {
+                "protocol":                             "Tcp",
+                "sourcePortRange":                      "*",
+                "destinationPortRange":                 port.to_string(),
+                "sourceApplicationSecurityGroups":      [{ "id": asg_ids[1] }],
+                "destinationApplicationSecurityGroups": [{ "id": asg_ids[0] }],
+                "access":                               "Allow",
+                "direction":                            "Inbound",
+                "priority":                             priority,
+            }
+        });
+        info!(enclave_id, rule_name, importer_id, exporter_id, port, priority, "Creating NSG rule for partition access");
+        self.arm_put_and_wait(&rule_url, &rule_body).await
+            .map_err(|e| DriverError::ProvisionFailed(format!("create NSG rule '{}': {}", rule_name, e)))?;
+
+        handle["nsg_rule"]     = json!(rule_name);
+        handle["nsg_priority"] = json!(priority);
+        handle["exporter_asg"] = json!(asg_ids[0]);
+        handle["importer_asg"] = json!(asg_ids[1]);
+        handle["port"]         = json!(port);
+        Ok(ProvisionResult { handle, outputs: HashMap::new() })
+    }
+
+    // ── teardown_partition_access ─────────────────────────────────────────────
+
+    async fn teardown_partition_access(
+        &self,
+        _enclave: &Enclave,
+        handle: &Handle,
+    ) -> Result<(), DriverError> {
+        let (Some(sub_id), Some(rule_name)) =
+            (handle["subscription_id"].as_str(), handle["nsg_rule"].as_str())
+        else {
+            return Ok(());
+        };
+        let rule_url = format!(
+            "{}/subscriptions/{}/resourceGroups/nclav-rg/providers/Microsoft.Network\
+             /networkSecurityGroups/nclav-partitions-nsg/securityRules/{}?api-version=2023-11-01",
+            self.base.management, sub_id, rule_name,
+        );
+        info!(sub_id, rule_name, "Deleting NSG rule for partition access");
+        self.arm_delete(&rule_url).await
+    }
+
     // ── observe_enclave ───────────────────────────────────────────────────────
 
     async fn observe_enclave(
@@ -1725,23 +1946,23 @@ impl Driver for AzureDriver {
 
 // ── Helper functions ──────────────────────────────────────────────────────────
 
-fn export_outputs_from_handle(h: &Handle) -> HashMap<String, String> {
+fn export_outputs_from_handle(h: &Handle) -> HashMap<String, Value> {
     let mut outputs = HashMap::new();
     match h["type"].as_str() {
         Some("http") => {
             if let Some(url) = h["endpoint_url"].as_str() {
-                outputs.insert("hostname".into(), extract_url_hostname(url));
+                outputs.insert("hostname".into(), extract_url_hostname(url).into());
             }
             if let Some(port) = h["port"].as_u64() {
-                outputs.insert("port".into(), port.to_string());
+                outputs.insert("port".into(), port.into());
             }
         }
         Some("tcp") => {
             if let Some(id) = h["pls_resource_id"].as_str() {
-                outputs.insert("pls_resource_id".into(), id.to_string());
+                outputs.insert("pls_resource_id".into(), id.into());
             }
             if let Some(port) = h["port"].as_u64() {
-                outputs.insert("port".into(), port.to_string());
+                outputs.insert("port".into(), port.into());
             }
         }
         Some("queue") => {
@@ -1749,7 +1970,7 @@ fn export_outputs_from_handle(h: &Handle) -> HashMap<String, String> {
                 h["service_bus_namespace_name"].as_str(),
                 h["topic_name"].as_str(),
             ) {
-                outputs.insert("queue_url".into(), format!("{}.servicebus.windows.net/{}", ns, topic));
+                outputs.insert("queue_url".into(), format!("{}.servicebus.windows.net/{}", ns, topic).into());
             }
         }
         _ => {}
@@ -1757,19 +1978,19 @@ fn export_outputs_from_handle(h: &Handle) -> HashMap<String, String> {
     outputs
 }
 
-fn import_outputs_from_handle(h: &Handle) -> HashMap<String, String> {
+fn import_outputs_from_handle(h: &Handle) -> HashMap<String, Value> {
     let mut outputs = HashMap::new();
     match h["type"].as_str() {
         Some("http") | Some("tcp") => {
             // Re-derive hostname from dns_record_name + enclave dns zone (not stored)
             // Return stored private_ip as fallback
             if let Some(ip) = h["private_ip"].as_str() {
-                outputs.insert("hostname".into(), ip.to_string());
+                outputs.insert("hostname".into(), ip.into());
             }
         }
         Some("queue") => {
             if let Some(url) = h["queue_url"].as_str() {
-                outputs.insert("queue_url".into(), url.to_string());
+                outputs.insert("queue_url".into(), url.into());
             }
         }
         _ => {}
@@ -1812,9 +2033,9 @@ fn extract_resource_names(resp: &Value) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nclav_domain::{CloudTarget, EnclaveId, PartitionId};
+    use nclav_domain::{AuthType, CloudTarget, EnclaveId, ExportTarget, PartitionId};
     use wiremock::{
-        matchers::{method, path, path_regex},
+        matchers::{body_partial_json, method, path, path_regex},
         Mock, MockServer, ResponseTemplate,
     };
 
@@ -1850,12 +2071,7 @@ mod tests {
             name:       "Product A Dev".into(),
             cloud:      Some(CloudTarget::Azure),
             region:     "eastus2".into(),
-            identity:   None,
-            network:    None,
-            dns:        None,
-            imports:    vec![],
-            exports:    vec![],
-            partitions: vec![],
+            ..Default::default()
         }
     }
 
@@ -2126,9 +2342,9 @@ mod tests {
 
         // Simulate what the reconciler does: inject context_vars (from the enclave handle)
         // into resolved_inputs. The driver reads nclav_subscription_id from there.
-        let mut resolved_inputs = HashMap::new();
-        resolved_inputs.insert("nclav_subscription_id".into(), sub_id.to_string());
-        resolved_inputs.insert("nclav_location".into(), "eastus2".to_string());
+        let mut resolved_inputs: HashMap<String, Value> = HashMap::new();
+        resolved_inputs.insert("nclav_subscription_id".into(), sub_id.into());
+        resolved_inputs.insert("nclav_location".into(), "eastus2".into());
 
         let result = d.provision_partition(&enc, &part, &resolved_inputs, None).await.unwrap();
         assert_eq!(result.handle["kind"].as_str(), Some("partition"));
@@ -2136,6 +2352,209 @@ mod tests {
         assert_eq!(result.handle["subscription_id"].as_str(), Some(sub_id));
     }
 
+    // ── provision_partition_access ────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn provision_partition_access_creates_asgs_nsg_and_rule() {
+        let server = MockServer::start().await;
+        let prefix = "/subscriptions/test-sub/resourceGroups/nclav-rg/providers/Microsoft.Network";
+
+        for part_id in ["api", "web"] {
+            Mock::given(method("PUT"))
+                .and(path(format!("{}/applicationSecurityGroups/nclav-asg-{}", prefix, part_id)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "id": format!("{}/applicationSecurityGroups/nclav-asg-{}", prefix, part_id),
+                })))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path(format!("{}/networkSecurityGroups/nclav-partitions-nsg", prefix)))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "error": { "code": "ResourceNotFound", "message": "not found" },
+            })))
+            .mount(&server)
+            .await;
+        let nsg_id = format!("{}/networkSecurityGroups/nclav-partitions-nsg", prefix);
+        Mock::given(method("PUT"))
+            .and(path(nsg_id.as_str()))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "id":   nsg_id,
+                "name": "nclav-partitions-nsg",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // subnet-0 is bare and gets the NSG; subnet-1 already has it.
+        Mock::given(method("GET"))
+            .and(path(format!("{}/virtualNetworks/nclav-vnet/subnets", prefix)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "value": [
+                {
+                    "id": format!("{}/virtualNetworks/nclav-vnet/subnets/subnet-0", prefix),
+                    "properties": { "addressPrefix": "10.0.0.0/24" },
+                },
+                {
+                    "id": format!("{}/virtualNetworks/nclav-vnet/subnets/subnet-1", prefix),
+                    "properties": { "addressPrefix": "10.0.1.0/24", "networkSecurityGroup": { "id": nsg_id } },
+                },
+            ]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/virtualNetworks/nclav-vnet/subnets/subnet-0", prefix)))
+            .and(body_partial_json(json!({
+                "properties": { "addressPrefix": "10.0.0.0/24", "networkSecurityGroup": { "id": nsg_id } },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "subnet-0" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{}/virtualNetworks/nclav-vnet/subnets/subnet-1", prefix)))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!(
+                "{}/networkSecurityGroups/nclav-partitions-nsg/securityRules/nclav-api-http-web",
+                prefix
+            )))
+            .and(body_partial_json(json!({
+                "properties": {
+                    "protocol":                             "Tcp",
+                    "destinationPortRange":                 "8443",
+                    "sourceApplicationSecurityGroups":      [{ "id": format!("{}/applicationSecurityGroups/nclav-asg-web", prefix) }],
+                    "destinationApplicationSecurityGroups": [{ "id": format!("{}/applicationSecurityGroups/nclav-asg-api", prefix) }],
+                    "access":                               "Allow",
+                    "direction":                            "Inbound",
+                    "priority":                             1000,
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "nclav-api-http-web" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let export = Export {
+            name:             "api-http".into(),
+            target_partition: PartitionId::new("api"),
+            export_type:      ExportType::Http,
+            to:               ExportTarget::Partition(PartitionId::new("web")),
+            auth:             AuthType::None,
+            hostname:         None,
+            port:             Some(8443),
+        };
+        let importer   = Partition { id: PartitionId::new("web"), ..dummy_partition() };
+        let enc_handle = json!({
+            "subscription_id":  "test-sub",
+            "location":         "eastus2",
+            "vnet_resource_id": format!("{}/virtualNetworks/nclav-vnet", prefix),
+        });
+
+        let result = driver(&server)
+            .provision_partition_access(&dummy_enclave(), &enc_handle, &export, &importer, &HashMap::new(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["nsg_rule"], "nclav-api-http-web");
+        assert_eq!(result.handle["nsg_priority"], 1000);
+        assert_eq!(result.handle["port"], 8443);
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_rejects_subnet_with_foreign_nsg() {
+        let server = MockServer::start().await;
+        let prefix = "/subscriptions/test-sub/resourceGroups/nclav-rg/providers/Microsoft.Network";
+        Mock::given(method("PUT"))
+            .and(path_regex("/applicationSecurityGroups/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{}/networkSecurityGroups/nclav-partitions-nsg", prefix)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": format!("{}/networkSecurityGroups/nclav-partitions-nsg", prefix),
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{}/virtualNetworks/nclav-vnet/subnets", prefix)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "value": [{
+                "id": format!("{}/virtualNetworks/nclav-vnet/subnets/subnet-0", prefix),
+                "properties": { "networkSecurityGroup": { "id": format!("{}/networkSecurityGroups/team-nsg", prefix) } },
+            }]})))
+            .mount(&server)
+            .await;
+
+        let export = Export {
+            name:             "api-http".into(),
+            target_partition: PartitionId::new("api"),
+            export_type:      ExportType::Http,
+            to:               ExportTarget::Partition(PartitionId::new("web")),
+            auth:             AuthType::None,
+            hostname:         None,
+            port:             Some(8443),
+        };
+        let importer   = Partition { id: PartitionId::new("web"), ..dummy_partition() };
+        let enc_handle = json!({
+            "subscription_id":  "test-sub",
+            "vnet_resource_id": format!("{}/virtualNetworks/nclav-vnet", prefix),
+        });
+
+        let err = driver(&server)
+            .provision_partition_access(&dummy_enclave(), &enc_handle, &export, &importer, &HashMap::new(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("team-nsg"), "{err}");
+    }
+
+    #[test]
+    fn partition_rule_priority_picks_lowest_free_or_keeps_own() {
+        let nsg = json!({ "properties": { "securityRules": [
+            { "name": "nclav-a-web", "properties": { "priority": 1000 } },
+            { "name": "team-rule",   "properties": { "priority": 1001 } },
+            { "name": "nclav-b-web", "properties": { "priority": 1003 } },
+        ]}});
+        assert_eq!(partition_rule_priority(&nsg, "nclav-c-web"), Some(1002));
+        assert_eq!(partition_rule_priority(&nsg, "nclav-b-web"), Some(1003));
+        assert_eq!(partition_rule_priority(&json!({}), "nclav-c-web"), Some(1000));
+
+        let full: Vec<Value> = PARTITION_RULE_PRIORITIES
+            .map(|p| json!({ "name": format!("r{}", p), "properties": { "priority": p } }))
+            .collect();
+        let full = json!({ "properties": { "securityRules": full } });
+        assert_eq!(partition_rule_priority(&full, "nclav-c-web"), None);
+    }
+
+    #[tokio::test]
+    async fn teardown_partition_access_deletes_nsg_rule() {
+        let server = MockServer::start().await;
+        let rules  = "/subscriptions/test-sub/resourceGroups/nclav-rg/providers/Microsoft.Network\
+                      /networkSecurityGroups/nclav-partitions-nsg/securityRules";
+        Mock::given(method("DELETE"))
+            .and(path(format!("{}/nclav-api-http-web", rules)))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // Already gone counts as deleted.
+        Mock::given(method("DELETE"))
+            .and(path(format!("{}/nclav-gone-web", rules)))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let d   = driver(&server);
+        let enc = dummy_enclave();
+        let handle = |rule: &str| json!({ "subscription_id": "test-sub", "nsg_rule": rule });
+        d.teardown_partition_access(&enc, &handle("nclav-api-http-web")).await.unwrap();
+        d.teardown_partition_access(&enc, &handle("nclav-gone-web")).await.unwrap();
+        // Queue or no-VNet handles carry no rule.
+        d.teardown_partition_access(&enc, &json!({ "subscription_id": "test-sub" })).await.unwrap();
+    }
+
     // ── observe_enclave ───────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -2219,6 +2638,12 @@ mod tests {
         assert_eq!(vars.get("nclav_identity_client_id").map(|s| s.as_str()), Some("mi-client-id"));
         // GCP-compat alias
         assert_eq!(vars.get("nclav_project_id").map(|s| s.as_str()), Some("my-sub-id"));
+
+        let mut keys: Vec<&str> = vars.keys().map(|s| s.as_str()).collect();
+        keys.sort();
+        let mut expected = CONTEXT_VARS.to_vec();
+        expected.sort();
+        assert_eq!(keys, expected, "CONTEXT_VARS must list every context_vars key");
     }
 
     // ── auth_env ──────────────────────────────────────────────────────────────
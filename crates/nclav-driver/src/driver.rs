@@ -1,11 +1,25 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use nclav_domain::{Enclave, Export, Import, Partition};
+use nclav_domain::{CloudTarget, Enclave, Export, ExportType, Import, Partition};
+use serde_json::Value;
 
 use crate::error::DriverError;
 use crate::Handle;
 
+/// Keys the driver for `cloud` returns from [`Driver::context_vars`].
+///
+/// Lets tooling (e.g. `nclav new partition`) know which `{{ nclav_* }}` tokens a
+/// partition can reference without a configured driver or a provisioned enclave.
+pub fn context_var_names(cloud: &CloudTarget) -> &'static [&'static str] {
+    match cloud {
+        CloudTarget::Local => &[],
+        CloudTarget::Gcp => crate::gcp::CONTEXT_VARS,
+        CloudTarget::Azure => crate::azure::CONTEXT_VARS,
+        CloudTarget::Aws => crate::aws::CONTEXT_VARS,
+    }
+}
+
 /// A GCP resource still labeled to a partition that no longer exists (or is unknown)
 /// in nclav's state. Returned by `list_orphaned_resources`.
 #[derive(Debug, Clone)]
@@ -26,7 +40,8 @@ pub struct ProvisionResult {
     /// Opaque handle that the driver uses to reference this resource.
     pub handle: Handle,
     /// Key/value outputs produced by the provisioning (e.g. hostname, port).
-    pub outputs: HashMap<String, String>,
+    /// Values may be any JSON type, e.g. a list of subnet IDs or a map of endpoints.
+    pub outputs: HashMap<String, Value>,
 }
 
 /// Read-only snapshot of a resource as it exists in the cloud right now.
@@ -39,11 +54,50 @@ pub struct ObservedState {
     pub healthy: bool,
     /// Current output values read from the cloud (may differ from stored outputs
     /// if cloud drift has occurred).
-    pub outputs: HashMap<String, String>,
+    pub outputs: HashMap<String, Value>,
     /// Full cloud API response, stored opaquely for debugging.
     pub raw: Handle,
 }
 
+/// Read `key` from an input/output map as a plain string.
+///
+/// Strings are returned as-is; numbers and booleans are rendered with
+/// `to_string()` so a Terraform `port` output works whether it was declared as
+/// a string or a number. Lists, maps, null and missing keys yield `None`.
+pub fn output_str(outputs: &HashMap<String, Value>, key: &str) -> Option<String> {
+    match outputs.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        v @ (Value::Number(_) | Value::Bool(_)) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+/// Copy a partition's outputs for use as export outputs, turning a string `port`
+/// (as emitted by a Terraform `output` declared without a type) into a number.
+/// Ports that do not parse are passed through unchanged.
+pub fn export_outputs(partition_outputs: &HashMap<String, Value>) -> HashMap<String, Value> {
+    let mut outputs = partition_outputs.clone();
+    if let Some(port) = outputs.get_mut("port") {
+        if let Some(n) = port.as_str().and_then(|p| p.parse::<u16>().ok()) {
+            *port = Value::from(n);
+        }
+    }
+    outputs
+}
+
+/// Port a partition-scoped access rule opens: the export's `port:` override, else
+/// the exporter's `port` output, else 443 for HTTP. `None` for queues (no network
+/// primitive) and for TCP exports with no known port.
+pub fn access_port(export: &Export, exporter_outputs: &HashMap<String, Value>) -> Option<u16> {
+    if export.export_type == ExportType::Queue {
+        return None;
+    }
+    export
+        .port
+        .or_else(|| output_str(exporter_outputs, "port").and_then(|p| p.parse().ok()))
+        .or((export.export_type == ExportType::Http).then_some(443))
+}
+
 #[async_trait]
 pub trait Driver: Send + Sync + 'static {
     fn name(&self) -> &'static str;
@@ -66,7 +120,7 @@ pub trait Driver: Send + Sync + 'static {
         &self,
         enclave: &Enclave,
         partition: &Partition,
-        resolved_inputs: &HashMap<String, String>,
+        resolved_inputs: &HashMap<String, Value>,
         existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError>;
 
@@ -81,7 +135,7 @@ pub trait Driver: Send + Sync + 'static {
         &self,
         enclave: &Enclave,
         export: &Export,
-        partition_outputs: &HashMap<String, String>,
+        partition_outputs: &HashMap<String, Value>,
         existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError>;
 
@@ -93,6 +147,37 @@ pub trait Driver: Send + Sync + 'static {
         existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError>;
 
+    /// Allow `importer` to reach the partition behind `export`, an export scoped
+    /// `to: partition:` within `enclave`.
+    ///
+    /// Partitions share the enclave's flat network, so this is the intra-enclave
+    /// firewall / NSG rule expressing the declared contract. Whether the workloads a
+    /// partition's Terraform creates actually attach to it is not verified.
+    /// `exporter_outputs` are the resolved outputs of the exporting partition.
+    async fn provision_partition_access(
+        &self,
+        enclave: &Enclave,
+        enclave_handle: &Handle,
+        export: &Export,
+        importer: &Partition,
+        exporter_outputs: &HashMap<String, Value>,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError>;
+
+    /// Remove the access rule described by `handle`, as returned by
+    /// [`provision_partition_access`](Self::provision_partition_access). Called when
+    /// the import is removed or either partition is torn down; a rule already gone
+    /// is not an error.
+    ///
+    /// Default implementation does nothing, for drivers whose rules need no cleanup.
+    async fn teardown_partition_access(
+        &self,
+        _enclave: &Enclave,
+        _handle: &Handle,
+    ) -> Result<(), DriverError> {
+        Ok(())
+    }
+
     // ── Read-only (drift detection) ───────────────────────────────────────────
 
     /// Read the current state of an enclave from the cloud without modifying
@@ -114,7 +199,7 @@ pub trait Driver: Send + Sync + 'static {
 
     // ── IaC support ───────────────────────────────────────────────────────────
 
-    /// Cloud-specific Terraform variable values (written to `nclav_context.auto.tfvars`).
+    /// Cloud-specific Terraform variable values (written to `nclav_context.auto.tfvars.json`).
     /// Implementations should extract values like `project_id` and `region` from
     /// the enclave handle produced by `provision_enclave`.
     fn context_vars(&self, enclave: &Enclave, handle: &Handle) -> HashMap<String, String>;
@@ -7,10 +7,12 @@ use nclav_domain::{AuthType, Enclave, Export, ExportType, Import, Partition};
 #[cfg(test)]
 use nclav_domain::ProducesType;
 use serde_json::{json, Value};
-use tracing::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, info_span, warn, Instrument};
 
-use crate::driver::{Driver, ObservedState, ProvisionResult};
+use crate::driver::{access_port, export_outputs, output_str, Driver, ObservedState, ProvisionResult};
 use crate::error::DriverError;
+use crate::propagation::PropagateTrace;
 use crate::Handle;
 
 // ── Configuration ─────────────────────────────────────────────────────────────
@@ -119,6 +121,9 @@ const REQUIRED_APIS: &[&str] = &[
     "cloudasset.googleapis.com",
 ];
 
+/// Keys returned by [`GcpDriver`]'s `context_vars`, usable as `{{ token }}` in `inputs:`.
+pub const CONTEXT_VARS: &[&str] = &["nclav_project_id", "nclav_region"];
+
 // ── GcpDriver ─────────────────────────────────────────────────────────────────
 
 pub struct GcpDriver {
@@ -310,60 +315,65 @@ impl GcpDriver {
     /// Backoff: 1 s, 2 s, 4 s, 8 s, 16 s, 30 s, 30 s, … (max 120 polls ≈ ~58 min).
     /// Progress is logged at INFO every 10 polls so operators can follow along.
     async fn wait_for_operation(&self, op_url: &str) -> Result<Value, DriverError> {
-        let token = self.bearer().await?;
-        let delays = [1u64, 2, 4, 8, 16, 30];
-        let max_polls = 120;
+        async {
+            let token = self.bearer().await?;
+            let delays = [1u64, 2, 4, 8, 16, 30];
+            let max_polls = 120;
 
-        for (i, &delay) in delays.iter().cycle().take(max_polls).enumerate() {
-            let resp: Value = self
-                .client
-                .get(op_url)
-                .bearer_auth(&token)
-                .send()
-                .await
-                .map_err(|e| DriverError::Internal(format!("poll {}: {}", op_url, e)))?
-                .json()
-                .await
-                .map_err(|e| DriverError::Internal(format!("poll decode: {}", e)))?;
-
-            // Two operation formats in play:
-            //   LRO  (Resource Manager, Service Usage, Cloud Run): "done": true
-            //   Compute API:                                        "status": "DONE"
-            let is_done = resp["done"].as_bool().unwrap_or(false)
-                || resp["status"].as_str() == Some("DONE");
-
-            if is_done {
-                // Compute API errors: { "error": { "errors": [{ "code": "...", "message": "..." }] } }
-                // LRO errors:         { "error": { "code": 403, "status": "...", "message": "..." } }
-                if let Some(errors) = resp["error"]["errors"].as_array() {
-                    if !errors.is_empty() {
-                        let msg = errors[0]["message"].as_str().unwrap_or("operation failed");
+            for (i, &delay) in delays.iter().cycle().take(max_polls).enumerate() {
+                let resp: Value = self
+                    .client
+                    .get(op_url)
+                    .bearer_auth(&token)
+                    .traced()
+                    .send()
+                    .await
+                    .map_err(|e| DriverError::Internal(format!("poll {}: {}", op_url, e)))?
+                    .json()
+                    .await
+                    .map_err(|e| DriverError::Internal(format!("poll decode: {}", e)))?;
+
+                // Two operation formats in play:
+                //   LRO  (Resource Manager, Service Usage, Cloud Run): "done": true
+                //   Compute API:                                        "status": "DONE"
+                let is_done = resp["done"].as_bool().unwrap_or(false)
+                    || resp["status"].as_str() == Some("DONE");
+
+                if is_done {
+                    // Compute API errors: { "error": { "errors": [{ "code": "...", "message": "..." }] } }
+                    // LRO errors:         { "error": { "code": 403, "status": "...", "message": "..." } }
+                    if let Some(errors) = resp["error"]["errors"].as_array() {
+                        if !errors.is_empty() {
+                            let msg = errors[0]["message"].as_str().unwrap_or("operation failed");
+                            return Err(DriverError::ProvisionFailed(
+                                format!("operation failed: {}", msg),
+                            ));
+                        }
+                    } else if resp["error"].is_object() {
+                        let msg = Self::extract_gcp_error(&json!({ "error": resp["error"] }));
                         return Err(DriverError::ProvisionFailed(
                             format!("operation failed: {}", msg),
                         ));
                     }
-                } else if resp["error"].is_object() {
-                    let msg = Self::extract_gcp_error(&json!({ "error": resp["error"] }));
-                    return Err(DriverError::ProvisionFailed(
-                        format!("operation failed: {}", msg),
-                    ));
+                    return Ok(resp["response"].clone());
                 }
-                return Ok(resp["response"].clone());
-            }
 
-            let poll = i + 1;
-            if poll % 10 == 0 {
-                info!(poll, op_url, "still waiting for GCP operation");
-            } else {
-                debug!(poll, op_url, delay, "GCP operation pending, waiting");
+                let poll = i + 1;
+                if poll % 10 == 0 {
+                    info!(poll, op_url, "still waiting for GCP operation");
+                } else {
+                    debug!(poll, op_url, delay, "GCP operation pending, waiting");
+                }
+                tokio::time::sleep(Duration::from_secs(delay)).await;
             }
-            tokio::time::sleep(Duration::from_secs(delay)).await;
-        }
 
-        Err(DriverError::ProvisionFailed(format!(
-            "GCP operation timed out after {} polls: {}",
-            max_polls, op_url
-        )))
+            Err(DriverError::ProvisionFailed(format!(
+                "GCP operation timed out after {} polls: {}",
+                max_polls, op_url
+            )))
+        }
+        .instrument(info_span!("wait_for_operation", op_url))
+        .await
     }
 
     // ── JSON helper ───────────────────────────────────────────────────────────
@@ -379,6 +389,7 @@ impl GcpDriver {
             .client
             .post(url)
             .bearer_auth(token)
+            .traced()
             .json(body)
             .send()
             .await
@@ -432,6 +443,115 @@ impl GcpDriver {
         Ok(())
     }
 
+    /// Create the global firewall rule `rule`, or patch it in place when the
+    /// existing rule differs in direction, service accounts or allowed ports.
+    async fn ensure_firewall_rule(
+        &self,
+        token: &str,
+        project_id: &str,
+        rule: &Value,
+    ) -> Result<(), DriverError> {
+        let rule_name = rule["name"].as_str().unwrap_or_default();
+        let fw_url = format!(
+            "{}/compute/v1/projects/{}/global/firewalls",
+            self.base.compute, project_id
+        );
+        let rule_url = format!("{}/{}", fw_url, rule_name);
+        let resp = self
+            .client
+            .get(&rule_url)
+            .bearer_auth(token)
+            .traced()
+            .send()
+            .await
+            .map_err(|e| DriverError::ProvisionFailed(format!("GET {rule_url}: {e}")))?;
+
+        let op = if resp.status().as_u16() == 404 {
+            info!(project_id, rule_name, "Creating firewall rule");
+            self.post_json(&fw_url, token, rule).await?
+        } else {
+            let current: Value = resp
+                .json()
+                .await
+                .map_err(|e| DriverError::Internal(format!("GET {rule_url} decode: {e}")))?;
+            if current.get("error").is_some() {
+                return Err(DriverError::ProvisionFailed(
+                    format!("GET {rule_url}: {}", Self::extract_gcp_error(&current)),
+                ));
+            }
+            let fields = ["direction", "sourceServiceAccounts", "targetServiceAccounts", "allowed"];
+            if fields.iter().all(|f| current[*f] == rule[*f]) {
+                debug!(project_id, rule_name, "Firewall rule up to date");
+                return Ok(());
+            }
+            info!(project_id, rule_name, "Updating firewall rule");
+            let patch: Value = fields.iter().map(|f| (f.to_string(), rule[*f].clone())).collect();
+            let resp: Value = self
+                .client
+                .patch(&rule_url)
+                .bearer_auth(token)
+                .traced()
+                .json(&patch)
+                .send()
+                .await
+                .map_err(|e| DriverError::ProvisionFailed(format!("PATCH {rule_url}: {e}")))?
+                .json()
+                .await
+                .map_err(|e| DriverError::Internal(format!("PATCH {rule_url} decode: {e}")))?;
+            if resp.get("error").is_some() {
+                return Err(DriverError::ProvisionFailed(
+                    format!("PATCH {rule_url}: {}", Self::extract_gcp_error(&resp)),
+                ));
+            }
+            resp
+        };
+        self.wait_for_global_operation(project_id, &op).await
+    }
+
+    /// Delete a global firewall rule. A rule already gone is not an error.
+    async fn delete_firewall_rule(
+        &self,
+        token: &str,
+        project_id: &str,
+        rule_name: &str,
+    ) -> Result<(), DriverError> {
+        info!(project_id, rule_name, "Deleting firewall rule");
+        let rule_url = format!(
+            "{}/compute/v1/projects/{}/global/firewalls/{}",
+            self.base.compute, project_id, rule_name
+        );
+        let resp = self
+            .client
+            .delete(&rule_url)
+            .bearer_auth(token)
+            .traced()
+            .send()
+            .await
+            .map_err(|e| DriverError::TeardownFailed(e.to_string()))?;
+
+        let status = resp.status();
+        if status.as_u16() == 404 {
+            return Ok(());
+        }
+        let body: Value = resp.json().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(DriverError::TeardownFailed(Self::extract_gcp_error(&body)));
+        }
+        self.wait_for_global_operation(project_id, &body).await
+    }
+
+    /// Wait for the global Compute operation `op` returned by a write, if it names one.
+    async fn wait_for_global_operation(&self, project_id: &str, op: &Value) -> Result<(), DriverError> {
+        if let Some(op_name) = op["name"].as_str() {
+            let op_url = format!(
+                "{}/compute/v1/projects/{}/global/operations/{}",
+                self.base.compute, project_id, op_name
+            );
+            self.wait_for_operation(&op_url).await?;
+        }
+        Ok(())
+    }
+
     /// Provision the nclav server platform in GCP.
     ///
     /// Called once before the server starts (e.g. from `bootstrap/gcp/` Terraform or manually).
@@ -647,6 +767,26 @@ fn partition_sa_id(partition_id: &str) -> String {
         .to_string()
 }
 
+/// Name of the firewall rule letting `importer_id` reach a partition export.
+///
+/// GCP resource names: 1–63 chars, lowercase letters/digits/hyphens, starts with a letter.
+/// Sanitizing and truncating can map distinct pairs to the same prefix, so the name
+/// ends with a short hash of the raw `(export_name, importer_id)` pair.
+fn partition_firewall_name(export_name: &str, importer_id: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(export_name.as_bytes())
+        .chain_update([0u8])
+        .chain_update(importer_id.as_bytes())
+        .finalize();
+    let suffix: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    let prefix: String = format!("nclav-{}-{}", export_name, importer_id)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .take(63 - suffix.len() - 1)
+        .collect();
+    format!("{}-{}", prefix.trim_end_matches('-'), suffix)
+}
+
 // ── Project ID sanitization ───────────────────────────────────────────────────
 
 /// Sanitize a raw string into a valid GCP project ID.
@@ -715,6 +855,7 @@ impl Driver for GcpDriver {
                         .client
                         .get(&url)
                         .bearer_auth(&token)
+                        .traced()
                         .send()
                         .await
                         .map_err(|e| DriverError::Internal(e.to_string()))?;
@@ -763,6 +904,7 @@ impl Driver for GcpDriver {
                     .client
                     .get(&get_url)
                     .bearer_auth(&token)
+                    .traced()
                     .send()
                     .await
                     .map_err(|e| DriverError::Internal(e.to_string()))?
@@ -803,6 +945,7 @@ impl Driver for GcpDriver {
             .client
             .patch(&label_url)
             .bearer_auth(&token)
+            .traced()
             .query(&[("updateMask", "labels")])
             .json(&json!({
                 "labels": {
@@ -834,6 +977,7 @@ impl Driver for GcpDriver {
         let billing_resp = self.client
             .put(&billing_url)
             .bearer_auth(&token)
+            .traced()
             .json(&json!({ "billingAccountName": self.config.billing_account }))
             .send()
             .await
@@ -1034,6 +1178,7 @@ impl Driver for GcpDriver {
             .client
             .delete(&url)
             .bearer_auth(&token)
+            .traced()
             .send()
             .await
             .map_err(|e| DriverError::TeardownFailed(e.to_string()))?;
@@ -1054,7 +1199,7 @@ impl Driver for GcpDriver {
         &self,
         enclave: &Enclave,
         partition: &Partition,
-        _resolved_inputs: &HashMap<String, String>,
+        _resolved_inputs: &HashMap<String, Value>,
         _existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         let token          = self.bearer().await?;
@@ -1155,6 +1300,7 @@ impl Driver for GcpDriver {
                 .client
                 .delete(&sa_url)
                 .bearer_auth(&token)
+                .traced()
                 .send()
                 .await
                 .map_err(|e| DriverError::TeardownFailed(e.to_string()))?;
@@ -1175,13 +1321,14 @@ impl Driver for GcpDriver {
         &self,
         enclave: &Enclave,
         export: &Export,
-        partition_outputs: &HashMap<String, String>,
+        partition_outputs: &HashMap<String, Value>,
         _existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         let token          = self.bearer().await?;
         let project_id_buf = self.gcp_project_id(enclave.id.as_str());
         let project_id     = project_id_buf.as_str();
         let region         = self.region(enclave);
+        let outputs        = export_outputs(partition_outputs);
 
         match export.export_type {
             ExportType::Http => {
@@ -1220,9 +1367,9 @@ impl Driver for GcpDriver {
                     } else {
                         json!([])
                     },
-                    "outputs": partition_outputs,
+                    "outputs": outputs,
                 });
-                Ok(ProvisionResult { handle, outputs: partition_outputs.clone() })
+                Ok(ProvisionResult { handle, outputs })
             }
 
             ExportType::Tcp => {
@@ -1232,9 +1379,7 @@ impl Driver for GcpDriver {
                 // VM Fleet L4 exports (MIG + ILB + PSC) are a separate builder — not implemented
                 // here. Services without a native PSC attachment should be wrapped behind an HTTP
                 // export or require hub-spoke topology.
-                let service_attachment = partition_outputs
-                    .get("psc_service_attachment")
-                    .cloned()
+                let service_attachment = output_str(partition_outputs, "psc_service_attachment")
                     .ok_or_else(|| DriverError::ProvisionFailed(format!(
                         "tcp export '{}': partition must declare 'psc_service_attachment' in its \
                          outputs. Catalog services (Cloud SQL, AlloyDB, Memorystore) support this \
@@ -1251,9 +1396,9 @@ impl Driver for GcpDriver {
                     "export_name":        export.name,
                     "region":             region,
                     "service_attachment": service_attachment,
-                    "outputs":            partition_outputs,
+                    "outputs":            outputs,
                 });
-                Ok(ProvisionResult { handle, outputs: partition_outputs.clone() })
+                Ok(ProvisionResult { handle, outputs })
             }
 
             ExportType::Queue => {
@@ -1263,10 +1408,10 @@ impl Driver for GcpDriver {
                     "type":        "queue",
                     "project_id":  project_id,
                     "export_name": export.name,
-                    "topic": partition_outputs.get("queue_url").cloned().unwrap_or_default(),
-                    "outputs":     partition_outputs,
+                    "topic": output_str(partition_outputs, "queue_url").unwrap_or_default(),
+                    "outputs":     outputs,
                 });
-                Ok(ProvisionResult { handle, outputs: partition_outputs.clone() })
+                Ok(ProvisionResult { handle, outputs })
             }
         }
     }
@@ -1284,16 +1429,14 @@ impl Driver for GcpDriver {
         let importer_project_buf = self.gcp_project_id(importer.id.as_str());
         let importer_project     = importer_project_buf.as_str();
         let export_type          = export_handle["type"].as_str().unwrap_or("");
-        let mut outputs      = HashMap::new();
+        let mut outputs: HashMap<String, Value> = HashMap::new();
 
         match export_type {
             "http" => {
                 // Inject resolved outputs from the export handle.
                 if let Some(obj) = export_handle["outputs"].as_object() {
                     for (k, v) in obj {
-                        if let Some(s) = v.as_str() {
-                            outputs.insert(k.clone(), s.to_string());
-                        }
+                        outputs.insert(k.clone(), v.clone());
                     }
                 }
 
@@ -1380,6 +1523,7 @@ impl Driver for GcpDriver {
                 let addr_resp: Value = self.client
                     .get(&get_addr_url)
                     .bearer_auth(&token)
+                    .traced()
                     .send()
                     .await
                     .map_err(|e| DriverError::ProvisionFailed(
@@ -1479,12 +1623,10 @@ impl Driver for GcpDriver {
                 // Build outputs: pass through all export outputs, override hostname with DNS name
                 if let Some(obj) = export_handle["outputs"].as_object() {
                     for (k, v) in obj {
-                        if let Some(s) = v.as_str() {
-                            outputs.insert(k.clone(), s.to_string());
-                        }
+                        outputs.insert(k.clone(), v.clone());
                     }
                 }
-                outputs.insert("hostname".into(), dns_name.clone());
+                outputs.insert("hostname".into(), json!(dns_name));
 
                 let handle = json!({
                     "driver":           "gcp",
@@ -1512,6 +1654,7 @@ impl Driver for GcpDriver {
                     .client
                     .put(&sub_url)
                     .bearer_auth(&token)
+                    .traced()
                     .json(&json!({
                         "topic":              exporter_topic,
                         "ackDeadlineSeconds": 60,
@@ -1530,7 +1673,7 @@ impl Driver for GcpDriver {
                     "projects/{}/subscriptions/{}",
                     importer_project, import.alias
                 );
-                outputs.insert("queue_url".into(), queue_url.clone());
+                outputs.insert("queue_url".into(), json!(queue_url));
 
                 let handle = json!({
                     "driver":           "gcp",
@@ -1551,6 +1694,99 @@ impl Driver for GcpDriver {
         }
     }
 
+    // ── provision_partition_access ────────────────────────────────────────────
+
+    async fn provision_partition_access(
+        &self,
+        enclave: &Enclave,
+        _enclave_handle: &Handle,
+        export: &Export,
+        importer: &Partition,
+        exporter_outputs: &HashMap<String, Value>,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        let project_id_buf = self.gcp_project_id(enclave.id.as_str());
+        let project_id     = project_id_buf.as_str();
+        let exporter_id    = export.target_partition.as_str();
+        let importer_id    = importer.id.as_str();
+
+        // Queues have no network primitive, and without a VPC there is nothing to firewall.
+        let port = match access_port(export, exporter_outputs) {
+            Some(port) if enclave.network.is_some() => port,
+            _ => {
+                debug!(project_id, export = %export.name, "No firewall rule needed for partition access");
+                if let Some(existing) = existing {
+                    self.teardown_partition_access(enclave, existing).await?;
+                }
+                let handle = json!({
+                    "driver":         "gcp",
+                    "kind":           "partition_access",
+                    "project_id":     project_id,
+                    "export_name":    export.name,
+                    "from_partition": importer_id,
+                    "to_partition":   exporter_id,
+                    "firewall_rule":  Value::Null,
+                });
+                return Ok(ProvisionResult { handle, outputs: HashMap::new() });
+            }
+        };
+
+        // Partitions share the enclave VPC; scope the rule by their service accounts.
+        let token       = self.bearer().await?;
+        let rule_name   = partition_firewall_name(&export.name, importer_id);
+        let importer_sa = format!("{}@{}.iam.gserviceaccount.com", partition_sa_id(importer_id), project_id);
+        let exporter_sa = format!("{}@{}.iam.gserviceaccount.com", partition_sa_id(exporter_id), project_id);
+        debug!(project_id, rule_name, importer_id, exporter_id, port, "Ensuring partition firewall rule");
+        self.ensure_firewall_rule(
+            &token,
+            project_id,
+            &json!({
+                "name":                  rule_name,
+                "network":               format!("projects/{}/global/networks/nclav-vpc", project_id),
+                "direction":             "INGRESS",
+                "sourceServiceAccounts": [importer_sa],
+                "targetServiceAccounts": [exporter_sa],
+                "allowed":               [{ "IPProtocol": "tcp", "ports": [port.to_string()] }],
+            }),
+        )
+        .await?;
+
+        // Rules created under an earlier naming scheme are replaced, not left behind.
+        if let Some(old) = existing.and_then(|h| h["firewall_rule"].as_str()) {
+            if old != rule_name {
+                self.delete_firewall_rule(&token, project_id, old).await?;
+            }
+        }
+
+        let handle = json!({
+            "driver":         "gcp",
+            "kind":           "partition_access",
+            "project_id":     project_id,
+            "export_name":    export.name,
+            "from_partition": importer_id,
+            "to_partition":   exporter_id,
+            "firewall_rule":  rule_name,
+            "port":           port,
+        });
+        Ok(ProvisionResult { handle, outputs: HashMap::new() })
+    }
+
+    // ── teardown_partition_access ─────────────────────────────────────────────
+
+    async fn teardown_partition_access(
+        &self,
+        _enclave: &Enclave,
+        handle: &Handle,
+    ) -> Result<(), DriverError> {
+        let (Some(project_id), Some(rule_name)) =
+            (handle["project_id"].as_str(), handle["firewall_rule"].as_str())
+        else {
+            return Ok(());
+        };
+        let token = self.bearer().await?;
+        self.delete_firewall_rule(&token, project_id, rule_name).await
+    }
+
     // ── observe_enclave ───────────────────────────────────────────────────────
 
     async fn observe_enclave(
@@ -1568,6 +1804,7 @@ impl Driver for GcpDriver {
             .client
             .get(&url)
             .bearer_auth(&token)
+            .traced()
             .send()
             .await
             .map_err(|e| DriverError::Internal(e.to_string()))?;
@@ -1701,6 +1938,7 @@ impl Driver for GcpDriver {
             .client
             .post(&url)
             .bearer_auth(&token)
+            .traced()
             .json(&json!({ "query": query }))
             .send()
             .await
@@ -1740,6 +1978,7 @@ impl Driver for GcpDriver {
             .client
             .post(&url)
             .bearer_auth(&token)
+            .traced()
             .json(&json!({ "query": "labels.nclav-managed=true" }))
             .send()
             .await
@@ -1781,7 +2020,7 @@ mod tests {
     use base64::Engine as _;
     use nclav_domain::{CloudTarget, DnsConfig, EnclaveId, ExportTarget, NetworkConfig, PartitionId};
     use wiremock::{
-        matchers::{method, path},
+        matchers::{body_partial_json, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
@@ -1882,12 +2121,7 @@ mod tests {
             name:       "Test Project".into(),
             cloud:      Some(CloudTarget::Gcp),
             region:     "us-central1".into(),
-            identity:   None,
-            network:    None,
-            dns:        None,
-            imports:    vec![],
-            exports:    vec![],
-            partitions: vec![],
+            ..Default::default()
         }
     }
 
@@ -2194,17 +2428,13 @@ mod tests {
             name:       "Importer".into(),
             cloud:      Some(CloudTarget::Gcp),
             region:     "us-central1".into(),
-            identity:   None,
-            network:    None,
-            dns:        None,
-            imports:    vec![],
-            exports:    vec![],
-            partitions: vec![],
+            ..Default::default()
         };
         let import = Import {
             from:        EnclaveId::new("exporter-proj"),
             export_name: "events".into(),
             alias:       "my-alias".into(),
+            import_type: None,
         };
         let export_handle = json!({
             "type":    "queue",
@@ -2763,7 +2993,7 @@ mod tests {
         );
         assert_eq!(result.handle["type"], "tcp");
         assert_eq!(result.outputs["hostname"], "10.1.0.5");
-        assert_eq!(result.outputs["port"], "5432");
+        assert_eq!(result.outputs["port"], 5432);
         assert_eq!(
             result.outputs["psc_service_attachment"],
             "projects/acme-gitea-db/regions/us-central1/serviceAttachments/gitea-psc",
@@ -2807,12 +3037,9 @@ mod tests {
             name:       "Importer".into(),
             cloud:      Some(CloudTarget::Gcp),
             region:     "us-central1".into(),
-            identity:   None,
             network:    Some(NetworkConfig { vpc_cidr: None, subnets: vec!["10.2.0.0/20".into()] }),
             dns:        Some(DnsConfig { zone: Some("gitea-app.local".into()) }),
-            imports:    vec![],
-            exports:    vec![],
-            partitions: vec![],
+            ..Default::default()
         }
     }
 
@@ -2824,7 +3051,7 @@ mod tests {
             "service_attachment": "projects/acme-gitea-db/regions/us-central1/serviceAttachments/gitea-psc",
             "outputs": {
                 "hostname":    "10.1.0.5",
-                "port":        "5432",
+                "port":        5432,
                 "db_name":     "gitea",
                 "db_user":     "gitea",
                 "db_password": "supersecret",
@@ -2837,6 +3064,7 @@ mod tests {
             from:        EnclaveId::new("exporter-proj"),
             export_name: "postgres-tcp".into(),
             alias:       "database".into(),
+            import_type: None,
         }
     }
 
@@ -2909,7 +3137,7 @@ mod tests {
             .unwrap();
 
         assert_eq!(result.outputs["hostname"], "database.gitea-app.local");
-        assert_eq!(result.outputs["port"],     "5432");
+        assert_eq!(result.outputs["port"],     5432);
         assert_eq!(result.outputs["db_name"],  "gitea");
         assert_eq!(result.handle["endpoint_ip"],  "10.2.5.10");
         assert_eq!(result.handle["dns_name"],     "database.gitea-app.local");
@@ -3019,4 +3247,219 @@ mod tests {
         let msg = err.to_string();
         assert!(msg.contains("network"), "expected network mention, got: {msg}");
     }
+
+    // ── provision_partition_access ────────────────────────────────────────────
+
+    fn partition_export() -> Export {
+        Export {
+            name:             "api-http".into(),
+            target_partition: PartitionId::new("api"),
+            export_type:      ExportType::Http,
+            to:               ExportTarget::Partition(PartitionId::new("web")),
+            auth:             AuthType::None,
+            hostname:         None,
+            port:             None,
+        }
+    }
+
+    fn web_partition() -> Partition {
+        Partition { id: PartitionId::new("web"), name: "Web".into(), ..http_partition() }
+    }
+
+    fn web_rule() -> String {
+        partition_firewall_name("api-http", "web")
+    }
+
+    #[test]
+    fn partition_firewall_name_is_valid_and_distinct() {
+        let a = partition_firewall_name("api-http", "web");
+        let b = partition_firewall_name("api.http", "web");
+        let c = partition_firewall_name("api", "http-web");
+        assert!(a.starts_with("nclav-api-http-web-"), "{a}");
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a, partition_firewall_name("api-http", "web"));
+
+        let long = partition_firewall_name(&"x".repeat(80), "importer");
+        assert!(long.len() <= 63, "{long}");
+        assert_ne!(long, partition_firewall_name(&"x".repeat(80), "other"));
+        assert!(long.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_creates_firewall_rule() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/compute/v1/projects/importer-proj/global/firewalls"))
+            .and(body_partial_json(json!({
+                "name":                  web_rule(),
+                "direction":             "INGRESS",
+                "sourceServiceAccounts": ["partition-web@importer-proj.iam.gserviceaccount.com"],
+                "targetServiceAccounts": ["partition-api@importer-proj.iam.gserviceaccount.com"],
+                "allowed":               [{ "IPProtocol": "tcp", "ports": ["8080"] }],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "fw-op" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/compute/v1/projects/importer-proj/global/operations/fw-op"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "DONE" })))
+            .mount(&server)
+            .await;
+
+        let outputs = HashMap::from([("port".to_string(), json!("8080"))]);
+        let result = driver(&server)
+            .provision_partition_access(
+                &tcp_import_enclave(),
+                &json!({}),
+                &partition_export(),
+                &web_partition(),
+                &outputs,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["firewall_rule"], web_rule());
+        assert_eq!(result.handle["port"], 8080);
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_patches_differing_firewall_rule() {
+        let server = MockServer::start().await;
+        let rule_path = format!("/compute/v1/projects/importer-proj/global/firewalls/{}", web_rule());
+        Mock::given(method("GET"))
+            .and(path(rule_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name":                  web_rule(),
+                "direction":             "INGRESS",
+                "sourceServiceAccounts": ["partition-web@importer-proj.iam.gserviceaccount.com"],
+                "targetServiceAccounts": ["partition-api@importer-proj.iam.gserviceaccount.com"],
+                "allowed":               [{ "IPProtocol": "tcp", "ports": ["80"] }],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path(rule_path.as_str()))
+            .and(body_partial_json(json!({
+                "allowed": [{ "IPProtocol": "tcp", "ports": ["8080"] }],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "fw-op" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/compute/v1/projects/importer-proj/global/firewalls"))
+            .respond_with(ResponseTemplate::new(409))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/compute/v1/projects/importer-proj/global/operations/fw-op"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "DONE" })))
+            .mount(&server)
+            .await;
+
+        let outputs = HashMap::from([("port".to_string(), json!("8080"))]);
+        let result = driver(&server)
+            .provision_partition_access(
+                &tcp_import_enclave(),
+                &json!({}),
+                &partition_export(),
+                &web_partition(),
+                &outputs,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["port"], 8080);
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_deletes_rule_under_previous_name() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/compute/v1/projects/importer-proj/global/firewalls"))
+            .and(body_partial_json(json!({ "name": web_rule() })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "fw-op" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/compute/v1/projects/importer-proj/global/firewalls/nclav-api-http-web"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "fw-op" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/compute/v1/projects/importer-proj/global/operations/fw-op"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "DONE" })))
+            .mount(&server)
+            .await;
+
+        let existing = json!({ "project_id": "importer-proj", "firewall_rule": "nclav-api-http-web", "port": 8080 });
+        let outputs = HashMap::from([("port".to_string(), json!(8080))]);
+        let result = driver(&server)
+            .provision_partition_access(
+                &tcp_import_enclave(),
+                &json!({}),
+                &partition_export(),
+                &web_partition(),
+                &outputs,
+                Some(&existing),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.handle["firewall_rule"], web_rule());
+    }
+
+    #[tokio::test]
+    async fn teardown_partition_access_deletes_firewall_rule() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/compute/v1/projects/importer-proj/global/firewalls/nclav-api-http-web"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "fw-del" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/compute/v1/projects/importer-proj/global/operations/fw-del"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "DONE" })))
+            .mount(&server)
+            .await;
+        // Already gone counts as deleted.
+        Mock::given(method("DELETE"))
+            .and(path("/compute/v1/projects/importer-proj/global/firewalls/nclav-gone-web"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let driver = driver(&server);
+        let enclave = tcp_import_enclave();
+        let handle = |rule: &str| json!({ "project_id": "importer-proj", "firewall_rule": rule });
+        driver.teardown_partition_access(&enclave, &handle("nclav-api-http-web")).await.unwrap();
+        driver.teardown_partition_access(&enclave, &handle("nclav-gone-web")).await.unwrap();
+        driver.teardown_partition_access(&enclave, &json!({ "firewall_rule": null })).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn provision_partition_access_without_network_is_noop() {
+        // No mocks mounted: any request would fail the call.
+        let result = driver(&MockServer::start().await)
+            .provision_partition_access(
+                &dummy_enclave(),
+                &json!({}),
+                &partition_export(),
+                &web_partition(),
+                &HashMap::new(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.handle["firewall_rule"].is_null());
+    }
 }
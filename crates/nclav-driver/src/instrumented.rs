@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use nclav_domain::{Enclave, Export, Import, Partition};
+use nclav_telemetry::metrics;
+use serde_json::Value;
+use tracing::{field, info_span, Instrument};
+
+use crate::driver::{Driver, ObservedState, OrphanedResource, ProvisionResult};
+use crate::error::DriverError;
+use crate::Handle;
+
+/// Wraps a [`Driver`], running every cloud call in a `driver_call` span and
+/// recording its latency and errors in `nclav_driver_call_duration_seconds`
+/// and `nclav_driver_call_errors_total`.
+///
+/// [`DriverRegistry::register`](crate::DriverRegistry::register) wraps every
+/// driver it is given, so callers never construct this directly.
+pub struct InstrumentedDriver {
+    inner: Arc<dyn Driver>,
+}
+
+impl InstrumentedDriver {
+    pub fn new(inner: Arc<dyn Driver>) -> Self {
+        Self { inner }
+    }
+
+    async fn timed<T>(
+        &self,
+        method: &'static str,
+        enclave: &Enclave,
+        call: impl Future<Output = Result<T, DriverError>>,
+    ) -> Result<T, DriverError> {
+        let labels = [self.inner.name(), method];
+        let span = info_span!(
+            "driver_call",
+            driver = labels[0],
+            method,
+            enclave_id = %enclave.id,
+            error = field::Empty,
+        );
+        let started = Instant::now();
+        let result = call.instrument(span.clone()).await;
+        metrics().driver_call_duration.observe(&labels, started.elapsed());
+        if let Err(e) = &result {
+            span.record("error", e.to_string());
+            metrics().driver_call_errors.inc(&labels);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Driver for InstrumentedDriver {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn provision_enclave(
+        &self,
+        enclave: &Enclave,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        self.timed("provision_enclave", enclave, self.inner.provision_enclave(enclave, existing))
+            .await
+    }
+
+    async fn teardown_enclave(&self, enclave: &Enclave, handle: &Handle) -> Result<(), DriverError> {
+        self.timed("teardown_enclave", enclave, self.inner.teardown_enclave(enclave, handle)).await
+    }
+
+    async fn provision_partition(
+        &self,
+        enclave: &Enclave,
+        partition: &Partition,
+        resolved_inputs: &HashMap<String, Value>,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        self.timed(
+            "provision_partition",
+            enclave,
+            self.inner.provision_partition(enclave, partition, resolved_inputs, existing),
+        )
+        .await
+    }
+
+    async fn teardown_partition(
+        &self,
+        enclave: &Enclave,
+        partition: &Partition,
+        handle: &Handle,
+    ) -> Result<(), DriverError> {
+        self.timed(
+            "teardown_partition",
+            enclave,
+            self.inner.teardown_partition(enclave, partition, handle),
+        )
+        .await
+    }
+
+    async fn provision_export(
+        &self,
+        enclave: &Enclave,
+        export: &Export,
+        partition_outputs: &HashMap<String, Value>,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        self.timed(
+            "provision_export",
+            enclave,
+            self.inner.provision_export(enclave, export, partition_outputs, existing),
+        )
+        .await
+    }
+
+    async fn provision_import(
+        &self,
+        importer: &Enclave,
+        import: &Import,
+        export_handle: &Handle,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        self.timed(
+            "provision_import",
+            importer,
+            self.inner.provision_import(importer, import, export_handle, existing),
+        )
+        .await
+    }
+
+    async fn provision_partition_access(
+        &self,
+        enclave: &Enclave,
+        enclave_handle: &Handle,
+        export: &Export,
+        importer: &Partition,
+        exporter_outputs: &HashMap<String, Value>,
+        existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        self.timed(
+            "provision_partition_access",
+            enclave,
+            self.inner.provision_partition_access(
+                enclave,
+                enclave_handle,
+                export,
+                importer,
+                exporter_outputs,
+                existing,
+            ),
+        )
+        .await
+    }
+
+    async fn observe_enclave(
+        &self,
+        enclave: &Enclave,
+        handle: &Handle,
+    ) -> Result<ObservedState, DriverError> {
+        self.timed("observe_enclave", enclave, self.inner.observe_enclave(enclave, handle)).await
+    }
+
+    async fn observe_partition(
+        &self,
+        enclave: &Enclave,
+        partition: &Partition,
+        handle: &Handle,
+    ) -> Result<ObservedState, DriverError> {
+        self.timed(
+            "observe_partition",
+            enclave,
+            self.inner.observe_partition(enclave, partition, handle),
+        )
+        .await
+    }
+
+    fn context_vars(&self, enclave: &Enclave, handle: &Handle) -> HashMap<String, String> {
+        self.inner.context_vars(enclave, handle)
+    }
+
+    fn auth_env(&self, enclave: &Enclave, handle: &Handle) -> HashMap<String, String> {
+        self.inner.auth_env(enclave, handle)
+    }
+
+    async fn list_partition_resources(
+        &self,
+        enclave: &Enclave,
+        enc_handle: &Handle,
+        partition: &Partition,
+    ) -> Result<Vec<String>, DriverError> {
+        self.timed(
+            "list_partition_resources",
+            enclave,
+            self.inner.list_partition_resources(enclave, enc_handle, partition),
+        )
+        .await
+    }
+
+    async fn list_orphaned_resources(
+        &self,
+        enclave: &Enclave,
+        enc_handle: &Handle,
+        known_partition_ids: &[&str],
+    ) -> Result<Vec<OrphanedResource>, DriverError> {
+        self.timed(
+            "list_orphaned_resources",
+            enclave,
+            self.inner.list_orphaned_resources(enclave, enc_handle, known_partition_ids),
+        )
+        .await
+    }
+}
@@ -3,18 +3,23 @@ pub mod azure;
 pub mod driver;
 pub mod error;
 pub mod gcp;
+pub mod instrumented;
 pub mod local;
+mod propagation;
 pub mod registry;
 pub mod terraform;
+pub mod tf_contract;
 
 pub use aws::{AwsDriver, AwsDriverConfig};
 pub use azure::{AzureDriver, AzureDriverConfig};
-pub use driver::{Driver, ObservedState, OrphanedResource, ProvisionResult};
+pub use driver::{access_port, context_var_names, export_outputs, output_str, Driver, ObservedState, OrphanedResource, ProvisionResult};
 pub use error::DriverError;
 pub use gcp::{GcpDriver, GcpDriverConfig};
+pub use instrumented::InstrumentedDriver;
 pub use local::LocalDriver;
 pub use registry::DriverRegistry;
-pub use terraform::TerraformBackend;
+pub use terraform::{list_workspaces, workspaces_dir, BackendTls, TerraformBackend, Workspace, PREAMBLE_VARS};
+pub use tf_contract::{check_partition_contract, check_tf_contracts, ContractViolation};
 
 /// Opaque driver handle — any JSON value.
 pub type Handle = serde_json::Value;
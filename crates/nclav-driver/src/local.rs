@@ -2,13 +2,16 @@ use std::collections::HashMap;
 
 use async_trait::async_trait;
 use nclav_domain::{Enclave, Export, Import, Partition};
-use serde_json::json;
+use serde_json::{json, Value};
 use tracing::debug;
 
-use crate::driver::{Driver, ObservedState, ProvisionResult};
+use crate::driver::{access_port, export_outputs, Driver, ObservedState, ProvisionResult};
 use crate::error::DriverError;
 use crate::Handle;
 
+/// Port reported by stubbed `http`/`tcp` partitions.
+const LOCAL_STUB_PORT: u16 = 8080;
+
 /// A stub driver that simulates infrastructure locally.
 ///
 /// - Produces synthetic handles (JSON objects describing what would be created).
@@ -60,7 +63,7 @@ impl Driver for LocalDriver {
         &self,
         enclave: &Enclave,
         partition: &Partition,
-        _resolved_inputs: &HashMap<String, String>,
+        _resolved_inputs: &HashMap<String, Value>,
         _existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         debug!(
@@ -80,7 +83,10 @@ impl Driver for LocalDriver {
         let mut outputs = HashMap::new();
         if let Some(produces) = &partition.produces {
             for key in produces.required_outputs() {
-                let val = format!("local://{}/{}", partition.id.as_str(), key);
+                let val = match *key {
+                    "port" => Value::from(LOCAL_STUB_PORT),
+                    _ => Value::String(format!("local://{}/{}", partition.id.as_str(), key)),
+                };
                 outputs.insert(key.to_string(), val);
             }
         }
@@ -102,7 +108,7 @@ impl Driver for LocalDriver {
         &self,
         enclave: &Enclave,
         export: &Export,
-        partition_outputs: &HashMap<String, String>,
+        partition_outputs: &HashMap<String, Value>,
         _existing: Option<&Handle>,
     ) -> Result<ProvisionResult, DriverError> {
         debug!(
@@ -111,18 +117,16 @@ impl Driver for LocalDriver {
             "LocalDriver: provision_export"
         );
 
+        let outputs = export_outputs(partition_outputs);
         let handle = json!({
             "driver": "local",
             "kind": "export",
             "enclave_id": enclave.id.as_str(),
             "export_name": export.name,
-            "outputs": partition_outputs,
+            "outputs": outputs,
         });
 
-        Ok(ProvisionResult {
-            handle,
-            outputs: partition_outputs.clone(),
-        })
+        Ok(ProvisionResult { handle, outputs })
     }
 
     async fn provision_import(
@@ -147,21 +151,42 @@ impl Driver for LocalDriver {
         });
 
         // Outputs are whatever the export handle carries
-        let outputs = if let Some(obj) = export_handle.get("outputs") {
-            obj.as_object()
-                .map(|m| {
-                    m.iter()
-                        .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
-                        .collect()
-                })
-                .unwrap_or_default()
-        } else {
-            HashMap::new()
-        };
+        let outputs = export_handle
+            .get("outputs")
+            .and_then(|obj| obj.as_object())
+            .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
 
         Ok(ProvisionResult { handle, outputs })
     }
 
+    async fn provision_partition_access(
+        &self,
+        enclave: &Enclave,
+        _enclave_handle: &Handle,
+        export: &Export,
+        importer: &Partition,
+        exporter_outputs: &HashMap<String, Value>,
+        _existing: Option<&Handle>,
+    ) -> Result<ProvisionResult, DriverError> {
+        debug!(
+            enclave_id = %enclave.id,
+            export = %export.name,
+            importer = %importer.id,
+            "LocalDriver: provision_partition_access"
+        );
+
+        let handle = json!({
+            "driver": "local",
+            "kind": "partition_access",
+            "export_name": export.name,
+            "from_partition": importer.id.as_str(),
+            "to_partition": export.target_partition.as_str(),
+            "port": access_port(export, exporter_outputs),
+        });
+        Ok(ProvisionResult { handle, outputs: HashMap::new() })
+    }
+
     async fn observe_enclave(
         &self,
         enclave: &Enclave,
@@ -189,7 +214,7 @@ impl Driver for LocalDriver {
             for key in produces.required_outputs() {
                 outputs.insert(
                     key.to_string(),
-                    format!("local://{}/{}", partition.id.as_str(), key),
+                    Value::String(format!("local://{}/{}", partition.id.as_str(), key)),
                 );
             }
         }
@@ -219,14 +244,8 @@ mod tests {
         Enclave {
             id: EnclaveId::new("test"),
             name: "test".to_string(),
-            cloud: None,
             region: "local".to_string(),
-            identity: None,
-            network: None,
-            dns: None,
-            imports: vec![],
-            exports: vec![],
-            partitions: vec![],
+            ..Default::default()
         }
     }
 
@@ -261,7 +280,33 @@ mod tests {
             .await
             .unwrap();
         assert!(result.outputs.contains_key("hostname"));
-        assert!(result.outputs.contains_key("port"));
+        assert_eq!(result.outputs["port"], LOCAL_STUB_PORT);
+    }
+
+    #[tokio::test]
+    async fn provision_export_emits_numeric_port() {
+        let driver = LocalDriver::new();
+        let enc = dummy_enclave();
+        let export = Export {
+            name: "api".into(),
+            target_partition: PartitionId::new("svc"),
+            export_type: ExportType::Http,
+            to: ExportTarget::AnyEnclave,
+            auth: AuthType::None,
+            hostname: None,
+            port: None,
+        };
+        let partition_outputs = HashMap::from([
+            ("hostname".to_string(), Value::from("svc.local")),
+            ("port".to_string(), Value::from("8443")),
+        ]);
+        let result = driver
+            .provision_export(&enc, &export, &partition_outputs, None)
+            .await
+            .unwrap();
+        assert_eq!(result.outputs["port"], 8443);
+        assert_eq!(result.handle["outputs"]["port"], 8443);
+        assert_eq!(result.outputs["hostname"], "svc.local");
     }
 
     #[tokio::test]
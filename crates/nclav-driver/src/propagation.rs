@@ -0,0 +1,16 @@
+use nclav_telemetry::trace;
+
+/// Adds the current trace context to outbound cloud API requests.
+pub(crate) trait PropagateTrace {
+    /// Set the W3C `traceparent` header when the caller is in a traced span.
+    fn traced(self) -> Self;
+}
+
+impl PropagateTrace for reqwest::RequestBuilder {
+    fn traced(self) -> Self {
+        match trace::current() {
+            Some(context) => self.header("traceparent", context.traceparent()),
+            None => self,
+        }
+    }
+}
@@ -5,6 +5,7 @@ use nclav_domain::{CloudTarget, Enclave};
 
 use crate::driver::Driver;
 use crate::error::DriverError;
+use crate::instrumented::InstrumentedDriver;
 
 /// Dispatches driver calls to the correct cloud-specific [`Driver`] implementation.
 ///
@@ -23,8 +24,11 @@ impl DriverRegistry {
     }
 
     /// Register a driver for a cloud target. Returns `&mut self` for chaining.
+    ///
+    /// The driver is wrapped in an [`InstrumentedDriver`] so its calls are
+    /// reported in the metrics.
     pub fn register(&mut self, cloud: CloudTarget, driver: Arc<dyn Driver>) -> &mut Self {
-        self.drivers.insert(cloud, driver);
+        self.drivers.insert(cloud, Arc::new(InstrumentedDriver::new(driver)));
         self
     }
 
@@ -1,19 +1,109 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use chrono::Utc;
 use nclav_domain::{Enclave, Partition, PartitionBackend};
-use nclav_store::{IacOperation, IacRun, IacRunStatus, StateStore};
+use nclav_telemetry::metrics;
+use nclav_telemetry::trace;
+use nclav_store::{
+    expire_stale_lock, sign_state_token, EventContext, IacOperation, IacRun, IacRunStatus, StateStore,
+    STATE_TOKEN_TTL,
+};
+use serde_json::Value;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
-use tracing::{debug, info, warn};
+use tracing::{debug, field, info, info_span, warn, Instrument};
 use uuid::Uuid;
 
 use crate::driver::{ObservedState, ProvisionResult};
 use crate::error::DriverError;
 use crate::Handle;
 
+/// TLS material Terraform uses to reach the nclav state backend. The file is
+/// read at the start of every command, so a renewed bundle is picked up.
+///
+/// There is deliberately no client certificate: partition code runs with
+/// whatever Terraform is given, and a certificate would authenticate it beyond
+/// the partition's own state token.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackendTls {
+    /// PEM bundle of CAs to trust for `api_base`; `None` trusts the system roots.
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl BackendTls {
+    /// `TF_HTTP_CLIENT_CA_CERTIFICATE_PEM` carrying the CA bundle for the http backend.
+    fn env(&self) -> Result<Vec<(&'static str, String)>, DriverError> {
+        let mut env = Vec::new();
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read_to_string(path)
+                .map_err(|e| DriverError::Internal(format!("read {}: {}", path.display(), e)))?;
+            env.push(("TF_HTTP_CLIENT_CA_CERTIFICATE_PEM", pem));
+        }
+        Ok(env)
+    }
+}
+
+/// Terraform state key of a partition, `<enclave>/<partition>`.
+fn state_key(enclave: &Enclave, partition: &Partition) -> String {
+    format!("{}/{}", enclave.id.0, partition.id.0)
+}
+
+// ── Workspaces on disk ────────────────────────────────────────────────────────
+
+/// Directory holding every partition workspace: `<root>/workspaces`, where
+/// `root` is [`TerraformBackend::workspace_root`] and defaults to `~/.nclav`.
+pub fn workspaces_dir(workspace_root: Option<&Path>) -> PathBuf {
+    let base = workspace_root.map(Path::to_path_buf).unwrap_or_else(|| {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+        PathBuf::from(home).join(".nclav")
+    });
+    base.join("workspaces")
+}
+
+/// A partition workspace found by [`list_workspaces`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub enclave_id: String,
+    pub partition_id: String,
+    pub path: PathBuf,
+    /// Last modification time of the workspace directory itself.
+    pub modified: Option<SystemTime>,
+}
+
+/// Every `<enclave_id>/<partition_id>` workspace under `dir`, sorted by path.
+/// A missing `dir` has no workspaces.
+pub async fn list_workspaces(dir: &Path) -> std::io::Result<Vec<Workspace>> {
+    let mut workspaces = Vec::new();
+    let mut enclaves = match tokio::fs::read_dir(dir).await {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(workspaces),
+        Err(e) => return Err(e),
+    };
+    while let Some(enclave) = enclaves.next_entry().await? {
+        if !enclave.file_type().await?.is_dir() {
+            continue;
+        }
+        let mut partitions = tokio::fs::read_dir(enclave.path()).await?;
+        while let Some(partition) = partitions.next_entry().await? {
+            let meta = partition.metadata().await?;
+            if !meta.is_dir() {
+                continue;
+            }
+            workspaces.push(Workspace {
+                enclave_id: enclave.file_name().to_string_lossy().into_owned(),
+                partition_id: partition.file_name().to_string_lossy().into_owned(),
+                path: partition.path(),
+                modified: meta.modified().ok(),
+            });
+        }
+    }
+    workspaces.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(workspaces)
+}
+
 // ── TerraformBackend ──────────────────────────────────────────────────────────
 
 /// Executes IaC-backed partitions by invoking the `terraform` or `tofu` binary.
@@ -21,15 +111,19 @@ use crate::Handle;
 /// Responsibilities:
 /// - Maintain a workspace under `~/.nclav/workspaces/{enclave_id}/{partition_id}/`
 /// - Symlink the partition's `.tf` files into the workspace
-/// - Generate `nclav_backend.tf` and `nclav_context.auto.tfvars`
+/// - Generate `nclav_backend.tf` and `nclav_context.auto.tfvars.json`
 /// - Run `terraform init` + `terraform apply` (or `destroy`)
 /// - Capture combined stdout+stderr into an [`IacRun`] log record
 /// - Extract declared outputs from `terraform output -json`
 pub struct TerraformBackend {
     /// nclav API base URL, used to configure the Terraform HTTP state backend.
     pub api_base: String,
-    /// nclav auth token, passed as `TF_HTTP_PASSWORD` to the subprocess.
+    /// nclav server token. Signs the short-lived token passed to each
+    /// subprocess as `TF_HTTP_PASSWORD`, which only grants access to the
+    /// partition's own state and lock routes.
     pub auth_token: Arc<String>,
+    /// CA bundle and client certificate for reaching `api_base` over TLS.
+    pub tls: BackendTls,
     /// Store for persisting [`IacRun`] log records.
     pub store: Arc<dyn StateStore>,
     /// When true, skip all subprocess invocations and return stubbed outputs.
@@ -47,15 +141,15 @@ impl TerraformBackend {
         &self,
         enclave: &Enclave,
         partition: &Partition,
-        resolved_inputs: &HashMap<String, String>,
+        resolved_inputs: &HashMap<String, Value>,
         auth_env: &HashMap<String, String>,
         reconcile_run_id: Option<Uuid>,
     ) -> Result<ProvisionResult, DriverError> {
         if self.test_mode {
-            let outputs: HashMap<String, String> = partition
+            let outputs: HashMap<String, Value> = partition
                 .declared_outputs
                 .iter()
-                .map(|k| (k.clone(), format!("test://{}", k)))
+                .map(|k| (k.clone(), Value::String(format!("test://{}", k))))
                 .collect();
             let handle = serde_json::json!({
                 "backend":      "test",
@@ -86,14 +180,16 @@ impl TerraformBackend {
             write_tfvars(&workspace, &enclave.id.0, &partition.id.0, resolved_inputs)?;
         }
 
-        let operation = IacOperation::Provision;
+        let run = self.start_run(enclave, partition, IacOperation::Provision, reconcile_run_id).await;
         let mut log = String::new();
+        let state_key = state_key(enclave, partition);
 
         // terraform init
         let init_log = self
             .run_tf(
                 binary,
                 &workspace,
+                &state_key,
                 &[
                     "init",
                     "-reconfigure",
@@ -128,11 +224,7 @@ impl TerraformBackend {
             Ok(out) => out,
             Err(e) => {
                 let msg = e.to_string();
-                self.write_run(
-                    enclave, partition, operation, reconcile_run_id,
-                    msg.clone(), Some(1),
-                )
-                .await;
+                self.write_run(run, msg.clone(), Some(1)).await;
                 return Err(DriverError::ProvisionFailed(format!("terraform init: {}", msg)));
             }
         };
@@ -141,11 +233,7 @@ impl TerraformBackend {
         log.push_str(&init_output);
 
         if init_exit != 0 {
-            self.write_run(
-                enclave, partition, operation, reconcile_run_id,
-                log.clone(), Some(init_exit),
-            )
-            .await;
+            self.write_run(run, log.clone(), Some(init_exit)).await;
             return Err(DriverError::ProvisionFailed(format!(
                 "terraform init exited with code {}", init_exit
             )));
@@ -153,7 +241,7 @@ impl TerraformBackend {
 
         // terraform apply
         let apply_log = self
-            .run_tf(binary, &workspace, &["apply", "-auto-approve", "-no-color"], auth_env)
+            .run_tf(binary, &workspace, &state_key, &["apply", "-auto-approve", "-no-color"], auth_env)
             .await;
 
         let (apply_exit, apply_output) = match apply_log {
@@ -162,11 +250,7 @@ impl TerraformBackend {
                 let msg = e.to_string();
                 log.push_str("\n=== terraform apply ===\n");
                 log.push_str(&msg);
-                self.write_run(
-                    enclave, partition, operation, reconcile_run_id,
-                    log, Some(1),
-                )
-                .await;
+                self.write_run(run, log, Some(1)).await;
                 return Err(DriverError::ProvisionFailed(format!("terraform apply: {}", msg)));
             }
         };
@@ -175,24 +259,28 @@ impl TerraformBackend {
         log.push_str(&apply_output);
 
         if apply_exit != 0 {
-            self.write_run(
-                enclave, partition, operation, reconcile_run_id,
-                log, Some(apply_exit),
-            )
-            .await;
+            self.write_run(run, log, Some(apply_exit)).await;
             return Err(DriverError::ProvisionFailed(format!(
                 "terraform apply exited with code {}", apply_exit
             )));
         }
 
         // Read outputs
-        let outputs = self.read_outputs(binary, &workspace, &partition.declared_outputs, auth_env).await?;
+        let outputs = match self
+            .read_outputs(binary, &workspace, &state_key, &partition.declared_outputs, auth_env)
+            .await
+        {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                // Close out the run so it doesn't stay `running` forever.
+                log.push_str("\n=== terraform output ===\n");
+                log.push_str(&e.to_string());
+                self.write_run(run, log, Some(1)).await;
+                return Err(e);
+            }
+        };
 
-        self.write_run(
-            enclave, partition, operation, reconcile_run_id,
-            log, Some(0),
-        )
-        .await;
+        self.write_run(run, log, Some(0)).await;
 
         let handle = serde_json::json!({
             "backend": binary.to_string(),
@@ -228,10 +316,17 @@ impl TerraformBackend {
             return Ok(());
         }
 
+        let run = self.start_run(enclave, partition, IacOperation::Teardown, reconcile_run_id).await;
         let mut log = String::new();
 
         let destroy_log = self
-            .run_tf(binary, &workspace, &["destroy", "-auto-approve", "-no-color"], auth_env)
+            .run_tf(
+                binary,
+                &workspace,
+                &state_key(enclave, partition),
+                &["destroy", "-auto-approve", "-no-color"],
+                auth_env,
+            )
             .await;
 
         let (exit_code, output) = match destroy_log {
@@ -239,11 +334,7 @@ impl TerraformBackend {
             Err(e) => {
                 let msg = e.to_string();
                 log.push_str(&msg);
-                self.write_run(
-                    enclave, partition, IacOperation::Teardown, reconcile_run_id,
-                    log, Some(1),
-                )
-                .await;
+                self.write_run(run, log, Some(1)).await;
                 return Err(DriverError::TeardownFailed(format!("terraform destroy: {}", msg)));
             }
         };
@@ -252,21 +343,13 @@ impl TerraformBackend {
         log.push_str(&output);
 
         if exit_code != 0 {
-            self.write_run(
-                enclave, partition, IacOperation::Teardown, reconcile_run_id,
-                log, Some(exit_code),
-            )
-            .await;
+            self.write_run(run, log, Some(exit_code)).await;
             return Err(DriverError::TeardownFailed(format!(
                 "terraform destroy exited with code {}", exit_code
             )));
         }
 
-        self.write_run(
-            enclave, partition, IacOperation::Teardown, reconcile_run_id,
-            log, Some(0),
-        )
-        .await;
+        self.write_run(run, log, Some(0)).await;
 
         Ok(())
     }
@@ -292,7 +375,8 @@ impl TerraformBackend {
             });
         }
 
-        match self.read_outputs(binary, &workspace, &partition.declared_outputs, auth_env).await {
+        let state_key = state_key(enclave, partition);
+        match self.read_outputs(binary, &workspace, &state_key, &partition.declared_outputs, auth_env).await {
             Ok(outputs) => Ok(ObservedState {
                 exists: true,
                 healthy: true,
@@ -311,11 +395,7 @@ impl TerraformBackend {
     // ── Workspace helpers ─────────────────────────────────────────────────────
 
     fn workspace_dir(&self, enclave_id: &str, partition_id: &str) -> PathBuf {
-        let base = self.workspace_root.clone().unwrap_or_else(|| {
-            let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
-            PathBuf::from(home).join(".nclav")
-        });
-        base.join("workspaces").join(enclave_id).join(partition_id)
+        workspaces_dir(self.workspace_root.as_deref()).join(enclave_id).join(partition_id)
     }
 
     /// Symlink all `.tf` files from `source_dir` into `workspace`.
@@ -372,12 +452,46 @@ impl TerraformBackend {
 
     // ── Process execution ─────────────────────────────────────────────────────
 
-    /// Run a terraform sub-command, capturing combined stdout+stderr.
-    /// Returns (exit_code, combined_log).
+    /// Run a terraform sub-command in its own span, capturing combined
+    /// stdout+stderr. Returns (exit_code, combined_log).
     async fn run_tf(
         &self,
         binary: &str,
         workspace: &Path,
+        state_key: &str,
+        args: &[&str],
+        auth_env: &HashMap<String, String>,
+    ) -> Result<(i32, String), DriverError> {
+        let span = info_span!(
+            "iac_command",
+            command = args.first().copied().unwrap_or(""),
+            state_key,
+            exit_code = field::Empty,
+            error = field::Empty,
+        );
+        let result = self
+            .exec_tf(binary, workspace, state_key, args, auth_env)
+            .instrument(span.clone())
+            .await;
+        match &result {
+            Ok((code, _)) => {
+                span.record("exit_code", code);
+                if *code != 0 {
+                    span.record("error", format!("exited with code {}", code));
+                }
+            }
+            Err(e) => {
+                span.record("error", e.to_string());
+            }
+        }
+        result
+    }
+
+    async fn exec_tf(
+        &self,
+        binary: &str,
+        workspace: &Path,
+        state_key: &str,
         args: &[&str],
         auth_env: &HashMap<String, String>,
     ) -> Result<(i32, String), DriverError> {
@@ -389,11 +503,15 @@ impl TerraformBackend {
             .stdin(std::process::Stdio::null())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
-            // State backend auth
-            .env("TF_HTTP_PASSWORD", self.auth_token.as_str())
+            // State backend auth, limited to this partition's state
+            .env("TF_HTTP_PASSWORD", sign_state_token(&self.auth_token, state_key, Utc::now() + STATE_TOKEN_TTL))
             // Disable interactive prompts and colour
             .env("TF_IN_AUTOMATION", "1")
             .env("TF_INPUT", "0")
+            // State backend TLS
+            .envs(self.tls.env()?)
+            // Trace context, so a Terraform exporting its own spans joins this trace
+            .envs(trace::current().map(|c| ("TRACEPARENT", c.traceparent())))
             // Cloud-specific auth
             .envs(auth_env);
 
@@ -472,11 +590,12 @@ impl TerraformBackend {
         &self,
         binary: &str,
         workspace: &Path,
+        state_key: &str,
         declared_outputs: &[String],
         auth_env: &HashMap<String, String>,
-    ) -> Result<HashMap<String, String>, DriverError> {
+    ) -> Result<HashMap<String, Value>, DriverError> {
         let (exit, out_json) = self
-            .run_tf(binary, workspace, &["output", "-json", "-no-color"], auth_env)
+            .run_tf(binary, workspace, state_key, &["output", "-json", "-no-color"], auth_env)
             .await?;
 
         if exit != 0 {
@@ -488,52 +607,68 @@ impl TerraformBackend {
         let map: serde_json::Value = serde_json::from_str(out_json.trim())
             .map_err(|e| DriverError::ProvisionFailed(format!("parse terraform output: {}", e)))?;
 
-        let mut outputs = HashMap::new();
-        for key in declared_outputs {
-            match map.get(key).and_then(|v| v.get("value")).and_then(|v| v.as_str()) {
-                Some(val) => { outputs.insert(key.clone(), val.to_string()); }
-                None => {
-                    return Err(DriverError::ProvisionFailed(format!(
-                        "declared output '{}' missing from terraform output", key
-                    )));
-                }
-            }
-        }
-        Ok(outputs)
+        extract_declared_outputs(&map, declared_outputs)
     }
 
     // ── IaC run logging ───────────────────────────────────────────────────────
 
-    async fn write_run(
+    /// Record a `Running` IaC run before invoking the tool, so state writes made
+    /// through the HTTP backend while it runs can be attributed to it.
+    async fn start_run(
         &self,
         enclave: &Enclave,
         partition: &Partition,
         operation: IacOperation,
         reconcile_run_id: Option<Uuid>,
-        log: String,
-        exit_code: Option<i32>,
-    ) {
-        let status = match exit_code {
-            Some(0) => IacRunStatus::Succeeded,
-            _ => IacRunStatus::Failed,
-        };
-
+    ) -> IacRun {
         let run = IacRun {
             id: Uuid::new_v4(),
             enclave_id: enclave.id.clone(),
             partition_id: partition.id.clone(),
             operation,
             started_at: Utc::now(),
-            finished_at: Some(Utc::now()),
-            status,
-            exit_code,
-            log,
+            finished_at: None,
+            status: IacRunStatus::Running,
+            exit_code: None,
+            log: String::new(),
             reconcile_run_id,
+            trace_id: trace::current_trace_id(),
         };
 
         if let Err(e) = self.store.upsert_iac_run(&run).await {
             warn!(error = %e, "failed to persist IaC run log");
         }
+        run
+    }
+
+    /// Finish a run started with [`start_run`](Self::start_run).
+    async fn write_run(&self, mut run: IacRun, log: String, exit_code: Option<i32>) {
+        run.status = match exit_code {
+            Some(0) => IacRunStatus::Succeeded,
+            _ => IacRunStatus::Failed,
+        };
+        run.finished_at = Some(Utc::now());
+        run.exit_code = exit_code;
+        run.log = log;
+
+        let operation = run.operation.to_string();
+        let elapsed = (Utc::now() - run.started_at).to_std().unwrap_or_default();
+        metrics().iac_run_duration.observe(&[&operation, &run.status.to_string()], elapsed);
+        let exit_code = exit_code.map_or_else(|| "none".to_string(), |c| c.to_string());
+        metrics().iac_runs.inc(&[&operation, &exit_code]);
+
+        if let Err(e) = self.store.upsert_iac_run(&run).await {
+            warn!(error = %e, "failed to persist IaC run log");
+        }
+
+        // A tool that crashed or was killed leaves its state lock behind.
+        let key = format!("{}/{}", run.enclave_id, run.partition_id);
+        let context = EventContext { run_id: run.reconcile_run_id, ..EventContext::controller() };
+        match expire_stale_lock(self.store.as_ref(), &key, &context).await {
+            Ok(true) => warn!(key = %key, run_id = %run.id, "released state lock left by finished IaC run"),
+            Ok(false) => {}
+            Err(e) => warn!(key = %key, error = %e, "failed to check for a stale state lock"),
+        }
     }
 }
 
@@ -554,41 +689,120 @@ fn extract_tf_config(partition: &Partition) -> Result<(String, nclav_domain::Ter
     }
 }
 
-/// Format a single HCL string variable assignment.
-fn tfvar(key: &str, value: &str) -> String {
-    // Escape backslashes and double-quotes inside the value.
-    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
-    format!("{} = \"{}\"\n", key, escaped)
+/// Pick the `declared_outputs` keys out of parsed `terraform output -json`.
+///
+/// Values are kept as-is, so lists, maps, numbers and booleans survive intact.
+fn extract_declared_outputs(
+    map: &Value,
+    declared_outputs: &[String],
+) -> Result<HashMap<String, Value>, DriverError> {
+    let mut outputs = HashMap::new();
+    for key in declared_outputs {
+        match map.get(key).and_then(|v| v.get("value")) {
+            Some(val) => { outputs.insert(key.clone(), val.clone()); }
+            None => {
+                return Err(DriverError::ProvisionFailed(format!(
+                    "declared output '{}' missing from terraform output", key
+                )));
+            }
+        }
+    }
+    Ok(outputs)
+}
+
+/// Quote a string as an HCL string literal.
+///
+/// Besides `\\` and `"`, control characters are escaped and template sequences
+/// (`${`, `%{`) are doubled so the value is taken literally.
+fn hcl_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '$' | '%' if chars.peek() == Some(&'{') => {
+                out.push(c);
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Render a JSON value as an HCL expression.
+///
+/// Strings are quoted, numbers and booleans are bare, arrays become tuples
+/// (`[a, b]`) and objects become HCL objects (`{ "k" = v }`) with sorted keys.
+fn hcl_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".into(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => hcl_string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(hcl_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            if map.is_empty() {
+                return "{}".into();
+            }
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{} = {}", hcl_string(k), hcl_value(&map[k])))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
 }
 
-/// Write `nclav_context.auto.tfvars` containing nclav metadata and the resolved partition inputs.
+/// Variables always written by [`write_tfvars`] ahead of the partition's own inputs.
+pub const PREAMBLE_VARS: [&str; 2] = ["nclav_enclave", "nclav_partition"];
+
+/// Generated variable file; JSON so values are never parsed as HCL templates.
+pub const TFVARS_FILE: &str = "nclav_context.auto.tfvars.json";
+
+/// HCL variable file written by earlier versions, removed when found.
+const LEGACY_TFVARS_FILE: &str = "nclav_context.auto.tfvars";
+
+/// Write [`TFVARS_FILE`] containing nclav metadata and the resolved partition inputs.
 ///
-/// `nclav_enclave` and `nclav_partition` are always injected as a preamble so Terraform
-/// authors can apply them as labels on every resource they create via `local.nclav_labels`.
-/// The partition's resolved inputs follow (sorted alphabetically).
+/// `nclav_enclave` and `nclav_partition` are always injected so Terraform authors
+/// can apply them as labels on every resource they create via `local.nclav_labels`.
+/// Keys are written sorted alphabetically.
 fn write_tfvars(
     workspace: &Path,
     enclave_id: &str,
     partition_id: &str,
-    resolved_inputs: &HashMap<String, String>,
+    resolved_inputs: &HashMap<String, Value>,
 ) -> Result<(), DriverError> {
-    let mut content = String::from("# Generated by nclav — do not edit\n\n");
-    content.push_str("# nclav metadata — always injected. Declare these as optional variables\n");
-    content.push_str("# and apply via local.nclav_labels on all resources you create.\n");
-    content.push_str(&tfvar("nclav_enclave", enclave_id));
-    content.push_str(&tfvar("nclav_partition", partition_id));
-    if !resolved_inputs.is_empty() {
-        content.push('\n');
-        content.push_str("# Partition inputs (from inputs: in config.yml)\n");
-        let mut keys: Vec<&String> = resolved_inputs.keys().collect();
-        keys.sort();
-        for k in keys {
-            content.push_str(&tfvar(k, &resolved_inputs[k]));
+    let mut vars: serde_json::Map<String, Value> = resolved_inputs
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    vars.insert(PREAMBLE_VARS[0].into(), Value::from(enclave_id));
+    vars.insert(PREAMBLE_VARS[1].into(), Value::from(partition_id));
+    let mut content = serde_json::to_string_pretty(&vars)
+        .map_err(|e| DriverError::Internal(format!("encode {}: {}", TFVARS_FILE, e)))?;
+    content.push('\n');
+    std::fs::write(workspace.join(TFVARS_FILE), content)
+        .map_err(|e| DriverError::Internal(format!("write {}: {}", TFVARS_FILE, e)))?;
+    match std::fs::remove_file(workspace.join(LEGACY_TFVARS_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(DriverError::Internal(format!("remove {}: {}", LEGACY_TFVARS_FILE, e)))
         }
+        _ => Ok(()),
     }
-    std::fs::write(workspace.join("nclav_context.auto.tfvars"), content)
-        .map_err(|e| DriverError::Internal(format!("write nclav_context.auto.tfvars: {}", e)))?;
-    Ok(())
 }
 
 /// Ensure the partition source directory contains no `.tf` files.
@@ -611,7 +825,7 @@ fn check_no_tf_files(dir: &Path) -> Result<(), DriverError> {
 }
 
 /// Remove artifacts left by a previous raw-tf setup so they don't interfere
-/// with a module-sourced workspace: symlinks to `.tf` files and `nclav_context.auto.tfvars`.
+/// with a module-sourced workspace: symlinks to `.tf` files and the generated tfvars.
 fn cleanup_raw_tf_artifacts(workspace: &Path) -> Result<(), DriverError> {
     let entries = match std::fs::read_dir(workspace) {
         Ok(e) => e,
@@ -630,7 +844,7 @@ fn cleanup_raw_tf_artifacts(workspace: &Path) -> Result<(), DriverError> {
             std::fs::remove_file(&path)
                 .map_err(|e| DriverError::Internal(format!("remove stale symlink: {}", e)))?;
         }
-        if name_str == "nclav_context.auto.tfvars" {
+        if name_str == TFVARS_FILE || name_str == LEGACY_TFVARS_FILE {
             std::fs::remove_file(&path)
                 .map_err(|e| DriverError::Internal(format!("remove stale tfvars: {}", e)))?;
         }
@@ -655,7 +869,7 @@ fn cleanup_module_artifacts(workspace: &Path) -> Result<(), DriverError> {
 fn write_module_tf(
     workspace: &Path,
     source: &str,
-    resolved_inputs: &HashMap<String, String>,
+    resolved_inputs: &HashMap<String, Value>,
 ) -> Result<(), DriverError> {
     let mut hcl = String::from("# Generated by nclav — do not edit\n");
     hcl.push_str("module \"nclav_partition\" {\n");
@@ -665,8 +879,7 @@ fn write_module_tf(
         let mut keys: Vec<&String> = resolved_inputs.keys().collect();
         keys.sort();
         for k in keys {
-            let escaped = resolved_inputs[k].replace('\\', "\\\\").replace('"', "\\\"");
-            hcl.push_str(&format!("  {} = \"{}\"\n", k, escaped));
+            hcl.push_str(&format!("  {} = {}\n", k, hcl_value(&resolved_inputs[k])));
         }
     }
     hcl.push_str("}\n");
@@ -702,91 +915,140 @@ mod tests {
         TerraformBackend {
             api_base:       "http://localhost:8080".into(),
             auth_token:     Arc::new("tok".into()),
+            tls:            BackendTls::default(),
             store:          Arc::new(InMemoryStore::new()),
             test_mode:      false,
             workspace_root: Some(workspace_root.path().to_path_buf()),
         }
     }
 
-    // ── tfvar() ───────────────────────────────────────────────────────────────
+    // ── write_tfvars ──────────────────────────────────────────────────────────
 
-    #[test]
-    fn tfvar_basic_assignment() {
-        assert_eq!(tfvar("key", "value"), "key = \"value\"\n");
+    fn read_tfvars(dir: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(dir.join(TFVARS_FILE)).unwrap()).unwrap()
     }
 
     #[test]
-    fn tfvar_escapes_backslash() {
-        // A single backslash in the value must become \\ in HCL.
-        assert_eq!(tfvar("k", "a\\b"), "k = \"a\\\\b\"\n");
+    fn write_tfvars_preamble_and_sorted_inputs() {
+        let dir = TempDir::new().unwrap();
+        let inputs: HashMap<String, Value> = [
+            ("zz_var".to_string(), Value::from("last")),
+            ("aa_var".to_string(), Value::from("first")),
+        ]
+        .into_iter()
+        .collect();
+
+        write_tfvars(dir.path(), "my-enc", "my-part", &inputs).unwrap();
+
+        let vars = read_tfvars(dir.path());
+        assert_eq!(vars["nclav_enclave"], "my-enc");
+        assert_eq!(vars["nclav_partition"], "my-part");
+        let keys: Vec<&String> = vars.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["aa_var", "nclav_enclave", "nclav_partition", "zz_var"]);
     }
 
     #[test]
-    fn tfvar_escapes_double_quote() {
-        // A double-quote in the value must be escaped to \" in HCL.
-        assert_eq!(tfvar("k", "say \"hi\""), "k = \"say \\\"hi\\\"\"\n");
+    fn write_tfvars_empty_inputs_writes_preamble_only() {
+        let dir = TempDir::new().unwrap();
+        write_tfvars(dir.path(), "enc", "part", &HashMap::new()).unwrap();
+
+        let vars = read_tfvars(dir.path());
+        assert_eq!(vars, serde_json::json!({ "nclav_enclave": "enc", "nclav_partition": "part" }));
     }
 
     #[test]
-    fn tfvar_empty_value() {
-        assert_eq!(tfvar("k", ""), "k = \"\"\n");
-    }
+    fn write_tfvars_keeps_template_sequences_and_newlines_literal() {
+        let dir = TempDir::new().unwrap();
+        let raw = "C:\\Users\\\"admin\"\n${var.secret} %{ if true }x%{ endif }";
+        let inputs: HashMap<String, Value> =
+            [("path".to_string(), Value::from(raw))].into_iter().collect();
 
-    // ── write_tfvars ──────────────────────────────────────────────────────────
+        write_tfvars(dir.path(), "e", "p", &inputs).unwrap();
+
+        assert_eq!(read_tfvars(dir.path())["path"], raw);
+    }
 
     #[test]
-    fn write_tfvars_preamble_and_sorted_inputs() {
+    fn write_tfvars_renders_structured_inputs() {
         let dir = TempDir::new().unwrap();
-        let inputs: HashMap<String, String> = [
-            ("zz_var".to_string(), "last".to_string()),
-            ("aa_var".to_string(), "first".to_string()),
+        let inputs: HashMap<String, Value> = [
+            ("replicas".to_string(), serde_json::json!(3)),
+            ("public".to_string(), serde_json::json!(false)),
+            ("zones".to_string(), serde_json::json!(["a", "b"])),
+            ("labels".to_string(), serde_json::json!({ "tier": "db", "env": "prod" })),
         ]
         .into_iter()
         .collect();
 
-        write_tfvars(dir.path(), "my-enc", "my-part", &inputs).unwrap();
+        write_tfvars(dir.path(), "e", "p", &inputs).unwrap();
+
+        let vars = read_tfvars(dir.path());
+        assert_eq!(vars["replicas"], 3);
+        assert_eq!(vars["public"], false);
+        assert_eq!(vars["zones"], serde_json::json!(["a", "b"]));
+        assert_eq!(vars["labels"], serde_json::json!({ "tier": "db", "env": "prod" }));
+    }
 
-        let content =
-            fs::read_to_string(dir.path().join("nclav_context.auto.tfvars")).unwrap();
+    #[test]
+    fn write_tfvars_removes_legacy_hcl_file() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(LEGACY_TFVARS_FILE), "nclav_enclave = \"old\"\n").unwrap();
 
-        // Preamble always present.
-        assert!(content.contains("nclav_enclave = \"my-enc\""));
-        assert!(content.contains("nclav_partition = \"my-part\""));
+        write_tfvars(dir.path(), "e", "p", &HashMap::new()).unwrap();
 
-        // Inputs sorted alphabetically: aa before zz.
-        let aa_pos = content.find("aa_var =").unwrap();
-        let zz_pos = content.find("zz_var =").unwrap();
-        assert!(aa_pos < zz_pos, "inputs should be sorted alphabetically");
+        assert!(!dir.path().join(LEGACY_TFVARS_FILE).exists());
     }
 
+    // ── hcl_value ─────────────────────────────────────────────────────────────
+
     #[test]
-    fn write_tfvars_empty_inputs_writes_preamble_only() {
-        let dir = TempDir::new().unwrap();
-        write_tfvars(dir.path(), "enc", "part", &HashMap::new()).unwrap();
+    fn hcl_value_nested_structures() {
+        let v = serde_json::json!({ "ports": [80, 443], "meta": {}, "note": null });
+        assert_eq!(hcl_value(&v), r#"{ "meta" = {}, "note" = null, "ports" = [80, 443] }"#);
+    }
 
-        let content =
-            fs::read_to_string(dir.path().join("nclav_context.auto.tfvars")).unwrap();
+    #[test]
+    fn hcl_value_escapes_strings_in_lists() {
+        let v = serde_json::json!(["a\"b"]);
+        assert_eq!(hcl_value(&v), r#"["a\"b"]"#);
+    }
 
-        assert!(content.contains("nclav_enclave = \"enc\""));
-        assert!(content.contains("nclav_partition = \"part\""));
-        // No partition inputs section when inputs is empty.
-        assert!(!content.contains("# Partition inputs"));
+    #[test]
+    fn hcl_string_escapes_backslash_and_quote() {
+        assert_eq!(hcl_string(r#"C:\Users\"admin""#), r#""C:\\Users\\\"admin\"""#);
+        assert_eq!(hcl_string(""), r#""""#);
     }
 
     #[test]
-    fn write_tfvars_escapes_special_chars_in_value() {
-        let dir = TempDir::new().unwrap();
-        let inputs: HashMap<String, String> =
-            [("path".to_string(), r#"C:\Users\"admin""#.to_string())]
-                .into_iter()
-                .collect();
+    fn hcl_string_keeps_templates_and_newlines_literal() {
+        assert_eq!(hcl_string("${a}"), r#""$${a}""#);
+        assert_eq!(hcl_string("%{ if x }"), r#""%%{ if x }""#);
+        assert_eq!(hcl_string("$5 or 50%"), r#""$5 or 50%""#);
+        assert_eq!(hcl_string("a\nb\tc"), r#""a\nb\tc""#);
+    }
 
-        write_tfvars(dir.path(), "e", "p", &inputs).unwrap();
+    // ── extract_declared_outputs ──────────────────────────────────────────────
+
+    #[test]
+    fn extract_declared_outputs_keeps_non_string_values() {
+        let map = serde_json::json!({
+            "port":      { "sensitive": false, "type": "number", "value": 5432 },
+            "endpoints": { "sensitive": false, "type": ["list", "string"], "value": ["a", "b"] },
+            "extra":     { "sensitive": false, "type": "string", "value": "ignored" },
+        });
+        let declared = vec!["port".to_string(), "endpoints".to_string()];
+
+        let outputs = extract_declared_outputs(&map, &declared).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs["port"], serde_json::json!(5432));
+        assert_eq!(outputs["endpoints"], serde_json::json!(["a", "b"]));
+    }
 
-        let content =
-            fs::read_to_string(dir.path().join("nclav_context.auto.tfvars")).unwrap();
-        // Backslash → \\\\ ; double-quote → \\\"
-        assert!(content.contains(r#"path = "C:\\Users\\\"admin\"""#));
+    #[test]
+    fn extract_declared_outputs_missing_key_errors() {
+        let map = serde_json::json!({});
+        let err = extract_declared_outputs(&map, &["hostname".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("hostname"));
     }
 
     // ── write_module_tf ───────────────────────────────────────────────────────
@@ -794,8 +1056,8 @@ mod tests {
     #[test]
     fn write_module_tf_with_inputs() {
         let dir = TempDir::new().unwrap();
-        let inputs: HashMap<String, String> =
-            [("db_name".to_string(), "mydb".to_string())]
+        let inputs: HashMap<String, Value> =
+            [("db_name".to_string(), Value::from("mydb"))]
                 .into_iter()
                 .collect();
 
@@ -985,7 +1247,7 @@ mod tests {
         std::os::unix::fs::symlink(&tf_src, &link).unwrap();
 
         // Create the generated tfvars.
-        fs::write(workspace.path().join("nclav_context.auto.tfvars"), "").unwrap();
+        fs::write(workspace.path().join(TFVARS_FILE), "").unwrap();
 
         // Create a regular (non-symlink) .tf file — should survive.
         fs::write(workspace.path().join("nclav_backend.tf"), "").unwrap();
@@ -994,7 +1256,7 @@ mod tests {
 
         assert!(!link.exists(), "symlinked .tf should be removed");
         assert!(
-            !workspace.path().join("nclav_context.auto.tfvars").exists(),
+            !workspace.path().join(TFVARS_FILE).exists(),
             "tfvars should be removed"
         );
         assert!(
@@ -1037,11 +1299,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn backend_tls_passes_pem_contents_to_terraform() {
+        let dir = TempDir::new().unwrap();
+        let ca = dir.path().join("ca.pem");
+        fs::write(&ca, "CA PEM").unwrap();
+        let tls = BackendTls { ca_cert: Some(ca) };
+        assert_eq!(tls.env().unwrap(), vec![("TF_HTTP_CLIENT_CA_CERTIFICATE_PEM", "CA PEM".to_string())]);
+
+        let missing = BackendTls { ca_cert: Some(dir.path().join("nope.pem")) };
+        assert!(missing.env().is_err());
+    }
+
     #[test]
     fn workspace_dir_falls_back_to_home_nclav_when_no_root() {
         let backend = TerraformBackend {
             api_base:       "http://localhost:8080".into(),
             auth_token:     Arc::new("tok".into()),
+            tls:            BackendTls::default(),
             store:          Arc::new(InMemoryStore::new()),
             test_mode:      false,
             workspace_root: None,
@@ -1051,4 +1326,29 @@ mod tests {
         assert!(ws.to_string_lossy().contains(".nclav"));
         assert!(ws.ends_with("workspaces/enc/part"));
     }
+
+    // ── list_workspaces ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn list_workspaces_finds_partition_dirs() {
+        let root = TempDir::new().unwrap();
+        let dir = workspaces_dir(Some(root.path()));
+        assert!(list_workspaces(&dir).await.unwrap().is_empty());
+
+        fs::create_dir_all(dir.join("enc-b/db")).unwrap();
+        fs::create_dir_all(dir.join("enc-a/api")).unwrap();
+        fs::write(dir.join("enc-a/stray.txt"), "x").unwrap();
+        fs::write(dir.join("stray.txt"), "x").unwrap();
+
+        let found: Vec<(String, String)> = list_workspaces(&dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|w| (w.enclave_id, w.partition_id))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("enc-a".into(), "api".into()), ("enc-b".into(), "db".into())]
+        );
+    }
 }
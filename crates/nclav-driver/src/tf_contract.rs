@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use nclav_domain::{Enclave, EnclaveId, Partition, PartitionBackend, PartitionId};
+use thiserror::Error;
+
+use crate::terraform::PREAMBLE_VARS;
+
+// ── Static contract check ─────────────────────────────────────────────────────
+//
+// Compares a partition's `config.yml` against the `.tf` files that sit next to it,
+// without invoking Terraform. Catches mismatches that would otherwise only surface
+// when `terraform apply` fails deep into a reconcile.
+
+/// A mismatch between a partition's YAML and its co-located Terraform files.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ContractViolation {
+    #[error("{enclave_id}/{partition_id}: declared output '{output}' has no matching `output` block")]
+    MissingOutput {
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        output: String,
+    },
+
+    #[error("{enclave_id}/{partition_id}: input '{input}' has no matching `variable` block")]
+    UndeclaredInput {
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        input: String,
+    },
+
+    #[error(
+        "{enclave_id}/{partition_id}: variable '{variable}' has no default and is not supplied \
+         by inputs or the nclav_* preamble"
+    )]
+    UnsuppliedVariable {
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        variable: String,
+    },
+
+    #[error("{enclave_id}/{partition_id}: module dir '{dir}' not found")]
+    ModuleDirNotFound {
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        dir: String,
+    },
+
+    #[error("{enclave_id}/{partition_id}: cannot parse {file}: {message}")]
+    Parse {
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        file: String,
+        message: String,
+    },
+}
+
+/// Why a partition's `.tf` files could not be read.
+#[derive(Debug)]
+enum ReadError {
+    DirNotFound,
+    Parse { file: String, message: String },
+}
+
+/// Variables and outputs declared across a partition's `.tf` files.
+#[derive(Debug, Default)]
+struct TfInterface {
+    /// Variable name → whether it has a `default`.
+    variables: BTreeMap<String, bool>,
+    outputs: BTreeSet<String>,
+}
+
+/// Check every Terraform-backed partition in `enclaves`.
+///
+/// Partitions that reference an external module via `terraform.source` are skipped —
+/// their interface is not available locally.
+pub fn check_tf_contracts(enclaves: &[Enclave]) -> Vec<ContractViolation> {
+    enclaves
+        .iter()
+        .flat_map(|enc| enc.partitions.iter().map(move |p| (enc, p)))
+        .flat_map(|(enc, p)| check_partition_contract(enc, p))
+        .collect()
+}
+
+/// Check a single partition's `inputs:` and `declared_outputs:` against its `.tf` files.
+pub fn check_partition_contract(enclave: &Enclave, partition: &Partition) -> Vec<ContractViolation> {
+    let cfg = match &partition.backend {
+        PartitionBackend::Terraform(cfg) | PartitionBackend::OpenTofu(cfg) => cfg,
+    };
+    if cfg.source.is_some() {
+        return Vec::new();
+    }
+
+    let enclave_id = enclave.id.clone();
+    let partition_id = partition.id.clone();
+
+    let iface = match read_tf_interface(&cfg.dir) {
+        Ok(iface) => iface,
+        Err(ReadError::DirNotFound) => {
+            let dir = cfg.dir.display().to_string();
+            return vec![ContractViolation::ModuleDirNotFound { enclave_id, partition_id, dir }];
+        }
+        Err(ReadError::Parse { file, message }) => {
+            return vec![ContractViolation::Parse { enclave_id, partition_id, file, message }];
+        }
+    };
+
+    let mut violations = Vec::new();
+
+    for output in &partition.declared_outputs {
+        if !iface.outputs.contains(output) {
+            violations.push(ContractViolation::MissingOutput {
+                enclave_id: enclave_id.clone(),
+                partition_id: partition_id.clone(),
+                output: output.clone(),
+            });
+        }
+    }
+
+    let mut inputs: Vec<&String> = partition.inputs.keys().collect();
+    inputs.sort();
+    for input in inputs {
+        if !iface.variables.contains_key(input) {
+            violations.push(ContractViolation::UndeclaredInput {
+                enclave_id: enclave_id.clone(),
+                partition_id: partition_id.clone(),
+                input: input.clone(),
+            });
+        }
+    }
+
+    for (variable, has_default) in &iface.variables {
+        let supplied = partition.inputs.contains_key(variable)
+            || PREAMBLE_VARS.contains(&variable.as_str());
+        if !has_default && !supplied {
+            violations.push(ContractViolation::UnsuppliedVariable {
+                enclave_id: enclave_id.clone(),
+                partition_id: partition_id.clone(),
+                variable: variable.clone(),
+            });
+        }
+    }
+
+    violations
+}
+
+/// Parse every `.tf` file in `dir` and collect its `variable` and `output` blocks.
+fn read_tf_interface(dir: &Path) -> Result<TfInterface, ReadError> {
+    let mut iface = TfInterface::default();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(ReadError::DirNotFound),
+        Err(e) => {
+            let file = dir.display().to_string();
+            return Err(ReadError::Parse { file, message: e.to_string() });
+        }
+    };
+
+    let mut files: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "tf"))
+        .collect();
+    files.sort();
+
+    for path in files {
+        let file = path.display().to_string();
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| ReadError::Parse { file: file.clone(), message: e.to_string() })?;
+        let body = hcl::parse(&content)
+            .map_err(|e| ReadError::Parse { file: file.clone(), message: e.to_string() })?;
+
+        for block in body.blocks() {
+            let Some(label) = block.labels().first() else { continue };
+            match block.identifier() {
+                "variable" => {
+                    let has_default = block.body().attributes().any(|a| a.key() == "default");
+                    iface.variables.insert(label.as_str().to_string(), has_default);
+                }
+                "output" => {
+                    iface.outputs.insert(label.as_str().to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(iface)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nclav_domain::TerraformConfig;
+    use std::collections::HashMap;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn enclave() -> Enclave {
+        Enclave {
+            id: EnclaveId::new("enc"),
+            name: "enc".into(),
+            region: "local".into(),
+            ..Default::default()
+        }
+    }
+
+    fn partition(dir: &Path, inputs: &[&str], outputs: &[&str]) -> Partition {
+        Partition {
+            id: PartitionId::new("part"),
+            name: "part".into(),
+            produces: None,
+            imports: vec![],
+            exports: vec![],
+            inputs: inputs
+                .iter()
+                .map(|k| (k.to_string(), serde_json::Value::from("v")))
+                .collect::<HashMap<_, _>>(),
+            declared_outputs: outputs.iter().map(|s| s.to_string()).collect(),
+            backend: PartitionBackend::Terraform(TerraformConfig {
+                tool: None,
+                source: None,
+                dir: dir.to_path_buf(),
+            }),
+        }
+    }
+
+    const MAIN_TF: &str = r#"
+variable "project_id" {}
+variable "replicas" {
+  type    = number
+  default = 1
+}
+variable "nclav_enclave" {}
+
+output "hostname" {
+  value = "h"
+}
+"#;
+
+    #[test]
+    fn matching_contract_has_no_violations() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.tf"), MAIN_TF).unwrap();
+        let part = partition(dir.path(), &["project_id"], &["hostname"]);
+
+        assert!(check_partition_contract(&enclave(), &part).is_empty());
+    }
+
+    #[test]
+    fn missing_output_block_reported() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.tf"), MAIN_TF).unwrap();
+        let part = partition(dir.path(), &["project_id"], &["hostname", "port"]);
+
+        let v = check_partition_contract(&enclave(), &part);
+        assert_eq!(v.len(), 1);
+        assert!(matches!(&v[0], ContractViolation::MissingOutput { output, .. } if output == "port"));
+    }
+
+    #[test]
+    fn input_without_variable_reported() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.tf"), MAIN_TF).unwrap();
+        let part = partition(dir.path(), &["project_id", "region"], &[]);
+
+        let v = check_partition_contract(&enclave(), &part);
+        assert_eq!(v.len(), 1);
+        assert!(matches!(&v[0], ContractViolation::UndeclaredInput { input, .. } if input == "region"));
+    }
+
+    #[test]
+    fn required_variable_not_supplied_reported() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.tf"), MAIN_TF).unwrap();
+        let part = partition(dir.path(), &[], &[]);
+
+        let v = check_partition_contract(&enclave(), &part);
+        // project_id is required; replicas has a default; nclav_enclave comes from the preamble.
+        assert_eq!(v.len(), 1);
+        assert!(matches!(&v[0], ContractViolation::UnsuppliedVariable { variable, .. } if variable == "project_id"));
+    }
+
+    #[test]
+    fn blocks_collected_across_files() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("variables.tf"), "variable \"project_id\" {}\n").unwrap();
+        fs::write(dir.path().join("outputs.tf"), "output \"hostname\" { value = \"h\" }\n").unwrap();
+        let part = partition(dir.path(), &["project_id"], &["hostname"]);
+
+        assert!(check_partition_contract(&enclave(), &part).is_empty());
+    }
+
+    #[test]
+    fn invalid_hcl_reported_as_parse_error() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.tf"), "variable \"x\" {\n").unwrap();
+        let part = partition(dir.path(), &[], &[]);
+
+        let v = check_partition_contract(&enclave(), &part);
+        assert_eq!(v.len(), 1);
+        assert!(matches!(&v[0], ContractViolation::Parse { file, .. } if file.ends_with("main.tf")));
+    }
+
+    #[test]
+    fn missing_module_dir_is_single_violation() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("gone");
+        let part = partition(&missing, &["region"], &["hostname", "port"]);
+
+        let v = check_partition_contract(&enclave(), &part);
+        assert_eq!(v.len(), 1, "{:?}", v);
+        assert!(matches!(&v[0], ContractViolation::ModuleDirNotFound { dir, .. } if dir.ends_with("gone")));
+    }
+
+    #[test]
+    fn module_sourced_partition_skipped() {
+        let dir = TempDir::new().unwrap();
+        let mut part = partition(dir.path(), &["anything"], &["hostname"]);
+        if let PartitionBackend::Terraform(cfg) = &mut part.backend {
+            cfg.source = Some("git::https://example.com/mod.git".into());
+        }
+
+        assert!(check_partition_contract(&enclave(), &part).is_empty());
+    }
+
+    #[test]
+    fn example_enclaves_satisfy_contract() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../enclaves");
+        let enclaves = nclav_config::load_enclaves(&dir).unwrap();
+        let violations = check_tf_contracts(&enclaves);
+        assert!(violations.is_empty(), "unexpected violations: {:?}", violations);
+    }
+}
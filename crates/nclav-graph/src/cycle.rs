@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::error::CycleEdge;
+
+/// One cycle per strongly connected component of `graph`, each as the ordered
+/// list of edges leading from the component's first node (by name) back to it.
+///
+/// Nodes are enclave or partition IDs; edge weights are the export names that
+/// create the dependency.
+pub(crate) fn find_cycles(graph: &DiGraph<String, String>) -> Vec<Vec<CycleEdge>> {
+    let mut cycles = Vec::new();
+    for scc in tarjan_scc(graph) {
+        let members: HashSet<NodeIndex> = scc.iter().copied().collect();
+        let Some(&start) = scc.iter().min_by_key(|n| &graph[**n]) else { continue };
+        if scc.len() == 1 && graph.find_edge(start, start).is_none() {
+            continue;
+        }
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+        if walk_back(graph, start, start, &members, &mut visited, &mut path) {
+            cycles.push(path);
+        }
+    }
+    cycles.sort_by(|a, b| a[0].from.cmp(&b[0].from));
+    cycles
+}
+
+/// Depth-first search from `node` to `start` inside `members`, appending the
+/// edges taken to `path`. Edges are tried in name order so output is stable.
+fn walk_back(
+    graph: &DiGraph<String, String>,
+    node: NodeIndex,
+    start: NodeIndex,
+    members: &HashSet<NodeIndex>,
+    visited: &mut HashSet<NodeIndex>,
+    path: &mut Vec<CycleEdge>,
+) -> bool {
+    visited.insert(node);
+    let mut edges: Vec<_> = graph
+        .edges_directed(node, Direction::Outgoing)
+        .filter(|e| members.contains(&e.target()))
+        .collect();
+    edges.sort_by(|a, b| (&graph[a.target()], a.weight()).cmp(&(&graph[b.target()], b.weight())));
+
+    for edge in edges {
+        path.push(CycleEdge {
+            from: graph[node].clone(),
+            to: graph[edge.target()].clone(),
+            export_name: edge.weight().clone(),
+        });
+        if edge.target() == start
+            || (!visited.contains(&edge.target())
+                && walk_back(graph, edge.target(), start, members, visited, path))
+        {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str, &str)]) -> DiGraph<String, String> {
+        let mut g = DiGraph::new();
+        let mut nodes = std::collections::HashMap::new();
+        for (from, to, export) in edges {
+            let f = *nodes.entry(*from).or_insert_with(|| g.add_node(from.to_string()));
+            let t = *nodes.entry(*to).or_insert_with(|| g.add_node(to.to_string()));
+            g.add_edge(f, t, export.to_string());
+        }
+        g
+    }
+
+    #[test]
+    fn reports_each_cycle_from_its_lowest_node() {
+        let g = graph(&[
+            ("c", "a", "c-out"),
+            ("a", "b", "a-out"),
+            ("b", "c", "b-out"),
+            ("b", "d", "b-out"),
+            ("x", "y", "x-out"),
+            ("y", "x", "y-out"),
+        ]);
+        let cycles = find_cycles(&g);
+        assert_eq!(cycles.len(), 2);
+        let rendered: Vec<Vec<&str>> = cycles
+            .iter()
+            .map(|c| c.iter().map(|e| e.export_name.as_str()).collect())
+            .collect();
+        assert_eq!(rendered, vec![vec!["a-out", "b-out", "c-out"], vec!["x-out", "y-out"]]);
+        assert_eq!(cycles[0][0].from, "a");
+        assert_eq!(cycles[0][2].to, "a");
+    }
+
+    #[test]
+    fn acyclic_graph_has_no_cycles() {
+        let g = graph(&[("a", "b", "x"), ("b", "c", "y"), ("a", "c", "z")]);
+        assert!(find_cycles(&g).is_empty());
+    }
+}
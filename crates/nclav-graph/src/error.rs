@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 use nclav_domain::{EnclaveId, PartitionId};
 
@@ -46,9 +48,139 @@ pub enum GraphError {
         key: String,
     },
 
-    #[error("cycle detected in enclave dependency graph")]
-    CycleDetected,
+    #[error("incompatible auth: export '{export_name}' on enclave '{enclave}' is {export_type}, which does not support auth {auth}")]
+    IncompatibleAuth {
+        enclave: EnclaveId,
+        export_name: String,
+        export_type: String,
+        auth: String,
+    },
+
+    #[error("duplicate enclave id '{id}'")]
+    DuplicateEnclaveId { id: EnclaveId },
+
+    #[error("duplicate partition id '{partition}' in enclave '{enclave}'")]
+    DuplicatePartitionId {
+        enclave: EnclaveId,
+        partition: PartitionId,
+    },
+
+    #[error("duplicate export name '{export_name}' in enclave '{enclave}'")]
+    DuplicateExportName {
+        enclave: EnclaveId,
+        export_name: String,
+    },
+
+    #[error("duplicate import alias '{alias}' in {}", scope(enclave, partition.as_ref()))]
+    DuplicateImportAlias {
+        enclave: EnclaveId,
+        partition: Option<PartitionId>,
+        alias: String,
+    },
+
+    #[error("invalid CIDR '{cidr}' in enclave '{enclave}': {reason}")]
+    InvalidCidr {
+        enclave: EnclaveId,
+        cidr: String,
+        reason: String,
+    },
+
+    #[error("subnet {subnet} of enclave '{enclave}' is outside its vpc_cidr {vpc_cidr}")]
+    SubnetOutsideVpc {
+        enclave: EnclaveId,
+        subnet: String,
+        vpc_cidr: String,
+    },
+
+    #[error("subnets {subnet} and {other} of enclave '{enclave}' overlap")]
+    SubnetOverlap {
+        enclave: EnclaveId,
+        subnet: String,
+        other: String,
+    },
+
+    #[error("network {cidr} of enclave '{enclave}' overlaps {other_cidr} of enclave '{other}'")]
+    NetworkOverlap {
+        enclave: EnclaveId,
+        cidr: String,
+        other: EnclaveId,
+        other_cidr: String,
+    },
+
+    #[error("invalid VPC supernet '{supernet}': {reason}")]
+    InvalidSupernet { supernet: String, reason: String },
+
+    #[error("no free /{prefix_len} block left in VPC supernet {supernet}")]
+    CidrExhausted { supernet: String, prefix_len: u8 },
+
+    #[error("invalid impact target '{target}': {reason}")]
+    InvalidImpactTarget { target: String, reason: String },
+
+    #[error("cycle detected in enclave dependency graph: {}", path(cycle))]
+    CycleDetected { cycle: Vec<CycleEdge> },
+
+    #[error("cycle detected between partitions of enclave '{enclave}': {}", path(cycle))]
+    PartitionCycle {
+        enclave: EnclaveId,
+        cycle: Vec<CycleEdge>,
+    },
+
+    #[error("{} validation error(s):{}", .0.len(), list(.0))]
+    Multiple(Vec<LocatedError>),
+}
+
+impl GraphError {
+    /// The individual errors, flattening [`GraphError::Multiple`].
+    pub fn errors(&self) -> Vec<&GraphError> {
+        match self {
+            GraphError::Multiple(errs) => errs.iter().flat_map(|e| e.error.errors()).collect(),
+            other => vec![other],
+        }
+    }
+}
+
+/// One dependency in a cycle: `to` imports `export_name` from `from`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleEdge {
+    pub from: String,
+    pub to: String,
+    pub export_name: String,
+}
+
+/// A [`GraphError`] together with the `config.yml` it was found in, when known.
+#[derive(Debug)]
+pub struct LocatedError {
+    pub location: Option<PathBuf>,
+    pub error: GraphError,
+}
+
+impl std::fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(path) => write!(f, "{}: {}", path.display(), self.error),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for LocatedError {}
+
+fn scope(enclave: &EnclaveId, partition: Option<&PartitionId>) -> String {
+    match partition {
+        Some(p) => format!("partition '{}/{}'", enclave, p),
+        None => format!("enclave '{}'", enclave),
+    }
+}
+
+/// `a -[a-svc]-> b -[b-svc]-> a`
+fn path(cycle: &[CycleEdge]) -> String {
+    let mut out = cycle.first().map(|e| e.from.clone()).unwrap_or_default();
+    for edge in cycle {
+        out.push_str(&format!(" -[{}]-> {}", edge.export_name, edge.to));
+    }
+    out
+}
 
-    #[error("multiple errors")]
-    Multiple(Vec<GraphError>),
+fn list(errors: &[LocatedError]) -> String {
+    errors.iter().map(|e| format!("\n  {}", e)).collect()
 }
@@ -0,0 +1,319 @@
+use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+
+use nclav_domain::{Enclave, EnclaveId, PartitionId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::GraphError;
+use crate::validate::ResolvedGraph;
+
+/// The subject of a proposed change: `<enclave>`, `<enclave>/<partition>` or
+/// `<enclave>:<export>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImpactTarget {
+    Enclave { enclave: EnclaveId },
+    Partition { enclave: EnclaveId, partition: PartitionId },
+    Export { enclave: EnclaveId, export_name: String },
+}
+
+impl ImpactTarget {
+    pub fn enclave(&self) -> &EnclaveId {
+        match self {
+            ImpactTarget::Enclave { enclave }
+            | ImpactTarget::Partition { enclave, .. }
+            | ImpactTarget::Export { enclave, .. } => enclave,
+        }
+    }
+}
+
+impl FromStr for ImpactTarget {
+    type Err = GraphError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| GraphError::InvalidImpactTarget {
+            target: s.to_string(),
+            reason: reason.to_string(),
+        };
+        let target = if let Some((enc, part)) = s.split_once('/') {
+            if part.is_empty() {
+                return Err(invalid("empty partition id"));
+            }
+            ImpactTarget::Partition { enclave: EnclaveId::new(enc), partition: PartitionId::new(part) }
+        } else if let Some((enc, export)) = s.split_once(':') {
+            if export.is_empty() {
+                return Err(invalid("empty export name"));
+            }
+            ImpactTarget::Export { enclave: EnclaveId::new(enc), export_name: export.to_string() }
+        } else {
+            ImpactTarget::Enclave { enclave: EnclaveId::new(s) }
+        };
+        if target.enclave().as_str().is_empty() {
+            return Err(invalid("empty enclave id"));
+        }
+        Ok(target)
+    }
+}
+
+impl std::fmt::Display for ImpactTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImpactTarget::Enclave { enclave } => write!(f, "{}", enclave),
+            ImpactTarget::Partition { enclave, partition } => write!(f, "{}/{}", enclave, partition),
+            ImpactTarget::Export { enclave, export_name } => write!(f, "{}:{}", enclave, export_name),
+        }
+    }
+}
+
+/// An importer that would see a change to the target, directly or transitively.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependent {
+    pub enclave: EnclaveId,
+    /// The importing partition; `None` for an enclave-level import.
+    pub partition: Option<PartitionId>,
+    /// The enclave owning the export this dependent consumes.
+    pub exporter: EnclaveId,
+    pub export_name: String,
+    pub alias: String,
+    /// `inputs:` keys whose templates reference `alias`. For enclave-level imports
+    /// these are qualified as `<partition>.<key>`.
+    pub inputs: Vec<String>,
+    /// 1 for direct importers of the target, 2 for importers of those, and so on.
+    pub depth: usize,
+}
+
+/// Every downstream enclave, partition and templated input affected by changing
+/// or deleting `target`, in breadth-first order.
+///
+/// Follows both cross-enclave and intra-enclave wiring. A changed partition
+/// changes the exports it backs; a changed enclave-level import is assumed to
+/// change every export of the importing enclave.
+pub fn impact(
+    enclaves: &[Enclave],
+    resolved: &ResolvedGraph,
+    target: &ImpactTarget,
+) -> Result<Vec<Dependent>, GraphError> {
+    let unknown = |reason: String| GraphError::InvalidImpactTarget {
+        target: target.to_string(),
+        reason,
+    };
+    let enc = enclaves
+        .iter()
+        .find(|e| &e.id == target.enclave())
+        .ok_or_else(|| unknown(format!("no enclave '{}'", target.enclave())))?;
+
+    let initial: Vec<String> = match target {
+        ImpactTarget::Enclave { .. } => export_names(enc, None),
+        ImpactTarget::Partition { partition, .. } => {
+            if !enc.partitions.iter().any(|p| &p.id == partition) {
+                return Err(unknown(format!("no partition '{}' in enclave '{}'", partition, enc.id)));
+            }
+            export_names(enc, Some(partition))
+        }
+        ImpactTarget::Export { export_name, .. } => {
+            if !export_names(enc, None).contains(export_name) {
+                return Err(unknown(format!("no export '{}' in enclave '{}'", export_name, enc.id)));
+            }
+            vec![export_name.clone()]
+        }
+    };
+
+    let mut queue: VecDeque<(EnclaveId, String, usize)> =
+        initial.into_iter().map(|name| (enc.id.clone(), name, 1)).collect();
+    let mut seen_exports: HashSet<(EnclaveId, String)> =
+        queue.iter().map(|(e, n, _)| (e.clone(), n.clone())).collect();
+    let mut dependents = Vec::new();
+
+    while let Some((exporter, export_name, depth)) = queue.pop_front() {
+        for w in &resolved.cross_enclave_wiring {
+            if w.exporter_enclave != exporter || w.export_name != export_name {
+                continue;
+            }
+            let Some(importer) = enclaves.iter().find(|e| e.id == w.importer_enclave) else {
+                continue;
+            };
+            dependents.push(Dependent {
+                enclave: importer.id.clone(),
+                partition: w.importer_partition.clone(),
+                exporter: exporter.clone(),
+                export_name: export_name.clone(),
+                alias: w.alias.clone(),
+                inputs: templated_inputs(importer, w.importer_partition.as_ref(), &w.alias),
+                depth,
+            });
+            for name in export_names(importer, w.importer_partition.as_ref()) {
+                if seen_exports.insert((importer.id.clone(), name.clone())) {
+                    queue.push_back((importer.id.clone(), name, depth + 1));
+                }
+            }
+        }
+    }
+    Ok(dependents)
+}
+
+/// Names of `enc`'s exports, limited to those backed by `partition` when given.
+fn export_names(enc: &Enclave, partition: Option<&PartitionId>) -> Vec<String> {
+    enc.exports
+        .iter()
+        .chain(enc.partitions.iter().flat_map(|p| p.exports.iter()))
+        .filter(|e| partition.is_none_or(|p| &e.target_partition == p))
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+fn templated_inputs(enc: &Enclave, partition: Option<&PartitionId>, alias: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    for part in &enc.partitions {
+        if partition.is_some_and(|p| p != &part.id) {
+            continue;
+        }
+        let mut part_keys: Vec<&String> = part
+            .inputs
+            .iter()
+            .filter(|(_, v)| references_alias(v, alias))
+            .map(|(k, _)| k)
+            .collect();
+        part_keys.sort();
+        keys.extend(part_keys.into_iter().map(|k| match partition {
+            Some(_) => k.clone(),
+            None => format!("{}.{}", part.id, k),
+        }));
+    }
+    keys
+}
+
+/// True if any string in `value` holds a `{{ alias }}` or `{{ alias.… }}` placeholder.
+fn references_alias(value: &Value, alias: &str) -> bool {
+    match value {
+        Value::String(s) => {
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(len) = rest[start..].find("}}") else { break };
+                let inner = rest[start + 2..start + len].trim();
+                if inner == alias || inner.strip_prefix(alias).is_some_and(|r| r.starts_with('.')) {
+                    return true;
+                }
+                rest = &rest[start + len + 2..];
+            }
+            false
+        }
+        Value::Array(items) => items.iter().any(|v| references_alias(v, alias)),
+        Value::Object(map) => map.values().any(|v| references_alias(v, alias)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+    use nclav_domain::{
+        AuthType, CloudTarget, Export, ExportTarget, ExportType, Import, Partition,
+        PartitionBackend, ProducesType,
+    };
+    use serde_json::json;
+
+    fn partition(id: &str, imports: Vec<Import>, inputs: Value) -> Partition {
+        Partition {
+            id: PartitionId::new(id),
+            name: id.into(),
+            produces: Some(ProducesType::Tcp),
+            imports,
+            exports: vec![],
+            inputs: serde_json::from_value(inputs).unwrap(),
+            declared_outputs: vec!["hostname".into(), "port".into()],
+            backend: PartitionBackend::default(),
+        }
+    }
+
+    fn export(name: &str, partition: &str) -> Export {
+        Export {
+            name: name.into(),
+            target_partition: PartitionId::new(partition),
+            export_type: ExportType::Tcp,
+            to: ExportTarget::AnyEnclave,
+            auth: AuthType::None,
+            hostname: None,
+            port: None,
+        }
+    }
+
+    fn import(from: &str, export_name: &str, alias: &str) -> Import {
+        Import {
+            from: EnclaveId::new(from),
+            export_name: export_name.into(),
+            alias: alias.into(),
+            import_type: None,
+        }
+    }
+
+    fn enclave(id: &str, partitions: Vec<Partition>, exports: Vec<Export>) -> Enclave {
+        Enclave {
+            id: EnclaveId::new(id),
+            name: id.into(),
+            cloud: Some(CloudTarget::Local),
+            region: "local".into(),
+            exports,
+            partitions,
+            ..Default::default()
+        }
+    }
+
+    /// `data/db` ← `app/api` (which re-exports) ← `web/ui`.
+    fn chain() -> Vec<Enclave> {
+        vec![
+            enclave("data", vec![partition("db", vec![], json!({}))], vec![export("db", "db")]),
+            enclave(
+                "app",
+                vec![partition(
+                    "api",
+                    vec![import("data", "db", "database")],
+                    json!({ "db_url": "{{ database.hostname }}:{{ database.port }}", "replicas": 2 }),
+                )],
+                vec![export("api", "api")],
+            ),
+            enclave(
+                "web",
+                vec![partition("ui", vec![import("app", "api", "backend")], json!({}))],
+                vec![],
+            ),
+        ]
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(
+            "a/b".parse::<ImpactTarget>().unwrap(),
+            ImpactTarget::Partition { enclave: EnclaveId::new("a"), partition: PartitionId::new("b") }
+        );
+        assert_eq!(
+            "a:x".parse::<ImpactTarget>().unwrap(),
+            ImpactTarget::Export { enclave: EnclaveId::new("a"), export_name: "x".into() }
+        );
+        assert!("/b".parse::<ImpactTarget>().is_err());
+    }
+
+    #[test]
+    fn walks_transitive_dependents_with_inputs() {
+        let enclaves = chain();
+        let resolved = validate(&enclaves).unwrap();
+        let deps = impact(&enclaves, &resolved, &"data/db".parse().unwrap()).unwrap();
+
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0].enclave.as_str(), "app");
+        assert_eq!(deps[0].inputs, vec!["db_url".to_string()]);
+        assert_eq!(deps[0].depth, 1);
+        assert_eq!(deps[1].enclave.as_str(), "web");
+        assert_eq!(deps[1].alias, "backend");
+        assert_eq!(deps[1].depth, 2);
+    }
+
+    #[test]
+    fn leaf_has_no_dependents_and_unknown_target_errors() {
+        let enclaves = chain();
+        let resolved = validate(&enclaves).unwrap();
+        assert!(impact(&enclaves, &resolved, &"web".parse().unwrap()).unwrap().is_empty());
+        assert!(impact(&enclaves, &resolved, &"data:nope".parse().unwrap()).is_err());
+    }
+}
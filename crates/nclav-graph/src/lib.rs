@@ -1,5 +1,10 @@
+mod cycle;
 mod error;
+mod impact;
+mod network;
 mod validate;
 
-pub use error::GraphError;
-pub use validate::{validate, CrossEnclaveWiring, NodeId, ResolvedGraph};
+pub use error::{CycleEdge, GraphError, LocatedError};
+pub use impact::{impact, Dependent, ImpactTarget};
+pub use network::CidrAllocator;
+pub use validate::{partition_order, validate, CrossEnclaveWiring, NodeId, ResolvedGraph};
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use ipnet::IpNet;
+use nclav_domain::{Enclave, EnclaveId};
+
+use crate::error::{GraphError, LocatedError};
+
+/// Check every enclave's `network:` block.
+///
+/// - `vpc_cidr` and `subnets` must be valid CIDRs with no host bits set
+/// - subnets must lie inside `vpc_cidr` (when given) and must not overlap each other
+/// - an enclave's address space must not overlap another enclave's in the same cloud
+///
+/// An enclave's address space is its `vpc_cidr`, or its subnets when `vpc_cidr`
+/// is omitted. Enclaves that omit `cloud:` are compared with each other.
+pub(crate) fn check_networks(enclaves: &[Enclave]) -> Vec<LocatedError> {
+    let mut errors = Vec::new();
+    let mut push = |location: &Option<PathBuf>, error: GraphError| {
+        errors.push(LocatedError { location: location.clone(), error });
+    };
+
+    // Address space per enclave, in declaration order, for the cross-enclave pass.
+    let mut spaces: Vec<(&Enclave, Vec<IpNet>)> = Vec::new();
+
+    for enc in enclaves {
+        let Some(network) = &enc.network else { continue };
+        let mut parse = |cidr: &str| match parse_cidr(cidr) {
+            Ok(net) => Some(net),
+            Err(reason) => {
+                push(
+                    &enc.source,
+                    GraphError::InvalidCidr {
+                        enclave: enc.id.clone(),
+                        cidr: cidr.to_string(),
+                        reason,
+                    },
+                );
+                None
+            }
+        };
+
+        let vpc = network.vpc_cidr.as_deref().and_then(&mut parse);
+        let subnets: Vec<(&str, IpNet)> = network
+            .subnets
+            .iter()
+            .filter_map(|s| parse(s).map(|net| (s.as_str(), net)))
+            .collect();
+
+        for (i, (cidr, net)) in subnets.iter().enumerate() {
+            if let (Some(vpc_net), Some(vpc_cidr)) = (vpc, &network.vpc_cidr) {
+                if !vpc_net.contains(net) {
+                    push(
+                        &enc.source,
+                        GraphError::SubnetOutsideVpc {
+                            enclave: enc.id.clone(),
+                            subnet: cidr.to_string(),
+                            vpc_cidr: vpc_cidr.clone(),
+                        },
+                    );
+                }
+            }
+            for (other_cidr, other) in &subnets[..i] {
+                if overlaps(net, other) {
+                    push(
+                        &enc.source,
+                        GraphError::SubnetOverlap {
+                            enclave: enc.id.clone(),
+                            subnet: cidr.to_string(),
+                            other: other_cidr.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+
+        let space = match vpc {
+            Some(net) => vec![net],
+            None => subnets.iter().map(|(_, net)| *net).collect(),
+        };
+        spaces.push((enc, space));
+    }
+
+    for (i, (enc, space)) in spaces.iter().enumerate() {
+        for (other, other_space) in &spaces[..i] {
+            if other.cloud != enc.cloud {
+                continue;
+            }
+            let clash = space
+                .iter()
+                .find_map(|a| other_space.iter().find(|b| overlaps(a, b)).map(|b| (a, b)));
+            if let Some((net, other_net)) = clash {
+                push(
+                    &enc.source,
+                    GraphError::NetworkOverlap {
+                        enclave: enc.id.clone(),
+                        cidr: net.to_string(),
+                        other: other.id.clone(),
+                        other_cidr: other_net.to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    errors
+}
+
+/// Parse a CIDR, rejecting addresses with host bits set (`10.0.0.1/16`), which
+/// cloud APIs refuse.
+fn parse_cidr(cidr: &str) -> Result<IpNet, String> {
+    let net: IpNet = cidr.trim().parse().map_err(|_| "not a CIDR block".to_string())?;
+    if net.trunc() != net {
+        return Err(format!("host bits set (did you mean {}?)", net.trunc()));
+    }
+    Ok(net)
+}
+
+fn overlaps(a: &IpNet, b: &IpNet) -> bool {
+    a.contains(&b.network()) || b.contains(&a.network())
+}
+
+/// Assigns a `vpc_cidr` to enclaves that declare a `network:` block with neither
+/// `vpc_cidr` nor `subnets`, carving fixed-size blocks out of a supernet.
+///
+/// Blocks already in use by any enclave are skipped. Allocations from a previous
+/// run are passed back in so an enclave keeps its range across reconciles.
+#[derive(Debug, Clone)]
+pub struct CidrAllocator {
+    supernet: IpNet,
+    prefix_len: u8,
+}
+
+impl CidrAllocator {
+    /// `supernet` is a CIDR such as `10.0.0.0/8`; each enclave gets a `/prefix_len` from it.
+    pub fn new(supernet: &str, prefix_len: u8) -> Result<Self, GraphError> {
+        let invalid = |reason: String| GraphError::InvalidSupernet {
+            supernet: supernet.to_string(),
+            reason,
+        };
+        let net = parse_cidr(supernet).map_err(invalid)?;
+        if prefix_len < net.prefix_len() || prefix_len > net.max_prefix_len() {
+            return Err(invalid(format!(
+                "block size /{} must be between /{} and /{}",
+                prefix_len,
+                net.prefix_len(),
+                net.max_prefix_len()
+            )));
+        }
+        Ok(Self { supernet: net, prefix_len })
+    }
+
+    /// Fill in `vpc_cidr` where it is missing. `previous` maps enclave IDs to the
+    /// `vpc_cidr` they were given last time. Returns the assignments made.
+    pub fn allocate(
+        &self,
+        enclaves: &mut [Enclave],
+        previous: &HashMap<EnclaveId, String>,
+    ) -> Result<Vec<(EnclaveId, String)>, GraphError> {
+        let wants_block = |enc: &Enclave| {
+            enc.network
+                .as_ref()
+                .is_some_and(|n| n.vpc_cidr.is_none() && n.subnets.is_empty())
+        };
+
+        // Everything declared explicitly is off limits. Malformed CIDRs are left
+        // for `validate` to report.
+        let mut taken: Vec<IpNet> = enclaves
+            .iter()
+            .filter_map(|e| e.network.as_ref())
+            .flat_map(|n| n.vpc_cidr.iter().chain(n.subnets.iter()))
+            .filter_map(|c| parse_cidr(c).ok())
+            .collect();
+
+        let mut assigned: HashMap<EnclaveId, IpNet> = HashMap::new();
+
+        // Keep previous allocations that still fit.
+        for enc in enclaves.iter().filter(|e| wants_block(e)) {
+            let Some(net) = previous.get(&enc.id).and_then(|c| parse_cidr(c).ok()) else {
+                continue;
+            };
+            if self.supernet.contains(&net) && !taken.iter().any(|t| overlaps(t, &net)) {
+                taken.push(net);
+                assigned.insert(enc.id.clone(), net);
+            }
+        }
+
+        // New allocations, in enclave ID order so the result doesn't depend on
+        // directory listing order.
+        let mut pending: Vec<&EnclaveId> = enclaves
+            .iter()
+            .filter(|e| wants_block(e) && !assigned.contains_key(&e.id))
+            .map(|e| &e.id)
+            .collect();
+        pending.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for id in pending {
+            let exhausted = || GraphError::CidrExhausted {
+                supernet: self.supernet.to_string(),
+                prefix_len: self.prefix_len,
+            };
+            let net = self
+                .supernet
+                .subnets(self.prefix_len)
+                .map_err(|_| exhausted())?
+                .find(|candidate| !taken.iter().any(|t| overlaps(t, candidate)))
+                .ok_or_else(exhausted)?;
+            taken.push(net);
+            assigned.insert(id.clone(), net);
+        }
+
+        let mut result = Vec::new();
+        for enc in enclaves.iter_mut() {
+            if let (Some(net), Some(network)) = (assigned.get(&enc.id), enc.network.as_mut()) {
+                network.vpc_cidr = Some(net.to_string());
+                result.push((enc.id.clone(), net.to_string()));
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nclav_domain::{CloudTarget, NetworkConfig};
+
+    fn enclave(id: &str, cloud: CloudTarget, vpc_cidr: Option<&str>, subnets: &[&str]) -> Enclave {
+        Enclave {
+            id: EnclaveId::new(id),
+            name: id.into(),
+            cloud: Some(cloud),
+            region: "local-1".into(),
+            network: Some(NetworkConfig {
+                vpc_cidr: vpc_cidr.map(Into::into),
+                subnets: subnets.iter().map(|s| s.to_string()).collect(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn errors(enclaves: &[Enclave]) -> Vec<GraphError> {
+        check_networks(enclaves).into_iter().map(|e| e.error).collect()
+    }
+
+    #[test]
+    fn valid_plan_passes() {
+        let enclaves = vec![
+            enclave("a", CloudTarget::Aws, Some("10.0.0.0/16"), &["10.0.1.0/24", "10.0.2.0/24"]),
+            enclave("b", CloudTarget::Aws, Some("10.1.0.0/16"), &[]),
+        ];
+        assert!(errors(&enclaves).is_empty());
+    }
+
+    #[test]
+    fn malformed_and_host_bits_rejected() {
+        let enclaves = vec![enclave("a", CloudTarget::Aws, Some("10.0.0.1/16"), &["banana"])];
+        let errs = errors(&enclaves);
+        assert_eq!(errs.len(), 2, "{errs:?}");
+        assert!(errs.iter().all(|e| matches!(e, GraphError::InvalidCidr { .. })));
+        assert!(errs[0].to_string().contains("did you mean 10.0.0.0/16"));
+    }
+
+    #[test]
+    fn subnet_outside_vpc_and_overlapping_subnets() {
+        let enclaves = vec![enclave(
+            "a",
+            CloudTarget::Aws,
+            Some("10.0.0.0/16"),
+            &["10.0.0.0/20", "10.0.8.0/24", "10.9.0.0/24"],
+        )];
+        let errs = errors(&enclaves);
+        assert!(errs.iter().any(|e| matches!(e, GraphError::SubnetOverlap { subnet, .. } if subnet == "10.0.8.0/24")));
+        assert!(errs.iter().any(|e| matches!(e, GraphError::SubnetOutsideVpc { subnet, .. } if subnet == "10.9.0.0/24")));
+    }
+
+    #[test]
+    fn overlapping_enclaves_only_flagged_within_a_cloud() {
+        let enclaves = vec![
+            enclave("a", CloudTarget::Gcp, None, &["10.0.0.0/20"]),
+            enclave("b", CloudTarget::Gcp, None, &["10.0.8.0/24"]),
+            enclave("c", CloudTarget::Aws, Some("10.0.0.0/16"), &[]),
+        ];
+        let errs = errors(&enclaves);
+        assert_eq!(errs.len(), 1, "{errs:?}");
+        assert!(matches!(
+            &errs[0],
+            GraphError::NetworkOverlap { enclave, other, .. } if enclave.as_str() == "b" && other.as_str() == "a"
+        ));
+    }
+
+    #[test]
+    fn allocator_skips_taken_blocks_and_keeps_previous() {
+        let mut enclaves = vec![
+            enclave("explicit", CloudTarget::Aws, Some("10.0.0.0/16"), &[]),
+            enclave("new", CloudTarget::Aws, None, &[]),
+            enclave("old", CloudTarget::Aws, None, &[]),
+        ];
+        let previous = HashMap::from([(EnclaveId::new("old"), "10.1.0.0/16".to_string())]);
+
+        let alloc = CidrAllocator::new("10.0.0.0/8", 16).unwrap();
+        let assigned = alloc.allocate(&mut enclaves, &previous).unwrap();
+
+        assert_eq!(assigned.len(), 2);
+        let cidr = |i: usize| enclaves[i].network.as_ref().unwrap().vpc_cidr.clone().unwrap();
+        assert_eq!(cidr(1), "10.2.0.0/16");
+        assert_eq!(cidr(2), "10.1.0.0/16");
+        assert!(errors(&enclaves).is_empty());
+    }
+
+    #[test]
+    fn allocator_reports_exhaustion_and_bad_config() {
+        let mut enclaves = vec![
+            enclave("a", CloudTarget::Aws, None, &[]),
+            enclave("b", CloudTarget::Aws, None, &[]),
+        ];
+        let alloc = CidrAllocator::new("10.0.0.0/16", 16).unwrap();
+        let err = alloc.allocate(&mut enclaves, &HashMap::new()).unwrap_err();
+        assert!(matches!(err, GraphError::CidrExhausted { .. }));
+
+        assert!(CidrAllocator::new("10.0.0.0/16", 8).is_err());
+        assert!(CidrAllocator::new("10.0.0.1/8", 16).is_err());
+    }
+}
@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
-use nclav_domain::{Enclave, EnclaveId, ExportTarget, ExportType, PartitionId};
-use petgraph::algo::is_cyclic_directed;
+use nclav_domain::{
+    Enclave, EnclaveId, Export, ExportTarget, ExportType, Import, Partition, PartitionBackend,
+    PartitionId,
+};
 use petgraph::graph::{DiGraph, NodeIndex};
 use serde::{Deserialize, Serialize};
 
-use crate::error::GraphError;
+use crate::cycle::find_cycles;
+use crate::error::{GraphError, LocatedError};
+use crate::network::check_networks;
 
 /// Opaque node identifier in the resolved graph.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -17,7 +22,11 @@ pub struct CrossEnclaveWiring {
     pub importer_enclave: EnclaveId,
     pub importer_partition: Option<PartitionId>,
     pub exporter_enclave: EnclaveId,
+    /// The partition backing the export.
+    pub exporter_partition: PartitionId,
     pub export_name: String,
+    /// The importer's local name for the export, used in `{{ alias.key }}` templates.
+    pub alias: String,
 }
 
 /// Result returned by [`validate`] on success.
@@ -27,42 +36,127 @@ pub struct ResolvedGraph {
     pub topo_order: Vec<NodeId>,
     /// All validated cross-enclave wiring.
     pub cross_enclave_wiring: Vec<CrossEnclaveWiring>,
+    /// Per enclave, partitions in topological order: a partition comes after every
+    /// partition of the same enclave whose exports it imports.
+    pub partition_order: HashMap<EnclaveId, Vec<PartitionId>>,
 }
 
 /// Validate a fully-loaded set of enclaves.
 ///
 /// Checks:
-/// 1. Dangling imports (source enclave/export exists)
-/// 2. Access control (`to:` permits the importer)
-/// 3. Output contract (`declared_outputs ⊇ produces.required_outputs()`)
-/// 4. Produces→export-type match
-/// 5. Cycle detection
+/// 1. Duplicate enclave IDs, partition IDs, export names and import aliases
+/// 2. Dangling imports (source enclave/export exists)
+/// 3. Access control (`to:` permits the importer; `to: partition:` only the named
+///    partition of the same enclave)
+/// 4. Import `type:` matches the export's type
+/// 5. Output contract (`declared_outputs ⊇ produces.required_outputs()`)
+/// 6. Produces→export-type match
+/// 7. Export auth is compatible with its type
+/// 8. Cycle detection, between enclaves and between partitions of one enclave;
+///    each cycle is reported with its path and the exports forming each edge
+/// 9. Network plan: valid CIDRs, subnets inside `vpc_cidr` and disjoint, no
+///    overlapping address space between enclaves of one cloud
+///
+/// Every problem found is reported; failures are returned together as
+/// [`GraphError::Multiple`], each tagged with the `config.yml` it came from.
 pub fn validate(enclaves: &[Enclave]) -> Result<ResolvedGraph, GraphError> {
     let by_id: HashMap<&EnclaveId, &Enclave> =
         enclaves.iter().map(|e| (&e.id, e)).collect();
 
-    let mut errors: Vec<GraphError> = Vec::new();
+    let mut errors: Vec<LocatedError> = Vec::new();
     let mut wiring: Vec<CrossEnclaveWiring> = Vec::new();
 
+    let mut push = |location: Option<PathBuf>, error: GraphError| {
+        errors.push(LocatedError { location, error });
+    };
+
+    // --- Duplicate enclave IDs ---
+    let mut seen_enclaves = HashSet::new();
+    for enc in enclaves {
+        if !seen_enclaves.insert(&enc.id) {
+            push(enc.source.clone(), GraphError::DuplicateEnclaveId { id: enc.id.clone() });
+        }
+    }
+
     // --- Per-enclave checks ---
     for enc in enclaves {
+        let mut seen_partitions = HashSet::new();
+        for part in &enc.partitions {
+            if !seen_partitions.insert(&part.id) {
+                push(
+                    partition_location(enc, part),
+                    GraphError::DuplicatePartitionId {
+                        enclave: enc.id.clone(),
+                        partition: part.id.clone(),
+                    },
+                );
+            }
+        }
+
+        // Enclave- and partition-level exports share one namespace per enclave.
+        let partition_exports = enc
+            .partitions
+            .iter()
+            .flat_map(|p| p.exports.iter().map(move |e| (partition_location(enc, p), e)));
+        let all_exports: Vec<(Option<PathBuf>, &Export)> =
+            enc.exports.iter().map(|e| (enc.source.clone(), e)).chain(partition_exports).collect();
+
+        let mut seen_exports = HashSet::new();
+        for (location, export) in &all_exports {
+            if !seen_exports.insert(&export.name) {
+                push(
+                    location.clone(),
+                    GraphError::DuplicateExportName {
+                        enclave: enc.id.clone(),
+                        export_name: export.name.clone(),
+                    },
+                );
+            }
+        }
+
+        for alias in duplicate_aliases(&enc.imports) {
+            push(
+                enc.source.clone(),
+                GraphError::DuplicateImportAlias {
+                    enclave: enc.id.clone(),
+                    partition: None,
+                    alias,
+                },
+            );
+        }
+        for part in &enc.partitions {
+            for alias in duplicate_aliases(&part.imports) {
+                push(
+                    partition_location(enc, part),
+                    GraphError::DuplicateImportAlias {
+                        enclave: enc.id.clone(),
+                        partition: Some(part.id.clone()),
+                        alias,
+                    },
+                );
+            }
+        }
+
         // Output contract per partition
         for part in &enc.partitions {
             if let Some(produces) = &part.produces {
                 for key in produces.required_outputs() {
                     if !part.declared_outputs.iter().any(|o| o == key) {
-                        errors.push(GraphError::MissingRequiredOutput {
-                            partition: part.id.clone(),
-                            produces_type: produces.to_string(),
-                            key: key.to_string(),
-                        });
+                        push(
+                            partition_location(enc, part),
+                            GraphError::MissingRequiredOutput {
+                                partition: part.id.clone(),
+                                produces_type: produces.to_string(),
+                                key: key.to_string(),
+                            },
+                        );
                     }
                 }
             }
         }
 
-        // Produces→export type match for enclave-level exports
-        for export in &enc.exports {
+        // Produces→export type match
+        for (location, export) in &all_exports {
             let target_partition = enc
                 .partitions
                 .iter()
@@ -71,22 +165,40 @@ pub fn validate(enclaves: &[Enclave]) -> Result<ResolvedGraph, GraphError> {
                 if let Some(produces) = &part.produces {
                     let expected_export_type = ExportType::from(produces);
                     if expected_export_type != export.export_type {
-                        errors.push(GraphError::ProducesExportMismatch {
-                            partition: part.id.clone(),
-                            produces_type: produces.to_string(),
-                            export_name: export.name.clone(),
-                            export_type: export.export_type.to_string(),
-                        });
+                        push(
+                            location.clone(),
+                            GraphError::ProducesExportMismatch {
+                                partition: part.id.clone(),
+                                produces_type: produces.to_string(),
+                                export_name: export.name.clone(),
+                                export_type: export.export_type.to_string(),
+                            },
+                        );
                     }
                 }
             }
         }
 
+        // Auth/type compatibility
+        for (location, export) in all_exports {
+            if !export.export_type.is_auth_compatible(&export.auth) {
+                push(
+                    location,
+                    GraphError::IncompatibleAuth {
+                        enclave: enc.id.clone(),
+                        export_name: export.name.clone(),
+                        export_type: export.export_type.to_string(),
+                        auth: export.auth.to_string(),
+                    },
+                );
+            }
+        }
+
         // Cross-enclave imports at enclave level
         for import in &enc.imports {
             match check_import(enc, import, &by_id) {
                 Ok(w) => wiring.push(w),
-                Err(e) => errors.push(e),
+                Err(e) => push(enc.source.clone(), e),
             }
         }
 
@@ -95,25 +207,18 @@ pub fn validate(enclaves: &[Enclave]) -> Result<ResolvedGraph, GraphError> {
             for import in &part.imports {
                 match check_import_partition(enc, part.id.clone(), import, &by_id) {
                     Ok(w) => wiring.push(w),
-                    Err(e) => errors.push(e),
+                    Err(e) => push(partition_location(enc, part), e),
                 }
             }
         }
     }
 
-    if !errors.is_empty() {
-        if errors.len() == 1 {
-            return Err(errors.remove(0));
-        }
-        return Err(GraphError::Multiple(errors));
-    }
-
     // --- Cycle detection ---
-    let mut graph: DiGraph<&EnclaveId, ()> = DiGraph::new();
-    let node_map: HashMap<&EnclaveId, NodeIndex> = enclaves
-        .iter()
-        .map(|e| (&e.id, graph.add_node(&e.id)))
-        .collect();
+    let mut graph: DiGraph<String, String> = DiGraph::new();
+    let mut node_map: HashMap<&EnclaveId, NodeIndex> = HashMap::new();
+    for e in enclaves {
+        node_map.entry(&e.id).or_insert_with(|| graph.add_node(e.id.to_string()));
+    }
 
     // Add edges: exporter → importer ("exporter must be provisioned before importer").
     // Intra-enclave imports (same enclave) are valid wiring but produce no graph edge.
@@ -123,30 +228,172 @@ pub fn validate(enclaves: &[Enclave]) -> Result<ResolvedGraph, GraphError> {
         }
         let from = node_map[&w.exporter_enclave];
         let to = node_map[&w.importer_enclave];
-        graph.add_edge(from, to, ());
+        graph.add_edge(from, to, w.export_name.clone());
     }
 
-    if is_cyclic_directed(&graph) {
-        return Err(GraphError::CycleDetected);
+    for cycle in find_cycles(&graph) {
+        push(None, GraphError::CycleDetected { cycle });
+    }
+
+    // --- Network plan ---
+    for located in check_networks(enclaves) {
+        push(located.location, located.error);
+    }
+
+    // --- Partition order within each enclave ---
+    let mut partition_order = HashMap::new();
+    for enc in enclaves {
+        match order_partitions(enc, &wiring) {
+            Some(order) => {
+                partition_order.insert(enc.id.clone(), order);
+            }
+            None => {
+                for cycle in find_cycles(&partition_graph(enc, &wiring)) {
+                    push(
+                        enc.source.clone(),
+                        GraphError::PartitionCycle { enclave: enc.id.clone(), cycle },
+                    );
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(GraphError::Multiple(errors));
     }
 
     // Topological order
     let topo = petgraph::algo::toposort(&graph, None)
-        .map_err(|_| GraphError::CycleDetected)?;
+        .expect("cycles are reported as errors above");
     let topo_order = topo
         .iter()
-        .map(|idx| NodeId(graph[*idx].to_string()))
+        .map(|idx| NodeId(graph[*idx].clone()))
         .collect();
 
     Ok(ResolvedGraph {
         topo_order,
         cross_enclave_wiring: wiring,
+        partition_order,
     })
 }
 
+/// The order [`ResolvedGraph::partition_order`] gives `enc`'s partitions,
+/// computed from `enc` alone, e.g. from the stored state of an enclave that is
+/// no longer declared. Imports naming unknown exports are ignored. `None` if
+/// the wiring is cyclic.
+pub fn partition_order(enc: &Enclave) -> Option<Vec<PartitionId>> {
+    let wiring: Vec<CrossEnclaveWiring> = enc
+        .partitions
+        .iter()
+        .flat_map(|part| part.imports.iter().filter(|i| i.from == enc.id).map(move |i| (part, i)))
+        .filter_map(|(part, import)| {
+            let export = find_export(enc, &import.export_name)?;
+            Some(CrossEnclaveWiring {
+                importer_enclave: enc.id.clone(),
+                importer_partition: Some(part.id.clone()),
+                exporter_enclave: enc.id.clone(),
+                exporter_partition: export.target_partition.clone(),
+                export_name: export.name.clone(),
+                alias: import.alias.clone(),
+            })
+        })
+        .collect();
+    order_partitions(enc, &wiring)
+}
+
+/// Topologically order `enc`'s partitions by its intra-enclave wiring, keeping
+/// declaration order where there is no dependency. `None` if the wiring is cyclic.
+fn order_partitions(enc: &Enclave, wiring: &[CrossEnclaveWiring]) -> Option<Vec<PartitionId>> {
+    // importer → partitions it must wait for
+    let mut deps: HashMap<&PartitionId, HashSet<&PartitionId>> = HashMap::new();
+    for w in wiring {
+        if w.importer_enclave != enc.id || w.exporter_enclave != enc.id {
+            continue;
+        }
+        let Some(importer) = &w.importer_partition else { continue };
+        if importer != &w.exporter_partition {
+            deps.entry(importer).or_default().insert(&w.exporter_partition);
+        }
+    }
+
+    let known: HashSet<&PartitionId> = enc.partitions.iter().map(|p| &p.id).collect();
+    let mut remaining: Vec<&PartitionId> = Vec::new();
+    for part in &enc.partitions {
+        if !remaining.contains(&&part.id) {
+            remaining.push(&part.id);
+        }
+    }
+
+    let mut order: Vec<PartitionId> = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|p| {
+            deps.get(p).is_none_or(|d| {
+                d.iter().all(|dep| !known.contains(dep) || order.contains(dep))
+            })
+        })?;
+        order.push(remaining.remove(ready).clone());
+    }
+    Some(order)
+}
+
+/// `enc`'s intra-enclave wiring as a graph of partition IDs, exporter → importer.
+fn partition_graph(enc: &Enclave, wiring: &[CrossEnclaveWiring]) -> DiGraph<String, String> {
+    let mut graph = DiGraph::new();
+    let nodes: HashMap<&PartitionId, NodeIndex> = enc
+        .partitions
+        .iter()
+        .map(|p| (&p.id, graph.add_node(p.id.to_string())))
+        .collect();
+    for w in wiring {
+        if w.importer_enclave != enc.id || w.exporter_enclave != enc.id {
+            continue;
+        }
+        let Some(importer) = &w.importer_partition else { continue };
+        if let (Some(&from), Some(&to)) = (nodes.get(&w.exporter_partition), nodes.get(importer)) {
+            if from != to {
+                graph.add_edge(from, to, w.export_name.clone());
+            }
+        }
+    }
+    graph
+}
+
+/// Look up `name` among an enclave's exports, enclave-level first, then partition-level.
+fn find_export<'a>(enc: &'a Enclave, name: &str) -> Option<&'a Export> {
+    enc.exports
+        .iter()
+        .chain(enc.partitions.iter().flat_map(|p| p.exports.iter()))
+        .find(|e| e.name == name)
+}
+
+/// Best-effort path to a partition's `config.yml`: the loader sets the backend
+/// `dir` to the directory holding it. Falls back to the enclave's config.
+fn partition_location(enc: &Enclave, part: &Partition) -> Option<PathBuf> {
+    let dir = match &part.backend {
+        PartitionBackend::Terraform(cfg) | PartitionBackend::OpenTofu(cfg) => &cfg.dir,
+    };
+    if dir.as_os_str().is_empty() {
+        enc.source.clone()
+    } else {
+        Some(dir.join("config.yml"))
+    }
+}
+
+/// Aliases that appear more than once in `imports`, each reported once.
+fn duplicate_aliases(imports: &[Import]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut dupes = Vec::new();
+    for import in imports {
+        if !seen.insert(&import.alias) && !dupes.contains(&import.alias) {
+            dupes.push(import.alias.clone());
+        }
+    }
+    dupes
+}
+
 fn check_import(
     importer_enc: &Enclave,
-    import: &nclav_domain::Import,
+    import: &Import,
     by_id: &HashMap<&EnclaveId, &Enclave>,
 ) -> Result<CrossEnclaveWiring, GraphError> {
     check_import_partition(importer_enc, PartitionId::new(""), import, by_id).map(|mut w| {
@@ -158,7 +405,7 @@ fn check_import(
 fn check_import_partition(
     importer_enc: &Enclave,
     partition_id: PartitionId,
-    import: &nclav_domain::Import,
+    import: &Import,
     by_id: &HashMap<&EnclaveId, &Enclave>,
 ) -> Result<CrossEnclaveWiring, GraphError> {
     // 1. Source enclave exists
@@ -170,10 +417,7 @@ fn check_import_partition(
         })?;
 
     // 2. Export exists on source
-    let export = source
-        .exports
-        .iter()
-        .find(|e| e.name == import.export_name)
+    let export = find_export(source, &import.export_name)
         .ok_or_else(|| GraphError::DanglingImportExport {
             importer: importer_enc.id.clone(),
             from: import.from.clone(),
@@ -185,7 +429,10 @@ fn check_import_partition(
         ExportTarget::Public | ExportTarget::AnyEnclave => true,
         ExportTarget::Vpn => true, // VPN access is topology-level, not name-checked here
         ExportTarget::Enclave(allowed_id) => allowed_id == &importer_enc.id,
-        ExportTarget::Partition(_) => false, // partition-level, different kind of check
+        // Only the named partition of the exporting enclave itself
+        ExportTarget::Partition(allowed_id) => {
+            import.from == importer_enc.id && &partition_id == allowed_id
+        }
     };
     if !permitted {
         return Err(GraphError::AccessDenied {
@@ -195,6 +442,18 @@ fn check_import_partition(
         });
     }
 
+    // 4. Declared import type matches the export
+    if let Some(import_type) = &import.import_type {
+        if import_type != &export.export_type {
+            return Err(GraphError::TypeMismatch {
+                importer: importer_enc.id.clone(),
+                export_name: import.export_name.clone(),
+                import_type: import_type.to_string(),
+                export_type: export.export_type.to_string(),
+            });
+        }
+    }
+
     let partition_id_opt = if partition_id.as_str().is_empty() {
         None
     } else {
@@ -205,7 +464,9 @@ fn check_import_partition(
         importer_enclave: importer_enc.id.clone(),
         importer_partition: partition_id_opt,
         exporter_enclave: import.from.clone(),
+        exporter_partition: export.target_partition.clone(),
         export_name: import.export_name.clone(),
+        alias: import.alias.clone(),
     })
 }
 
@@ -213,19 +474,16 @@ fn check_import_partition(
 mod tests {
     use super::*;
     use nclav_domain::*;
+    use std::path::Path;
 
     fn make_enclave(id: &str, exports: Vec<Export>, partitions: Vec<Partition>) -> Enclave {
         Enclave {
             id: EnclaveId::new(id),
             name: id.to_string(),
-            cloud: None,
             region: "local".to_string(),
-            identity: None,
-            network: None,
-            dns: None,
-            imports: vec![],
             exports,
             partitions,
+            ..Default::default()
         }
     }
 
@@ -259,6 +517,15 @@ mod tests {
             from: EnclaveId::new(from),
             export_name: export_name.to_string(),
             alias: alias.to_string(),
+            import_type: None,
+        }
+    }
+
+    /// The single error inside a failed validation's `GraphError::Multiple`.
+    fn only_error(result: Result<ResolvedGraph, GraphError>) -> GraphError {
+        match result {
+            Err(GraphError::Multiple(mut errs)) if errs.len() == 1 => errs.remove(0).error,
+            other => panic!("expected exactly one error, got {:?}", other.err()),
         }
     }
 
@@ -280,12 +547,8 @@ mod tests {
     fn dangling_import_detected() {
         let mut enc = make_enclave("b", vec![], vec![]);
         enc.imports.push(make_import("nonexistent", "x", "x"));
-        let result = validate(&[enc]);
-        assert!(
-            matches!(result, Err(GraphError::DanglingImportEnclave { .. })),
-            "expected DanglingImportEnclave, got {:?}",
-            result.err()
-        );
+        let err = only_error(validate(&[enc]));
+        assert!(matches!(err, GraphError::DanglingImportEnclave { .. }), "expected DanglingImportEnclave, got {:?}", err);
     }
 
     #[test]
@@ -293,12 +556,8 @@ mod tests {
         let enc_a = make_enclave("a", vec![], vec![]);
         let mut enc_b = make_enclave("b", vec![], vec![]);
         enc_b.imports.push(make_import("a", "no-such-export", "x"));
-        let result = validate(&[enc_a, enc_b]);
-        assert!(
-            matches!(result, Err(GraphError::DanglingImportExport { .. })),
-            "expected DanglingImportExport, got {:?}",
-            result.err()
-        );
+        let err = only_error(validate(&[enc_a, enc_b]));
+        assert!(matches!(err, GraphError::DanglingImportExport { .. }), "expected DanglingImportExport, got {:?}", err);
     }
 
     #[test]
@@ -310,12 +569,8 @@ mod tests {
         );
         let mut enc_b = make_enclave("b", vec![], vec![]);
         enc_b.imports.push(make_import("a", "svc", "up"));
-        let result = validate(&[enc_a, enc_b]);
-        assert!(
-            matches!(result, Err(GraphError::AccessDenied { .. })),
-            "expected AccessDenied, got {:?}",
-            result.err()
-        );
+        let err = only_error(validate(&[enc_a, enc_b]));
+        assert!(matches!(err, GraphError::AccessDenied { .. }), "expected AccessDenied, got {:?}", err);
     }
 
     #[test]
@@ -326,8 +581,8 @@ mod tests {
             vec![],
             vec![make_partition("svc", Some(ProducesType::Http), vec!["hostname"])], // missing port
         );
-        let result = validate(&[enc]);
-        assert!(matches!(result, Err(GraphError::MissingRequiredOutput { .. })));
+        let err = only_error(validate(&[enc]));
+        assert!(matches!(err, GraphError::MissingRequiredOutput { .. }));
     }
 
     #[test]
@@ -344,8 +599,12 @@ mod tests {
         );
         enc_a.imports.push(make_import("b", "b-svc", "b_up"));
         enc_b.imports.push(make_import("a", "a-svc", "a_up"));
-        let result = validate(&[enc_a, enc_b]);
-        assert!(matches!(result, Err(GraphError::CycleDetected)));
+        let err = only_error(validate(&[enc_a, enc_b]));
+        assert!(matches!(err, GraphError::CycleDetected { .. }), "got {:?}", err);
+        assert_eq!(
+            err.to_string(),
+            "cycle detected in enclave dependency graph: a -[a-svc]-> b -[b-svc]-> a"
+        );
     }
 
     #[test]
@@ -364,4 +623,179 @@ mod tests {
         let pos_b = graph.topo_order.iter().position(|n| n.0 == "b").unwrap();
         assert!(pos_a < pos_b, "a must come before b in topo order");
     }
+
+    #[test]
+    fn incompatible_auth_detected() {
+        let mut export = make_export("db", "db", ExportType::Tcp, ExportTarget::AnyEnclave);
+        export.auth = AuthType::Token;
+        let enc = make_enclave(
+            "a",
+            vec![export],
+            vec![make_partition("db", Some(ProducesType::Tcp), vec!["hostname", "port"])],
+        );
+        let err = only_error(validate(&[enc]));
+        assert!(
+            matches!(&err, GraphError::IncompatibleAuth { export_name, auth, .. } if export_name == "db" && auth == "token"),
+            "expected IncompatibleAuth, got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn import_type_mismatch_detected() {
+        let enc_a = make_enclave(
+            "a",
+            vec![make_export("a-http", "svc", ExportType::Http, ExportTarget::AnyEnclave)],
+            vec![make_partition("svc", Some(ProducesType::Http), vec!["hostname", "port"])],
+        );
+        let mut enc_b = make_enclave("b", vec![], vec![]);
+        let mut import = make_import("a", "a-http", "upstream");
+        import.import_type = Some(ExportType::Tcp);
+        enc_b.imports.push(import);
+
+        let err = only_error(validate(&[enc_a, enc_b]));
+        assert!(
+            matches!(&err, GraphError::TypeMismatch { import_type, export_type, .. } if import_type == "tcp" && export_type == "http"),
+            "expected TypeMismatch, got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn duplicates_detected() {
+        let enc_a = make_enclave(
+            "a",
+            vec![
+                make_export("api", "svc", ExportType::Http, ExportTarget::AnyEnclave),
+                make_export("api", "svc", ExportType::Http, ExportTarget::AnyEnclave),
+            ],
+            vec![
+                make_partition("svc", Some(ProducesType::Http), vec!["hostname", "port"]),
+                make_partition("svc", Some(ProducesType::Http), vec!["hostname", "port"]),
+            ],
+        );
+        let mut enc_b = make_enclave("b", vec![], vec![make_partition("worker", None, vec![])]);
+        enc_b.partitions[0].imports.push(make_import("a", "api", "up"));
+        enc_b.partitions[0].imports.push(make_import("a", "api", "up"));
+        let enc_b2 = make_enclave("b", vec![], vec![]);
+
+        let err = validate(&[enc_a, enc_b, enc_b2]).unwrap_err();
+        let errs = err.errors();
+        assert_eq!(errs.len(), 4, "{:?}", errs);
+        assert!(errs.iter().any(|e| matches!(e, GraphError::DuplicateEnclaveId { id } if id.as_str() == "b")));
+        assert!(errs.iter().any(|e| matches!(e, GraphError::DuplicatePartitionId { partition, .. } if partition.as_str() == "svc")));
+        assert!(errs.iter().any(|e| matches!(e, GraphError::DuplicateExportName { export_name, .. } if export_name == "api")));
+        assert!(errs.iter().any(|e| matches!(
+            e,
+            GraphError::DuplicateImportAlias { partition: Some(p), alias, .. } if p.as_str() == "worker" && alias == "up"
+        )));
+    }
+
+    #[test]
+    fn all_errors_collected_with_locations() {
+        let mut enc_a = make_enclave(
+            "a",
+            vec![],
+            vec![make_partition("svc", Some(ProducesType::Http), vec!["hostname"])],
+        );
+        enc_a.source = Some(PathBuf::from("enclaves/a/config.yml"));
+        enc_a.partitions[0].backend = PartitionBackend::Terraform(TerraformConfig {
+            tool: None,
+            source: None,
+            dir: PathBuf::from("enclaves/a/svc"),
+        });
+        enc_a.imports.push(make_import("nonexistent", "x", "x"));
+
+        let err = validate(&[enc_a]).unwrap_err();
+        let GraphError::Multiple(errs) = &err else { panic!("expected Multiple, got {:?}", err) };
+        assert_eq!(errs.len(), 2);
+
+        let missing = errs.iter().find(|e| matches!(e.error, GraphError::MissingRequiredOutput { .. })).unwrap();
+        assert_eq!(missing.location.as_deref(), Some(Path::new("enclaves/a/svc/config.yml")));
+        let dangling = errs.iter().find(|e| matches!(e.error, GraphError::DanglingImportEnclave { .. })).unwrap();
+        assert_eq!(dangling.location.as_deref(), Some(Path::new("enclaves/a/config.yml")));
+
+        assert!(err.to_string().contains("enclaves/a/svc/config.yml: missing required output"));
+    }
+
+    /// Enclave `a` with partition `db` exporting `pg` to partition `api`, and
+    /// partitions `api` and `worker` listed before `db`.
+    fn partition_scoped_enclave() -> Enclave {
+        let mut db = make_partition("db", Some(ProducesType::Tcp), vec!["hostname", "port"]);
+        db.exports.push(make_export(
+            "pg",
+            "db",
+            ExportType::Tcp,
+            ExportTarget::Partition(PartitionId::new("api")),
+        ));
+        let api = make_partition("api", None, vec![]);
+        let worker = make_partition("worker", None, vec![]);
+        make_enclave("a", vec![], vec![api, worker, db])
+    }
+
+    #[test]
+    fn partition_scoped_import_permitted_and_ordered() {
+        let mut enc = partition_scoped_enclave();
+        enc.partitions[0].imports.push(make_import("a", "pg", "database"));
+
+        let graph = validate(std::slice::from_ref(&enc)).unwrap();
+        let order: Vec<&str> = graph.partition_order[&EnclaveId::new("a")]
+            .iter()
+            .map(|p| p.as_str())
+            .collect();
+        assert_eq!(order, vec!["worker", "db", "api"]);
+        assert_eq!(partition_order(&enc).unwrap(), graph.partition_order[&EnclaveId::new("a")]);
+
+        let w = &graph.cross_enclave_wiring[0];
+        assert_eq!(w.importer_partition.as_ref().map(|p| p.as_str()), Some("api"));
+        assert_eq!(w.exporter_partition.as_str(), "db");
+    }
+
+    #[test]
+    fn partition_scoped_import_from_other_partition_denied() {
+        let mut enc = partition_scoped_enclave();
+        enc.partitions[1].imports.push(make_import("a", "pg", "database"));
+
+        let err = only_error(validate(&[enc]));
+        assert!(matches!(err, GraphError::AccessDenied { .. }), "got {:?}", err);
+    }
+
+    #[test]
+    fn partition_scoped_import_at_enclave_level_denied() {
+        let mut enc = partition_scoped_enclave();
+        enc.imports.push(make_import("a", "pg", "database"));
+
+        let err = only_error(validate(&[enc]));
+        assert!(matches!(err, GraphError::AccessDenied { .. }), "got {:?}", err);
+    }
+
+    #[test]
+    fn partition_scoped_import_from_other_enclave_denied() {
+        let enc_a = partition_scoped_enclave();
+        let mut enc_b = make_enclave("b", vec![], vec![make_partition("api", None, vec![])]);
+        enc_b.partitions[0].imports.push(make_import("a", "pg", "database"));
+
+        let err = only_error(validate(&[enc_a, enc_b]));
+        assert!(matches!(err, GraphError::AccessDenied { .. }), "got {:?}", err);
+    }
+
+    #[test]
+    fn intra_enclave_partition_cycle_detected() {
+        let mut enc = partition_scoped_enclave();
+        enc.partitions[0].exports.push(make_export(
+            "api-http",
+            "api",
+            ExportType::Http,
+            ExportTarget::Partition(PartitionId::new("db")),
+        ));
+        enc.partitions[0].imports.push(make_import("a", "pg", "database"));
+        enc.partitions[2].imports.push(make_import("a", "api-http", "api"));
+
+        let err = only_error(validate(&[enc]));
+        assert!(matches!(err, GraphError::PartitionCycle { .. }), "got {:?}", err);
+        assert_eq!(
+            err.to_string(),
+            "cycle detected between partitions of enclave 'a': api -[api-http]-> db -[pg]-> api"
+        );
+    }
 }
@@ -0,0 +1,241 @@
+use nclav_domain::{CloudTarget, Enclave, EnclaveId, Export, ExportTarget};
+use serde::{Deserialize, Serialize};
+
+use crate::policy::{Check, DenyExport, Policy, Rule, Selector, Severity};
+
+/// One rule failing on one enclave.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    pub rule: String,
+    pub severity: Severity,
+    pub enclave: EnclaveId,
+    pub message: String,
+    /// The reason from the enclave's matching `policy_exceptions` entry, if any.
+    pub exception: Option<String>,
+}
+
+impl PolicyViolation {
+    /// True for error-severity violations the enclave has not been granted an
+    /// exception for. Any such violation blocks apply.
+    pub fn is_blocking(&self) -> bool {
+        self.severity == Severity::Error && self.exception.is_none()
+    }
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "policy '{}' on enclave '{}': {}", self.rule, self.enclave, self.message)?;
+        if let Some(reason) = &self.exception {
+            write!(f, " (excepted: {})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluate every rule against every enclave.
+///
+/// `default_cloud` resolves enclaves that omit `cloud:`. When it is `None`
+/// (e.g. offline validation), cloud-specific conditions skip those enclaves.
+pub fn evaluate(
+    policy: &Policy,
+    enclaves: &[Enclave],
+    default_cloud: Option<&CloudTarget>,
+) -> Vec<PolicyViolation> {
+    let mut violations = Vec::new();
+    for enc in enclaves {
+        let cloud = enc.cloud.as_ref().or(default_cloud);
+        for rule in &policy.rules {
+            if !selects(rule.selector.as_ref(), enc, cloud) {
+                continue;
+            }
+            let exception = enc
+                .policy_exceptions
+                .iter()
+                .find(|e| e.rule == rule.id)
+                .map(|e| e.reason.clone());
+            for message in check(rule, enc, cloud) {
+                violations.push(PolicyViolation {
+                    rule: rule.id.clone(),
+                    severity: rule.severity,
+                    enclave: enc.id.clone(),
+                    message,
+                    exception: exception.clone(),
+                });
+            }
+        }
+    }
+    violations
+}
+
+fn selects(selector: Option<&Selector>, enc: &Enclave, cloud: Option<&CloudTarget>) -> bool {
+    let Some(selector) = selector else { return true };
+    let labels_match = selector
+        .labels
+        .iter()
+        .all(|(k, v)| enc.labels.get(k) == Some(v));
+    let cloud_matches = selector.cloud.as_ref().is_none_or(|c| cloud == Some(c));
+    labels_match && cloud_matches
+}
+
+/// Returns one message per way `enc` breaks `rule`.
+fn check(rule: &Rule, enc: &Enclave, cloud: Option<&CloudTarget>) -> Vec<String> {
+    match &rule.check {
+        Check::AllowedRegions(allowed) => {
+            let Some(regions) = cloud.and_then(|c| allowed.get(c)) else {
+                return vec![];
+            };
+            if regions.contains(&enc.region) {
+                vec![]
+            } else {
+                vec![format!(
+                    "region '{}' is not approved for {}; allowed: {}",
+                    enc.region,
+                    cloud.expect("regions found for a resolved cloud"),
+                    regions.join(", ")
+                )]
+            }
+        }
+        Check::DenyExport(deny) => {
+            let partition_exports = enc.partitions.iter().flat_map(|p| p.exports.iter());
+            enc.exports
+                .iter()
+                .chain(partition_exports)
+                .filter(|e| export_denied(deny, e))
+                .map(|e| format!("export '{}' is not allowed ({})", e.name, describe(deny)))
+                .collect()
+        }
+        Check::MaxPartitions(max) => {
+            if enc.partitions.len() > *max {
+                vec![format!(
+                    "{} partitions exceeds the limit of {}",
+                    enc.partitions.len(),
+                    max
+                )]
+            } else {
+                vec![]
+            }
+        }
+    }
+}
+
+fn export_denied(deny: &DenyExport, export: &Export) -> bool {
+    deny.export_type.as_ref().is_none_or(|t| *t == export.export_type)
+        && deny.to.as_ref().is_none_or(|t| *t == export.to)
+        && deny.auth.as_ref().is_none_or(|a| *a == export.auth)
+}
+
+fn describe(deny: &DenyExport) -> String {
+    let mut parts = Vec::new();
+    if let Some(t) = &deny.export_type {
+        parts.push(format!("type {}", t));
+    }
+    if let Some(to) = &deny.to {
+        let target = match to {
+            ExportTarget::Public => "public".to_string(),
+            ExportTarget::AnyEnclave => "any_enclave".to_string(),
+            ExportTarget::Vpn => "vpn".to_string(),
+            ExportTarget::Enclave(id) => format!("enclave {}", id),
+            ExportTarget::Partition(id) => format!("partition {}", id),
+        };
+        parts.push(format!("to {}", target));
+    }
+    if let Some(a) = &deny.auth {
+        parts.push(format!("auth {}", a));
+    }
+    if parts.is_empty() {
+        "all exports denied".into()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nclav_domain::{AuthType, ExportType, PartitionId, PolicyException};
+
+    fn policy(yaml: &str) -> Policy {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn enclave(id: &str, region: &str) -> Enclave {
+        Enclave {
+            id: EnclaveId::new(id),
+            name: id.into(),
+            cloud: Some(CloudTarget::Gcp),
+            region: region.into(),
+            ..Default::default()
+        }
+    }
+
+    fn export(name: &str, export_type: ExportType, to: ExportTarget, auth: AuthType) -> Export {
+        Export {
+            name: name.into(),
+            target_partition: PartitionId::new("api"),
+            export_type,
+            to,
+            auth,
+            hostname: None,
+            port: None,
+        }
+    }
+
+    #[test]
+    fn public_export_denied_only_in_labelled_enclaves() {
+        let p = policy(
+            "rules:\n  - id: no-public-prod\n    match: { labels: { env: prod } }\n    \
+             deny_export: { to: public }\n",
+        );
+        let mut prod = enclave("prod", "us-central1");
+        prod.labels.insert("env".into(), "prod".into());
+        prod.exports.push(export("web", ExportType::Http, ExportTarget::Public, AuthType::Token));
+        let mut dev = prod.clone();
+        dev.id = EnclaveId::new("dev");
+        dev.labels.insert("env".into(), "dev".into());
+
+        let v = evaluate(&p, &[prod, dev], None);
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].enclave.as_str(), "prod");
+        assert!(v[0].is_blocking());
+    }
+
+    #[test]
+    fn region_checked_against_resolved_cloud() {
+        let p = policy("rules:\n  - id: regions\n    allowed_regions:\n      gcp: [europe-west1]\n");
+        let mut inherits = enclave("a", "us-east1");
+        inherits.cloud = None;
+
+        assert_eq!(evaluate(&p, &[enclave("b", "europe-west1")], None), vec![]);
+        assert_eq!(evaluate(&p, std::slice::from_ref(&inherits), None), vec![]);
+        let v = evaluate(&p, &[inherits], Some(&CloudTarget::Gcp));
+        assert_eq!(v.len(), 1);
+        assert!(v[0].message.contains("us-east1"), "{}", v[0].message);
+    }
+
+    #[test]
+    fn deny_export_requires_every_field_to_match() {
+        let p = policy("rules:\n  - id: http-auth\n    deny_export: { type: http, auth: none }\n");
+        let mut enc = enclave("a", "us-central1");
+        enc.exports.push(export("open", ExportType::Http, ExportTarget::AnyEnclave, AuthType::None));
+        enc.exports.push(export("db", ExportType::Tcp, ExportTarget::AnyEnclave, AuthType::None));
+
+        let v = evaluate(&p, &[enc], None);
+        assert_eq!(v.len(), 1);
+        assert!(v[0].message.contains("'open'"), "{}", v[0].message);
+    }
+
+    #[test]
+    fn exception_downgrades_violation() {
+        let p = policy("rules:\n  - id: regions\n    allowed_regions: { gcp: [eu] }\n");
+        let mut enc = enclave("a", "us-central1");
+        enc.policy_exceptions.push(PolicyException {
+            rule: "regions".into(),
+            reason: "legacy workload".into(),
+        });
+
+        let v = evaluate(&p, &[enc], None);
+        assert_eq!(v.len(), 1);
+        assert!(!v[0].is_blocking());
+        assert!(v[0].to_string().contains("excepted: legacy workload"));
+    }
+}
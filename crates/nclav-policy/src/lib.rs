@@ -0,0 +1,7 @@
+mod error;
+mod evaluate;
+mod policy;
+
+pub use error::PolicyError;
+pub use evaluate::{evaluate, PolicyViolation};
+pub use policy::{load_policy, Check, DenyExport, Policy, Rule, Selector, Severity};
@@ -0,0 +1,144 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use nclav_domain::{AuthType, CloudTarget, ExportTarget, ExportType};
+use serde::{Deserialize, Serialize};
+
+use crate::error::PolicyError;
+
+/// An organization policy file: a list of guardrail rules evaluated against
+/// every enclave after graph validation.
+///
+/// ```yaml
+/// rules:
+///   - id: no-public-prod
+///     match: { labels: { env: prod } }
+///     deny_export: { to: public }
+///   - id: approved-regions
+///     allowed_regions:
+///       gcp: [us-central1, europe-west1]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// Stable identifier; enclaves name it in `policy_exceptions`.
+    pub id: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Restricts the rule to matching enclaves. Absent means every enclave.
+    #[serde(rename = "match", default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<Selector>,
+    #[serde(flatten)]
+    pub check: Check,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Blocks apply unless the enclave declares an exception.
+    #[default]
+    Error,
+    /// Reported only.
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Which enclaves a rule applies to. All given conditions must hold.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Selector {
+    /// Every listed label must be present on the enclave with the same value.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// The enclave's cloud, after falling back to the server default.
+    pub cloud: Option<CloudTarget>,
+}
+
+/// The condition a rule enforces. Exactly one per rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// Approved regions per cloud. Clouds not listed are unrestricted.
+    AllowedRegions(HashMap<CloudTarget, Vec<String>>),
+    /// Forbids enclave or partition exports matching every given field.
+    DenyExport(DenyExport),
+    /// Upper bound on the number of partitions in one enclave.
+    MaxPartitions(usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DenyExport {
+    #[serde(rename = "type")]
+    pub export_type: Option<ExportType>,
+    pub to: Option<ExportTarget>,
+    pub auth: Option<AuthType>,
+}
+
+/// Read and parse a policy file, rejecting duplicate rule ids.
+pub fn load_policy(path: &Path) -> Result<Policy, PolicyError> {
+    let content = std::fs::read_to_string(path).map_err(|e| PolicyError::Io {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    let policy: Policy = serde_yaml::from_str(&content).map_err(|e| PolicyError::YamlParse {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    let mut seen = HashSet::new();
+    for rule in &policy.rules {
+        if !seen.insert(rule.id.as_str()) {
+            return Err(PolicyError::Invalid {
+                path: path.display().to_string(),
+                message: format!("duplicate rule id '{}'", rule.id),
+            });
+        }
+    }
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_policy(yaml: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), yaml).unwrap();
+        file
+    }
+
+    #[test]
+    fn load_policy_rejects_duplicate_rule_ids() {
+        let file = write_policy(
+            "rules:\n  - id: cap\n    max_partitions: 3\n  - id: cap\n    max_partitions: 5\n",
+        );
+        match load_policy(file.path()) {
+            Err(PolicyError::Invalid { message, .. }) => {
+                assert_eq!(message, "duplicate rule id 'cap'");
+            }
+            other => panic!("expected a duplicate id error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn load_policy_accepts_distinct_rule_ids() {
+        let file = write_policy(
+            "rules:\n  - id: cap\n    max_partitions: 3\n  - id: no-public\n    deny_export: { to: public }\n",
+        );
+        let policy = load_policy(file.path()).unwrap();
+        assert_eq!(policy.rules.len(), 2);
+    }
+}
@@ -1,7 +1,9 @@
 pub mod error;
 pub mod reconcile;
 pub mod report;
+pub mod teardown;
 
 pub use error::ReconcileError;
 pub use reconcile::reconcile;
-pub use report::{Change, ReconcileReport, ReconcileRequest};
+pub use report::{Change, ChangeImpact, ReconcileReport, ReconcileRequest};
+pub use teardown::{teardown_enclave, teardown_partition, TeardownOutcome};
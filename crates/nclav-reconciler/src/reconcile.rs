@@ -1,28 +1,51 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
 use chrono::Utc;
-use nclav_domain::{Enclave, EnclaveId};
+use nclav_domain::{Enclave, EnclaveId, Export, ExportTarget, Import, Partition};
 use nclav_store::{
-    AuditEvent, EnclaveState, PartitionState, ProvisioningStatus, StateStore,
+    AuditEvent, EnclaveState, EventContext, PartitionState, ProvisioningStatus, StateStore,
     compute_desired_hash,
 };
-use nclav_driver::{DriverRegistry, TerraformBackend};
-use nclav_graph::validate;
+use nclav_telemetry::metrics;
+use nclav_driver::{check_tf_contracts, Driver, DriverError, DriverRegistry, TerraformBackend};
+use nclav_graph::{impact, validate, ImpactTarget, ResolvedGraph};
+use serde_json::Value;
 use uuid::Uuid;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, info_span, warn, Instrument};
 
 use crate::error::ReconcileError;
-use crate::report::{Change, ReconcileReport, ReconcileRequest};
+use crate::report::{Change, ChangeImpact, ReconcileReport, ReconcileRequest};
+use crate::teardown::{teardown_enclave, teardown_order, teardown_partition};
 
 pub async fn reconcile(
     req: ReconcileRequest,
     store: Arc<dyn StateStore>,
     registry: Arc<DriverRegistry>,
+) -> Result<ReconcileReport, ReconcileError> {
+    let dry_run = req.dry_run.to_string();
+    let started = Instant::now();
+    let span = info_span!("reconcile", dry_run = req.dry_run);
+    let result = run_reconcile(req, store, registry).instrument(span).await;
+    let outcome = match &result {
+        Ok(report) if report.errors.is_empty() => "success",
+        Ok(_) => "partial",
+        Err(_) => "error",
+    };
+    metrics().reconcile_duration.observe(&[outcome, &dry_run], started.elapsed());
+    result
+}
+
+async fn run_reconcile(
+    req: ReconcileRequest,
+    store: Arc<dyn StateStore>,
+    registry: Arc<DriverRegistry>,
 ) -> Result<ReconcileReport, ReconcileError> {
     let tf_backend = Arc::new(TerraformBackend {
         api_base: req.api_base.clone(),
         auth_token: req.auth_token.clone(),
+        tls: req.backend_tls.clone(),
         store: store.clone(),
         test_mode: req.test_mode,
         workspace_root: None,
@@ -31,9 +54,22 @@ pub async fn reconcile(
 
     // 1. Load YAML
     info!("Loading enclaves from {:?}", req.enclaves_dir);
-    let desired_enclaves = nclav_config::load_enclaves(&req.enclaves_dir)?;
+    let mut desired_enclaves = nclav_config::load_enclaves(&req.enclaves_dir)?;
     debug!("Loaded {} enclaves", desired_enclaves.len());
 
+    // Fill in omitted VPC ranges, keeping each enclave's range from its last apply
+    if let Some(allocator) = &req.vpc_allocator {
+        let previous: HashMap<EnclaveId, String> = store
+            .list_enclaves()
+            .await?
+            .into_iter()
+            .filter_map(|s| Some((s.desired.id, s.desired.network?.vpc_cidr?)))
+            .collect();
+        for (id, cidr) in allocator.allocate(&mut desired_enclaves, &previous)? {
+            info!(enclave_id = %id, %cidr, "allocated VPC range");
+        }
+    }
+
     // 2. Validate graph — abort entire reconcile on structural errors
     info!("Validating enclave graph");
     let resolved = validate(&desired_enclaves)?;
@@ -42,6 +78,25 @@ pub async fn reconcile(
         resolved.topo_order.iter().map(|n| &n.0).collect::<Vec<_>>()
     );
 
+    // Organization policy — unexcepted error-level violations block apply
+    if let Some(policy) = &req.policy {
+        let violations =
+            nclav_policy::evaluate(policy, &desired_enclaves, Some(&registry.default_cloud));
+        let mut blocked = false;
+        for v in violations {
+            if v.is_blocking() {
+                blocked = true;
+                report.errors.push(v.to_string());
+            } else {
+                report.warnings.push(v.to_string());
+            }
+        }
+        if blocked && !req.dry_run {
+            warn!("Policy violations block apply; nothing was provisioned");
+            return Ok(report);
+        }
+    }
+
     // 3. Load actual state
     let actual_states: HashMap<EnclaveId, EnclaveState> = store
         .list_enclaves()
@@ -84,7 +139,9 @@ pub async fn reconcile(
     for enc in &ordered_desired {
         let existing = actual_states.get(&enc.id);
         let enc_hash = compute_desired_hash(enc);
+        // A tombstone still in the YAML is re-provisioned whatever its hash.
         let hash_unchanged = existing
+            .filter(|s| !s.meta.is_tombstone())
             .and_then(|s| s.meta.desired_hash.as_deref())
             .map_or(false, |h| h == enc_hash);
 
@@ -98,6 +155,7 @@ pub async fn reconcile(
             let part_hash = compute_desired_hash(part);
             let part_existing = existing.and_then(|s| s.partitions.get(&part.id));
             let part_hash_unchanged = part_existing
+                .filter(|ps| !ps.meta.is_tombstone())
                 .and_then(|ps| ps.meta.desired_hash.as_deref())
                 .map_or(false, |h| h == part_hash);
 
@@ -114,7 +172,18 @@ pub async fn reconcile(
             }
         }
 
-        for export in &enc.exports {
+        // Partitions no longer in the YAML are torn down, tombstones again
+        for part_id in existing.map(|s| s.partitions.keys()).into_iter().flatten() {
+            if !enc.partitions.iter().any(|p| &p.id == part_id) {
+                report.changes.push(Change::PartitionDeleted {
+                    enclave_id: enc.id.clone(),
+                    partition_id: part_id.clone(),
+                });
+            }
+        }
+
+        let partition_exports = enc.partitions.iter().flat_map(|p| p.exports.iter());
+        for export in enc.exports.iter().chain(partition_exports) {
             let already_wired = existing
                 .and_then(|s| s.export_handles.get(&export.name))
                 .is_some();
@@ -152,303 +221,67 @@ pub async fn reconcile(
 
     // 5. Dry-run gate
     if req.dry_run {
+        // Surface YAML ↔ .tf mismatches now rather than when terraform apply fails.
+        report.errors.extend(
+            check_tf_contracts(&desired_enclaves).iter().map(|v| v.to_string()),
+        );
+        report.impact = change_impact(&report.changes, &desired_enclaves, &resolved);
         info!("Dry run — skipping provisioning");
         return Ok(report);
     }
 
     let run_id = Uuid::new_v4();
+    let context = req.context.with_run(run_id);
     store
         .append_event(&AuditEvent::ReconcileStarted {
             id: run_id,
             at: Utc::now(),
+            context: context.clone(),
             dry_run: false,
         })
         .await?;
 
-    // 6. Teardowns for removed enclaves
+    // 6. Teardowns for removed enclaves. A failed teardown keeps the enclave as
+    //    a tombstone, so it is still "removed" and retried by the next reconcile.
     for id in actual_ids.difference(&desired_ids) {
         if let Some(existing) = actual_states.get(id) {
             // Use resolved_cloud from persisted state so teardown works after YAML removal
             let cloud = existing.resolved_cloud.clone().unwrap_or_else(|| registry.default_cloud.clone());
-            if let Ok(driver) = registry.for_cloud(cloud) {
-                if let Some(handle) = &existing.enclave_handle {
-                    // Teardown IaC partitions before tearing down the enclave itself
-                    let auth_env = driver.auth_env(&existing.desired, handle);
-                    for (part_id, part_state) in &existing.partitions {
-                        if let Err(e) = tf_backend
-                            .teardown(&existing.desired, &part_state.desired, &auth_env, Some(run_id))
-                            .await
-                        {
-                            warn!(
-                                enclave_id = %id,
-                                partition_id = %part_id,
-                                error = %e,
-                                "IaC partition teardown failed during enclave removal"
-                            );
-                            report.errors.push(format!(
-                                "teardown {}/{}: {}", id, part_id, e
-                            ));
-                        }
-                        // Clean up the partition SA after terraform destroy
-                        if let Some(handle) = &part_state.partition_handle {
-                            if let Err(e) = driver
-                                .teardown_partition(&existing.desired, &part_state.desired, handle)
-                                .await
-                            {
-                                warn!(
-                                    enclave_id = %id,
-                                    partition_id = %part_id,
-                                    error = %e,
-                                    "Partition SA cleanup failed during enclave removal"
-                                );
-                            }
-                        }
-                    }
-
-                    if req.resources_only {
-                        info!(enclave_id = %id, "resources_only: skipping project deletion");
-                    } else {
-                        driver.teardown_enclave(&existing.desired, handle).await?;
-                    }
+            let driver = match registry.for_cloud(cloud) {
+                Ok(d) => d,
+                Err(e) => {
+                    warn!(enclave_id = %id, error = %e, "no driver for removed enclave; keeping it");
+                    report.errors.push(format!("enclave teardown {}: {}", id, e));
+                    continue;
                 }
-            }
-            store.delete_enclave(id).await?;
+            };
+            let outcome = teardown_enclave(
+                store.as_ref(),
+                driver.as_ref(),
+                &tf_backend,
+                existing.clone(),
+                req.resources_only,
+                &context,
+            )
+            .instrument(info_span!("enclave", enclave_id = %id, operation = "teardown"))
+            .await?;
+            report.errors.extend(outcome.errors);
         }
     }
 
     // 7. Provision / update in topo order
+    let run = ReconcileRun {
+        registry: &registry,
+        store: &store,
+        tf_backend: &tf_backend,
+        context: &context,
+        resolved: &resolved,
+        run_id,
+    };
     for enc in &ordered_desired {
-        // Resolve the driver for this enclave — per-enclave error, not global abort
-        let driver = match registry.for_enclave(enc) {
-            Ok(d) => d,
-            Err(e) => {
-                let msg = e.to_string();
-                warn!(enclave_id = %enc.id, error = %msg, "no driver for enclave cloud");
-                report.errors.push(format!("enclave {}: {}", enc.id, msg));
-                continue;
-            }
-        };
-
-        let existing = actual_states.get(&enc.id);
-        let enc_hash = compute_desired_hash(enc);
-        let _hash_unchanged = existing
-            .and_then(|s| s.meta.desired_hash.as_deref())
-            .map_or(false, |h| h == enc_hash);
-
-        // Initialise or clone state
-        let mut enc_state = existing
-            .cloned()
-            .unwrap_or_else(|| EnclaveState::new((*enc).clone()));
-        enc_state.desired = (*enc).clone();
-
-        // Stamp resolved cloud before the first upsert so teardown always knows which driver to use
-        enc_state.resolved_cloud = Some(registry.resolved_cloud(enc));
-
-        // Mark in-flight status before driver call
-        enc_state.meta.status = if existing.is_some() {
-            ProvisioningStatus::Updating
-        } else {
-            ProvisioningStatus::Provisioning
-        };
-        store.upsert_enclave(&enc_state).await?;
-
-        // Provision enclave
-        match driver
-            .provision_enclave(enc, existing.and_then(|s| s.enclave_handle.as_ref()))
-            .await
-        {
-            Ok(result) => {
-                let now = Utc::now();
-                enc_state.enclave_handle = Some(result.handle);
-                enc_state.meta.mark_active(now, enc_hash);
-            }
-            Err(e) => {
-                let msg = e.to_string();
-                warn!(enclave_id = %enc.id, error = %msg, "enclave provision failed");
-                enc_state.meta.mark_error(Utc::now(), msg.clone());
-                store.upsert_enclave(&enc_state).await?;
-                store
-                    .append_event(&AuditEvent::EnclaveError {
-                        id: Uuid::new_v4(),
-                        at: Utc::now(),
-                        enclave_id: enc.id.clone(),
-                        message: msg.clone(),
-                    })
-                    .await?;
-                report.errors.push(format!("enclave {}: {}", enc.id, msg));
-                continue; // skip partitions for this enclave
-            }
-        }
-
-        // Provision partitions
-        for part in &enc.partitions {
-            let part_hash = compute_desired_hash(part);
-            let part_existing = enc_state.partitions.get(&part.id).cloned();
-            let part_hash_unchanged = part_existing
-                .as_ref()
-                .and_then(|ps| ps.meta.desired_hash.as_deref())
-                .map_or(false, |h| h == part_hash);
-
-            if part_hash_unchanged {
-                debug!(partition_id = %part.id, "skipping unchanged partition");
-                continue;
-            }
-
-            // context_vars powers {{ nclav_* }} template substitution for all backends
-            let context_vars = enc_state
-                .enclave_handle
-                .as_ref()
-                .map(|h| driver.context_vars(enc, h))
-                .unwrap_or_default();
-            let resolved_inputs = resolve_inputs(&part.inputs, &enc_state, &context_vars);
-
-            let mut part_state = part_existing
-                .unwrap_or_else(|| PartitionState::new(part.clone()));
-            part_state.desired = part.clone();
-            part_state.meta.status = if part_state.partition_handle.is_some() {
-                ProvisioningStatus::Updating
-            } else {
-                ProvisioningStatus::Provisioning
-            };
-            enc_state.partitions.insert(part.id.clone(), part_state.clone());
-            store.upsert_enclave(&enc_state).await?;
-
-            // 1. Create partition SA (returns a handle containing "partition_sa").
-            let sa_result = driver
-                .provision_partition(enc, part, &resolved_inputs, part_state.partition_handle.as_ref())
-                .await
-                .map_err(|e| e.to_string());
-
-            let provision_result = match sa_result {
-                Err(e) => Err(e),
-                Ok(sa_provision) => {
-                    // Persist the SA handle immediately so partition_sa survives
-                    // the next reconcile even if Terraform subsequently fails.
-                    {
-                        let ps = enc_state.partitions
-                            .entry(part.id.clone())
-                            .or_insert_with(|| PartitionState::new(part.clone()));
-                        ps.partition_handle = Some(sa_provision.handle.clone());
-                    }
-                    store.upsert_enclave(&enc_state).await.ok();
-
-                    // 2. Build auth_env, override GOOGLE_IMPERSONATE_SERVICE_ACCOUNT
-                    //    with the partition SA so Terraform runs under it.
-                    //    Only in SA-key mode (GOOGLE_APPLICATION_CREDENTIALS present);
-                    //    in ADC mode the operator's credentials run Terraform directly.
-                    let mut auth_env = enc_state
-                        .enclave_handle
-                        .as_ref()
-                        .map(|h| driver.auth_env(enc, h))
-                        .unwrap_or_default();
-                    if auth_env.contains_key("GOOGLE_APPLICATION_CREDENTIALS") {
-                        if let Some(sa) = sa_provision.handle["partition_sa"].as_str() {
-                            auth_env.insert(
-                                "GOOGLE_IMPERSONATE_SERVICE_ACCOUNT".into(),
-                                sa.to_string(),
-                            );
-                        }
-                    }
-
-                    // 3. Run Terraform under the partition SA identity.
-                    tf_backend
-                        .provision(enc, part, &resolved_inputs, &auth_env, Some(run_id))
-                        .await
-                        .map_err(|e| e.to_string())
-                        // Merge the SA handle fields into the Terraform handle for storage.
-                        .map(|mut tf_result| {
-                            if let Some(sa) = sa_provision.handle["partition_sa"].as_str() {
-                                tf_result.handle["partition_sa"] = serde_json::json!(sa);
-                            }
-                            tf_result
-                        })
-                }
-            };
-
-            match provision_result {
-                Ok(result) => {
-                    let now = Utc::now();
-                    let ps = enc_state.partitions.entry(part.id.clone()).or_insert_with(|| PartitionState::new(part.clone()));
-                    ps.partition_handle = Some(result.handle);
-                    ps.resolved_outputs = result.outputs;
-                    ps.meta.mark_active(now, part_hash);
-
-                    store
-                        .append_event(&AuditEvent::PartitionProvisioned {
-                            id: Uuid::new_v4(),
-                            at: Utc::now(),
-                            enclave_id: enc.id.clone(),
-                            partition_id: part.id.clone(),
-                        })
-                        .await?;
-                }
-                Err(msg) => {
-                    warn!(partition_id = %part.id, error = %msg, "partition provision failed");
-                    let ps = enc_state.partitions.entry(part.id.clone()).or_insert_with(|| PartitionState::new(part.clone()));
-                    ps.meta.mark_error(Utc::now(), msg.clone());
-
-                    store
-                        .append_event(&AuditEvent::PartitionError {
-                            id: Uuid::new_v4(),
-                            at: Utc::now(),
-                            enclave_id: enc.id.clone(),
-                            partition_id: part.id.clone(),
-                            message: msg.clone(),
-                        })
-                        .await?;
-                    report.errors.push(format!(
-                        "partition {}/{}: {}", enc.id, part.id, msg
-                    ));
-                    // Continue with remaining partitions
-                }
-            }
-        }
-
-        // Provision exports
-        for export in &enc.exports {
-            let part_outputs = enc_state
-                .partitions
-                .get(&export.target_partition)
-                .map(|ps| ps.resolved_outputs.clone())
-                .unwrap_or_default();
-
-            match driver
-                .provision_export(
-                    enc,
-                    export,
-                    &part_outputs,
-                    enc_state.export_handles.get(&export.name),
-                )
-                .await
-            {
-                Ok(result) => {
-                    enc_state.export_handles.insert(export.name.clone(), result.handle);
-                    store
-                        .append_event(&AuditEvent::ExportWired {
-                            id: Uuid::new_v4(),
-                            at: Utc::now(),
-                            enclave_id: enc.id.clone(),
-                            export_name: export.name.clone(),
-                        })
-                        .await?;
-                }
-                Err(e) => {
-                    let msg = e.to_string();
-                    warn!(export = %export.name, error = %msg, "export provision failed");
-                    report.errors.push(format!("export {}/{}: {}", enc.id, export.name, msg));
-                }
-            }
-        }
-
-        store
-            .append_event(&AuditEvent::EnclaveProvisioned {
-                id: Uuid::new_v4(),
-                at: Utc::now(),
-                enclave_id: enc.id.clone(),
-            })
-            .await?;
-
-        store.upsert_enclave(&enc_state).await?;
+        provision_enclave(&run, &mut report, enc, actual_states.get(&enc.id))
+        .instrument(info_span!("enclave", enclave_id = %enc.id, operation = "provision"))
+        .await?;
     }
 
     // 8. Wire cross-enclave imports (second pass, after all enclaves provisioned)
@@ -465,9 +298,13 @@ pub async fn reconcile(
         };
         let mut changed = false;
 
-        for import in enc.imports.iter().chain(
-            enc.partitions.iter().flat_map(|p| p.imports.iter())
-        ) {
+        // Imports from sibling partitions were wired in step 7.
+        let partition_imports = enc
+            .partitions
+            .iter()
+            .flat_map(|p| p.imports.iter())
+            .filter(|i| i.from != enc.id);
+        for import in enc.imports.iter().chain(partition_imports) {
             if enc_state.import_handles.contains_key(&import.alias) {
                 continue; // already wired
             }
@@ -489,6 +326,7 @@ pub async fn reconcile(
                                 .append_event(&AuditEvent::ImportWired {
                                     id: Uuid::new_v4(),
                                     at: Utc::now(),
+                                    context: context.clone(),
                                     importer_enclave: enc.id.clone(),
                                     export_name: import.export_name.clone(),
                                 })
@@ -517,6 +355,7 @@ pub async fn reconcile(
         .append_event(&AuditEvent::ReconcileCompleted {
             id: run_id,
             at: Utc::now(),
+            context: context.clone(),
             changes: report.changes.len(),
             dry_run: false,
         })
@@ -530,22 +369,626 @@ pub async fn reconcile(
     Ok(report)
 }
 
+/// What every provisioning step of one reconcile run shares.
+struct ReconcileRun<'a> {
+    registry: &'a DriverRegistry,
+    store: &'a Arc<dyn StateStore>,
+    tf_backend: &'a TerraformBackend,
+    context: &'a EventContext,
+    resolved: &'a ResolvedGraph,
+    run_id: Uuid,
+}
+
+/// Provision `enc`, then its partitions and exports, recording driver failures
+/// in `report`. Only store failures abort the reconcile.
+async fn provision_enclave(
+    run: &ReconcileRun<'_>,
+    report: &mut ReconcileReport,
+    enc: &Enclave,
+    existing: Option<&EnclaveState>,
+) -> Result<(), ReconcileError> {
+    let ReconcileRun { registry, store, tf_backend, context, resolved, .. } = *run;
+    // Resolve the driver for this enclave — per-enclave error, not global abort
+    let driver = match registry.for_enclave(enc) {
+        Ok(d) => d,
+        Err(e) => {
+            let msg = e.to_string();
+            warn!(enclave_id = %enc.id, error = %msg, "no driver for enclave cloud");
+            report.errors.push(format!("enclave {}: {}", enc.id, msg));
+            return Ok(());
+        }
+    };
+
+    let enc_hash = compute_desired_hash(enc);
+
+    // Initialise or clone state
+    let mut enc_state = existing
+        .cloned()
+        .unwrap_or_else(|| EnclaveState::new(enc.clone()));
+    enc_state.desired = enc.clone();
+
+    // Stamp resolved cloud before the first upsert so teardown always knows which driver to use
+    enc_state.resolved_cloud = Some(registry.resolved_cloud(enc));
+
+    // Mark in-flight status before driver call
+    enc_state.meta.status = if existing.is_some() {
+        ProvisioningStatus::Updating
+    } else {
+        ProvisioningStatus::Provisioning
+    };
+    store.upsert_enclave(&enc_state).await?;
+
+    // Provision enclave
+    match driver
+        .provision_enclave(enc, existing.and_then(|s| s.enclave_handle.as_ref()))
+        .await
+    {
+        Ok(result) => {
+            let now = Utc::now();
+            enc_state.enclave_handle = Some(result.handle);
+            enc_state.meta.mark_active(now, enc_hash);
+        }
+        Err(e) => {
+            let msg = e.to_string();
+            warn!(enclave_id = %enc.id, error = %msg, "enclave provision failed");
+            enc_state.meta.mark_error(Utc::now(), msg.clone());
+            store.upsert_enclave(&enc_state).await?;
+            store
+                .append_event(&AuditEvent::EnclaveError {
+                    id: Uuid::new_v4(),
+                    at: Utc::now(),
+                    context: context.clone(),
+                    enclave_id: enc.id.clone(),
+                    message: msg.clone(),
+                })
+                .await?;
+            report.errors.push(format!("enclave {}: {}", enc.id, msg));
+            return Ok(()); // skip partitions for this enclave
+        }
+    }
+
+    // Tear down partitions that are no longer declared, importers first by the
+    // wiring they were applied with. Failures stay as tombstones and are retried.
+    let undeclared = enc_state
+        .partitions
+        .keys()
+        .filter(|id| !enc.partitions.iter().any(|p| &p.id == *id))
+        .cloned();
+    let removed = teardown_order(existing.map_or(enc, |s| &s.desired), undeclared);
+    for part_id in &removed {
+        let outcome = teardown_partition(
+            store.as_ref(),
+            driver.as_ref(),
+            tf_backend,
+            &mut enc_state,
+            part_id,
+            context,
+        )
+        .instrument(info_span!(
+            "partition",
+            enclave_id = %enc.id,
+            partition_id = %part_id,
+            operation = "teardown",
+        ))
+        .await?;
+        report.errors.extend(outcome.errors);
+    }
+
+    remove_undeclared_access(driver.as_ref(), report, enc, &mut enc_state).await;
+
+    // Provision partitions, exporters before the partitions importing from them
+    let ordered_partitions: Vec<&Partition> = match resolved.partition_order.get(&enc.id) {
+        Some(order) => order
+            .iter()
+            .filter_map(|id| enc.partitions.iter().find(|p| &p.id == id))
+            .collect(),
+        None => enc.partitions.iter().collect(),
+    };
+    let mut exports_wired: HashSet<String> = HashSet::new();
+    for part in ordered_partitions {
+        provision_partition(run, driver.as_ref(), report, enc, &mut enc_state, part, &mut exports_wired)
+        .instrument(info_span!(
+            "partition",
+            enclave_id = %enc.id,
+            partition_id = %part.id,
+            operation = "provision",
+        ))
+        .await?;
+    }
+
+    // Provision exports not already provisioned while wiring sibling imports
+    let partition_exports = enc.partitions.iter().flat_map(|p| p.exports.iter());
+    for export in enc.exports.iter().chain(partition_exports) {
+        if exports_wired.contains(&export.name) {
+            continue;
+        }
+        provision_export(driver.as_ref(), store, context, report, enc, &mut enc_state, export).await?;
+    }
+
+    store
+        .append_event(&AuditEvent::EnclaveProvisioned {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: context.clone(),
+            enclave_id: enc.id.clone(),
+        })
+        .await?;
+
+    store.upsert_enclave(&enc_state).await?;
+    Ok(())
+}
+
+/// Provision `part` of `enc` — its sibling imports, identity and Terraform —
+/// unless it is unchanged. Driver and IaC failures are added to `report`.
+async fn provision_partition(
+    run: &ReconcileRun<'_>,
+    driver: &dyn Driver,
+    report: &mut ReconcileReport,
+    enc: &Enclave,
+    enc_state: &mut EnclaveState,
+    part: &Partition,
+    exports_wired: &mut HashSet<String>,
+) -> Result<(), ReconcileError> {
+    let ReconcileRun { store, tf_backend, context, run_id, .. } = *run;
+    // Wire imports from sibling partitions first so their outputs resolve below.
+    for import in part.imports.iter().filter(|i| i.from == enc.id) {
+        if enc_state.import_handles.contains_key(&import.alias) {
+            refresh_partition_access(driver, report, enc, enc_state, part, import).await;
+            continue; // already wired
+        }
+        wire_local_import(
+            driver,
+            store,
+            context,
+            report,
+            enc,
+            enc_state,
+            part,
+            import,
+            exports_wired,
+        )
+        .await?;
+    }
+
+    let part_hash = compute_desired_hash(part);
+    let part_existing = enc_state.partitions.get(&part.id).cloned();
+    let part_hash_unchanged = part_existing
+        .as_ref()
+        .filter(|ps| !ps.meta.is_tombstone())
+        .and_then(|ps| ps.meta.desired_hash.as_deref())
+        .map_or(false, |h| h == part_hash);
+
+    if part_hash_unchanged {
+        debug!(partition_id = %part.id, "skipping unchanged partition");
+        return Ok(());
+    }
+
+    // context_vars powers {{ nclav_* }} template substitution for all backends
+    let context_vars = enc_state
+        .enclave_handle
+        .as_ref()
+        .map(|h| driver.context_vars(enc, h))
+        .unwrap_or_default();
+    let resolved_inputs = resolve_inputs(&part.inputs, enc_state, &context_vars);
+
+    let mut part_state = part_existing
+        .unwrap_or_else(|| PartitionState::new(part.clone()));
+    part_state.desired = part.clone();
+    part_state.meta.status = if part_state.partition_handle.is_some() {
+        ProvisioningStatus::Updating
+    } else {
+        ProvisioningStatus::Provisioning
+    };
+    enc_state.partitions.insert(part.id.clone(), part_state.clone());
+    store.upsert_enclave(enc_state).await?;
+
+    // 1. Create partition SA (returns a handle containing "partition_sa").
+    let sa_result = driver
+        .provision_partition(enc, part, &resolved_inputs, part_state.partition_handle.as_ref())
+        .await
+        .map_err(|e| e.to_string());
+
+    let provision_result = match sa_result {
+        Err(e) => Err(e),
+        Ok(sa_provision) => {
+            // Persist the SA handle immediately so partition_sa survives
+            // the next reconcile even if Terraform subsequently fails.
+            {
+                let ps = enc_state.partitions
+                    .entry(part.id.clone())
+                    .or_insert_with(|| PartitionState::new(part.clone()));
+                ps.partition_handle = Some(sa_provision.handle.clone());
+            }
+            store.upsert_enclave(enc_state).await.ok();
+
+            // 2. Build auth_env, override GOOGLE_IMPERSONATE_SERVICE_ACCOUNT
+            //    with the partition SA so Terraform runs under it.
+            //    Only in SA-key mode (GOOGLE_APPLICATION_CREDENTIALS present);
+            //    in ADC mode the operator's credentials run Terraform directly.
+            let mut auth_env = enc_state
+                .enclave_handle
+                .as_ref()
+                .map(|h| driver.auth_env(enc, h))
+                .unwrap_or_default();
+            if auth_env.contains_key("GOOGLE_APPLICATION_CREDENTIALS") {
+                if let Some(sa) = sa_provision.handle["partition_sa"].as_str() {
+                    auth_env.insert(
+                        "GOOGLE_IMPERSONATE_SERVICE_ACCOUNT".into(),
+                        sa.to_string(),
+                    );
+                }
+            }
+
+            // 3. Run Terraform under the partition SA identity.
+            tf_backend
+                .provision(enc, part, &resolved_inputs, &auth_env, Some(run_id))
+                .await
+                .map_err(|e| e.to_string())
+                // Merge the SA handle fields into the Terraform handle for storage.
+                .map(|mut tf_result| {
+                    if let Some(sa) = sa_provision.handle["partition_sa"].as_str() {
+                        tf_result.handle["partition_sa"] = serde_json::json!(sa);
+                    }
+                    tf_result
+                })
+        }
+    };
+
+    match provision_result {
+        Ok(result) => {
+            let now = Utc::now();
+            let ps = enc_state.partitions.entry(part.id.clone()).or_insert_with(|| PartitionState::new(part.clone()));
+            ps.partition_handle = Some(result.handle);
+            ps.resolved_outputs = result.outputs;
+            ps.meta.mark_active(now, part_hash);
+
+            store
+                .append_event(&AuditEvent::PartitionProvisioned {
+                    id: Uuid::new_v4(),
+                    at: Utc::now(),
+                    context: context.clone(),
+                    enclave_id: enc.id.clone(),
+                    partition_id: part.id.clone(),
+                })
+                .await?;
+        }
+        Err(msg) => {
+            warn!(partition_id = %part.id, error = %msg, "partition provision failed");
+            let ps = enc_state.partitions.entry(part.id.clone()).or_insert_with(|| PartitionState::new(part.clone()));
+            ps.meta.mark_error(Utc::now(), msg.clone());
+
+            store
+                .append_event(&AuditEvent::PartitionError {
+                    id: Uuid::new_v4(),
+                    at: Utc::now(),
+                    context: context.clone(),
+                    enclave_id: enc.id.clone(),
+                    partition_id: part.id.clone(),
+                    message: msg.clone(),
+                })
+                .await?;
+            report.errors.push(format!(
+                "partition {}/{}: {}", enc.id, part.id, msg
+            ));
+            // Continue with remaining partitions
+        }
+    }
+    Ok(())
+}
+
+/// Provision `export` and record its handle in `enc_state`.
+///
+/// Driver failures are added to `report` and yield `Ok(false)`; only store
+/// failures abort the reconcile.
+async fn provision_export(
+    driver: &dyn Driver,
+    store: &Arc<dyn StateStore>,
+    context: &EventContext,
+    report: &mut ReconcileReport,
+    enc: &Enclave,
+    enc_state: &mut EnclaveState,
+    export: &Export,
+) -> Result<bool, ReconcileError> {
+    let part_outputs = enc_state
+        .partitions
+        .get(&export.target_partition)
+        .map(|ps| ps.resolved_outputs.clone())
+        .unwrap_or_default();
+
+    match driver
+        .provision_export(enc, export, &part_outputs, enc_state.export_handles.get(&export.name))
+        .await
+    {
+        Ok(result) => {
+            enc_state.export_handles.insert(export.name.clone(), result.handle);
+            store
+                .append_event(&AuditEvent::ExportWired {
+                    id: Uuid::new_v4(),
+                    at: Utc::now(),
+                    context: context.clone(),
+                    enclave_id: enc.id.clone(),
+                    export_name: export.name.clone(),
+                })
+                .await?;
+            Ok(true)
+        }
+        Err(e) => {
+            let msg = e.to_string();
+            warn!(export = %export.name, error = %msg, "export provision failed");
+            report.errors.push(format!("export {}/{}: {}", enc.id, export.name, msg));
+            Ok(false)
+        }
+    }
+}
+
+/// Wire `import`, declared by partition `importer`, to an export of its own enclave.
+///
+/// Provisions the export first if this run has not yet done so. For a
+/// `to: partition:` export the driver's access rule is stored in the import
+/// handle under `partition_access`. Errors are reported as for [`provision_export`].
+#[allow(clippy::too_many_arguments)]
+async fn wire_local_import(
+    driver: &dyn Driver,
+    store: &Arc<dyn StateStore>,
+    context: &EventContext,
+    report: &mut ReconcileReport,
+    enc: &Enclave,
+    enc_state: &mut EnclaveState,
+    importer: &Partition,
+    import: &Import,
+    exports_wired: &mut HashSet<String>,
+) -> Result<(), ReconcileError> {
+    let fail = |report: &mut ReconcileReport, msg: String| {
+        warn!(alias = %import.alias, error = %msg, "import wiring failed");
+        report.errors.push(format!("import {}/{}: {}", enc.id, import.alias, msg));
+        Ok(())
+    };
+
+    let Some(export) = enc
+        .exports
+        .iter()
+        .chain(enc.partitions.iter().flat_map(|p| p.exports.iter()))
+        .find(|e| e.name == import.export_name)
+    else {
+        return fail(report, format!("export '{}' not found", import.export_name));
+    };
+
+    if exports_wired.insert(export.name.clone())
+        && !provision_export(driver, store, context, report, enc, enc_state, export).await?
+    {
+        return Ok(()); // reported by provision_export
+    }
+    let Some(export_handle) = enc_state.export_handles.get(&export.name) else {
+        return fail(report, format!("export '{}' has no handle", export.name));
+    };
+
+    let mut result = match driver
+        .provision_import(enc, import, export_handle, enc_state.import_handles.get(&import.alias))
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => return fail(report, e.to_string()),
+    };
+
+    if matches!(export.to, ExportTarget::Partition(_)) {
+        match provision_access(driver, enc, enc_state, export, importer, import).await {
+            Ok(access) => result.handle["partition_access"] = access,
+            Err(e) => return fail(report, format!("access rule: {}", e)),
+        }
+    }
+
+    enc_state.import_handles.insert(import.alias.clone(), result.handle);
+    store
+        .append_event(&AuditEvent::ImportWired {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: context.clone(),
+            importer_enclave: enc.id.clone(),
+            export_name: import.export_name.clone(),
+        })
+        .await?;
+    Ok(())
+}
+
+/// Provision the access rule letting `importer` reach the partition behind
+/// `export`, passing the driver the rule already recorded for `import`.
+async fn provision_access(
+    driver: &dyn Driver,
+    enc: &Enclave,
+    enc_state: &EnclaveState,
+    export: &Export,
+    importer: &Partition,
+    import: &Import,
+) -> Result<Value, DriverError> {
+    let exporter_outputs = enc_state
+        .partitions
+        .get(&export.target_partition)
+        .map(|ps| ps.resolved_outputs.clone())
+        .unwrap_or_default();
+    let enclave_handle = enc_state.enclave_handle.clone().unwrap_or(Value::Null);
+    let existing = enc_state
+        .import_handles
+        .get(&import.alias)
+        .and_then(|h| h.get("partition_access"));
+    driver
+        .provision_partition_access(enc, &enclave_handle, export, importer, &exporter_outputs, existing)
+        .await
+        .map(|access| access.handle)
+}
+
+/// Re-provision the access rule of an already wired sibling import, so a rule
+/// changed in the cloud or by new exporter outputs is brought back in line.
+/// Driver failures are added to `report`.
+async fn refresh_partition_access(
+    driver: &dyn Driver,
+    report: &mut ReconcileReport,
+    enc: &Enclave,
+    enc_state: &mut EnclaveState,
+    importer: &Partition,
+    import: &Import,
+) {
+    let Some(export) = enc
+        .exports
+        .iter()
+        .chain(enc.partitions.iter().flat_map(|p| p.exports.iter()))
+        .find(|e| e.name == import.export_name && matches!(e.to, ExportTarget::Partition(_)))
+    else {
+        return;
+    };
+    match provision_access(driver, enc, enc_state, export, importer, import).await {
+        Ok(access) => {
+            if let Some(handle) = enc_state.import_handles.get_mut(&import.alias) {
+                handle["partition_access"] = access;
+            }
+        }
+        Err(e) => {
+            warn!(alias = %import.alias, error = %e, "access rule refresh failed");
+            report.errors.push(format!("import {}/{}: access rule: {}", enc.id, import.alias, e));
+        }
+    }
+}
+
+/// Tear down the access rules of imports `enc` no longer declares and drop
+/// their handles. A failed teardown keeps the handle, so the next reconcile
+/// retries it; the failure is added to `report`.
+async fn remove_undeclared_access(
+    driver: &dyn Driver,
+    report: &mut ReconcileReport,
+    enc: &Enclave,
+    enc_state: &mut EnclaveState,
+) {
+    let declared: HashSet<&str> = enc
+        .imports
+        .iter()
+        .chain(enc.partitions.iter().flat_map(|p| p.imports.iter()))
+        .map(|i| i.alias.as_str())
+        .collect();
+    let mut removed: Vec<String> = enc_state
+        .import_handles
+        .iter()
+        .filter(|(alias, h)| !declared.contains(alias.as_str()) && h.get("partition_access").is_some())
+        .map(|(alias, _)| alias.clone())
+        .collect();
+    removed.sort();
+    for alias in removed {
+        let access = &enc_state.import_handles[&alias]["partition_access"];
+        match driver.teardown_partition_access(enc, access).await {
+            Ok(()) => {
+                info!(enclave_id = %enc.id, alias, "removed access rule of undeclared import");
+                enc_state.import_handles.remove(&alias);
+            }
+            Err(e) => {
+                warn!(alias, error = %e, "access rule teardown failed");
+                report.errors.push(format!("import {}/{}: access rule: {}", enc.id, alias, e));
+            }
+        }
+    }
+}
+
+/// Dependents of each updated enclave or partition, for annotating a diff.
+/// Changes with no dependents are left out.
+fn change_impact(
+    changes: &[Change],
+    enclaves: &[Enclave],
+    resolved: &ResolvedGraph,
+) -> Vec<ChangeImpact> {
+    changes
+        .iter()
+        .filter_map(|change| {
+            let target = match change {
+                Change::EnclaveUpdated { id } => ImpactTarget::Enclave { enclave: id.clone() },
+                Change::PartitionUpdated { enclave_id, partition_id } => ImpactTarget::Partition {
+                    enclave: enclave_id.clone(),
+                    partition: partition_id.clone(),
+                },
+                _ => return None,
+            };
+            let dependents = impact(enclaves, resolved, &target).ok()?;
+            (!dependents.is_empty()).then(|| ChangeImpact { change: change.clone(), dependents })
+        })
+        .collect()
+}
+
 /// Resolve template variables in `inputs:` values.
 ///
 /// Two forms are supported:
-/// - `{{ alias.key }}` — resolved from cross-partition import handles
+/// - `{{ alias.key }}` — resolved from cross-partition import handles; `key` may be a
+///   dotted path into a structured output (e.g. `{{ db.endpoints.0 }}`)
 /// - `{{ nclav_token }}` (no dot) — resolved from `context_vars` (e.g. `nclav_project_id`)
+///
+/// Templates are resolved inside nested lists and maps. A string that consists of a
+/// single placeholder is replaced by the referenced value itself, so list and map
+/// outputs flow through unchanged; placeholders embedded in a longer string are
+/// rendered as text.
 fn resolve_inputs(
-    inputs: &HashMap<String, String>,
+    inputs: &HashMap<String, Value>,
     enc_state: &EnclaveState,
     context_vars: &HashMap<String, String>,
-) -> HashMap<String, String> {
+) -> HashMap<String, Value> {
     inputs
         .iter()
-        .map(|(k, v)| (k.clone(), resolve_template(v, enc_state, context_vars)))
+        .map(|(k, v)| (k.clone(), resolve_value(v, enc_state, context_vars)))
         .collect()
 }
 
+fn resolve_value(
+    value: &Value,
+    enc_state: &EnclaveState,
+    context_vars: &HashMap<String, String>,
+) -> Value {
+    match value {
+        Value::String(s) => {
+            if let Some(resolved) = sole_placeholder(s)
+                .and_then(|inner| lookup_placeholder(inner, enc_state, context_vars))
+            {
+                return resolved;
+            }
+            Value::String(resolve_template(s, enc_state, context_vars))
+        }
+        Value::Array(items) => Value::Array(
+            items.iter().map(|v| resolve_value(v, enc_state, context_vars)).collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), resolve_value(v, enc_state, context_vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// If `template` is exactly one `{{ ... }}` placeholder, return its trimmed contents.
+fn sole_placeholder(template: &str) -> Option<&str> {
+    let inner = template.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    if inner.contains("{{") || inner.contains("}}") {
+        return None;
+    }
+    Some(inner.trim())
+}
+
+/// Look up the value a placeholder refers to, or `None` if it cannot be resolved.
+fn lookup_placeholder(
+    inner: &str,
+    enc_state: &EnclaveState,
+    context_vars: &HashMap<String, String>,
+) -> Option<Value> {
+    match inner.split_once('.') {
+        Some((alias, path)) => {
+            // {{ alias.key[.path] }} — cross-partition import
+            let mut current = enc_state.import_handles.get(alias)?.get("outputs")?;
+            for segment in path.split('.') {
+                current = match current {
+                    Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                    other => other.get(segment)?,
+                };
+            }
+            Some(current.clone())
+        }
+        // {{ token }} — single-token lookup in context_vars (e.g. {{ nclav_project_id }})
+        None => context_vars.get(inner).map(|v| Value::String(v.clone())),
+    }
+}
+
 fn resolve_template(
     template: &str,
     enc_state: &EnclaveState,
@@ -553,39 +996,21 @@ fn resolve_template(
 ) -> String {
     let mut result = template.to_string();
     let mut search_start = 0;
-    loop {
-        let Some(start) = result[search_start..].find("{{") else { break };
+    while let Some(start) = result[search_start..].find("{{") {
         let abs_start = search_start + start;
         let Some(end) = result[abs_start..].find("}}") else { break };
         let abs_end = abs_start + end + 2;
 
         let inner = result[abs_start + 2..abs_end - 2].trim();
-        let parts: Vec<&str> = inner.splitn(2, '.').collect();
-        if parts.len() == 2 {
-            // {{ alias.key }} — cross-partition import
-            let alias = parts[0];
-            let key = parts[1];
-            let resolved_val = enc_state
-                .import_handles
-                .get(alias)
-                .and_then(|h| h.get("outputs"))
-                .and_then(|o| o.get(key))
-                .and_then(|v| v.as_str())
-                .map(String::from);
-
-            if let Some(val) = resolved_val {
-                result = format!("{}{}{}", &result[..abs_start], val, &result[abs_end..]);
-                search_start = abs_start + val.len();
-                continue;
-            }
-        } else {
-            // {{ token }} — single-token lookup in context_vars (e.g. {{ nclav_project_id }})
-            if let Some(val) = context_vars.get(inner) {
-                let val = val.clone();
-                result = format!("{}{}{}", &result[..abs_start], val, &result[abs_end..]);
-                search_start = abs_start + val.len();
-                continue;
-            }
+        if let Some(val) = lookup_placeholder(inner, enc_state, context_vars) {
+            // Scalars are spliced in as text; lists and maps as compact JSON.
+            let val = match val {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            result = format!("{}{}{}", &result[..abs_start], val, &result[abs_end..]);
+            search_start = abs_start + val.len();
+            continue;
         }
         search_start = abs_end;
     }
@@ -625,6 +1050,21 @@ mod tests {
         assert!(store.list_enclaves().await.unwrap().is_empty(), "dry run must not persist");
     }
 
+    #[tokio::test]
+    async fn dry_run_reports_tf_contract_violations() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/enclaves");
+
+        // The fixture partitions declare outputs but ship no .tf files.
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest { enclaves_dir: dir, dry_run: true, ..Default::default() };
+        let report = reconcile(req, store, test_registry()).await.unwrap();
+        assert!(
+            report.errors.iter().any(|e| e.contains("no matching `output` block")),
+            "expected contract violations, got {:?}",
+            report.errors
+        );
+    }
+
     #[tokio::test]
     async fn apply_sets_active_status() {
         let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/enclaves");
@@ -677,4 +1117,257 @@ mod tests {
             .collect();
         assert!(creates.is_empty(), "second apply should not create enclaves again");
     }
+
+    #[tokio::test]
+    async fn vpc_allocation_is_stable_across_applies() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vpc-allocation");
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest {
+            enclaves_dir: dir,
+            test_mode: true,
+            vpc_allocator: Some(nclav_graph::CidrAllocator::new("10.128.0.0/9", 20).unwrap()),
+            ..Default::default()
+        };
+
+        reconcile(req.clone(), store.clone(), test_registry()).await.unwrap();
+        let vpc_cidr = || async {
+            let state = store.get_enclave(&EnclaveId::new("net")).await.unwrap().unwrap();
+            state.desired.network.unwrap().vpc_cidr
+        };
+        assert_eq!(vpc_cidr().await.as_deref(), Some("10.128.0.0/20"));
+
+        let report = reconcile(req, store.clone(), test_registry()).await.unwrap();
+        assert!(
+            !report.changes.iter().any(|c| matches!(c, Change::EnclaveUpdated { .. })),
+            "re-allocation must not change the enclave: {:?}",
+            report.changes
+        );
+        assert_eq!(vpc_cidr().await.as_deref(), Some("10.128.0.0/20"));
+    }
+
+    #[tokio::test]
+    async fn partition_export_wired_with_access_rule() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/partition-access");
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest { enclaves_dir: dir, dry_run: false, test_mode: true, ..Default::default() };
+
+        let report = reconcile(req, store.clone(), test_registry()).await.unwrap();
+        assert!(report.errors.is_empty(), "expected no errors: {:?}", report.errors);
+
+        let state = store.get_enclave(&EnclaveId::new("shop")).await.unwrap().unwrap();
+        assert!(state.export_handles.contains_key("db-tcp"));
+        let access = &state.import_handles["database"]["partition_access"];
+        assert_eq!(access["kind"], "partition_access");
+        assert_eq!(access["from_partition"], "web");
+        assert_eq!(access["to_partition"], "db");
+    }
+
+    #[tokio::test]
+    async fn access_rules_of_undeclared_imports_are_removed() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/partition-access");
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest { enclaves_dir: dir, test_mode: true, ..Default::default() };
+        reconcile(req.clone(), store.clone(), test_registry()).await.unwrap();
+
+        let id = EnclaveId::new("shop");
+        let mut state = store.get_enclave(&id).await.unwrap().unwrap();
+        let mut stale = state.import_handles["database"].clone();
+        stale["partition_access"]["port"] = serde_json::json!(1);
+        state.import_handles.insert("dropped".into(), stale.clone());
+        state.import_handles.insert("database".into(), stale);
+        store.upsert_enclave(&state).await.unwrap();
+
+        let report = reconcile(req, store.clone(), test_registry()).await.unwrap();
+        assert!(report.errors.is_empty(), "expected no errors: {:?}", report.errors);
+        let state = store.get_enclave(&id).await.unwrap().unwrap();
+        assert!(!state.import_handles.contains_key("dropped"));
+        // The declared import's rule is provisioned again from the current outputs.
+        assert_ne!(state.import_handles["database"]["partition_access"]["port"], 1);
+    }
+
+    #[tokio::test]
+    async fn reconcile_retries_partition_tombstones() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/partition-access");
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest { enclaves_dir: dir, test_mode: true, ..Default::default() };
+        reconcile(req.clone(), store.clone(), test_registry()).await.unwrap();
+
+        // `db` is still declared, `old` is not; both were left by failed teardowns.
+        let db = nclav_domain::PartitionId::new("db");
+        let old = nclav_domain::PartitionId::new("old");
+        let mut state = store.get_enclave(&EnclaveId::new("shop")).await.unwrap().unwrap();
+        let mut old_state = state.partitions[&db].clone();
+        old_state.desired.id = old.clone();
+        old_state.desired.exports.clear();
+        for ps in [state.partitions.get_mut(&db).unwrap(), &mut old_state] {
+            ps.meta.mark_teardown_failed(Utc::now(), "terraform destroy exited with code 1".into());
+        }
+        state.partitions.insert(old.clone(), old_state);
+        store.upsert_enclave(&state).await.unwrap();
+
+        let report = reconcile(req, store.clone(), test_registry()).await.unwrap();
+        assert!(report.errors.is_empty(), "expected no errors: {:?}", report.errors);
+        assert!(report.changes.iter().any(|c| matches!(
+            c,
+            Change::PartitionDeleted { partition_id, .. } if *partition_id == old
+        )));
+
+        let state = store.get_enclave(&EnclaveId::new("shop")).await.unwrap().unwrap();
+        assert!(!state.partitions.contains_key(&old), "clean teardown purges the tombstone");
+        assert_eq!(state.partitions[&db].meta.status, ProvisioningStatus::Active);
+    }
+
+    #[tokio::test]
+    async fn reconcile_tears_down_active_partitions_removed_from_yaml() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/partition-access");
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest { enclaves_dir: dir, test_mode: true, ..Default::default() };
+        reconcile(req.clone(), store.clone(), test_registry()).await.unwrap();
+
+        // `old` was provisioned by an earlier apply and has since been deleted from the YAML.
+        let db = nclav_domain::PartitionId::new("db");
+        let old = nclav_domain::PartitionId::new("old");
+        let mut state = store.get_enclave(&EnclaveId::new("shop")).await.unwrap().unwrap();
+        let mut old_state = state.partitions[&db].clone();
+        old_state.desired.id = old.clone();
+        old_state.desired.exports.clear();
+        assert_eq!(old_state.meta.status, ProvisioningStatus::Active);
+        state.partitions.insert(old.clone(), old_state);
+        store.upsert_enclave(&state).await.unwrap();
+
+        let dry = ReconcileRequest { dry_run: true, ..req.clone() };
+        let report = reconcile(dry, store.clone(), test_registry()).await.unwrap();
+        assert!(report.changes.iter().any(|c| matches!(
+            c,
+            Change::PartitionDeleted { partition_id, .. } if *partition_id == old
+        )));
+
+        let report = reconcile(req, store.clone(), test_registry()).await.unwrap();
+        assert!(report.errors.is_empty(), "expected no errors: {:?}", report.errors);
+        let state = store.get_enclave(&EnclaveId::new("shop")).await.unwrap().unwrap();
+        assert!(!state.partitions.contains_key(&old), "removed partition is torn down and purged");
+        assert_eq!(state.partitions[&db].meta.status, ProvisioningStatus::Active);
+    }
+
+    #[tokio::test]
+    async fn dry_run_annotates_updates_with_dependents() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/partition-access");
+        let store = Arc::new(InMemoryStore::new());
+        let req = ReconcileRequest { enclaves_dir: dir, test_mode: true, ..Default::default() };
+        reconcile(req.clone(), store.clone(), test_registry()).await.unwrap();
+
+        // Make `db` look out of date so the diff reports it as updated.
+        let mut state = store.get_enclave(&EnclaveId::new("shop")).await.unwrap().unwrap();
+        state.partitions.get_mut(&nclav_domain::PartitionId::new("db")).unwrap().meta.desired_hash = None;
+        store.upsert_enclave(&state).await.unwrap();
+
+        let report = reconcile(ReconcileRequest { dry_run: true, ..req }, store, test_registry())
+            .await
+            .unwrap();
+        assert_eq!(report.impact.len(), 1, "{:?}", report.impact);
+        let dependents = &report.impact[0].dependents;
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].partition.as_ref().map(|p| p.as_str()), Some("web"));
+        assert_eq!(dependents[0].inputs, vec!["db_host".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn policy_violation_blocks_apply() {
+        use nclav_policy::{Check, Policy, Rule, Severity};
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/partition-access");
+        let store = Arc::new(InMemoryStore::new());
+        let policy = Policy {
+            rules: vec![Rule {
+                id: "one-partition".into(),
+                description: None,
+                severity: Severity::Error,
+                selector: None,
+                check: Check::MaxPartitions(1),
+            }],
+        };
+        let req = ReconcileRequest {
+            enclaves_dir: dir,
+            test_mode: true,
+            policy: Some(Arc::new(policy)),
+            ..Default::default()
+        };
+
+        let report = reconcile(req, store.clone(), test_registry()).await.unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert!(report.errors[0].contains("one-partition"));
+        assert!(store.get_enclave(&EnclaveId::new("shop")).await.unwrap().is_none());
+    }
+
+    // ── template resolution ───────────────────────────────────────────────────
+
+    fn state_with_import(alias: &str, outputs: Value) -> EnclaveState {
+        let enc = Enclave {
+            id: EnclaveId::new("enc"),
+            name: "enc".into(),
+            region: "local".into(),
+            ..Default::default()
+        };
+        let mut state = EnclaveState::new(enc);
+        state
+            .import_handles
+            .insert(alias.into(), serde_json::json!({ "outputs": outputs }));
+        state
+    }
+
+    #[test]
+    fn sole_placeholder_passes_structured_value_through() {
+        let state = state_with_import("db", serde_json::json!({
+            "endpoints": ["10.0.0.1", "10.0.0.2"],
+            "port": 5432,
+        }));
+        let ctx = HashMap::new();
+
+        let v = resolve_value(&Value::from("{{ db.endpoints }}"), &state, &ctx);
+        assert_eq!(v, serde_json::json!(["10.0.0.1", "10.0.0.2"]));
+
+        let v = resolve_value(&Value::from("{{ db.port }}"), &state, &ctx);
+        assert_eq!(v, serde_json::json!(5432));
+    }
+
+    #[test]
+    fn nested_path_and_array_index() {
+        let state = state_with_import("db", serde_json::json!({
+            "endpoints": [{ "host": "primary.local" }],
+        }));
+        let v = resolve_value(&Value::from("{{ db.endpoints.0.host }}"), &state, &HashMap::new());
+        assert_eq!(v, Value::from("primary.local"));
+    }
+
+    #[test]
+    fn embedded_placeholder_renders_as_text() {
+        let state = state_with_import("db", serde_json::json!({ "host": "h", "port": 5432 }));
+        let v = resolve_value(&Value::from("{{ db.host }}:{{ db.port }}"), &state, &HashMap::new());
+        assert_eq!(v, Value::from("h:5432"));
+    }
+
+    #[test]
+    fn templates_resolved_inside_lists_and_maps() {
+        let state = state_with_import("db", serde_json::json!({ "host": "h" }));
+        let ctx: HashMap<String, String> =
+            [("nclav_project_id".to_string(), "proj".to_string())].into_iter().collect();
+        let input = serde_json::json!({
+            "hosts": ["{{ db.host }}", "static"],
+            "project": "{{ nclav_project_id }}",
+            "replicas": 3,
+        });
+        let v = resolve_value(&input, &state, &ctx);
+        assert_eq!(v, serde_json::json!({
+            "hosts": ["h", "static"],
+            "project": "proj",
+            "replicas": 3,
+        }));
+    }
+
+    #[test]
+    fn unresolved_placeholder_left_intact() {
+        let state = state_with_import("db", serde_json::json!({}));
+        let v = resolve_value(&Value::from("{{ db.missing }}"), &state, &HashMap::new());
+        assert_eq!(v, Value::from("{{ db.missing }}"));
+    }
 }
@@ -2,6 +2,10 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use nclav_domain::{EnclaveId, PartitionId};
+use nclav_driver::BackendTls;
+use nclav_graph::{CidrAllocator, Dependent};
+use nclav_policy::Policy;
+use nclav_store::EventContext;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,10 +16,14 @@ pub struct ReconcileRequest {
     /// Used to configure the Terraform HTTP state backend.
     #[serde(default = "default_api_base")]
     pub api_base: String,
-    /// nclav bearer token. Passed as TF_HTTP_PASSWORD to IaC subprocesses.
+    /// nclav server token. Signs the state-backend token each IaC subprocess gets.
     /// Not serialized — callers must supply it directly.
     #[serde(skip, default)]
     pub auth_token: Arc<String>,
+    /// CA bundle and client certificate IaC subprocesses use to reach `api_base`.
+    /// Server configuration — not serialized.
+    #[serde(skip, default)]
+    pub backend_tls: BackendTls,
     /// When true, the TerraformBackend skips subprocess invocations and returns stubbed outputs.
     /// Use in tests to avoid requiring a terraform binary.
     #[serde(default)]
@@ -26,6 +34,18 @@ pub struct ReconcileRequest {
     /// but keep the GCP project (and its config, quotas, etc.) intact.
     #[serde(default)]
     pub resources_only: bool,
+    /// Assigns `vpc_cidr` to enclaves whose `network:` block omits it.
+    /// Server configuration — not serialized.
+    #[serde(skip, default)]
+    pub vpc_allocator: Option<CidrAllocator>,
+    /// Organization policy evaluated after graph validation.
+    /// Server configuration — not serialized.
+    #[serde(skip, default)]
+    pub policy: Option<Arc<Policy>>,
+    /// Actor, source and request ID recorded on the run's audit events.
+    /// Filled in by the API from the calling credential — not serialized.
+    #[serde(skip, default)]
+    pub context: EventContext,
 }
 
 fn default_api_base() -> String {
@@ -39,8 +59,12 @@ impl Default for ReconcileRequest {
             dry_run: false,
             api_base: default_api_base(),
             auth_token: Arc::new(String::new()),
+            backend_tls: BackendTls::default(),
             test_mode: false,
             resources_only: false,
+            vpc_allocator: None,
+            policy: None,
+            context: EventContext::default(),
         }
     }
 }
@@ -63,6 +87,19 @@ pub struct ReconcileReport {
     pub dry_run: bool,
     pub changes: Vec<Change>,
     pub errors: Vec<String>,
+    /// Non-blocking findings, e.g. warning-level or excepted policy violations.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Dry run only: who depends on each updated enclave or partition.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub impact: Vec<ChangeImpact>,
+}
+
+/// The downstream dependents of one planned change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeImpact {
+    pub change: Change,
+    pub dependents: Vec<Dependent>,
 }
 
 impl ReconcileReport {
@@ -71,6 +108,8 @@ impl ReconcileReport {
             dry_run,
             changes: Vec::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            impact: Vec::new(),
         }
     }
 }
@@ -0,0 +1,555 @@
+//! Tombstone-based teardown of enclaves and partitions.
+//!
+//! Teardown marks a record `Deleting` and keeps it — handles, outputs and all —
+//! until every step has succeeded. A failed step leaves the record behind as a
+//! tombstone with `last_error` set, which the next reconcile or
+//! `nclav destroy --retry` tears down again. Only a clean teardown marks the
+//! record `Deleted` and purges it from the store. Either way the outcome is
+//! recorded as an audit event under the caller's context.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::Utc;
+use nclav_domain::{Enclave, PartitionId};
+use nclav_driver::{Driver, TerraformBackend};
+use nclav_store::{AuditEvent, EnclaveState, EventContext, StateStore};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::error::ReconcileError;
+
+/// Result of tearing down an enclave or partition.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TeardownOutcome {
+    /// Failed teardown steps. Non-empty means the tombstone was kept.
+    pub errors: Vec<String>,
+    /// Resources the driver still reports for the torn-down partitions.
+    pub remaining_resources: Vec<String>,
+    /// The record was removed from the store.
+    pub purged: bool,
+}
+
+impl TeardownOutcome {
+    fn merge(&mut self, other: TeardownOutcome) {
+        self.errors.extend(other.errors);
+        self.remaining_resources.extend(other.remaining_resources);
+    }
+}
+
+/// `part_ids` in the order to tear them down: the reverse of `enc`'s partition
+/// order, so importers go before the partitions they import from. IDs `enc`
+/// does not declare come first, sorted.
+pub(crate) fn teardown_order(enc: &Enclave, part_ids: impl IntoIterator<Item = PartitionId>) -> Vec<PartitionId> {
+    let order = nclav_graph::partition_order(enc)
+        .unwrap_or_else(|| enc.partitions.iter().map(|p| p.id.clone()).collect());
+    let rank: HashMap<PartitionId, usize> = order.into_iter().enumerate().map(|(i, id)| (id, i)).collect();
+    let mut ids: Vec<PartitionId> = part_ids.into_iter().collect();
+    ids.sort_by_cached_key(|id| {
+        let r = rank.get(id).copied();
+        (r.is_some(), Reverse(r), id.0.clone())
+    });
+    ids
+}
+
+/// Tear down one partition of `enc_state`, persisting each transition.
+///
+/// Runs `terraform destroy`, then removes the access rules naming the partition
+/// and the partition SA, then asks the driver for resources still labeled to
+/// the partition. Rules and SA are kept until the destroy succeeded, so a
+/// failed destroy leaves everything as it was. Imports whose access rule was
+/// removed are dropped, to be wired again if still declared.
+/// Store failures are returned as errors; teardown failures are reported in
+/// the outcome.
+pub async fn teardown_partition(
+    store: &dyn StateStore,
+    driver: &dyn Driver,
+    tf_backend: &TerraformBackend,
+    enc_state: &mut EnclaveState,
+    partition_id: &PartitionId,
+    context: &EventContext,
+) -> Result<TeardownOutcome, ReconcileError> {
+    let mut outcome = TeardownOutcome::default();
+    let Some(ps) = enc_state.partitions.get_mut(partition_id) else {
+        outcome.purged = true;
+        return Ok(outcome);
+    };
+    ps.meta.mark_deleting();
+    let part = ps.desired.clone();
+    let part_handle = ps.partition_handle.clone();
+    store.upsert_enclave(enc_state).await?;
+
+    let enc = &enc_state.desired;
+    let key = format!("{}/{}", enc.id, part.id);
+    let auth_env = enc_state
+        .enclave_handle
+        .as_ref()
+        .map(|h| driver.auth_env(enc, h))
+        .unwrap_or_default();
+
+    let mut unwired = Vec::new();
+    match tf_backend.teardown(enc, &part, &auth_env, context.run_id).await {
+        Err(e) => {
+            warn!(enclave_id = %enc.id, partition_id = %part.id, error = %e, "IaC partition teardown failed");
+            outcome.errors.push(format!("teardown {}: {}", key, e));
+        }
+        Ok(()) => {
+            let pid = Some(part.id.as_str());
+            for (alias, handle) in &enc_state.import_handles {
+                let access = &handle["partition_access"];
+                if access["from_partition"].as_str() != pid && access["to_partition"].as_str() != pid {
+                    continue;
+                }
+                match driver.teardown_partition_access(enc, access).await {
+                    Ok(()) => unwired.push(alias.clone()),
+                    Err(e) => {
+                        warn!(enclave_id = %enc.id, partition_id = %part.id, alias, error = %e, "access rule cleanup failed");
+                        outcome.errors.push(format!("access rule {}/{}: {}", enc.id, alias, e));
+                    }
+                }
+            }
+            if let Some(handle) = &part_handle {
+                if let Err(e) = driver.teardown_partition(enc, &part, handle).await {
+                    warn!(enclave_id = %enc.id, partition_id = %part.id, error = %e, "partition SA cleanup failed");
+                    outcome.errors.push(format!("SA cleanup {}: {}", key, e));
+                }
+            }
+        }
+    }
+
+    // Post-destroy check: anything still labeled to the partition means the
+    // teardown is not confirmed. Drivers without resource listing report none.
+    if outcome.errors.is_empty() {
+        if let Some(enc_handle) = &enc_state.enclave_handle {
+            match driver.list_partition_resources(enc, enc_handle, &part).await {
+                Ok(remaining) if remaining.is_empty() => {}
+                Ok(remaining) => {
+                    warn!(enclave_id = %enc.id, partition_id = %part.id, count = remaining.len(), "resources remain after teardown");
+                    outcome.errors.push(format!(
+                        "teardown {}: {} resource(s) still present", key, remaining.len()
+                    ));
+                    outcome.remaining_resources = remaining;
+                }
+                Err(e) => {
+                    warn!(enclave_id = %enc.id, partition_id = %part.id, error = %e, "could not verify partition teardown");
+                    outcome.errors.push(format!("verify teardown {}: {}", key, e));
+                }
+            }
+        }
+    }
+
+    for alias in &unwired {
+        enc_state.import_handles.remove(alias);
+    }
+    let now = Utc::now();
+    let enclave_id = enc_state.desired.id.clone();
+    let Some(ps) = enc_state.partitions.get_mut(partition_id) else {
+        return Ok(outcome);
+    };
+    let event = if outcome.errors.is_empty() {
+        ps.meta.mark_deleted(now);
+        store.upsert_enclave(enc_state).await?;
+        enc_state.partitions.remove(partition_id);
+        store.upsert_enclave(enc_state).await?;
+        outcome.purged = true;
+        info!(enclave_id = %enclave_id, partition_id = %partition_id, "partition torn down");
+        AuditEvent::PartitionDeleted {
+            id: Uuid::new_v4(),
+            at: now,
+            context: context.clone(),
+            enclave_id,
+            partition_id: partition_id.clone(),
+        }
+    } else {
+        let message = outcome.errors.join("; ");
+        ps.meta.mark_teardown_failed(now, message.clone());
+        store.upsert_enclave(enc_state).await?;
+        AuditEvent::PartitionTeardownFailed {
+            id: Uuid::new_v4(),
+            at: now,
+            context: context.clone(),
+            enclave_id,
+            partition_id: partition_id.clone(),
+            message,
+        }
+    };
+    store.append_event(&event).await?;
+    Ok(outcome)
+}
+
+/// Tear down an enclave: every partition first, in [`teardown_order`], then
+/// the enclave itself.
+///
+/// The enclave is only torn down once no partition tombstone remains, and is
+/// kept in the store until that succeeded. With `resources_only` the cloud
+/// project is left in place but the record is still purged.
+pub async fn teardown_enclave(
+    store: &dyn StateStore,
+    driver: &dyn Driver,
+    tf_backend: &TerraformBackend,
+    mut enc_state: EnclaveState,
+    resources_only: bool,
+    context: &EventContext,
+) -> Result<TeardownOutcome, ReconcileError> {
+    let id = enc_state.desired.id.clone();
+    enc_state.meta.mark_deleting();
+    store.upsert_enclave(&enc_state).await?;
+
+    let mut outcome = TeardownOutcome::default();
+    if enc_state.enclave_handle.is_some() {
+        let part_ids = teardown_order(&enc_state.desired, enc_state.partitions.keys().cloned());
+        for part_id in &part_ids {
+            let part = teardown_partition(store, driver, tf_backend, &mut enc_state, part_id, context).await?;
+            outcome.merge(part);
+        }
+
+        if !enc_state.partitions.is_empty() {
+            outcome.errors.push(format!(
+                "enclave teardown {}: skipped while {} partition(s) are still being torn down",
+                id,
+                enc_state.partitions.len()
+            ));
+        } else if resources_only {
+            info!(enclave_id = %id, "resources_only: skipping project deletion");
+        } else if let Some(handle) = &enc_state.enclave_handle {
+            if let Err(e) = driver.teardown_enclave(&enc_state.desired, handle).await {
+                warn!(enclave_id = %id, error = %e, "enclave teardown failed");
+                outcome.errors.push(format!("enclave teardown {}: {}", id, e));
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let event = if outcome.errors.is_empty() {
+        enc_state.meta.mark_deleted(now);
+        store.upsert_enclave(&enc_state).await?;
+        store.delete_enclave(&id).await?;
+        outcome.purged = true;
+        info!(enclave_id = %id, "enclave torn down");
+        AuditEvent::EnclaveDeleted { id: Uuid::new_v4(), at: now, context: context.clone(), enclave_id: id }
+    } else {
+        let message = outcome.errors.join("; ");
+        enc_state.meta.mark_teardown_failed(now, message.clone());
+        store.upsert_enclave(&enc_state).await?;
+        AuditEvent::EnclaveTeardownFailed {
+            id: Uuid::new_v4(),
+            at: now,
+            context: context.clone(),
+            enclave_id: id,
+            message,
+        }
+    };
+    store.append_event(&event).await?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use nclav_domain::{Enclave, EnclaveId, Export, Import, Partition};
+    use nclav_driver::{BackendTls, DriverError, Handle, LocalDriver, ObservedState, ProvisionResult};
+    use nclav_store::{InMemoryStore, PartitionState, ProvisioningStatus};
+    use serde_json::{json, Value};
+
+    /// LocalDriver whose partition SA cleanup fails until `heal()` is called.
+    struct FlakyDriver {
+        inner: LocalDriver,
+        healed: AtomicBool,
+    }
+
+    impl FlakyDriver {
+        fn new() -> Self {
+            Self { inner: LocalDriver::new(), healed: AtomicBool::new(false) }
+        }
+
+        fn heal(&self) {
+            self.healed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl Driver for FlakyDriver {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn provision_enclave(&self, enclave: &Enclave, existing: Option<&Handle>) -> Result<ProvisionResult, DriverError> {
+            self.inner.provision_enclave(enclave, existing).await
+        }
+
+        async fn teardown_enclave(&self, enclave: &Enclave, handle: &Handle) -> Result<(), DriverError> {
+            self.inner.teardown_enclave(enclave, handle).await
+        }
+
+        async fn provision_partition(
+            &self,
+            enclave: &Enclave,
+            partition: &Partition,
+            resolved_inputs: &HashMap<String, Value>,
+            existing: Option<&Handle>,
+        ) -> Result<ProvisionResult, DriverError> {
+            self.inner.provision_partition(enclave, partition, resolved_inputs, existing).await
+        }
+
+        async fn teardown_partition(&self, enclave: &Enclave, partition: &Partition, handle: &Handle) -> Result<(), DriverError> {
+            if !self.healed.load(Ordering::SeqCst) {
+                return Err(DriverError::TeardownFailed("permission denied".into()));
+            }
+            self.inner.teardown_partition(enclave, partition, handle).await
+        }
+
+        async fn provision_export(
+            &self,
+            enclave: &Enclave,
+            export: &Export,
+            partition_outputs: &HashMap<String, Value>,
+            existing: Option<&Handle>,
+        ) -> Result<ProvisionResult, DriverError> {
+            self.inner.provision_export(enclave, export, partition_outputs, existing).await
+        }
+
+        async fn provision_import(
+            &self,
+            importer: &Enclave,
+            import: &Import,
+            export_handle: &Handle,
+            existing: Option<&Handle>,
+        ) -> Result<ProvisionResult, DriverError> {
+            self.inner.provision_import(importer, import, export_handle, existing).await
+        }
+
+        async fn provision_partition_access(
+            &self,
+            enclave: &Enclave,
+            enclave_handle: &Handle,
+            export: &Export,
+            importer: &Partition,
+            exporter_outputs: &HashMap<String, Value>,
+            existing: Option<&Handle>,
+        ) -> Result<ProvisionResult, DriverError> {
+            self.inner
+                .provision_partition_access(enclave, enclave_handle, export, importer, exporter_outputs, existing)
+                .await
+        }
+
+        async fn observe_enclave(&self, enclave: &Enclave, handle: &Handle) -> Result<ObservedState, DriverError> {
+            self.inner.observe_enclave(enclave, handle).await
+        }
+
+        async fn observe_partition(
+            &self,
+            enclave: &Enclave,
+            partition: &Partition,
+            handle: &Handle,
+        ) -> Result<ObservedState, DriverError> {
+            self.inner.observe_partition(enclave, partition, handle).await
+        }
+
+        fn context_vars(&self, enclave: &Enclave, handle: &Handle) -> HashMap<String, String> {
+            self.inner.context_vars(enclave, handle)
+        }
+
+        fn auth_env(&self, enclave: &Enclave, handle: &Handle) -> HashMap<String, String> {
+            self.inner.auth_env(enclave, handle)
+        }
+    }
+
+    fn tf_backend(store: Arc<InMemoryStore>) -> TerraformBackend {
+        TerraformBackend {
+            api_base: "http://127.0.0.1:8080".into(),
+            auth_token: Arc::new(String::new()),
+            tls: BackendTls::default(),
+            store,
+            test_mode: true,
+            workspace_root: None,
+        }
+    }
+
+    fn enclave_with_partition() -> EnclaveState {
+        let enclave = Enclave {
+            id: EnclaveId::new("enc"),
+            name: "enc".to_string(),
+            region: "local".to_string(),
+            ..Default::default()
+        };
+        let partition = Partition {
+            id: PartitionId::new("db"),
+            name: "db".to_string(),
+            produces: None,
+            imports: vec![],
+            exports: vec![],
+            inputs: HashMap::new(),
+            declared_outputs: vec![],
+            backend: Default::default(),
+        };
+        let mut state = EnclaveState::new(enclave);
+        state.enclave_handle = Some(json!({ "type": "local" }));
+        let mut ps = PartitionState::new(partition);
+        ps.partition_handle = Some(json!({ "partition_sa": "db@local" }));
+        state.partitions.insert(PartitionId::new("db"), ps);
+        state
+    }
+
+    fn partition(id: &str) -> Partition {
+        Partition {
+            id: PartitionId::new(id),
+            name: id.to_string(),
+            produces: None,
+            imports: vec![],
+            exports: vec![],
+            inputs: HashMap::new(),
+            declared_outputs: vec![],
+            backend: Default::default(),
+        }
+    }
+
+    /// `enc` declaring `db` and `web`, with `web` importing `db`'s `pg` export.
+    /// `web` is declared last, so sorted or declaration order would tear `db` down first.
+    fn enclave_with_importer() -> EnclaveState {
+        let mut db = partition("db");
+        db.exports.push(Export {
+            name: "pg".into(),
+            target_partition: PartitionId::new("db"),
+            export_type: nclav_domain::ExportType::Tcp,
+            to: nclav_domain::ExportTarget::Partition(PartitionId::new("web")),
+            auth: nclav_domain::AuthType::None,
+            hostname: None,
+            port: None,
+        });
+        let mut web = partition("web");
+        web.imports.push(Import {
+            from: EnclaveId::new("enc"),
+            export_name: "pg".into(),
+            alias: "database".into(),
+            import_type: None,
+        });
+        let enclave = Enclave {
+            id: EnclaveId::new("enc"),
+            name: "enc".to_string(),
+            region: "local".to_string(),
+            partitions: vec![db.clone(), web.clone()],
+            ..Default::default()
+        };
+        let mut state = EnclaveState::new(enclave);
+        state.enclave_handle = Some(json!({ "type": "local" }));
+        for p in [db, web] {
+            let mut ps = PartitionState::new(p.clone());
+            ps.partition_handle = Some(json!({ "partition_sa": format!("{}@local", p.id.as_str()) }));
+            state.partitions.insert(p.id, ps);
+        }
+        state
+    }
+
+    #[test]
+    fn teardown_order_puts_importers_and_undeclared_partitions_first() {
+        let state = enclave_with_importer();
+        let ids = ["db", "zz", "web", "old"].map(PartitionId::new);
+        let order = teardown_order(&state.desired, ids);
+        let order: Vec<&str> = order.iter().map(|p| p.as_str()).collect();
+        assert_eq!(order, ["old", "zz", "web", "db"]);
+    }
+
+    #[tokio::test]
+    async fn enclave_teardown_removes_importers_before_exporters() {
+        let store = Arc::new(InMemoryStore::new());
+        let driver = FlakyDriver::new();
+        driver.heal();
+        let state = enclave_with_importer();
+        store.upsert_enclave(&state).await.unwrap();
+
+        let outcome = teardown_enclave(store.as_ref(), &driver, &tf_backend(store.clone()), state, false, &EventContext::default())
+            .await
+            .unwrap();
+        assert!(outcome.purged, "{:?}", outcome.errors);
+
+        let events = store.list_events(None, 10).await.unwrap();
+        let deleted: Vec<&str> = events
+            .iter()
+            .filter(|e| e.kind() == "PartitionDeleted")
+            .filter_map(|e| e.partition_id().map(|p| p.as_str()))
+            .collect();
+        assert_eq!(deleted, ["web", "db"]);
+    }
+
+    #[tokio::test]
+    async fn failed_teardown_keeps_tombstone_until_retry_succeeds() {
+        let store = Arc::new(InMemoryStore::new());
+        let driver = FlakyDriver::new();
+        let backend = tf_backend(store.clone());
+        let state = enclave_with_partition();
+        store.upsert_enclave(&state).await.unwrap();
+        let id = EnclaveId::new("enc");
+        let context = EventContext::controller().with_run(Uuid::new_v4());
+
+        let outcome = teardown_enclave(store.as_ref(), &driver, &backend, state, false, &context).await.unwrap();
+        assert!(!outcome.purged);
+        assert!(outcome.errors.iter().any(|e| e.contains("SA cleanup enc/db")), "{:?}", outcome.errors);
+
+        let kept = store.get_enclave(&id).await.unwrap().expect("enclave tombstone kept");
+        assert_eq!(kept.meta.status, ProvisioningStatus::Deleting);
+        assert!(kept.enclave_handle.is_some());
+        let part = &kept.partitions[&PartitionId::new("db")];
+        assert_eq!(part.meta.status, ProvisioningStatus::Deleting);
+        assert!(part.partition_handle.is_some());
+        assert!(part.meta.last_error.as_ref().unwrap().message.contains("permission denied"));
+
+        driver.heal();
+        let outcome = teardown_enclave(store.as_ref(), &driver, &backend, kept, false, &context).await.unwrap();
+        assert!(outcome.purged, "{:?}", outcome.errors);
+        assert!(store.get_enclave(&id).await.unwrap().is_none());
+
+        let events = store.list_events(None, 10).await.unwrap();
+        let kinds: Vec<_> = events.iter().map(|e| e.kind()).collect();
+        assert_eq!(
+            kinds,
+            ["PartitionTeardownFailed", "EnclaveTeardownFailed", "PartitionDeleted", "EnclaveDeleted"]
+        );
+        assert!(events.iter().all(|e| e.context() == &context));
+    }
+
+    #[tokio::test]
+    async fn clean_partition_teardown_purges_only_that_partition() {
+        let store = Arc::new(InMemoryStore::new());
+        let driver = FlakyDriver::new();
+        driver.heal();
+        let mut state = enclave_with_partition();
+        store.upsert_enclave(&state).await.unwrap();
+
+        let pid = PartitionId::new("db");
+        let outcome = teardown_partition(store.as_ref(), &driver, &tf_backend(store.clone()), &mut state, &pid, &EventContext::default())
+            .await
+            .unwrap();
+        assert!(outcome.purged);
+        let kept = store.get_enclave(&EnclaveId::new("enc")).await.unwrap().unwrap();
+        assert!(kept.partitions.is_empty());
+        assert_eq!(kept.meta.status, ProvisioningStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn partition_teardown_drops_access_rules_naming_it() {
+        let store = Arc::new(InMemoryStore::new());
+        let driver = FlakyDriver::new();
+        driver.heal();
+        let mut state = enclave_with_partition();
+        let access = |from: &str, to: &str| json!({ "partition_access": { "from_partition": from, "to_partition": to } });
+        state.import_handles.insert("to-db".into(), access("web", "db"));
+        state.import_handles.insert("from-db".into(), access("db", "cache"));
+        state.import_handles.insert("elsewhere".into(), access("web", "cache"));
+        store.upsert_enclave(&state).await.unwrap();
+
+        let pid = PartitionId::new("db");
+        let outcome = teardown_partition(store.as_ref(), &driver, &tf_backend(store.clone()), &mut state, &pid, &EventContext::default())
+            .await
+            .unwrap();
+        assert!(outcome.purged, "{:?}", outcome.errors);
+        let kept = store.get_enclave(&EnclaveId::new("enc")).await.unwrap().unwrap();
+        let aliases: Vec<_> = kept.import_handles.keys().collect();
+        assert_eq!(aliases, ["elsewhere"]);
+    }
+}
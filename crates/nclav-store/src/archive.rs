@@ -0,0 +1,686 @@
+use std::collections::{HashMap, HashSet};
+
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use nclav_domain::EnclaveId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use crate::state::{compute_desired_hash, AuditEvent, EnclaveState, IacRun};
+use crate::store::StateStore;
+use crate::tf_state::{TfStateHeader, TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
+
+/// Archive layout version written by [`StateArchive::export`]. Bump when a
+/// field is added that older readers would silently drop.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 3;
+
+/// A portable dump of everything a [`StateStore`] holds.
+///
+/// Written by `nclav state export` and read by `nclav state import`; the same
+/// archive loads into any backend. `checksum` covers every other field, and each
+/// Terraform state blob, current or retained version, carries its own SHA-256
+/// so a truncated or hand-edited archive is rejected before anything is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateArchive {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub enclaves: Vec<EnclaveState>,
+    /// Audit events in chronological order.
+    pub events: Vec<AuditEvent>,
+    pub tf_state: Vec<TfStateEntry>,
+    pub tf_locks: Vec<TfLockEntry>,
+    /// IaC runs across all partitions, oldest first.
+    pub iac_runs: Vec<IacRun>,
+    /// API tokens (hashes only), sorted by name. Absent before format version 2.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_tokens: Vec<ApiToken>,
+    pub checksum: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TfStateEntry {
+    pub key: String,
+    /// Base64-encoded state blob.
+    pub state: String,
+    /// Hex SHA-256 of the decoded blob.
+    pub sha256: String,
+    /// Retained versions of the state, oldest first. Absent before format version 3.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<TfStateVersionEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TfStateVersionEntry {
+    #[serde(flatten)]
+    pub meta: TfStateVersion,
+    /// Base64-encoded state blob.
+    pub state: String,
+    /// Hex SHA-256 of the decoded blob.
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TfLockEntry {
+    pub key: String,
+    pub lock_info: serde_json::Value,
+}
+
+/// Record counts written by [`StateArchive::import_into`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    pub enclaves: usize,
+    pub events: usize,
+    pub tf_state: usize,
+    pub tf_state_versions: usize,
+    pub tf_locks: usize,
+    pub iac_runs: usize,
+    pub api_tokens: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} enclave(s), {} audit event(s), {} tf state blob(s) with {} version(s), {} tf lock(s), {} IaC run(s), {} API token(s)",
+            self.enclaves,
+            self.events,
+            self.tf_state,
+            self.tf_state_versions,
+            self.tf_locks,
+            self.iac_runs,
+            self.api_tokens
+        )
+    }
+}
+
+impl StateArchive {
+    /// Read every record from `store` into a checksummed archive.
+    pub async fn export(store: &dyn StateStore) -> Result<Self, StoreError> {
+        let mut tf_state = Vec::new();
+        for key in store.list_tf_state_keys().await? {
+            // A blob deleted between listing and reading is simply skipped.
+            tf_state.extend(TfStateEntry::read(store, key).await?);
+        }
+        let tf_locks = store
+            .list_tf_locks()
+            .await?
+            .into_iter()
+            .map(|(key, lock_info)| TfLockEntry { key, lock_info })
+            .collect();
+
+        let mut archive = Self {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            enclaves: store.list_enclaves().await?,
+            events: store.list_events(None, u32::MAX).await?,
+            tf_state,
+            tf_locks,
+            iac_runs: store.list_all_iac_runs().await?,
+            api_tokens: store.list_api_tokens().await?,
+            checksum: String::new(),
+        };
+        archive.checksum = archive.compute_checksum()?;
+        Ok(archive)
+    }
+
+    /// Parse an archive and verify its checksums.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, StoreError> {
+        let archive: Self = serde_json::from_slice(bytes)
+            .map_err(|e| StoreError::InvalidArchive(e.to_string()))?;
+        archive.verify()?;
+        Ok(archive)
+    }
+
+    /// Check the format version, the archive checksum, every blob checksum,
+    /// and that no two records claim the same key.
+    pub fn verify(&self) -> Result<(), StoreError> {
+        if self.format_version > ARCHIVE_FORMAT_VERSION {
+            return Err(StoreError::InvalidArchive(format!(
+                "format version {} is newer than supported version {}",
+                self.format_version, ARCHIVE_FORMAT_VERSION
+            )));
+        }
+        let expected = self.compute_checksum()?;
+        if self.checksum != expected {
+            return Err(StoreError::InvalidArchive(format!(
+                "checksum mismatch: archive says {}, contents hash to {}",
+                self.checksum, expected
+            )));
+        }
+        for entry in &self.tf_state {
+            let blob = entry.decode()?;
+            if sha256_hex(&blob) != entry.sha256 {
+                return Err(StoreError::InvalidArchive(format!(
+                    "tf state '{}' does not match its checksum",
+                    entry.key
+                )));
+            }
+            for version in &entry.versions {
+                let blob = version.decode(&entry.key)?;
+                if sha256_hex(&blob) != version.sha256 {
+                    return Err(StoreError::InvalidArchive(format!(
+                        "tf state '{}' version {} does not match its checksum",
+                        entry.key, version.meta.version
+                    )));
+                }
+            }
+            if !entry.versions.windows(2).all(|w| w[0].meta.version < w[1].meta.version) {
+                return Err(StoreError::InvalidArchive(format!(
+                    "tf state '{}' versions are not in ascending order",
+                    entry.key
+                )));
+            }
+        }
+        check_unique("enclave", self.enclaves.iter().map(|e| e.desired.id.to_string()))?;
+        check_unique("tf state", self.tf_state.iter().map(|e| e.key.clone()))?;
+        check_unique("tf lock", self.tf_locks.iter().map(|l| l.key.clone()))?;
+        check_unique("IaC run", self.iac_runs.iter().map(|r| r.id.to_string()))?;
+        check_unique("API token", self.api_tokens.iter().map(|t| t.name.clone()))?;
+        check_unique("API token hash", self.api_tokens.iter().map(|t| t.token_hash.clone()))?;
+        Ok(())
+    }
+
+    /// Verify the archive and write it into `store`.
+    ///
+    /// Everything that can be checked up front is checked before the first
+    /// write. Should a write still fail, the records written so far are put
+    /// back the way they were and the error is returned.
+    ///
+    /// Refuses with [`StoreError::TargetNotEmpty`] if `store` already holds any
+    /// records, unless `force` is set. A forced import replaces records with the
+    /// same key (enclave ID, tf state key, run ID, token name), replaces the version
+    /// history of imported tf state keys that carry one, takes over existing locks on
+    /// imported keys, and appends the archive's audit events after any already
+    /// present; records absent from the archive are left alone.
+    ///
+    /// Retained tf state versions keep their version numbers, creation times
+    /// and provenance.
+    pub async fn import_into(
+        &self,
+        store: &dyn StateStore,
+        force: bool,
+    ) -> Result<ImportSummary, StoreError> {
+        self.verify()?;
+        if !force {
+            let existing = describe_contents(store).await?;
+            if !existing.is_empty() {
+                return Err(StoreError::TargetNotEmpty(existing.join(", ")));
+            }
+        }
+        // A token replaced by name must not collide with another kept token.
+        let replaced: HashSet<&str> = self.api_tokens.iter().map(|t| t.name.as_str()).collect();
+        for kept in store.list_api_tokens().await?.into_iter().filter(|t| !replaced.contains(t.name.as_str())) {
+            if let Some(token) = self.api_tokens.iter().find(|t| t.token_hash == kept.token_hash) {
+                return Err(StoreError::InvalidArchive(format!(
+                    "API token '{}' has the same secret as existing token '{}'",
+                    token.name, kept.name
+                )));
+            }
+        }
+
+        let snapshot = Snapshot::take(self, store).await?;
+        if let Err(e) = self.write_into(store, force).await {
+            return match snapshot.restore(store).await {
+                Ok(()) => Err(e),
+                Err(rollback) => Err(StoreError::Internal(format!(
+                    "{}; rolling back the partial import also failed: {}",
+                    e, rollback
+                ))),
+            };
+        }
+
+        Ok(ImportSummary {
+            enclaves: self.enclaves.len(),
+            events: self.events.len(),
+            tf_state: self.tf_state.len(),
+            tf_state_versions: self.tf_state.iter().map(|e| e.versions.len()).sum(),
+            tf_locks: self.tf_locks.len(),
+            iac_runs: self.iac_runs.len(),
+            api_tokens: self.api_tokens.len(),
+        })
+    }
+
+    /// Write every record, audit events last and all at once so they need no
+    /// rolling back.
+    async fn write_into(&self, store: &dyn StateStore, force: bool) -> Result<(), StoreError> {
+        for enclave in &self.enclaves {
+            store.upsert_enclave(enclave).await?;
+        }
+        for entry in &self.tf_state {
+            entry.import_into(store, force).await?;
+        }
+        for lock in &self.tf_locks {
+            if force {
+                store.force_unlock_tf_state(&lock.key).await?;
+            }
+            store.lock_tf_state(&lock.key, lock.lock_info.clone()).await?;
+        }
+        for run in &self.iac_runs {
+            store.upsert_iac_run(run).await?;
+        }
+        for token in &self.api_tokens {
+            if force {
+                store.delete_api_token(&token.name).await?;
+            }
+            store.create_api_token(token).await?;
+        }
+        store.append_events(&self.events).await
+    }
+
+    /// Hash of the archive's canonical JSON with `checksum` itself removed.
+    fn compute_checksum(&self) -> Result<String, StoreError> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(map) = value.as_object_mut() {
+            map.remove("checksum");
+        }
+        Ok(compute_desired_hash(&value))
+    }
+}
+
+impl TfStateEntry {
+    /// The current blob at `key` with its retained versions, or `None` if
+    /// there is no state.
+    async fn read(store: &dyn StateStore, key: String) -> Result<Option<Self>, StoreError> {
+        let Some(blob) = store.get_tf_state(&key).await? else { return Ok(None) };
+        let mut versions = Vec::new();
+        for meta in store.list_tf_state_versions(&key).await?.into_iter().rev() {
+            // A version pruned since listing is skipped.
+            let Some(blob) = store.get_tf_state_version(&key, meta.version).await? else { continue };
+            versions.push(TfStateVersionEntry {
+                meta,
+                sha256: sha256_hex(&blob),
+                state: base64::engine::general_purpose::STANDARD.encode(&blob),
+            });
+        }
+        Ok(Some(Self {
+            key,
+            sha256: sha256_hex(&blob),
+            state: base64::engine::general_purpose::STANDARD.encode(&blob),
+            versions,
+        }))
+    }
+
+    fn decode(&self) -> Result<Vec<u8>, StoreError> {
+        base64::engine::general_purpose::STANDARD
+            .decode(&self.state)
+            .map_err(|e| StoreError::InvalidArchive(format!("tf state '{}': {}", self.key, e)))
+    }
+
+    /// Replay the retained versions oldest first, then store the current blob
+    /// unless it is the newest version already.
+    async fn import_into(&self, store: &dyn StateStore, force: bool) -> Result<(), StoreError> {
+        if self.versions.is_empty() {
+            store.put_tf_state(&self.key, self.decode()?, &TfStateWrite::default()).await?;
+            return Ok(());
+        }
+        if force {
+            // Imported version numbers must not collide with existing ones.
+            store.delete_tf_state(&self.key).await?;
+        }
+        let keep_versions = self.versions.len() + 1;
+        for version in &self.versions {
+            let write = TfStateWrite {
+                run_id: version.meta.run_id,
+                restored_from: version.meta.restored_from,
+                keep_versions,
+                // The blob may be ciphertext from an encrypted store, so the
+                // header and size come from the recorded version, not the blob.
+                header: Some(TfStateHeader {
+                    serial: version.meta.serial,
+                    lineage: version.meta.lineage.clone(),
+                }),
+                size: Some(version.meta.size),
+                version: Some(version.meta.version),
+                created_at: Some(version.meta.created_at),
+                guard: None,
+            };
+            store.put_tf_state(&self.key, version.decode(&self.key)?, &write).await?;
+        }
+        if self.versions.last().map(|v| &v.sha256) != Some(&self.sha256) {
+            let write = TfStateWrite { keep_versions, ..Default::default() };
+            store.put_tf_state(&self.key, self.decode()?, &write).await?;
+        }
+        Ok(())
+    }
+}
+
+impl TfStateVersionEntry {
+    fn decode(&self, key: &str) -> Result<Vec<u8>, StoreError> {
+        base64::engine::general_purpose::STANDARD.decode(&self.state).map_err(|e| {
+            StoreError::InvalidArchive(format!("tf state '{}' version {}: {}", key, self.meta.version, e))
+        })
+    }
+}
+
+/// The records an import overwrites, as they were before it. `None` means
+/// the record did not exist.
+struct Snapshot {
+    enclaves: Vec<(EnclaveId, Option<EnclaveState>)>,
+    tf_state: Vec<(String, Option<TfStateEntry>)>,
+    tf_locks: Vec<(String, Option<serde_json::Value>)>,
+    iac_runs: Vec<(Uuid, Option<IacRun>)>,
+    api_tokens: Vec<(String, Option<ApiToken>)>,
+}
+
+impl Snapshot {
+    async fn take(archive: &StateArchive, store: &dyn StateStore) -> Result<Self, StoreError> {
+        let mut enclaves = Vec::new();
+        for enclave in &archive.enclaves {
+            let id = enclave.desired.id.clone();
+            enclaves.push((id.clone(), store.get_enclave(&id).await?));
+        }
+        let mut tf_state = Vec::new();
+        for entry in &archive.tf_state {
+            tf_state.push((entry.key.clone(), TfStateEntry::read(store, entry.key.clone()).await?));
+        }
+        let mut locks: HashMap<String, serde_json::Value> = store.list_tf_locks().await?.into_iter().collect();
+        let tf_locks = archive.tf_locks.iter().map(|l| (l.key.clone(), locks.remove(&l.key))).collect();
+        let mut iac_runs = Vec::new();
+        for run in &archive.iac_runs {
+            iac_runs.push((run.id, store.get_iac_run(run.id).await?));
+        }
+        let mut tokens: HashMap<String, ApiToken> =
+            store.list_api_tokens().await?.into_iter().map(|t| (t.name.clone(), t)).collect();
+        let api_tokens = archive.api_tokens.iter().map(|t| (t.name.clone(), tokens.remove(&t.name))).collect();
+        Ok(Self { enclaves, tf_state, tf_locks, iac_runs, api_tokens })
+    }
+
+    /// Put every snapshotted record back, deleting those that did not exist.
+    async fn restore(&self, store: &dyn StateStore) -> Result<(), StoreError> {
+        for (name, token) in &self.api_tokens {
+            store.delete_api_token(name).await?;
+            if let Some(token) = token {
+                store.create_api_token(token).await?;
+            }
+        }
+        let added: Vec<Uuid> = self.iac_runs.iter().filter(|(_, r)| r.is_none()).map(|(id, _)| *id).collect();
+        store.delete_iac_runs(&added).await?;
+        for run in self.iac_runs.iter().filter_map(|(_, r)| r.as_ref()) {
+            store.upsert_iac_run(run).await?;
+        }
+        for (key, lock_info) in &self.tf_locks {
+            store.force_unlock_tf_state(key).await?;
+            if let Some(lock_info) = lock_info {
+                store.lock_tf_state(key, lock_info.clone()).await?;
+            }
+        }
+        for (key, entry) in &self.tf_state {
+            store.delete_tf_state(key).await?;
+            if let Some(entry) = entry {
+                entry.import_into(store, false).await?;
+            }
+        }
+        for (id, enclave) in &self.enclaves {
+            match enclave {
+                Some(enclave) => store.upsert_enclave(enclave).await?,
+                None => store.delete_enclave(id).await?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reject the archive if two of its records share a key.
+fn check_unique(what: &str, keys: impl IntoIterator<Item = String>) -> Result<(), StoreError> {
+    let mut seen = HashSet::new();
+    for key in keys {
+        if !seen.insert(key.clone()) {
+            return Err(StoreError::InvalidArchive(format!("{} '{}' appears more than once", what, key)));
+        }
+    }
+    Ok(())
+}
+
+/// Human-readable counts of whatever `store` already holds; empty if nothing.
+async fn describe_contents(store: &dyn StateStore) -> Result<Vec<String>, StoreError> {
+    let counts = [
+        (store.list_enclaves().await?.len(), "enclave(s)"),
+        (store.list_tf_state_keys().await?.len(), "tf state blob(s)"),
+        (store.list_tf_locks().await?.len(), "tf lock(s)"),
+        (store.list_all_iac_runs().await?.len(), "IaC run(s)"),
+        (store.list_api_tokens().await?.len(), "API token(s)"),
+        (store.list_events(None, u32::MAX).await?.len(), "audit event(s)"),
+    ];
+    Ok(counts
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, what)| format!("{} {}", n, what))
+        .collect())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use crate::redb_store::RedbStore;
+    use crate::sqlite_store::SqliteStore;
+    use crate::state::{IacOperation, IacRunStatus, PartitionState};
+    use nclav_domain::*;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn enclave(id: &str) -> EnclaveState {
+        let mut state = EnclaveState::new(Enclave {
+            id: EnclaveId::new(id),
+            name: id.to_string(),
+            cloud: Some(CloudTarget::Local),
+            region: "local".to_string(),
+            ..Default::default()
+        });
+        for p in ["db", "api"] {
+            let part = PartitionState::new(Partition {
+                id: PartitionId::new(p),
+                name: p.to_string(),
+                produces: None,
+                imports: vec![],
+                exports: vec![],
+                inputs: Default::default(),
+                declared_outputs: vec![],
+                backend: PartitionBackend::default(),
+            });
+            state.partitions.insert(part.desired.id.clone(), part);
+        }
+        state
+    }
+
+    async fn populate(store: &dyn StateStore) -> Uuid {
+        store.upsert_enclave(&enclave("a")).await.unwrap();
+        store.upsert_enclave(&enclave("b")).await.unwrap();
+        store.put_tf_state("a/db", b"{\"version\":4}".to_vec(), &TfStateWrite::default()).await.unwrap();
+        store.lock_tf_state("a/db", serde_json::json!({ "ID": "l1" })).await.unwrap();
+        store
+            .append_event(&AuditEvent::EnclaveProvisioned {
+                id: Uuid::new_v4(),
+                at: Utc::now(),
+                context: Default::default(),
+                enclave_id: EnclaveId::new("a"),
+            })
+            .await
+            .unwrap();
+        let run = IacRun {
+            id: Uuid::new_v4(),
+            enclave_id: EnclaveId::new("a"),
+            partition_id: PartitionId::new("db"),
+            operation: IacOperation::Provision,
+            started_at: Utc::now(),
+            finished_at: None,
+            status: IacRunStatus::Succeeded,
+            exit_code: Some(0),
+            log: "ok".into(),
+            reconcile_run_id: None,
+            trace_id: None,
+        };
+        store.upsert_iac_run(&run).await.unwrap();
+        let (token, _) = ApiToken::generate("ci", vec![crate::tokens::Scope::Apply], vec![], None);
+        store.create_api_token(&token).await.unwrap();
+        run.id
+    }
+
+    #[tokio::test]
+    async fn round_trips_between_backends() {
+        let dir = TempDir::new().unwrap();
+        let source = RedbStore::open(&dir.path().join("state.redb")).unwrap();
+        let run_id = populate(&source).await;
+
+        let bytes = serde_json::to_vec(&StateArchive::export(&source).await.unwrap()).unwrap();
+        let archive = StateArchive::from_slice(&bytes).unwrap();
+
+        let target = SqliteStore::open(&dir.path().join("state.db")).await.unwrap();
+        let summary = archive.import_into(&target, false).await.unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                enclaves: 2,
+                events: 1,
+                tf_state: 1,
+                tf_state_versions: 1,
+                tf_locks: 1,
+                iac_runs: 1,
+                api_tokens: 1,
+            }
+        );
+
+        let enc = target.get_enclave(&EnclaveId::new("a")).await.unwrap().unwrap();
+        assert_eq!(enc.partitions.len(), 2);
+        assert_eq!(target.get_tf_state("a/db").await.unwrap().unwrap(), b"{\"version\":4}");
+        assert!(target.lock_tf_state("a/db", serde_json::json!({ "ID": "l2" })).await.is_err());
+        assert!(target.get_iac_run(run_id).await.unwrap().is_some());
+        assert_eq!(target.list_events(None, 10).await.unwrap().len(), 1);
+        let token = &target.list_api_tokens().await.unwrap()[0];
+        assert_eq!(target.find_api_token(&token.token_hash).await.unwrap().as_ref(), Some(token));
+    }
+
+    #[tokio::test]
+    async fn tf_state_history_survives_a_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let source = RedbStore::open(&dir.path().join("state.redb")).unwrap();
+        let keep_two = TfStateWrite { keep_versions: 2, ..Default::default() };
+        for serial in 1..=3 {
+            let blob = serde_json::json!({ "version": 4, "serial": serial, "lineage": "abc" });
+            source.put_tf_state("a/db", blob.to_string().into_bytes(), &keep_two).await.unwrap();
+        }
+        let restore = TfStateWrite { run_id: Some(Uuid::new_v4()), restored_from: Some(2), ..keep_two };
+        let old = source.get_tf_state_version("a/db", 2).await.unwrap().unwrap();
+        source.put_tf_state("a/db", old, &restore).await.unwrap();
+
+        let bytes = serde_json::to_vec(&StateArchive::export(&source).await.unwrap()).unwrap();
+        let archive = StateArchive::from_slice(&bytes).unwrap();
+        let target = SqliteStore::open(&dir.path().join("state.db")).await.unwrap();
+        target.put_tf_state("a/db", b"{}".to_vec(), &TfStateWrite::default()).await.unwrap();
+        let summary = archive.import_into(&target, true).await.unwrap();
+        assert_eq!(summary.tf_state_versions, 2);
+
+        let versions = target.list_tf_state_versions("a/db").await.unwrap();
+        assert_eq!(versions, source.list_tf_state_versions("a/db").await.unwrap());
+        assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), vec![4, 3]);
+        assert_eq!(versions[0].serial, Some(2));
+        assert_eq!(versions[0].restored_from, Some(2));
+        assert_eq!(versions[1].lineage.as_deref(), Some("abc"));
+        for v in &versions {
+            assert_eq!(
+                target.get_tf_state_version("a/db", v.version).await.unwrap(),
+                source.get_tf_state_version("a/db", v.version).await.unwrap()
+            );
+        }
+        assert_eq!(target.get_tf_state("a/db").await.unwrap(), source.get_tf_state("a/db").await.unwrap());
+
+        // Numbering carries on from the imported history.
+        let next = target.put_tf_state("a/db", b"{}".to_vec(), &TfStateWrite::default()).await.unwrap();
+        assert_eq!(next.version, 5);
+    }
+
+    #[tokio::test]
+    async fn tampered_archive_is_rejected() {
+        let source = InMemoryStore::new();
+        populate(&source).await;
+        let archive = StateArchive::export(&source).await.unwrap();
+
+        let mut edited = archive.clone();
+        edited.enclaves[0].desired.region = "elsewhere".into();
+        let err = StateArchive::from_slice(&serde_json::to_vec(&edited).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{err}");
+
+        // A blob swapped along with a recomputed archive checksum still fails.
+        let mut swapped = archive.clone();
+        swapped.tf_state[0].state = base64::engine::general_purpose::STANDARD.encode(b"{}");
+        swapped.checksum = swapped.compute_checksum().unwrap();
+        let err = swapped.verify().unwrap_err();
+        assert!(err.to_string().contains("'a/db'"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn non_empty_target_requires_force() {
+        let source = InMemoryStore::new();
+        populate(&source).await;
+        let archive = StateArchive::export(&source).await.unwrap();
+
+        let target = InMemoryStore::new();
+        target.upsert_enclave(&enclave("a")).await.unwrap();
+        target.lock_tf_state("a/db", serde_json::json!({ "ID": "stale" })).await.unwrap();
+
+        let err = archive.import_into(&target, false).await.unwrap_err();
+        assert!(matches!(err, StoreError::TargetNotEmpty(ref s) if s.contains("1 enclave(s)")), "{err}");
+        assert_eq!(target.list_enclaves().await.unwrap().len(), 1);
+
+        archive.import_into(&target, true).await.unwrap();
+        assert_eq!(target.list_enclaves().await.unwrap().len(), 2);
+        assert_eq!(target.list_tf_locks().await.unwrap()[0].1["ID"], "l1");
+    }
+
+    #[tokio::test]
+    async fn duplicate_records_are_rejected_before_writing() {
+        let source = InMemoryStore::new();
+        populate(&source).await;
+        let mut archive = StateArchive::export(&source).await.unwrap();
+        let token = archive.api_tokens[0].clone();
+        archive.api_tokens.push(token);
+        archive.checksum = archive.compute_checksum().unwrap();
+
+        let target = InMemoryStore::new();
+        let err = archive.import_into(&target, false).await.unwrap_err();
+        assert!(err.to_string().contains("API token 'ci' appears more than once"), "{err}");
+        assert!(describe_contents(&target).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_import_is_rolled_back() {
+        let dir = TempDir::new().unwrap();
+        let source = InMemoryStore::new();
+        populate(&source).await;
+        let archive = StateArchive::export(&source).await.unwrap();
+
+        let path = dir.path().join("state.db");
+        let target = SqliteStore::open(&path).await.unwrap();
+        let mut before = enclave("a");
+        before.desired.region = "before".into();
+        target.upsert_enclave(&before).await.unwrap();
+        target.put_tf_state("a/db", b"{}".to_vec(), &TfStateWrite::default()).await.unwrap();
+        target.lock_tf_state("a/db", serde_json::json!({ "ID": "held" })).await.unwrap();
+
+        // Audit events are written last; make them fail.
+        let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+        sqlx::query("CREATE TRIGGER no_events BEFORE INSERT ON audit_events BEGIN SELECT RAISE(ABORT, 'read-only'); END")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = archive.import_into(&target, true).await.unwrap_err();
+        assert!(err.to_string().contains("read-only"), "{err}");
+
+        let enclaves = target.list_enclaves().await.unwrap();
+        assert_eq!(enclaves.len(), 1);
+        assert_eq!(enclaves[0].desired.region, "before");
+        assert_eq!(target.get_tf_state("a/db").await.unwrap().unwrap(), b"{}");
+        assert_eq!(target.list_tf_state_versions("a/db").await.unwrap().len(), 1);
+        assert_eq!(target.list_tf_locks().await.unwrap()[0].1["ID"], "held");
+        assert!(target.list_all_iac_runs().await.unwrap().is_empty());
+        assert!(target.list_api_tokens().await.unwrap().is_empty());
+    }
+}
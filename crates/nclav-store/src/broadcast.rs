@@ -0,0 +1,264 @@
+//! In-process fan-out of audit events.
+//!
+//! [`BroadcastStore`] wraps any [`StateStore`] and publishes every event it
+//! appends on an [`EventBus`], which feeds `GET /events/stream` and webhook
+//! delivery. Publishing happens after the event is stored, so subscribers only
+//! ever see events that are also in the audit log.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use nclav_domain::{EnclaveId, PartitionId};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use crate::events::{DeadLetter, EventPage, EventQuery};
+use crate::state::{AuditEvent, EnclaveState, IacRun, IacRunSummary, PartitionState};
+use crate::store::StateStore;
+use crate::tf_state::{TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
+
+/// Events buffered per subscriber before a slow one starts missing events.
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Broadcast channel of appended audit events. Cloning shares the channel.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<AuditEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    /// Receive every event published from now on. A receiver that falls more
+    /// than the bus capacity behind gets `RecvError::Lagged` and skips ahead.
+    pub fn subscribe(&self) -> broadcast::Receiver<AuditEvent> {
+        self.tx.subscribe()
+    }
+
+    fn publish(&self, event: &AuditEvent) {
+        // No subscribers is not an error.
+        let _ = self.tx.send(event.clone());
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_BUS_CAPACITY)
+    }
+}
+
+/// A [`StateStore`] that publishes every appended event on an [`EventBus`].
+/// Everything else passes through to `inner` unchanged.
+pub struct BroadcastStore {
+    inner: Arc<dyn StateStore>,
+    bus: EventBus,
+}
+
+impl BroadcastStore {
+    pub fn new(inner: Arc<dyn StateStore>, bus: EventBus) -> Self {
+        Self { inner, bus }
+    }
+}
+
+#[async_trait]
+impl StateStore for BroadcastStore {
+    async fn get_enclave(&self, id: &EnclaveId) -> Result<Option<EnclaveState>, StoreError> {
+        self.inner.get_enclave(id).await
+    }
+
+    async fn list_enclaves(&self) -> Result<Vec<EnclaveState>, StoreError> {
+        self.inner.list_enclaves().await
+    }
+
+    async fn upsert_enclave(&self, state: &EnclaveState) -> Result<(), StoreError> {
+        self.inner.upsert_enclave(state).await
+    }
+
+    async fn delete_enclave(&self, id: &EnclaveId) -> Result<(), StoreError> {
+        self.inner.delete_enclave(id).await
+    }
+
+    async fn upsert_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        state: &PartitionState,
+    ) -> Result<(), StoreError> {
+        self.inner.upsert_partition(enclave_id, state).await
+    }
+
+    async fn delete_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+    ) -> Result<(), StoreError> {
+        self.inner.delete_partition(enclave_id, partition_id).await
+    }
+
+    async fn append_event(&self, event: &AuditEvent) -> Result<(), StoreError> {
+        self.inner.append_event(event).await?;
+        self.bus.publish(event);
+        Ok(())
+    }
+
+    async fn append_events(&self, events: &[AuditEvent]) -> Result<(), StoreError> {
+        self.inner.append_events(events).await?;
+        for event in events {
+            self.bus.publish(event);
+        }
+        Ok(())
+    }
+
+    async fn query_events(&self, query: &EventQuery) -> Result<EventPage, StoreError> {
+        self.inner.query_events(query).await
+    }
+
+    async fn prune_events(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<u64, StoreError> {
+        self.inner.prune_events(cutoff, dry_run).await
+    }
+
+    async fn get_tf_state(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        self.inner.get_tf_state(key).await
+    }
+
+    async fn put_tf_state(
+        &self,
+        key: &str,
+        state: Vec<u8>,
+        write: &TfStateWrite,
+    ) -> Result<TfStateVersion, StoreError> {
+        self.inner.put_tf_state(key, state, write).await
+    }
+
+    async fn delete_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        self.inner.delete_tf_state(key).await
+    }
+
+    async fn list_tf_state_versions(&self, key: &str) -> Result<Vec<TfStateVersion>, StoreError> {
+        self.inner.list_tf_state_versions(key).await
+    }
+
+    async fn get_tf_state_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        self.inner.get_tf_state_version(key, version).await
+    }
+
+    async fn rewrite_tf_state(
+        &self,
+        key: &str,
+        version: Option<u64>,
+        state: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        self.inner.rewrite_tf_state(key, version, state).await
+    }
+
+    async fn lock_tf_state(
+        &self,
+        key: &str,
+        lock_info: serde_json::Value,
+    ) -> Result<(), StoreError> {
+        self.inner.lock_tf_state(key, lock_info).await
+    }
+
+    async fn unlock_tf_state(&self, key: &str, lock_id: &str) -> Result<(), StoreError> {
+        self.inner.unlock_tf_state(key, lock_id).await
+    }
+
+    async fn force_unlock_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        self.inner.force_unlock_tf_state(key).await
+    }
+
+    async fn list_tf_state_keys(&self) -> Result<Vec<String>, StoreError> {
+        self.inner.list_tf_state_keys().await
+    }
+
+    async fn list_tf_locks(&self) -> Result<Vec<(String, serde_json::Value)>, StoreError> {
+        self.inner.list_tf_locks().await
+    }
+
+    async fn upsert_iac_run(&self, run: &IacRun) -> Result<(), StoreError> {
+        self.inner.upsert_iac_run(run).await
+    }
+
+    async fn list_iac_runs(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+    ) -> Result<Vec<IacRun>, StoreError> {
+        self.inner.list_iac_runs(enclave_id, partition_id).await
+    }
+
+    async fn get_iac_run(&self, run_id: Uuid) -> Result<Option<IacRun>, StoreError> {
+        self.inner.get_iac_run(run_id).await
+    }
+
+    async fn list_all_iac_runs(&self) -> Result<Vec<IacRun>, StoreError> {
+        self.inner.list_all_iac_runs().await
+    }
+
+    async fn list_iac_run_summaries(&self) -> Result<Vec<IacRunSummary>, StoreError> {
+        self.inner.list_iac_run_summaries().await
+    }
+
+    async fn delete_iac_runs(&self, run_ids: &[Uuid]) -> Result<(), StoreError> {
+        self.inner.delete_iac_runs(run_ids).await
+    }
+
+    async fn append_dead_letter(&self, letter: &DeadLetter) -> Result<(), StoreError> {
+        self.inner.append_dead_letter(letter).await
+    }
+
+    async fn list_dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, StoreError> {
+        self.inner.list_dead_letters(limit).await
+    }
+
+    async fn create_api_token(&self, token: &ApiToken) -> Result<(), StoreError> {
+        self.inner.create_api_token(token).await
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, StoreError> {
+        self.inner.find_api_token(token_hash).await
+    }
+
+    async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, StoreError> {
+        self.inner.list_api_tokens().await
+    }
+
+    async fn delete_api_token(&self, name: &str) -> Result<bool, StoreError> {
+        self.inner.delete_api_token(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use crate::state::EventContext;
+
+    #[tokio::test]
+    async fn appended_events_reach_subscribers_after_they_are_stored() {
+        let bus = EventBus::new(8);
+        let store = BroadcastStore::new(Arc::new(InMemoryStore::new()), bus.clone());
+        let mut rx = bus.subscribe();
+
+        let event = AuditEvent::EnclaveDeleted {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: EventContext::controller(),
+            enclave_id: EnclaveId::new("enc"),
+        };
+        store.append_event(&event).await.unwrap();
+
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.kind(), "EnclaveDeleted");
+        assert_eq!(store.list_events(None, 10).await.unwrap().len(), 1);
+    }
+}
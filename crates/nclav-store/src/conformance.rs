@@ -0,0 +1,294 @@
+//! Behaviour every [`StateStore`] backend must share.
+//!
+//! Each backend's test module calls these checks against its own store.
+//! Records are named after `ns`, so the checks can run against a shared
+//! database without seeing each other's rows.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use nclav_domain::*;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use crate::events::EventQuery;
+use crate::state::{AuditEvent, EnclaveState, EventContext, IacOperation, IacRun, IacRunStatus, IacRunSummary, PartitionState};
+use crate::store::StateStore;
+use crate::tf_state::{TfStateGuard, TfStateWrite};
+use crate::tokens::{hash_token, ApiToken, Scope};
+
+fn enclave(id: &str) -> EnclaveState {
+    EnclaveState::new(Enclave {
+        id: EnclaveId::new(id),
+        name: id.to_string(),
+        region: "local".to_string(),
+        ..Default::default()
+    })
+}
+
+fn partition(id: &str) -> PartitionState {
+    PartitionState::new(Partition {
+        id: PartitionId::new(id),
+        name: id.to_string(),
+        produces: None,
+        imports: vec![],
+        exports: vec![],
+        inputs: Default::default(),
+        declared_outputs: vec![],
+        backend: PartitionBackend::default(),
+    })
+}
+
+fn run(enclave: &str, partition: &str, started_at: DateTime<Utc>) -> IacRun {
+    IacRun {
+        id: Uuid::new_v4(),
+        enclave_id: EnclaveId::new(enclave),
+        partition_id: PartitionId::new(partition),
+        operation: IacOperation::Provision,
+        started_at,
+        finished_at: None,
+        status: IacRunStatus::Succeeded,
+        exit_code: Some(0),
+        log: "ok".into(),
+        reconcile_run_id: None,
+        trace_id: None,
+    }
+}
+
+fn state(serial: u64, lineage: &str) -> Vec<u8> {
+    json!({ "version": 4, "serial": serial, "lineage": lineage }).to_string().into_bytes()
+}
+
+pub(crate) async fn check_enclaves(store: &impl StateStore, ns: &str) {
+    let (a, b) = (format!("{ns}-a"), format!("{ns}-b"));
+    store.upsert_enclave(&enclave(&b)).await.unwrap();
+    store.upsert_enclave(&enclave(&a)).await.unwrap();
+    let got = store.get_enclave(&EnclaveId::new(&a)).await.unwrap();
+    assert_eq!(got.unwrap().desired.id.as_str(), a);
+
+    let ids: Vec<String> = store
+        .list_enclaves()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.desired.id.0)
+        .filter(|id| id.starts_with(ns))
+        .collect();
+    assert_eq!(ids, [a.clone(), b.clone()]);
+
+    store.delete_enclave(&EnclaveId::new(&a)).await.unwrap();
+    assert!(store.get_enclave(&EnclaveId::new(&a)).await.unwrap().is_none());
+    store.delete_enclave(&EnclaveId::new(&b)).await.unwrap();
+}
+
+pub(crate) async fn check_partitions(store: &impl StateStore, ns: &str) {
+    let eid = EnclaveId::new(format!("{ns}-enc"));
+    store.upsert_enclave(&enclave(eid.as_str())).await.unwrap();
+
+    let part = partition("db");
+    store.upsert_partition(&eid, &part).await.unwrap();
+    let fetched = store.get_enclave(&eid).await.unwrap().unwrap();
+    assert!(fetched.partitions.contains_key(&part.desired.id));
+
+    store.delete_partition(&eid, &part.desired.id).await.unwrap();
+    let after = store.get_enclave(&eid).await.unwrap().unwrap();
+    assert!(!after.partitions.contains_key(&part.desired.id));
+
+    let missing = store.upsert_partition(&EnclaveId::new(format!("{ns}-nope")), &part).await;
+    assert!(matches!(missing, Err(StoreError::EnclaveNotFound(_))));
+    store.delete_enclave(&eid).await.unwrap();
+}
+
+pub(crate) async fn check_events(store: &impl StateStore, ns: &str) {
+    let (a, b) = (EnclaveId::new(format!("{ns}-a")), EnclaveId::new(format!("{ns}-b")));
+    let run_id = Uuid::new_v4();
+    let start = Utc::now();
+    for (i, part) in ["x", "y", "x", "x"].into_iter().enumerate() {
+        store
+            .append_event(&AuditEvent::PartitionDeleted {
+                id: Uuid::new_v4(),
+                at: start + TimeDelta::seconds(i as i64),
+                context: EventContext { run_id: Some(run_id), ..Default::default() },
+                enclave_id: a.clone(),
+                partition_id: PartitionId::new(part),
+            })
+            .await
+            .unwrap();
+    }
+    let batch = [
+        AuditEvent::EnclaveDeleted { id: Uuid::new_v4(), at: start, context: Default::default(), enclave_id: a.clone() },
+        AuditEvent::EnclaveDeleted { id: Uuid::new_v4(), at: start, context: Default::default(), enclave_id: b.clone() },
+    ];
+    store.append_events(&batch).await.unwrap();
+
+    let for_a = store.list_events(Some(&a), 100).await.unwrap();
+    assert_eq!(for_a.len(), 5);
+    assert_eq!(for_a.last().unwrap().kind(), "EnclaveDeleted");
+    assert_eq!(store.list_events(Some(&b), 100).await.unwrap().len(), 1);
+
+    // Pages run backwards from the newest match.
+    let query = EventQuery {
+        partition_id: Some(PartitionId::new("x")),
+        kind: Some("PartitionDeleted".into()),
+        run_id: Some(run_id),
+        limit: 2,
+        ..Default::default()
+    };
+    let newest = store.query_events(&query).await.unwrap();
+    assert_eq!(newest.events.len(), 2);
+    assert_eq!(newest.events[1].at(), start + TimeDelta::seconds(3));
+    let cursor = newest.next_cursor.expect("an older page exists");
+    let older = store.query_events(&EventQuery { before: Some(cursor), ..query.clone() }).await.unwrap();
+    assert_eq!(older.events.len(), 1);
+    assert_eq!(older.events[0].at(), start);
+    assert_eq!(older.next_cursor, None);
+
+    let windowed = EventQuery {
+        enclave_id: Some(a.clone()),
+        since: Some(start + TimeDelta::seconds(1)),
+        until: Some(start + TimeDelta::seconds(3)),
+        ..Default::default()
+    };
+    let parts: Vec<_> = store
+        .query_events(&windowed)
+        .await
+        .unwrap()
+        .events
+        .iter()
+        .map(|e| e.partition_id().unwrap().0.clone())
+        .collect();
+    assert_eq!(parts, ["y", "x"]);
+}
+
+pub(crate) async fn check_tf_state(store: &impl StateStore, ns: &str) {
+    let key = format!("{ns}/part");
+    assert!(store.get_tf_state(&key).await.unwrap().is_none());
+    store.put_tf_state(&key, b"v1".to_vec(), &TfStateWrite::default()).await.unwrap();
+    store.put_tf_state(&key, b"v2".to_vec(), &TfStateWrite::default()).await.unwrap();
+    assert_eq!(store.get_tf_state(&key).await.unwrap().unwrap(), b"v2");
+    assert!(store.list_tf_state_keys().await.unwrap().contains(&key));
+    store.delete_tf_state(&key).await.unwrap();
+    assert!(store.get_tf_state(&key).await.unwrap().is_none());
+}
+
+pub(crate) async fn check_tf_state_history(store: &impl StateStore, ns: &str) {
+    let key = format!("{ns}/part");
+    let write = TfStateWrite { run_id: Some(Uuid::new_v4()), keep_versions: 2, ..Default::default() };
+    for serial in 1..=3 {
+        let v = store.put_tf_state(&key, state(serial, "abc"), &write).await.unwrap();
+        assert_eq!(v.version, serial);
+    }
+
+    let versions = store.list_tf_state_versions(&key).await.unwrap();
+    let numbers: Vec<u64> = versions.iter().map(|v| v.version).collect();
+    assert_eq!(numbers, vec![3, 2]);
+    assert_eq!(versions[0].serial, Some(3));
+    assert_eq!(versions[0].lineage.as_deref(), Some("abc"));
+    assert_eq!(versions[0].run_id, write.run_id);
+    assert!(store.get_tf_state_version(&key, 1).await.unwrap().is_none());
+    assert_eq!(store.get_tf_state_version(&key, 2).await.unwrap().unwrap(), state(2, "abc"));
+
+    store.delete_tf_state(&key).await.unwrap();
+    assert!(store.list_tf_state_versions(&key).await.unwrap().is_empty());
+}
+
+/// A guarded write must hold the current lock and must not replace the
+/// stored state with an older serial or another lineage. Refused writes
+/// leave the state and its history untouched.
+pub(crate) async fn check_tf_state_guard(store: &impl StateStore, ns: &str) {
+    let key = format!("{ns}/part");
+    let guarded = |lock_id: Option<&str>| TfStateWrite {
+        guard: Some(TfStateGuard { lock_id: lock_id.map(String::from) }),
+        ..Default::default()
+    };
+    store.put_tf_state(&key, state(1, "abc"), &guarded(None)).await.unwrap();
+
+    store.lock_tf_state(&key, json!({ "ID": "held" })).await.unwrap();
+    for lock_id in [None, Some("other")] {
+        let err = store.put_tf_state(&key, state(2, "abc"), &guarded(lock_id)).await.unwrap_err();
+        assert!(matches!(err, StoreError::LockConflict { ref holder } if holder["ID"] == "held"), "{err:?}");
+    }
+    store.put_tf_state(&key, state(2, "abc"), &guarded(Some("held"))).await.unwrap();
+    for stale in [state(1, "abc"), state(3, "xyz")] {
+        let err = store.put_tf_state(&key, stale, &guarded(Some("held"))).await.unwrap_err();
+        assert!(matches!(err, StoreError::StaleState(_)), "{err:?}");
+    }
+
+    store.unlock_tf_state(&key, "held").await.unwrap();
+    let err = store.put_tf_state(&key, state(3, "abc"), &guarded(Some("held"))).await.unwrap_err();
+    assert!(matches!(err, StoreError::StaleState(_)), "{err:?}");
+
+    assert_eq!(store.get_tf_state(&key).await.unwrap().unwrap(), state(2, "abc"));
+    assert_eq!(store.list_tf_state_versions(&key).await.unwrap().len(), 2);
+    // Unguarded writes, such as restores, skip the checks.
+    store.put_tf_state(&key, state(1, "abc"), &TfStateWrite::default()).await.unwrap();
+    store.delete_tf_state(&key).await.unwrap();
+}
+
+pub(crate) async fn check_tf_locks(store: &impl StateStore, ns: &str) {
+    let key = format!("{ns}/part");
+    store.lock_tf_state(&key, json!({ "ID": "lock-aaa", "Operation": "plan" })).await.unwrap();
+    let err = store.lock_tf_state(&key, json!({ "ID": "lock-bbb", "Operation": "apply" })).await.unwrap_err();
+    match err {
+        StoreError::LockConflict { holder } => assert_eq!(holder["ID"], "lock-aaa"),
+        other => panic!("expected LockConflict, got {other:?}"),
+    }
+    let locks = store.list_tf_locks().await.unwrap();
+    assert!(locks.iter().any(|(k, info)| k == &key && info["ID"] == "lock-aaa"));
+
+    // A wrong or empty ID leaves the lock in place.
+    for wrong in ["lock-bbb", ""] {
+        store.unlock_tf_state(&key, wrong).await.unwrap();
+        assert!(store.lock_tf_state(&key, json!({ "ID": "x" })).await.is_err());
+    }
+
+    store.unlock_tf_state(&key, "lock-aaa").await.unwrap();
+    store.lock_tf_state(&key, json!({ "ID": "lock-ccc" })).await.unwrap();
+    store.force_unlock_tf_state(&key).await.unwrap();
+    store.force_unlock_tf_state(&key).await.unwrap();
+    store.lock_tf_state(&key, json!({ "ID": "lock-ddd" })).await.unwrap();
+    store.force_unlock_tf_state(&key).await.unwrap();
+}
+
+pub(crate) async fn check_iac_runs(store: &impl StateStore, ns: &str) {
+    let enc = format!("{ns}-enc");
+    let now = Utc::now();
+    let older = run(&enc, "part", now - TimeDelta::seconds(60));
+    let newer = run(&enc, "part", now);
+    let other = run(&enc, "other", now);
+    for r in [&older, &newer, &other] {
+        store.upsert_iac_run(r).await.unwrap();
+    }
+    let mut updated = newer.clone();
+    updated.log = "done".into();
+    store.upsert_iac_run(&updated).await.unwrap();
+
+    let runs = store.list_iac_runs(&EnclaveId::new(&enc), &PartitionId::new("part")).await.unwrap();
+    let ids: Vec<Uuid> = runs.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![newer.id, older.id]);
+    assert_eq!(store.get_iac_run(newer.id).await.unwrap().unwrap().log, "done");
+    assert!(store.get_iac_run(Uuid::new_v4()).await.unwrap().is_none());
+
+    store.delete_iac_runs(&[older.id, other.id, Uuid::new_v4()]).await.unwrap();
+    let mine = |id: &EnclaveId| id.as_str() == enc;
+    let ids: Vec<Uuid> =
+        store.list_all_iac_runs().await.unwrap().iter().filter(|r| mine(&r.enclave_id)).map(|r| r.id).collect();
+    assert_eq!(ids, vec![newer.id]);
+    let summaries: Vec<IacRunSummary> =
+        store.list_iac_run_summaries().await.unwrap().into_iter().filter(|s| mine(&s.enclave_id)).collect();
+    assert_eq!(summaries, vec![IacRunSummary::from(&updated)]);
+    store.delete_iac_runs(&[newer.id]).await.unwrap();
+}
+
+pub(crate) async fn check_api_tokens(store: &impl StateStore, ns: &str) {
+    let name = format!("{ns}-ci");
+    let (token, secret) = ApiToken::generate(&name, vec![Scope::Read], vec![], None);
+    store.create_api_token(&token).await.unwrap();
+    let (dup, _) = ApiToken::generate(&name, vec![], vec![], None);
+    assert!(matches!(store.create_api_token(&dup).await, Err(StoreError::Conflict(_))));
+
+    assert_eq!(store.find_api_token(&hash_token(&secret)).await.unwrap(), Some(token.clone()));
+    assert!(store.list_api_tokens().await.unwrap().contains(&token));
+    assert!(store.delete_api_token(&name).await.unwrap());
+    assert!(!store.delete_api_token(&name).await.unwrap());
+    assert!(store.find_api_token(&hash_token(&secret)).await.unwrap().is_none());
+}
@@ -0,0 +1,747 @@
+//! Envelope encryption at rest.
+//!
+//! [`EncryptedStore`] wraps any [`StateStore`] and encrypts Terraform state
+//! blobs, driver handles and partition outputs before they reach it. Each value is sealed with a
+//! fresh AES-256-GCM data key, and that data key is wrapped by a
+//! [`KeyEncryptionKey`] and stored inside the envelope next to the ciphertext.
+//! Only the key-encryption key has to be kept secret.
+//!
+//! Values written before encryption was enabled are still read as plaintext.
+//! [`EncryptedStore::reencrypt_all`] moves every row onto the primary key.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use nclav_domain::{EnclaveId, PartitionId};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use crate::events::{DeadLetter, EventPage, EventQuery};
+use crate::state::{AuditEvent, EnclaveState, IacRun, IacRunSummary, PartitionState};
+use crate::store::StateStore;
+use crate::tf_state::{TfStateHeader, TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
+
+/// Prefix of every sealed blob; the trailing byte is the envelope format version.
+const MAGIC: &[u8] = b"NCLAVENC\x01";
+const NONCE_LEN: usize = 12;
+/// A sealed handle or output map is stored as `{"$nclav_encrypted": "<base64 envelope>"}`.
+const HANDLE_FIELD: &str = "$nclav_encrypted";
+
+// ── Key-encryption keys ───────────────────────────────────────────────────────
+
+/// Protects data keys. Local keys are built in; KMS-backed providers implement
+/// the same trait.
+#[async_trait]
+pub trait KeyEncryptionKey: Send + Sync + 'static {
+    /// Stable identifier recorded in every envelope so reads pick the right key.
+    fn id(&self) -> &str;
+
+    /// Encrypt a data key.
+    async fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, StoreError>;
+
+    /// Decrypt a data key produced by [`wrap`](Self::wrap).
+    async fn unwrap(&self, wrapped: &[u8]) -> Result<Vec<u8>, StoreError>;
+}
+
+/// A 256-bit AES key held in process memory, read from a key file or the
+/// `NCLAV_ENCRYPTION_KEY` environment variable.
+pub struct LocalKek {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl LocalKek {
+    /// Build from base64 of exactly 32 bytes, e.g. the output of `openssl rand -base64 32`.
+    pub fn from_base64(encoded: &str) -> Result<Self, StoreError> {
+        let bytes = B64
+            .decode(encoded.trim())
+            .map_err(|e| StoreError::Encryption(format!("key is not valid base64: {}", e)))?;
+        if bytes.len() != 32 {
+            return Err(StoreError::Encryption(format!(
+                "key must be 32 bytes, got {}",
+                bytes.len()
+            )));
+        }
+        // The id is derived from the key so the same key always gets the same id.
+        let digest = Sha256::digest(&bytes);
+        let id = format!("local:{}", digest[..8].iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        Ok(Self { id, cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)) })
+    }
+
+    /// Read a base64 key from `path`. Surrounding whitespace is ignored.
+    pub fn from_file(path: &Path) -> Result<Self, StoreError> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            StoreError::Encryption(format!("cannot read key file {}: {}", path.display(), e))
+        })?;
+        Self::from_base64(&text)
+    }
+}
+
+impl std::fmt::Debug for LocalKek {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalKek").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl KeyEncryptionKey for LocalKek {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, StoreError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, data_key)
+            .map_err(|_| StoreError::Encryption("failed to wrap data key".into()))?;
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    async fn unwrap(&self, wrapped: &[u8]) -> Result<Vec<u8>, StoreError> {
+        if wrapped.len() < NONCE_LEN {
+            return Err(StoreError::Encryption("wrapped data key is truncated".into()));
+        }
+        let (nonce, sealed) = wrapped.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| StoreError::Encryption(format!("key {} cannot unwrap data key", self.id)))
+    }
+}
+
+/// The primary key-encryption key, used for every write, plus retired keys
+/// that are still accepted on read.
+#[derive(Clone)]
+pub struct Keyring {
+    primary: Arc<dyn KeyEncryptionKey>,
+    previous: Vec<Arc<dyn KeyEncryptionKey>>,
+}
+
+impl Keyring {
+    pub fn new(primary: Arc<dyn KeyEncryptionKey>) -> Self {
+        Self { primary, previous: Vec::new() }
+    }
+
+    /// Also accept `kek` when reading values sealed before a rotation.
+    pub fn with_previous(mut self, kek: Arc<dyn KeyEncryptionKey>) -> Self {
+        self.previous.push(kek);
+        self
+    }
+
+    pub fn primary_id(&self) -> &str {
+        self.primary.id()
+    }
+
+    fn get(&self, id: &str) -> Result<&Arc<dyn KeyEncryptionKey>, StoreError> {
+        std::iter::once(&self.primary)
+            .chain(&self.previous)
+            .find(|k| k.id() == id)
+            .ok_or_else(|| {
+                StoreError::Encryption(format!(
+                    "value was encrypted with key {} which is not configured",
+                    id
+                ))
+            })
+    }
+
+    /// Encrypt `plaintext` under a fresh data key. `context` is bound in as
+    /// associated data, so a sealed value only opens at the location it was written for.
+    async fn seal(&self, plaintext: &[u8], context: &str) -> Result<Vec<u8>, StoreError> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let wrapped = self.primary.wrap(&data_key).await?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(&data_key)
+            .encrypt(&nonce, Payload { msg: plaintext, aad: context.as_bytes() })
+            .map_err(|_| StoreError::Encryption(format!("failed to encrypt {}", context)))?;
+
+        let kek_id = self.primary.id().as_bytes();
+        let kek_id_len = u8::try_from(kek_id.len())
+            .map_err(|_| StoreError::Encryption("key id is longer than 255 bytes".into()))?;
+        let wrapped_len = u16::try_from(wrapped.len())
+            .map_err(|_| StoreError::Encryption("wrapped data key is too large".into()))?;
+
+        let mut out = Vec::with_capacity(
+            MAGIC.len() + 3 + kek_id.len() + wrapped.len() + NONCE_LEN + ciphertext.len(),
+        );
+        out.extend_from_slice(MAGIC);
+        out.push(kek_id_len);
+        out.extend_from_slice(kek_id);
+        out.extend_from_slice(&wrapped_len.to_be_bytes());
+        out.extend_from_slice(&wrapped);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypt an envelope produced by [`seal`](Self::seal) with the same `context`.
+    async fn open(&self, envelope: &[u8], context: &str) -> Result<Vec<u8>, StoreError> {
+        let truncated = || StoreError::Encryption(format!("encrypted {} is truncated", context));
+        let rest = envelope.strip_prefix(MAGIC).ok_or_else(truncated)?;
+        let (&id_len, rest) = rest.split_first().ok_or_else(truncated)?;
+        let (kek_id, rest) = split_checked(rest, id_len as usize).ok_or_else(truncated)?;
+        let (wrapped_len, rest) = split_checked(rest, 2).ok_or_else(truncated)?;
+        let wrapped_len = u16::from_be_bytes([wrapped_len[0], wrapped_len[1]]) as usize;
+        let (wrapped, rest) = split_checked(rest, wrapped_len).ok_or_else(truncated)?;
+        let (nonce, ciphertext) = split_checked(rest, NONCE_LEN).ok_or_else(truncated)?;
+
+        let kek_id = std::str::from_utf8(kek_id)
+            .map_err(|_| StoreError::Encryption(format!("encrypted {} has a malformed key id", context)))?;
+        let data_key = self.get(kek_id)?.unwrap(wrapped).await?;
+        if data_key.len() != 32 {
+            return Err(StoreError::Encryption(format!("data key for {} has the wrong length", context)));
+        }
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key))
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: context.as_bytes() })
+            .map_err(|_| StoreError::Encryption(format!("failed to decrypt {}", context)))
+    }
+}
+
+fn split_checked(bytes: &[u8], at: usize) -> Option<(&[u8], &[u8])> {
+    (bytes.len() >= at).then(|| bytes.split_at(at))
+}
+
+// ── EncryptedStore ────────────────────────────────────────────────────────────
+
+/// Record counts rewritten by [`EncryptedStore::reencrypt_all`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReencryptSummary {
+    pub enclaves: usize,
+    pub tf_state: usize,
+    pub tf_state_versions: usize,
+}
+
+impl std::fmt::Display for ReencryptSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} enclave(s), {} tf state blob(s), {} retained tf state version(s)",
+            self.enclaves, self.tf_state, self.tf_state_versions
+        )
+    }
+}
+
+/// A [`StateStore`] that encrypts Terraform state blobs, driver handles
+/// (enclave, partition, export and import) and partition outputs before
+/// handing them to `inner`, and decrypts them on the way out. Everything else passes through unchanged.
+pub struct EncryptedStore {
+    inner: Arc<dyn StateStore>,
+    keys: Keyring,
+}
+
+impl EncryptedStore {
+    pub fn new(inner: Arc<dyn StateStore>, keys: Keyring) -> Self {
+        Self { inner, keys }
+    }
+
+    /// Re-encrypt every handle, partition output map and Terraform state blob, including retained
+    /// versions, under the primary key. Values still in plaintext are encrypted
+    /// too. Rows are rewritten one at a time, so run this with the server stopped.
+    pub async fn reencrypt_all(&self) -> Result<ReencryptSummary, StoreError> {
+        let mut summary = ReencryptSummary::default();
+        for stored in self.inner.list_enclaves().await? {
+            let mut state = stored;
+            self.open_handles(&mut state).await?;
+            self.seal_handles(&mut state).await?;
+            self.inner.upsert_enclave(&state).await?;
+            summary.enclaves += 1;
+        }
+        for key in self.inner.list_tf_state_keys().await? {
+            if let Some(blob) = self.inner.get_tf_state(&key).await? {
+                let plain = self.open_blob(&key, blob).await?;
+                let sealed = self.keys.seal(&plain, &blob_context(&key)).await?;
+                self.inner.rewrite_tf_state(&key, None, sealed).await?;
+                summary.tf_state += 1;
+            }
+            for v in self.inner.list_tf_state_versions(&key).await? {
+                let Some(blob) = self.inner.get_tf_state_version(&key, v.version).await? else {
+                    continue;
+                };
+                let plain = self.open_blob(&key, blob).await?;
+                let sealed = self.keys.seal(&plain, &blob_context(&key)).await?;
+                self.inner.rewrite_tf_state(&key, Some(v.version), sealed).await?;
+                summary.tf_state_versions += 1;
+            }
+        }
+        Ok(summary)
+    }
+
+    async fn open_blob(&self, key: &str, blob: Vec<u8>) -> Result<Vec<u8>, StoreError> {
+        if blob.starts_with(MAGIC) {
+            self.keys.open(&blob, &blob_context(key)).await
+        } else {
+            Ok(blob)
+        }
+    }
+
+    async fn seal_handle(&self, handle: &mut Value, context: &str) -> Result<(), StoreError> {
+        let plain = serde_json::to_vec(handle)?;
+        let sealed = self.keys.seal(&plain, context).await?;
+        *handle = json!({ HANDLE_FIELD: B64.encode(sealed) });
+        Ok(())
+    }
+
+    async fn open_handle(&self, handle: &mut Value, context: &str) -> Result<(), StoreError> {
+        let Some(encoded) = sealed_handle(handle) else {
+            return Ok(());
+        };
+        let sealed = B64
+            .decode(encoded)
+            .map_err(|e| StoreError::Encryption(format!("encrypted {} is not base64: {}", context, e)))?;
+        let plain = self.keys.open(&sealed, context).await?;
+        *handle = serde_json::from_slice(&plain)?;
+        Ok(())
+    }
+
+    /// Seal `outputs` as a whole, so output names are hidden along with their
+    /// values. An empty map is left as is.
+    async fn seal_outputs(&self, outputs: &mut HashMap<String, Value>, context: &str) -> Result<(), StoreError> {
+        if outputs.is_empty() {
+            return Ok(());
+        }
+        let mut value = serde_json::to_value(&*outputs)?;
+        self.seal_handle(&mut value, context).await?;
+        *outputs = serde_json::from_value(value)?;
+        Ok(())
+    }
+
+    async fn open_outputs(&self, outputs: &mut HashMap<String, Value>, context: &str) -> Result<(), StoreError> {
+        let mut value = serde_json::to_value(&*outputs)?;
+        if sealed_handle(&value).is_none() {
+            return Ok(());
+        }
+        self.open_handle(&mut value, context).await?;
+        *outputs = serde_json::from_value(value)?;
+        Ok(())
+    }
+
+    async fn seal_partition(&self, enclave_id: &EnclaveId, state: &mut PartitionState) -> Result<(), StoreError> {
+        if let Some(h) = state.partition_handle.as_mut() {
+            self.seal_handle(h, &partition_context(enclave_id, &state.desired.id)).await?;
+        }
+        self.seal_outputs(&mut state.resolved_outputs, &outputs_context(enclave_id, &state.desired.id))
+            .await
+    }
+
+    async fn open_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+        state: &mut PartitionState,
+    ) -> Result<(), StoreError> {
+        if let Some(h) = state.partition_handle.as_mut() {
+            self.open_handle(h, &partition_context(enclave_id, partition_id)).await?;
+        }
+        self.open_outputs(&mut state.resolved_outputs, &outputs_context(enclave_id, partition_id))
+            .await
+    }
+
+    async fn seal_handles(&self, state: &mut EnclaveState) -> Result<(), StoreError> {
+        let id = state.desired.id.clone();
+        if let Some(h) = state.enclave_handle.as_mut() {
+            self.seal_handle(h, &format!("enclave:{}", id)).await?;
+        }
+        for part in state.partitions.values_mut() {
+            self.seal_partition(&id, part).await?;
+        }
+        for (name, h) in state.export_handles.iter_mut() {
+            self.seal_handle(h, &format!("export:{}/{}", id, name)).await?;
+        }
+        for (alias, h) in state.import_handles.iter_mut() {
+            self.seal_handle(h, &format!("import:{}/{}", id, alias)).await?;
+        }
+        Ok(())
+    }
+
+    async fn open_handles(&self, state: &mut EnclaveState) -> Result<(), StoreError> {
+        let id = state.desired.id.clone();
+        if let Some(h) = state.enclave_handle.as_mut() {
+            self.open_handle(h, &format!("enclave:{}", id)).await?;
+        }
+        for (part_id, part) in state.partitions.iter_mut() {
+            self.open_partition(&id, part_id, part).await?;
+        }
+        for (name, h) in state.export_handles.iter_mut() {
+            self.open_handle(h, &format!("export:{}/{}", id, name)).await?;
+        }
+        for (alias, h) in state.import_handles.iter_mut() {
+            self.open_handle(h, &format!("import:{}/{}", id, alias)).await?;
+        }
+        Ok(())
+    }
+}
+
+fn blob_context(key: &str) -> String {
+    format!("tf_state:{}", key)
+}
+
+fn partition_context(enclave_id: &EnclaveId, partition_id: &PartitionId) -> String {
+    format!("partition:{}/{}", enclave_id, partition_id)
+}
+
+fn outputs_context(enclave_id: &EnclaveId, partition_id: &PartitionId) -> String {
+    format!("outputs:{}/{}", enclave_id, partition_id)
+}
+
+/// The base64 envelope of a sealed handle, or `None` for a plaintext handle.
+fn sealed_handle(handle: &Value) -> Option<&str> {
+    let obj = handle.as_object()?;
+    if obj.len() != 1 {
+        return None;
+    }
+    obj.get(HANDLE_FIELD)?.as_str()
+}
+
+#[async_trait]
+impl StateStore for EncryptedStore {
+    async fn get_enclave(&self, id: &EnclaveId) -> Result<Option<EnclaveState>, StoreError> {
+        let Some(mut state) = self.inner.get_enclave(id).await? else {
+            return Ok(None);
+        };
+        self.open_handles(&mut state).await?;
+        Ok(Some(state))
+    }
+
+    async fn list_enclaves(&self) -> Result<Vec<EnclaveState>, StoreError> {
+        let mut states = self.inner.list_enclaves().await?;
+        for state in &mut states {
+            self.open_handles(state).await?;
+        }
+        Ok(states)
+    }
+
+    async fn upsert_enclave(&self, state: &EnclaveState) -> Result<(), StoreError> {
+        let mut sealed = state.clone();
+        self.seal_handles(&mut sealed).await?;
+        self.inner.upsert_enclave(&sealed).await
+    }
+
+    async fn delete_enclave(&self, id: &EnclaveId) -> Result<(), StoreError> {
+        self.inner.delete_enclave(id).await
+    }
+
+    async fn upsert_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        state: &PartitionState,
+    ) -> Result<(), StoreError> {
+        let mut sealed = state.clone();
+        self.seal_partition(enclave_id, &mut sealed).await?;
+        self.inner.upsert_partition(enclave_id, &sealed).await
+    }
+
+    async fn delete_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+    ) -> Result<(), StoreError> {
+        self.inner.delete_partition(enclave_id, partition_id).await
+    }
+
+    async fn append_event(&self, event: &AuditEvent) -> Result<(), StoreError> {
+        self.inner.append_event(event).await
+    }
+
+    async fn append_events(&self, events: &[AuditEvent]) -> Result<(), StoreError> {
+        self.inner.append_events(events).await
+    }
+
+    async fn query_events(&self, query: &EventQuery) -> Result<EventPage, StoreError> {
+        self.inner.query_events(query).await
+    }
+
+    async fn prune_events(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<u64, StoreError> {
+        self.inner.prune_events(cutoff, dry_run).await
+    }
+
+    // ── Terraform HTTP state backend ──────────────────────────────────────────
+
+    async fn get_tf_state(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        match self.inner.get_tf_state(key).await? {
+            Some(blob) => Ok(Some(self.open_blob(key, blob).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn put_tf_state(
+        &self,
+        key: &str,
+        state: Vec<u8>,
+        write: &TfStateWrite,
+    ) -> Result<TfStateVersion, StoreError> {
+        // The inner store only sees ciphertext, so hand it the plaintext's header.
+        let write = TfStateWrite {
+            header: Some(write.header.clone().unwrap_or_else(|| TfStateHeader::parse(&state))),
+            size: Some(write.size.unwrap_or(state.len() as u64)),
+            ..write.clone()
+        };
+        let sealed = self.keys.seal(&state, &blob_context(key)).await?;
+        self.inner.put_tf_state(key, sealed, &write).await
+    }
+
+    async fn delete_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        self.inner.delete_tf_state(key).await
+    }
+
+    async fn list_tf_state_versions(&self, key: &str) -> Result<Vec<TfStateVersion>, StoreError> {
+        self.inner.list_tf_state_versions(key).await
+    }
+
+    async fn get_tf_state_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        match self.inner.get_tf_state_version(key, version).await? {
+            Some(blob) => Ok(Some(self.open_blob(key, blob).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn rewrite_tf_state(
+        &self,
+        key: &str,
+        version: Option<u64>,
+        state: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let sealed = self.keys.seal(&state, &blob_context(key)).await?;
+        self.inner.rewrite_tf_state(key, version, sealed).await
+    }
+
+    async fn lock_tf_state(
+        &self,
+        key: &str,
+        lock_info: serde_json::Value,
+    ) -> Result<(), StoreError> {
+        self.inner.lock_tf_state(key, lock_info).await
+    }
+
+    async fn unlock_tf_state(&self, key: &str, lock_id: &str) -> Result<(), StoreError> {
+        self.inner.unlock_tf_state(key, lock_id).await
+    }
+
+    async fn force_unlock_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        self.inner.force_unlock_tf_state(key).await
+    }
+
+    async fn list_tf_state_keys(&self) -> Result<Vec<String>, StoreError> {
+        self.inner.list_tf_state_keys().await
+    }
+
+    async fn list_tf_locks(&self) -> Result<Vec<(String, serde_json::Value)>, StoreError> {
+        self.inner.list_tf_locks().await
+    }
+
+    // ── IaC run log ───────────────────────────────────────────────────────────
+
+    async fn upsert_iac_run(&self, run: &IacRun) -> Result<(), StoreError> {
+        self.inner.upsert_iac_run(run).await
+    }
+
+    async fn list_iac_runs(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+    ) -> Result<Vec<IacRun>, StoreError> {
+        self.inner.list_iac_runs(enclave_id, partition_id).await
+    }
+
+    async fn get_iac_run(&self, run_id: Uuid) -> Result<Option<IacRun>, StoreError> {
+        self.inner.get_iac_run(run_id).await
+    }
+
+    async fn list_all_iac_runs(&self) -> Result<Vec<IacRun>, StoreError> {
+        self.inner.list_all_iac_runs().await
+    }
+
+    async fn list_iac_run_summaries(&self) -> Result<Vec<IacRunSummary>, StoreError> {
+        self.inner.list_iac_run_summaries().await
+    }
+
+    async fn delete_iac_runs(&self, run_ids: &[Uuid]) -> Result<(), StoreError> {
+        self.inner.delete_iac_runs(run_ids).await
+    }
+
+    async fn append_dead_letter(&self, letter: &DeadLetter) -> Result<(), StoreError> {
+        self.inner.append_dead_letter(letter).await
+    }
+
+    async fn list_dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, StoreError> {
+        self.inner.list_dead_letters(limit).await
+    }
+
+    async fn create_api_token(&self, token: &ApiToken) -> Result<(), StoreError> {
+        self.inner.create_api_token(token).await
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, StoreError> {
+        self.inner.find_api_token(token_hash).await
+    }
+
+    async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, StoreError> {
+        self.inner.list_api_tokens().await
+    }
+
+    async fn delete_api_token(&self, name: &str) -> Result<bool, StoreError> {
+        self.inner.delete_api_token(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use nclav_domain::*;
+
+    const KEY_A: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+    const KEY_B: &str = "BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBA=";
+    const STATE: &[u8] = br#"{"version":4,"serial":3,"lineage":"abc","outputs":{"db_password":"hunter2"}}"#;
+
+    fn keyring(primary: &str) -> Keyring {
+        Keyring::new(Arc::new(LocalKek::from_base64(primary).unwrap()))
+    }
+
+    fn enclave_with_handles(id: &str) -> EnclaveState {
+        let mut state = EnclaveState::new(Enclave {
+            id: EnclaveId::new(id),
+            name: id.to_string(),
+            region: "local".to_string(),
+            ..Default::default()
+        });
+        state.enclave_handle = Some(json!({ "sa_key": "secret-key-material" }));
+        let mut part = PartitionState::new(Partition {
+            id: PartitionId::new("db"),
+            name: "db".to_string(),
+            produces: None,
+            imports: vec![],
+            exports: vec![],
+            inputs: Default::default(),
+            declared_outputs: vec![],
+            backend: PartitionBackend::default(),
+        });
+        part.partition_handle = Some(json!({ "token": "partition-secret" }));
+        part.resolved_outputs.insert("db_password".into(), json!("output-secret"));
+        state.partitions.insert(PartitionId::new("db"), part);
+        state.export_handles.insert("api".into(), json!("export-secret"));
+        state
+    }
+
+    #[tokio::test]
+    async fn seals_state_and_handles_transparently() {
+        let raw = Arc::new(InMemoryStore::new());
+        let store = EncryptedStore::new(raw.clone(), keyring(KEY_A));
+
+        let version = store.put_tf_state("enc/db", STATE.to_vec(), &TfStateWrite::default()).await.unwrap();
+        assert_eq!((version.serial, version.lineage.as_deref()), (Some(3), Some("abc")));
+        assert_eq!(version.size, STATE.len() as u64);
+        store.upsert_enclave(&enclave_with_handles("enc")).await.unwrap();
+
+        // The wrapped store never sees plaintext.
+        let stored_blob = raw.get_tf_state("enc/db").await.unwrap().unwrap();
+        assert!(stored_blob.starts_with(MAGIC));
+        let stored_enclave = serde_json::to_string(&raw.list_enclaves().await.unwrap()).unwrap();
+        for secret in ["secret-key-material", "partition-secret", "output-secret", "db_password", "export-secret"] {
+            assert!(!stored_enclave.contains(secret), "{} stored in plaintext", secret);
+        }
+
+        assert_eq!(store.get_tf_state("enc/db").await.unwrap().unwrap(), STATE);
+        assert_eq!(store.get_tf_state_version("enc/db", 1).await.unwrap().unwrap(), STATE);
+        let got = store.get_enclave(&EnclaveId::new("enc")).await.unwrap().unwrap();
+        assert_eq!(got.enclave_handle, Some(json!({ "sa_key": "secret-key-material" })));
+        assert_eq!(
+            got.partitions[&PartitionId::new("db")].partition_handle,
+            Some(json!({ "token": "partition-secret" }))
+        );
+        assert_eq!(got.partitions[&PartitionId::new("db")].resolved_outputs["db_password"], json!("output-secret"));
+        assert_eq!(got.export_handles["api"], json!("export-secret"));
+    }
+
+    #[tokio::test]
+    async fn sealed_outputs_only_open_for_their_partition() {
+        let raw = Arc::new(InMemoryStore::new());
+        let store = EncryptedStore::new(raw.clone(), keyring(KEY_A));
+        let mut state = enclave_with_handles("enc");
+        let part = state.partitions.remove(&PartitionId::new("db")).unwrap();
+        store.upsert_enclave(&state).await.unwrap();
+        store.upsert_partition(&state.desired.id, &part).await.unwrap();
+
+        // A sealed output map copied onto another partition fails to open.
+        let stolen = raw.get_enclave(&EnclaveId::new("enc")).await.unwrap().unwrap().partitions
+            [&PartitionId::new("db")]
+            .resolved_outputs
+            .clone();
+        assert!(sealed_handle(&serde_json::to_value(&stolen).unwrap()).is_some());
+        let mut other = part.clone();
+        other.desired.id = PartitionId::new("cache");
+        other.partition_handle = None;
+        other.resolved_outputs = stolen;
+        state.partitions.insert(PartitionId::new("cache"), other);
+        raw.upsert_enclave(&state).await.unwrap();
+        let err = store.get_enclave(&EnclaveId::new("enc")).await.unwrap_err();
+        assert!(matches!(err, StoreError::Encryption(ref m) if m.contains("outputs:enc/cache")), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn reads_plaintext_and_rejects_unknown_key() {
+        let raw = Arc::new(InMemoryStore::new());
+        raw.put_tf_state("enc/db", STATE.to_vec(), &TfStateWrite::default()).await.unwrap();
+        let store = EncryptedStore::new(raw.clone(), keyring(KEY_A));
+        assert_eq!(store.get_tf_state("enc/db").await.unwrap().unwrap(), STATE);
+
+        store.put_tf_state("enc/db", STATE.to_vec(), &TfStateWrite::default()).await.unwrap();
+        let other = EncryptedStore::new(raw, keyring(KEY_B));
+        let err = other.get_tf_state("enc/db").await.unwrap_err();
+        assert!(matches!(err, StoreError::Encryption(ref m) if m.contains("not configured")), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn reencrypt_all_moves_every_row_to_the_primary_key() {
+        let raw = Arc::new(InMemoryStore::new());
+        // One version written before encryption was enabled, one under key A.
+        raw.put_tf_state("enc/db", STATE.to_vec(), &TfStateWrite::default()).await.unwrap();
+        let old = EncryptedStore::new(raw.clone(), keyring(KEY_A));
+        old.put_tf_state("enc/db", STATE.to_vec(), &TfStateWrite::default()).await.unwrap();
+        old.upsert_enclave(&enclave_with_handles("enc")).await.unwrap();
+
+        let rotating = EncryptedStore::new(
+            raw.clone(),
+            keyring(KEY_B).with_previous(Arc::new(LocalKek::from_base64(KEY_A).unwrap())),
+        );
+        let summary = rotating.reencrypt_all().await.unwrap();
+        assert_eq!(summary, ReencryptSummary { enclaves: 1, tf_state: 1, tf_state_versions: 2 });
+
+        let rotated = EncryptedStore::new(raw, keyring(KEY_B));
+        assert_eq!(rotated.get_tf_state("enc/db").await.unwrap().unwrap(), STATE);
+        for v in [1, 2] {
+            assert_eq!(rotated.get_tf_state_version("enc/db", v).await.unwrap().unwrap(), STATE);
+        }
+        let got = rotated.get_enclave(&EnclaveId::new("enc")).await.unwrap().unwrap();
+        assert_eq!(got.enclave_handle, Some(json!({ "sa_key": "secret-key-material" })));
+        assert_eq!(got.partitions[&PartitionId::new("db")].resolved_outputs["db_password"], json!("output-secret"));
+    }
+
+    #[tokio::test]
+    async fn passes_the_store_conformance_checks() {
+        use crate::conformance::*;
+
+        let store = EncryptedStore::new(Arc::new(InMemoryStore::new()), keyring(KEY_A));
+        check_enclaves(&store, "enc").await;
+        check_partitions(&store, "enc").await;
+        check_tf_state(&store, "enc").await;
+        check_tf_state_history(&store, "enc").await;
+        check_tf_state_guard(&store, "enc").await;
+        check_tf_locks(&store, "enc").await;
+    }
+}
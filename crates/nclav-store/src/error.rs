@@ -15,4 +15,25 @@ pub enum StoreError {
     /// `holder` is the full lock-info JSON as stored (Terraform displays it on conflict).
     #[error("state lock conflict")]
     LockConflict { holder: serde_json::Value },
+
+    /// A Terraform state write was refused: it is older than or unrelated to the
+    /// stored state, or the lock it was made under is no longer held.
+    #[error("state write rejected: {0}")]
+    StaleState(String),
+
+    /// A state archive failed to parse or its checksums do not match its contents.
+    #[error("invalid state archive: {0}")]
+    InvalidArchive(String),
+
+    /// A value could not be encrypted or decrypted, or its key is not configured.
+    #[error("encryption error: {0}")]
+    Encryption(String),
+
+    /// A record with the same unique name already exists.
+    #[error("{0} already exists")]
+    Conflict(String),
+
+    /// Import target already holds state and overwriting was not requested.
+    #[error("target store is not empty ({0})")]
+    TargetNotEmpty(String),
 }
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use nclav_domain::{EnclaveId, PartitionId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::state::AuditEvent;
+
+/// Filter and page for [`StateStore::query_events`](crate::StateStore::query_events).
+///
+/// Pages run backwards in time: each holds the newest `limit` matching events
+/// older than `before`, oldest first, and its `next_cursor` is the `before`
+/// for the page preceding it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventQuery {
+    pub enclave_id: Option<EnclaveId>,
+    pub partition_id: Option<PartitionId>,
+    /// Event kind, e.g. `PartitionDeleted`.
+    pub kind: Option<String>,
+    /// Reconcile or destroy run, matched against the event context.
+    pub run_id: Option<Uuid>,
+    /// Only events at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only events before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Cursor from a previous page: only events recorded before it.
+    pub before: Option<u64>,
+    pub limit: u32,
+}
+
+impl Default for EventQuery {
+    fn default() -> Self {
+        Self {
+            enclave_id: None,
+            partition_id: None,
+            kind: None,
+            run_id: None,
+            since: None,
+            until: None,
+            before: None,
+            limit: 100,
+        }
+    }
+}
+
+impl EventQuery {
+    /// Whether `event`, stored at sequence number `seq`, passes every filter.
+    pub fn matches(&self, seq: u64, event: &AuditEvent) -> bool {
+        self.before.is_none_or(|b| seq < b) && self.matches_event(event)
+    }
+
+    /// Whether `event` passes every filter except the `before` cursor. Used
+    /// for live events, which have no sequence number yet.
+    pub fn matches_event(&self, event: &AuditEvent) -> bool {
+        let at = event.at();
+        self.enclave_id.as_ref().is_none_or(|id| event.enclave_id() == Some(id))
+            && self.partition_id.as_ref().is_none_or(|id| event.partition_id() == Some(id))
+            && self.kind.as_deref().is_none_or(|k| event.kind() == k)
+            && self.run_id.is_none_or(|id| event.context().run_id == Some(id))
+            && self.since.is_none_or(|t| at >= t)
+            && self.until.is_none_or(|t| at < t)
+    }
+
+    /// Build the page from matching `(seq, event)` pairs, oldest first.
+    pub(crate) fn page(&self, matching: Vec<(u64, AuditEvent)>) -> EventPage {
+        let start = matching.len().saturating_sub(self.limit as usize);
+        let next_cursor = matching.get(start).filter(|_| start > 0).map(|(seq, _)| *seq);
+        EventPage {
+            events: matching.into_iter().skip(start).map(|(_, e)| e).collect(),
+            next_cursor,
+        }
+    }
+}
+
+/// A webhook delivery of `event` that still failed after its last retry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    /// Name of the webhook sink.
+    pub webhook: String,
+    pub url: String,
+    pub event: AuditEvent,
+    pub attempts: u32,
+    /// Error from the last attempt: the HTTP status or the transport error.
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// One page of audit events, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPage {
+    pub events: Vec<AuditEvent>,
+    /// Pass as `before` to fetch the preceding page; `None` on the oldest page.
+    pub next_cursor: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{EventContext, EventSource};
+    use serde_json::json;
+
+    #[test]
+    fn context_is_flattened_and_optional() {
+        let event = AuditEvent::EnclaveDeleted {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: EventContext {
+                actor: Some("admin".into()),
+                source: Some(EventSource::Cli),
+                ..Default::default()
+            },
+            enclave_id: EnclaveId::new("enc"),
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["kind"], "EnclaveDeleted");
+        assert_eq!(value["actor"], "admin");
+        assert_eq!(value["source"], "cli");
+        assert!(value.get("run_id").is_none());
+
+        // Events recorded before the context existed still load.
+        let legacy: AuditEvent = serde_json::from_value(json!({
+            "kind": "EnclaveProvisioned",
+            "id": Uuid::new_v4(),
+            "at": Utc::now(),
+            "enclave_id": "enc",
+        }))
+        .unwrap();
+        assert_eq!(legacy.context(), &EventContext::default());
+        assert!(EventQuery { kind: Some("EnclaveProvisioned".into()), ..Default::default() }.matches(1, &legacy));
+    }
+}
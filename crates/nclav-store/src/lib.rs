@@ -1,17 +1,43 @@
+pub mod archive;
+pub mod broadcast;
+#[cfg(test)]
+mod conformance;
+pub mod encrypted;
 pub mod error;
+pub mod events;
+pub mod retention;
 pub mod state;
 pub mod store;
+pub mod tf_lock;
+pub mod tf_state;
+pub mod tokens;
 pub mod memory;
 pub mod redb_store;
 pub mod postgres_store;
+pub mod sqlite_store;
 
+pub use archive::{ImportSummary, StateArchive, ARCHIVE_FORMAT_VERSION};
+pub use broadcast::{BroadcastStore, EventBus, DEFAULT_EVENT_BUS_CAPACITY};
+pub use encrypted::{EncryptedStore, KeyEncryptionKey, Keyring, LocalKek, ReencryptSummary};
 pub use error::StoreError;
+pub use events::{DeadLetter, EventPage, EventQuery};
+pub use retention::{prune_store, PruneSummary, RetentionPolicy};
 pub use state::{
-    AuditEvent, EnclaveState, IacOperation, IacRun, IacRunStatus, PartitionState,
-    ProvisioningStatus, ResourceError, ResourceMeta,
+    AuditEvent, EnclaveState, EventContext, EventSource, IacOperation, IacRun, IacRunStatus, IacRunSummary,
+    PartitionState, ProvisioningStatus, ResourceError, ResourceMeta,
     compute_desired_hash,
 };
 pub use store::StateStore;
+pub use tf_lock::{
+    expire_stale_lock, observe_lock_granted, observe_lock_released, TfLockStatus,
+    LOCK_ACQUIRED_FIELD, LOCK_RUN_FIELD,
+};
+pub use tf_state::{TfStateGuard, TfStateHeader, TfStateVersion, TfStateWrite, DEFAULT_TF_STATE_VERSIONS};
+pub use tokens::{
+    hash_token, sign_state_token, verify_state_token, ApiToken, Scope, API_TOKEN_PREFIX,
+    STATE_TOKEN_PREFIX, STATE_TOKEN_TTL,
+};
 pub use memory::InMemoryStore;
 pub use redb_store::RedbStore;
 pub use postgres_store::PostgresStore;
+pub use sqlite_store::SqliteStore;
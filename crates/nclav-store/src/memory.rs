@@ -2,21 +2,32 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use nclav_domain::{EnclaveId, PartitionId};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::error::StoreError;
-use crate::state::{AuditEvent, EnclaveState, IacRun, PartitionState};
+use crate::events::{DeadLetter, EventPage, EventQuery};
+use crate::state::{AuditEvent, EnclaveState, IacRun, IacRunSummary, PartitionState};
 use crate::store::StateStore;
+use crate::tf_state::{incoming_header, new_version, stored_header, TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
 
 #[derive(Debug, Default)]
 struct Inner {
     enclaves: HashMap<EnclaveId, EnclaveState>,
-    events: Vec<AuditEvent>,
+    /// Events with their sequence numbers, oldest first.
+    events: Vec<(u64, AuditEvent)>,
     tf_state: HashMap<String, Vec<u8>>,
     tf_locks: HashMap<String, serde_json::Value>,
+    /// Retained versions per key, oldest first.
+    tf_versions: HashMap<String, Vec<(TfStateVersion, Vec<u8>)>>,
     iac_runs: HashMap<Uuid, IacRun>,
+    /// Oldest first.
+    dead_letters: Vec<DeadLetter>,
+    /// Keyed by name.
+    api_tokens: HashMap<String, ApiToken>,
 }
 
 /// In-memory implementation of [`StateStore`].
@@ -42,7 +53,9 @@ impl StateStore for InMemoryStore {
 
     async fn list_enclaves(&self) -> Result<Vec<EnclaveState>, StoreError> {
         let guard = self.inner.read().await;
-        Ok(guard.enclaves.values().cloned().collect())
+        let mut enclaves: Vec<EnclaveState> = guard.enclaves.values().cloned().collect();
+        enclaves.sort_by(|a, b| a.desired.id.0.cmp(&b.desired.id.0));
+        Ok(enclaves)
     }
 
     async fn upsert_enclave(&self, state: &EnclaveState) -> Result<(), StoreError> {
@@ -86,32 +99,37 @@ impl StateStore for InMemoryStore {
     }
 
     async fn append_event(&self, event: &AuditEvent) -> Result<(), StoreError> {
+        self.append_events(std::slice::from_ref(event)).await
+    }
+
+    async fn append_events(&self, events: &[AuditEvent]) -> Result<(), StoreError> {
         let mut guard = self.inner.write().await;
-        guard.events.push(event.clone());
+        for event in events {
+            let seq = guard.events.last().map_or(1, |(seq, _)| seq + 1);
+            guard.events.push((seq, event.clone()));
+        }
         Ok(())
     }
 
-    async fn list_events(
-        &self,
-        enclave_id: Option<&EnclaveId>,
-        limit: u32,
-    ) -> Result<Vec<AuditEvent>, StoreError> {
+    async fn query_events(&self, query: &EventQuery) -> Result<EventPage, StoreError> {
         let guard = self.inner.read().await;
-        let filtered: Vec<AuditEvent> = guard
+        let matching: Vec<(u64, AuditEvent)> = guard
             .events
             .iter()
-            .filter(|ev| {
-                if let Some(eid) = enclave_id {
-                    ev.enclave_id().map_or(false, |id| id == eid)
-                } else {
-                    true
-                }
-            })
+            .filter(|(seq, ev)| query.matches(*seq, ev))
             .cloned()
             .collect();
+        Ok(query.page(matching))
+    }
 
-        let start = filtered.len().saturating_sub(limit as usize);
-        Ok(filtered[start..].to_vec())
+    async fn prune_events(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<u64, StoreError> {
+        let mut guard = self.inner.write().await;
+        let before = guard.events.len();
+        if dry_run {
+            return Ok(guard.events.iter().filter(|(_, ev)| ev.at() < cutoff).count() as u64);
+        }
+        guard.events.retain(|(_, ev)| ev.at() >= cutoff);
+        Ok((before - guard.events.len()) as u64)
     }
 
     // ── Terraform HTTP state backend ──────────────────────────────────────────
@@ -121,16 +139,76 @@ impl StateStore for InMemoryStore {
         Ok(guard.tf_state.get(key).cloned())
     }
 
-    async fn put_tf_state(&self, key: &str, state: Vec<u8>) -> Result<(), StoreError> {
+    async fn put_tf_state(
+        &self,
+        key: &str,
+        state: Vec<u8>,
+        write: &TfStateWrite,
+    ) -> Result<TfStateVersion, StoreError> {
         let mut guard = self.inner.write().await;
+        if let Some(check) = &write.guard {
+            let latest = guard.tf_versions.get(key).and_then(|vs| vs.last()).map(|(v, _)| v);
+            let current = stored_header(latest, guard.tf_state.get(key).map(Vec::as_slice));
+            check.check(guard.tf_locks.get(key), current.as_ref(), &incoming_header(&state, write))?;
+        }
+        let versions = guard.tf_versions.entry(key.to_string()).or_default();
+        let next = versions.last().map_or(1, |(v, _)| v.version + 1);
+        let version = new_version(next, &state, write);
+        versions.push((version.clone(), state.clone()));
+        let excess = versions.len().saturating_sub(write.keep_versions.max(1));
+        versions.drain(..excess);
         guard.tf_state.insert(key.to_string(), state);
-        Ok(())
+        Ok(version)
     }
 
     async fn delete_tf_state(&self, key: &str) -> Result<(), StoreError> {
         let mut guard = self.inner.write().await;
         guard.tf_state.remove(key);
         guard.tf_locks.remove(key);
+        guard.tf_versions.remove(key);
+        Ok(())
+    }
+
+    async fn list_tf_state_versions(&self, key: &str) -> Result<Vec<TfStateVersion>, StoreError> {
+        let guard = self.inner.read().await;
+        Ok(guard
+            .tf_versions
+            .get(key)
+            .map(|vs| vs.iter().rev().map(|(v, _)| v.clone()).collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_tf_state_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        let guard = self.inner.read().await;
+        Ok(guard
+            .tf_versions
+            .get(key)
+            .and_then(|vs| vs.iter().find(|(v, _)| v.version == version))
+            .map(|(_, blob)| blob.clone()))
+    }
+
+    async fn rewrite_tf_state(
+        &self,
+        key: &str,
+        version: Option<u64>,
+        state: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let mut guard = self.inner.write().await;
+        let slot = match version {
+            None => guard.tf_state.get_mut(key),
+            Some(n) => guard
+                .tf_versions
+                .get_mut(key)
+                .and_then(|vs| vs.iter_mut().find(|(v, _)| v.version == n))
+                .map(|(_, blob)| blob),
+        };
+        if let Some(blob) = slot {
+            *blob = state;
+        }
         Ok(())
     }
 
@@ -150,14 +228,33 @@ impl StateStore for InMemoryStore {
     async fn unlock_tf_state(&self, key: &str, lock_id: &str) -> Result<(), StoreError> {
         let mut guard = self.inner.write().await;
         if let Some(existing) = guard.tf_locks.get(key) {
-            // Empty lock_id = force-unlock (no ID check).
-            if lock_id.is_empty() || existing["ID"].as_str().unwrap_or("") == lock_id {
+            if existing["ID"].as_str().unwrap_or("") == lock_id {
                 guard.tf_locks.remove(key);
             }
         }
         Ok(())
     }
 
+    async fn force_unlock_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        self.inner.write().await.tf_locks.remove(key);
+        Ok(())
+    }
+
+    async fn list_tf_state_keys(&self) -> Result<Vec<String>, StoreError> {
+        let guard = self.inner.read().await;
+        let mut keys: Vec<String> = guard.tf_state.keys().cloned().collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn list_tf_locks(&self) -> Result<Vec<(String, serde_json::Value)>, StoreError> {
+        let guard = self.inner.read().await;
+        let mut locks: Vec<(String, serde_json::Value)> =
+            guard.tf_locks.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        locks.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(locks)
+    }
+
     // ── IaC run log ───────────────────────────────────────────────────────────
 
     async fn upsert_iac_run(&self, run: &IacRun) -> Result<(), StoreError> {
@@ -187,87 +284,114 @@ impl StateStore for InMemoryStore {
         let guard = self.inner.read().await;
         Ok(guard.iac_runs.get(&run_id).cloned())
     }
+
+    async fn list_all_iac_runs(&self) -> Result<Vec<IacRun>, StoreError> {
+        let guard = self.inner.read().await;
+        let mut runs: Vec<IacRun> = guard.iac_runs.values().cloned().collect();
+        runs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(runs)
+    }
+
+    async fn list_iac_run_summaries(&self) -> Result<Vec<IacRunSummary>, StoreError> {
+        let guard = self.inner.read().await;
+        let mut runs: Vec<IacRunSummary> = guard.iac_runs.values().map(IacRunSummary::from).collect();
+        runs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(runs)
+    }
+
+    async fn delete_iac_runs(&self, run_ids: &[Uuid]) -> Result<(), StoreError> {
+        let mut guard = self.inner.write().await;
+        for id in run_ids {
+            guard.iac_runs.remove(id);
+        }
+        Ok(())
+    }
+
+    async fn append_dead_letter(&self, letter: &DeadLetter) -> Result<(), StoreError> {
+        let mut guard = self.inner.write().await;
+        guard.dead_letters.push(letter.clone());
+        Ok(())
+    }
+
+    async fn list_dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, StoreError> {
+        let guard = self.inner.read().await;
+        Ok(guard.dead_letters.iter().rev().take(limit as usize).cloned().collect())
+    }
+
+    async fn create_api_token(&self, token: &ApiToken) -> Result<(), StoreError> {
+        let mut guard = self.inner.write().await;
+        if guard.api_tokens.contains_key(&token.name) {
+            return Err(StoreError::Conflict(format!("token '{}'", token.name)));
+        }
+        guard.api_tokens.insert(token.name.clone(), token.clone());
+        Ok(())
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, StoreError> {
+        let guard = self.inner.read().await;
+        Ok(guard.api_tokens.values().find(|t| t.token_hash == token_hash).cloned())
+    }
+
+    async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, StoreError> {
+        let guard = self.inner.read().await;
+        let mut tokens: Vec<ApiToken> = guard.api_tokens.values().cloned().collect();
+        tokens.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tokens)
+    }
+
+    async fn delete_api_token(&self, name: &str) -> Result<bool, StoreError> {
+        let mut guard = self.inner.write().await;
+        Ok(guard.api_tokens.remove(name).is_some())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nclav_domain::*;
-
-    fn dummy_enclave(id: &str) -> EnclaveState {
-        EnclaveState::new(Enclave {
-            id: EnclaveId::new(id),
-            name: id.to_string(),
-            cloud: None,
-            region: "local".to_string(),
-            identity: None,
-            network: None,
-            dns: None,
-            imports: vec![],
-            exports: vec![],
-            partitions: vec![],
-        })
+    use crate::conformance;
+
+    #[tokio::test]
+    async fn enclaves() {
+        conformance::check_enclaves(&InMemoryStore::new(), "mem").await;
     }
 
     #[tokio::test]
-    async fn upsert_and_get() {
-        let store = InMemoryStore::new();
-        let state = dummy_enclave("test");
-        store.upsert_enclave(&state).await.unwrap();
+    async fn partitions() {
+        conformance::check_partitions(&InMemoryStore::new(), "mem").await;
+    }
+
+    #[tokio::test]
+    async fn events() {
+        conformance::check_events(&InMemoryStore::new(), "mem").await;
+    }
+
+    #[tokio::test]
+    async fn tf_state() {
+        conformance::check_tf_state(&InMemoryStore::new(), "mem").await;
+    }
 
-        let got = store.get_enclave(&EnclaveId::new("test")).await.unwrap();
-        assert!(got.is_some());
-        assert_eq!(got.unwrap().desired.id.as_str(), "test");
+    #[tokio::test]
+    async fn tf_state_history() {
+        conformance::check_tf_state_history(&InMemoryStore::new(), "mem").await;
     }
 
     #[tokio::test]
-    async fn list_enclaves() {
-        let store = InMemoryStore::new();
-        store.upsert_enclave(&dummy_enclave("a")).await.unwrap();
-        store.upsert_enclave(&dummy_enclave("b")).await.unwrap();
+    async fn tf_state_guard() {
+        conformance::check_tf_state_guard(&InMemoryStore::new(), "mem").await;
+    }
 
-        let list = store.list_enclaves().await.unwrap();
-        assert_eq!(list.len(), 2);
+    #[tokio::test]
+    async fn tf_locks() {
+        conformance::check_tf_locks(&InMemoryStore::new(), "mem").await;
     }
 
     #[tokio::test]
-    async fn delete_enclave() {
-        let store = InMemoryStore::new();
-        store.upsert_enclave(&dummy_enclave("del")).await.unwrap();
-        store.delete_enclave(&EnclaveId::new("del")).await.unwrap();
-        assert!(store.get_enclave(&EnclaveId::new("del")).await.unwrap().is_none());
+    async fn iac_runs() {
+        conformance::check_iac_runs(&InMemoryStore::new(), "mem").await;
     }
 
     #[tokio::test]
-    async fn events_filtered_by_enclave() {
-        use uuid::Uuid;
-        use chrono::Utc;
-
-        let store = InMemoryStore::new();
-        store
-            .append_event(&AuditEvent::EnclaveProvisioned {
-                id: Uuid::new_v4(),
-                at: Utc::now(),
-                enclave_id: EnclaveId::new("a"),
-            })
-            .await
-            .unwrap();
-        store
-            .append_event(&AuditEvent::EnclaveProvisioned {
-                id: Uuid::new_v4(),
-                at: Utc::now(),
-                enclave_id: EnclaveId::new("b"),
-            })
-            .await
-            .unwrap();
-
-        let all = store.list_events(None, 100).await.unwrap();
-        assert_eq!(all.len(), 2);
-
-        let for_a = store
-            .list_events(Some(&EnclaveId::new("a")), 100)
-            .await
-            .unwrap();
-        assert_eq!(for_a.len(), 1);
+    async fn api_tokens() {
+        conformance::check_api_tokens(&InMemoryStore::new(), "mem").await;
     }
 }
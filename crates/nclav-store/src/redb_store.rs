@@ -2,13 +2,17 @@ use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use nclav_domain::{EnclaveId, PartitionId};
 use redb::{Database, ReadableTable, TableDefinition};
 use uuid::Uuid;
 
 use crate::error::StoreError;
-use crate::state::{AuditEvent, EnclaveState, IacRun, PartitionState};
+use crate::events::{DeadLetter, EventPage, EventQuery};
+use crate::state::{AuditEvent, EnclaveState, IacRun, IacRunSummary, PartitionState};
 use crate::store::StateStore;
+use crate::tf_state::{incoming_header, new_version, stored_header, TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
 
 const ENCLAVES: TableDefinition<&str, &[u8]>  = TableDefinition::new("enclaves");
 const EVENTS:   TableDefinition<u64, &[u8]>   = TableDefinition::new("events");
@@ -16,10 +20,18 @@ const META:     TableDefinition<&str, u64>     = TableDefinition::new("meta");
 // Terraform state backend
 const TF_STATE: TableDefinition<&str, &[u8]>  = TableDefinition::new("tf_state");
 const TF_LOCKS: TableDefinition<&str, &[u8]>  = TableDefinition::new("tf_locks");
+// Terraform state history — keyed by "{key}@{version:020}" so a prefix scan
+// yields a key's versions oldest first. Metadata is JSON-encoded TfStateVersion.
+const TF_VERSIONS:     TableDefinition<&str, &[u8]> = TableDefinition::new("tf_state_versions");
+const TF_VERSION_META: TableDefinition<&str, &[u8]> = TableDefinition::new("tf_state_version_meta");
 // IaC run log — keyed by "{enclave_id}/{partition_id}/{started_at_rfc3339}/{run_id}"
 // for efficient partition-scoped queries in chronological order.
 const IAC_RUNS:         TableDefinition<&str, &[u8]> = TableDefinition::new("iac_runs");
 const IAC_RUNS_BY_PART: TableDefinition<&str, &str>  = TableDefinition::new("iac_runs_by_part");
+// Webhook dead letters keyed by sequence number (`dead_letter_seq` in META).
+const DEAD_LETTERS: TableDefinition<u64, &[u8]> = TableDefinition::new("webhook_dead_letters");
+// API tokens keyed by name. Lookups by hash scan the table; it stays small.
+const API_TOKENS: TableDefinition<&str, &[u8]> = TableDefinition::new("api_tokens");
 
 /// Persistent state store backed by a redb database file.
 ///
@@ -49,8 +61,12 @@ impl RedbStore {
             wtxn.open_table(META).map_err(|e| StoreError::Internal(e.to_string()))?;
             wtxn.open_table(TF_STATE).map_err(|e| StoreError::Internal(e.to_string()))?;
             wtxn.open_table(TF_LOCKS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            wtxn.open_table(TF_VERSIONS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            wtxn.open_table(TF_VERSION_META).map_err(|e| StoreError::Internal(e.to_string()))?;
             wtxn.open_table(IAC_RUNS).map_err(|e| StoreError::Internal(e.to_string()))?;
             wtxn.open_table(IAC_RUNS_BY_PART).map_err(|e| StoreError::Internal(e.to_string()))?;
+            wtxn.open_table(DEAD_LETTERS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            wtxn.open_table(API_TOKENS).map_err(|e| StoreError::Internal(e.to_string()))?;
             wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
         }
 
@@ -58,6 +74,41 @@ impl RedbStore {
     }
 }
 
+/// Key of `run` in `IAC_RUNS_BY_PART`.
+fn run_index_key(run: &IacRun) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        run.enclave_id.as_str(),
+        run.partition_id.as_str(),
+        run.started_at.to_rfc3339(),
+        run.id,
+    )
+}
+
+fn version_prefix(key: &str) -> String {
+    format!("{}@", key)
+}
+
+fn version_key(key: &str, version: u64) -> String {
+    format!("{}@{:020}", key, version)
+}
+
+/// Keys in `table` starting with `prefix`, in order.
+fn keys_with_prefix(
+    table: &impl ReadableTable<&'static str, &'static [u8]>,
+    prefix: &str,
+) -> Result<Vec<String>, StoreError> {
+    let mut keys = Vec::new();
+    for entry in table.range(prefix..).map_err(|e| StoreError::Internal(e.to_string()))? {
+        let (k, _v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+        if !k.value().starts_with(prefix) {
+            break;
+        }
+        keys.push(k.value().to_string());
+    }
+    Ok(keys)
+}
+
 #[async_trait]
 impl StateStore for RedbStore {
     async fn get_enclave(&self, id: &EnclaveId) -> Result<Option<EnclaveState>, StoreError> {
@@ -132,46 +183,76 @@ impl StateStore for RedbStore {
     }
 
     async fn append_event(&self, event: &AuditEvent) -> Result<(), StoreError> {
-        let bytes = serde_json::to_vec(event)?;
+        self.append_events(std::slice::from_ref(event)).await
+    }
+
+    async fn append_events(&self, events: &[AuditEvent]) -> Result<(), StoreError> {
         let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
         {
             let mut meta = wtxn.open_table(META).map_err(|e| StoreError::Internal(e.to_string()))?;
-            let seq = meta
+            let mut seq = meta
                 .get("event_seq")
                 .map_err(|e| StoreError::Internal(e.to_string()))?
                 .map(|g| g.value())
                 .unwrap_or(0);
-            let new_seq = seq + 1;
-            meta.insert("event_seq", new_seq).map_err(|e| StoreError::Internal(e.to_string()))?;
-
-            let mut events = wtxn.open_table(EVENTS).map_err(|e| StoreError::Internal(e.to_string()))?;
-            events.insert(new_seq, bytes.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut table = wtxn.open_table(EVENTS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            for event in events {
+                seq += 1;
+                let bytes = serde_json::to_vec(event)?;
+                table.insert(seq, bytes.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            }
+            meta.insert("event_seq", seq).map_err(|e| StoreError::Internal(e.to_string()))?;
         }
         wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
         Ok(())
     }
 
-    async fn list_events(
-        &self,
-        enclave_id: Option<&EnclaveId>,
-        limit: u32,
-    ) -> Result<Vec<AuditEvent>, StoreError> {
+    async fn query_events(&self, query: &EventQuery) -> Result<EventPage, StoreError> {
         let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
         let table = rtxn.open_table(EVENTS).map_err(|e| StoreError::Internal(e.to_string()))?;
-        let mut all: Vec<AuditEvent> = Vec::new();
-        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
-            let (_k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+        // Walk back from the cursor; one match past the limit shows an older page exists.
+        let mut matching: Vec<(u64, AuditEvent)> = Vec::new();
+        let upper = query.before.unwrap_or(u64::MAX);
+        for entry in table.range(..upper).map_err(|e| StoreError::Internal(e.to_string()))?.rev() {
+            let (k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
             let event: AuditEvent = serde_json::from_slice(v.value())?;
-            if let Some(eid) = enclave_id {
-                if event.enclave_id().map_or(false, |id| id == eid) {
-                    all.push(event);
+            if query.matches(k.value(), &event) {
+                matching.push((k.value(), event));
+                if matching.len() > query.limit as usize {
+                    break;
+                }
+            }
+        }
+        matching.reverse();
+        Ok(query.page(matching))
+    }
+
+    async fn prune_events(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<u64, StoreError> {
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let expired: Vec<u64>;
+        {
+            let mut table = wtxn.open_table(EVENTS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut seqs = Vec::new();
+            for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
+                let (k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+                let event: AuditEvent = serde_json::from_slice(v.value())?;
+                if event.at() < cutoff {
+                    seqs.push(k.value());
+                }
+            }
+            expired = seqs;
+            if !dry_run {
+                for seq in &expired {
+                    table.remove(*seq).map_err(|e| StoreError::Internal(e.to_string()))?;
                 }
-            } else {
-                all.push(event);
             }
         }
-        let start = all.len().saturating_sub(limit as usize);
-        Ok(all[start..].to_vec())
+        if dry_run {
+            wtxn.abort().map_err(|e| StoreError::Internal(e.to_string()))?;
+        } else {
+            wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        Ok(expired.len() as u64)
     }
 
     // ── Terraform HTTP state backend ──────────────────────────────────────────
@@ -185,14 +266,60 @@ impl StateStore for RedbStore {
             .map(|g| g.value().to_vec()))
     }
 
-    async fn put_tf_state(&self, key: &str, state: Vec<u8>) -> Result<(), StoreError> {
+    async fn put_tf_state(
+        &self,
+        key: &str,
+        state: Vec<u8>,
+        write: &TfStateWrite,
+    ) -> Result<TfStateVersion, StoreError> {
         let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let version;
         {
             let mut table = wtxn.open_table(TF_STATE).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut meta = wtxn.open_table(TF_VERSION_META).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut blobs = wtxn.open_table(TF_VERSIONS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut existing = keys_with_prefix(&meta, &version_prefix(key))?;
+            let last: Option<TfStateVersion> = match existing.last() {
+                Some(k) => Some(serde_json::from_slice(
+                    meta.get(k.as_str())
+                        .map_err(|e| StoreError::Internal(e.to_string()))?
+                        .expect("key listed from the same table")
+                        .value(),
+                )?),
+                None => None,
+            };
+
+            if let Some(check) = &write.guard {
+                let locks = wtxn.open_table(TF_LOCKS).map_err(|e| StoreError::Internal(e.to_string()))?;
+                let held: Option<serde_json::Value> = match locks.get(key).map_err(|e| StoreError::Internal(e.to_string()))? {
+                    Some(g) => Some(serde_json::from_slice(g.value())?),
+                    None => None,
+                };
+                let blob = table
+                    .get(key)
+                    .map_err(|e| StoreError::Internal(e.to_string()))?
+                    .map(|g| g.value().to_vec());
+                let current = stored_header(last.as_ref(), blob.as_deref());
+                check.check(held.as_ref(), current.as_ref(), &incoming_header(&state, write))?;
+            }
+
             table.insert(key, state.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let next = last.map_or(1, |v| v.version + 1);
+            version = new_version(next, &state, write);
+            let vkey = version_key(key, version.version);
+            let meta_bytes = serde_json::to_vec(&version)?;
+            meta.insert(vkey.as_str(), meta_bytes.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            blobs.insert(vkey.as_str(), state.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            existing.push(vkey);
+
+            let excess = existing.len().saturating_sub(write.keep_versions.max(1));
+            for old in &existing[..excess] {
+                meta.remove(old.as_str()).map_err(|e| StoreError::Internal(e.to_string()))?;
+                blobs.remove(old.as_str()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            }
         }
         wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
-        Ok(())
+        Ok(version)
     }
 
     async fn delete_tf_state(&self, key: &str) -> Result<(), StoreError> {
@@ -202,6 +329,69 @@ impl StateStore for RedbStore {
             state_table.remove(key).map_err(|e| StoreError::Internal(e.to_string()))?;
             let mut lock_table = wtxn.open_table(TF_LOCKS).map_err(|e| StoreError::Internal(e.to_string()))?;
             lock_table.remove(key).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut meta = wtxn.open_table(TF_VERSION_META).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut blobs = wtxn.open_table(TF_VERSIONS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            for vkey in keys_with_prefix(&meta, &version_prefix(key))? {
+                meta.remove(vkey.as_str()).map_err(|e| StoreError::Internal(e.to_string()))?;
+                blobs.remove(vkey.as_str()).map_err(|e| StoreError::Internal(e.to_string()))?;
+            }
+        }
+        wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_tf_state_versions(&self, key: &str) -> Result<Vec<TfStateVersion>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let meta = rtxn.open_table(TF_VERSION_META).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut versions = Vec::new();
+        for vkey in keys_with_prefix(&meta, &version_prefix(key))?.iter().rev() {
+            if let Some(g) = meta.get(vkey.as_str()).map_err(|e| StoreError::Internal(e.to_string()))? {
+                versions.push(serde_json::from_slice(g.value())?);
+            }
+        }
+        Ok(versions)
+    }
+
+    async fn get_tf_state_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let blobs = rtxn.open_table(TF_VERSIONS).map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(blobs
+            .get(version_key(key, version).as_str())
+            .map_err(|e| StoreError::Internal(e.to_string()))?
+            .map(|g| g.value().to_vec()))
+    }
+
+    async fn rewrite_tf_state(
+        &self,
+        key: &str,
+        version: Option<u64>,
+        state: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        {
+            let (mut table, row_key) = match version {
+                None => (
+                    wtxn.open_table(TF_STATE).map_err(|e| StoreError::Internal(e.to_string()))?,
+                    key.to_string(),
+                ),
+                Some(n) => (
+                    wtxn.open_table(TF_VERSIONS).map_err(|e| StoreError::Internal(e.to_string()))?,
+                    version_key(key, n),
+                ),
+            };
+            let exists = table
+                .get(row_key.as_str())
+                .map_err(|e| StoreError::Internal(e.to_string()))?
+                .is_some();
+            if exists {
+                table
+                    .insert(row_key.as_str(), state.as_slice())
+                    .map_err(|e| StoreError::Internal(e.to_string()))?;
+            }
         }
         wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
         Ok(())
@@ -242,8 +432,7 @@ impl StateStore for RedbStore {
                 .map(|g| g.value().to_vec());
             if let Some(bytes) = existing_bytes {
                 let existing: serde_json::Value = serde_json::from_slice(&bytes)?;
-                // Empty lock_id = force-unlock (no ID check).
-                if lock_id.is_empty() || existing["ID"].as_str().unwrap_or("") == lock_id {
+                if existing["ID"].as_str().unwrap_or("") == lock_id {
                     table.remove(key).map_err(|e| StoreError::Internal(e.to_string()))?;
                 }
             }
@@ -252,6 +441,38 @@ impl StateStore for RedbStore {
         Ok(())
     }
 
+    async fn force_unlock_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        {
+            let mut table = wtxn.open_table(TF_LOCKS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            table.remove(key).map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_tf_state_keys(&self) -> Result<Vec<String>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let table = rtxn.open_table(TF_STATE).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut keys = Vec::new();
+        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
+            let (k, _v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+            keys.push(k.value().to_string());
+        }
+        Ok(keys)
+    }
+
+    async fn list_tf_locks(&self) -> Result<Vec<(String, serde_json::Value)>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let table = rtxn.open_table(TF_LOCKS).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut locks = Vec::new();
+        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
+            let (k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+            locks.push((k.value().to_string(), serde_json::from_slice(v.value())?));
+        }
+        Ok(locks)
+    }
+
     // ── IaC run log ───────────────────────────────────────────────────────────
 
     async fn upsert_iac_run(&self, run: &IacRun) -> Result<(), StoreError> {
@@ -259,13 +480,7 @@ impl StateStore for RedbStore {
         let run_id = run.id.to_string();
         // Secondary index key: "{enclave_id}/{partition_id}/{started_at}/{run_id}"
         // Lexicographic iteration gives chronological order; reverse for newest-first.
-        let index_key = format!(
-            "{}/{}/{}/{}",
-            run.enclave_id.as_str(),
-            run.partition_id.as_str(),
-            run.started_at.to_rfc3339(),
-            run_id,
-        );
+        let index_key = run_index_key(run);
 
         let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
         {
@@ -323,11 +538,129 @@ impl StateStore for RedbStore {
             None => Ok(None),
         }
     }
+
+    async fn list_all_iac_runs(&self) -> Result<Vec<IacRun>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let table = rtxn.open_table(IAC_RUNS).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut runs: Vec<IacRun> = Vec::new();
+        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
+            let (_k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+            runs.push(serde_json::from_slice(v.value())?);
+        }
+        runs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(runs)
+    }
+
+    async fn list_iac_run_summaries(&self) -> Result<Vec<IacRunSummary>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let table = rtxn.open_table(IAC_RUNS).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut runs: Vec<IacRunSummary> = Vec::new();
+        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
+            let (_k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+            // The log is skipped while parsing, so only one is read at a time.
+            runs.push(serde_json::from_slice(v.value())?);
+        }
+        runs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        Ok(runs)
+    }
+
+    async fn delete_iac_runs(&self, run_ids: &[Uuid]) -> Result<(), StoreError> {
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        {
+            let mut runs = wtxn.open_table(IAC_RUNS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let mut idx = wtxn.open_table(IAC_RUNS_BY_PART).map_err(|e| StoreError::Internal(e.to_string()))?;
+            for id in run_ids {
+                let removed = runs
+                    .remove(id.to_string().as_str())
+                    .map_err(|e| StoreError::Internal(e.to_string()))?;
+                if let Some(g) = removed {
+                    let run: IacRun = serde_json::from_slice(g.value())?;
+                    idx.remove(run_index_key(&run).as_str())
+                        .map_err(|e| StoreError::Internal(e.to_string()))?;
+                }
+            }
+        }
+        wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn append_dead_letter(&self, letter: &DeadLetter) -> Result<(), StoreError> {
+        let bytes = serde_json::to_vec(letter)?;
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        {
+            let mut meta = wtxn.open_table(META).map_err(|e| StoreError::Internal(e.to_string()))?;
+            let seq = meta
+                .get("dead_letter_seq")
+                .map_err(|e| StoreError::Internal(e.to_string()))?
+                .map(|g| g.value())
+                .unwrap_or(0)
+                + 1;
+            meta.insert("dead_letter_seq", seq).map_err(|e| StoreError::Internal(e.to_string()))?;
+
+            let mut letters = wtxn.open_table(DEAD_LETTERS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            letters.insert(seq, bytes.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let table = rtxn.open_table(DEAD_LETTERS).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut letters = Vec::new();
+        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))?.rev().take(limit as usize) {
+            let (_k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+            letters.push(serde_json::from_slice(v.value())?);
+        }
+        Ok(letters)
+    }
+
+    async fn create_api_token(&self, token: &ApiToken) -> Result<(), StoreError> {
+        let bytes = serde_json::to_vec(token)?;
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        {
+            let mut table = wtxn.open_table(API_TOKENS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            if table.get(token.name.as_str()).map_err(|e| StoreError::Internal(e.to_string()))?.is_some() {
+                return Err(StoreError::Conflict(format!("token '{}'", token.name)));
+            }
+            table.insert(token.name.as_str(), bytes.as_slice()).map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, StoreError> {
+        Ok(self.list_api_tokens().await?.into_iter().find(|t| t.token_hash == token_hash))
+    }
+
+    async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, StoreError> {
+        let rtxn = self.db.begin_read().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let table = rtxn.open_table(API_TOKENS).map_err(|e| StoreError::Internal(e.to_string()))?;
+        let mut tokens = Vec::new();
+        for entry in table.iter().map_err(|e| StoreError::Internal(e.to_string()))? {
+            let (_k, v) = entry.map_err(|e| StoreError::Internal(e.to_string()))?;
+            tokens.push(serde_json::from_slice(v.value())?);
+        }
+        Ok(tokens)
+    }
+
+    async fn delete_api_token(&self, name: &str) -> Result<bool, StoreError> {
+        let wtxn = self.db.begin_write().map_err(|e| StoreError::Internal(e.to_string()))?;
+        let existed;
+        {
+            let mut table = wtxn.open_table(API_TOKENS).map_err(|e| StoreError::Internal(e.to_string()))?;
+            existed = table.remove(name).map_err(|e| StoreError::Internal(e.to_string()))?.is_some();
+        }
+        wtxn.commit().map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(existed)
+    }
 }
 
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance;
     use nclav_domain::*;
     use tempfile::TempDir;
 
@@ -335,14 +668,8 @@ mod tests {
         EnclaveState::new(Enclave {
             id: EnclaveId::new(id),
             name: id.to_string(),
-            cloud: None,
             region: "local".to_string(),
-            identity: None,
-            network: None,
-            dns: None,
-            imports: vec![],
-            exports: vec![],
-            partitions: vec![],
+            ..Default::default()
         })
     }
 
@@ -350,17 +677,6 @@ mod tests {
         RedbStore::open(&dir.path().join("state.redb")).unwrap()
     }
 
-    #[tokio::test]
-    async fn upsert_and_get() {
-        let dir = TempDir::new().unwrap();
-        let store = open_store(&dir);
-        let state = dummy_enclave("test");
-        store.upsert_enclave(&state).await.unwrap();
-        let got = store.get_enclave(&EnclaveId::new("test")).await.unwrap();
-        assert!(got.is_some());
-        assert_eq!(got.unwrap().desired.id.as_str(), "test");
-    }
-
     #[tokio::test]
     async fn persistence_survives_reopen() {
         let dir = TempDir::new().unwrap();
@@ -382,52 +698,108 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn delete_enclave() {
+    async fn rewrite_tf_state_replaces_bytes_in_place() {
         let dir = TempDir::new().unwrap();
         let store = open_store(&dir);
-        store.upsert_enclave(&dummy_enclave("del")).await.unwrap();
-        store.delete_enclave(&EnclaveId::new("del")).await.unwrap();
-        assert!(store.get_enclave(&EnclaveId::new("del")).await.unwrap().is_none());
+        store.put_tf_state("enc/part", b"v1".to_vec(), &TfStateWrite::default()).await.unwrap();
+
+        store.rewrite_tf_state("enc/part", None, b"current".to_vec()).await.unwrap();
+        store.rewrite_tf_state("enc/part", Some(1), b"version".to_vec()).await.unwrap();
+        // Missing targets are left alone rather than created.
+        store.rewrite_tf_state("enc/part", Some(7), b"x".to_vec()).await.unwrap();
+        store.rewrite_tf_state("other/part", None, b"x".to_vec()).await.unwrap();
+
+        assert_eq!(store.get_tf_state("enc/part").await.unwrap().unwrap(), b"current");
+        assert_eq!(store.get_tf_state_version("enc/part", 1).await.unwrap().unwrap(), b"version");
+        assert_eq!(store.list_tf_state_versions("enc/part").await.unwrap().len(), 1);
+        assert!(store.get_tf_state_version("enc/part", 7).await.unwrap().is_none());
+        assert!(store.get_tf_state("other/part").await.unwrap().is_none());
     }
 
     #[tokio::test]
-    async fn list_enclaves() {
+    async fn deleted_iac_runs_leave_the_partition_index() {
+        use crate::state::{IacOperation, IacRunStatus};
+
         let dir = TempDir::new().unwrap();
         let store = open_store(&dir);
-        store.upsert_enclave(&dummy_enclave("a")).await.unwrap();
-        store.upsert_enclave(&dummy_enclave("b")).await.unwrap();
-        let list = store.list_enclaves().await.unwrap();
-        assert_eq!(list.len(), 2);
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let run = IacRun {
+                id: Uuid::new_v4(),
+                enclave_id: EnclaveId::new("enc"),
+                partition_id: PartitionId::new("part"),
+                operation: IacOperation::Provision,
+                started_at: chrono::Utc::now(),
+                finished_at: None,
+                status: IacRunStatus::Succeeded,
+                exit_code: Some(0),
+                log: "ok".into(),
+                reconcile_run_id: None,
+                trace_id: None,
+            };
+            store.upsert_iac_run(&run).await.unwrap();
+            ids.push(run.id);
+        }
+
+        store.delete_iac_runs(&ids[..2]).await.unwrap();
+        let runs = store.list_iac_runs(&EnclaveId::new("enc"), &PartitionId::new("part")).await.unwrap();
+        assert_eq!(runs.iter().map(|r| r.id).collect::<Vec<_>>(), vec![ids[2]]);
+        assert!(store.get_iac_run(ids[0]).await.unwrap().is_none());
+        let summaries = store.list_iac_run_summaries().await.unwrap();
+        assert_eq!(summaries.iter().map(|r| r.id).collect::<Vec<_>>(), vec![ids[2]]);
     }
 
     #[tokio::test]
-    async fn events_append_and_list() {
-        use chrono::Utc;
-        use uuid::Uuid;
+    async fn enclaves() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_enclaves(&open_store(&dir), "redb").await;
+    }
 
+    #[tokio::test]
+    async fn partitions() {
         let dir = TempDir::new().unwrap();
-        let store = open_store(&dir);
-        store
-            .append_event(&AuditEvent::EnclaveProvisioned {
-                id: Uuid::new_v4(),
-                at: Utc::now(),
-                enclave_id: EnclaveId::new("a"),
-            })
-            .await
-            .unwrap();
-        store
-            .append_event(&AuditEvent::EnclaveProvisioned {
-                id: Uuid::new_v4(),
-                at: Utc::now(),
-                enclave_id: EnclaveId::new("b"),
-            })
-            .await
-            .unwrap();
+        conformance::check_partitions(&open_store(&dir), "redb").await;
+    }
 
-        let all = store.list_events(None, 100).await.unwrap();
-        assert_eq!(all.len(), 2);
+    #[tokio::test]
+    async fn events() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_events(&open_store(&dir), "redb").await;
+    }
+
+    #[tokio::test]
+    async fn tf_state() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_state(&open_store(&dir), "redb").await;
+    }
+
+    #[tokio::test]
+    async fn tf_state_history() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_state_history(&open_store(&dir), "redb").await;
+    }
 
-        let for_a = store.list_events(Some(&EnclaveId::new("a")), 100).await.unwrap();
-        assert_eq!(for_a.len(), 1);
+    #[tokio::test]
+    async fn tf_state_guard() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_state_guard(&open_store(&dir), "redb").await;
+    }
+
+    #[tokio::test]
+    async fn tf_locks() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_locks(&open_store(&dir), "redb").await;
+    }
+
+    #[tokio::test]
+    async fn iac_runs() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_iac_runs(&open_store(&dir), "redb").await;
+    }
+
+    #[tokio::test]
+    async fn api_tokens() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_api_tokens(&open_store(&dir), "redb").await;
     }
 }
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use crate::state::{IacRunStatus, IacRunSummary};
+use crate::store::StateStore;
+
+/// How long audit events and IaC runs are kept. Every limit is optional; with
+/// none set nothing is pruned.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Delete audit events older than this many days.
+    pub event_max_age_days: Option<u32>,
+    /// Delete IaC runs that finished more than this many days ago.
+    pub iac_run_max_age_days: Option<u32>,
+    /// Keep at most this many IaC runs per partition, newest first.
+    pub iac_runs_per_partition: Option<u32>,
+}
+
+impl RetentionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// IDs of the runs in `runs` this policy expires as of `now`. Running runs
+    /// are never expired, but still count towards the per-partition limit.
+    pub fn expired_iac_runs(&self, runs: &[IacRunSummary], now: DateTime<Utc>) -> Vec<Uuid> {
+        let cutoff = self.iac_run_max_age_days.map(|d| now - days(d));
+        let mut by_partition: HashMap<(&str, &str), Vec<&IacRunSummary>> = HashMap::new();
+        for run in runs {
+            by_partition
+                .entry((run.enclave_id.as_str(), run.partition_id.as_str()))
+                .or_default()
+                .push(run);
+        }
+
+        let mut expired = Vec::new();
+        for mut partition_runs in by_partition.into_values() {
+            partition_runs.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+            for (i, run) in partition_runs.into_iter().enumerate() {
+                if run.status == IacRunStatus::Running {
+                    continue;
+                }
+                let over_count = self.iac_runs_per_partition.is_some_and(|n| i >= n as usize);
+                let ended = run.finished_at.unwrap_or(run.started_at);
+                let too_old = cutoff.is_some_and(|c| ended < c);
+                if over_count || too_old {
+                    expired.push(run.id);
+                }
+            }
+        }
+        expired.sort();
+        expired
+    }
+}
+
+/// What one [`prune_store`] pass removed, or with `dry_run` would remove.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneSummary {
+    pub events: u64,
+    pub iac_runs: u64,
+    pub dry_run: bool,
+}
+
+/// Apply `policy` to the audit log and IaC run log of `store` as of `now`.
+pub async fn prune_store(
+    store: &dyn StateStore,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<PruneSummary, StoreError> {
+    let mut summary = PruneSummary { dry_run, ..Default::default() };
+
+    if let Some(d) = policy.event_max_age_days {
+        summary.events = store.prune_events(now - days(d), dry_run).await?;
+    }
+
+    if policy.iac_run_max_age_days.is_some() || policy.iac_runs_per_partition.is_some() {
+        let expired = policy.expired_iac_runs(&store.list_iac_run_summaries().await?, now);
+        summary.iac_runs = expired.len() as u64;
+        if !dry_run && !expired.is_empty() {
+            store.delete_iac_runs(&expired).await?;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn days(n: u32) -> TimeDelta {
+    TimeDelta::days(n as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use crate::state::{AuditEvent, EventContext, IacOperation, IacRun};
+    use nclav_domain::{EnclaveId, PartitionId};
+
+    fn run(partition: &str, age_days: i64, status: IacRunStatus, now: DateTime<Utc>) -> IacRun {
+        let started_at = now - TimeDelta::days(age_days);
+        IacRun {
+            id: Uuid::new_v4(),
+            enclave_id: EnclaveId::new("enc"),
+            partition_id: PartitionId::new(partition),
+            operation: IacOperation::Provision,
+            started_at,
+            finished_at: (status != IacRunStatus::Running).then_some(started_at),
+            status,
+            exit_code: None,
+            log: "output".into(),
+            reconcile_run_id: None,
+            trace_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn prunes_runs_by_age_and_count_but_never_running_ones() {
+        let now = Utc::now();
+        let store = InMemoryStore::new();
+        let stuck = run("db", 60, IacRunStatus::Running, now);
+        let old = run("db", 40, IacRunStatus::Failed, now);
+        let third = run("db", 3, IacRunStatus::Succeeded, now);
+        let second = run("db", 2, IacRunStatus::Succeeded, now);
+        let newest = run("db", 1, IacRunStatus::Succeeded, now);
+        let other = run("api", 40, IacRunStatus::Succeeded, now);
+        for r in [&stuck, &old, &third, &second, &newest, &other] {
+            store.upsert_iac_run(r).await.unwrap();
+        }
+        let policy = RetentionPolicy {
+            iac_run_max_age_days: Some(30),
+            iac_runs_per_partition: Some(2),
+            ..Default::default()
+        };
+
+        let preview = prune_store(&store, &policy, now, true).await.unwrap();
+        assert_eq!(preview.iac_runs, 3);
+        assert_eq!(store.list_all_iac_runs().await.unwrap().len(), 6);
+
+        let summary = prune_store(&store, &policy, now, false).await.unwrap();
+        assert_eq!(summary.iac_runs, 3);
+        let mut left: Vec<Uuid> =
+            store.list_all_iac_runs().await.unwrap().into_iter().map(|r| r.id).collect();
+        left.sort();
+        let mut expected = vec![stuck.id, second.id, newest.id];
+        expected.sort();
+        assert_eq!(left, expected);
+    }
+
+    #[tokio::test]
+    async fn prunes_events_older_than_max_age() {
+        let now = Utc::now();
+        let store = InMemoryStore::new();
+        for age in [10, 5, 1] {
+            let event = AuditEvent::EnclaveDeleted {
+                id: Uuid::new_v4(),
+                at: now - TimeDelta::days(age),
+                context: EventContext::controller(),
+                enclave_id: EnclaveId::new("enc"),
+            };
+            store.append_event(&event).await.unwrap();
+        }
+        let policy = RetentionPolicy { event_max_age_days: Some(7), ..Default::default() };
+
+        assert_eq!(prune_store(&store, &policy, now, true).await.unwrap().events, 1);
+        assert_eq!(prune_store(&store, &policy, now, false).await.unwrap().events, 1);
+        assert_eq!(store.list_events(None, 10).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,841 @@
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use nclav_domain::{EnclaveId, PartitionId};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use crate::events::{DeadLetter, EventPage, EventQuery};
+use crate::state::{AuditEvent, EnclaveState, IacRun, IacRunSummary, PartitionState};
+use crate::store::StateStore;
+use crate::tf_state::{incoming_header, new_version, stored_header, TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
+
+// DDL — idempotent; run at every startup via migrate().
+// Same layout as the PostgreSQL store: JSON columns are TEXT, BYTEA is BLOB,
+// timestamps are fixed-width RFC 3339 TEXT so they sort lexically.
+const MIGRATIONS: &str = r#"
+CREATE TABLE IF NOT EXISTS enclaves (
+    id         TEXT PRIMARY KEY,
+    state      TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS audit_events (
+    seq         INTEGER PRIMARY KEY AUTOINCREMENT,
+    enclave_id  TEXT,
+    event       TEXT NOT NULL,
+    occurred_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+CREATE INDEX IF NOT EXISTS idx_audit_events_enclave
+    ON audit_events (enclave_id) WHERE enclave_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS tf_state (
+    key   TEXT PRIMARY KEY,
+    state BLOB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tf_locks (
+    key       TEXT PRIMARY KEY,
+    lock_info TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tf_state_versions (
+    key     TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    meta    TEXT NOT NULL,
+    state   BLOB NOT NULL,
+    PRIMARY KEY (key, version)
+);
+
+CREATE TABLE IF NOT EXISTS iac_runs (
+    run_id       TEXT PRIMARY KEY,
+    enclave_id   TEXT NOT NULL,
+    partition_id TEXT NOT NULL,
+    started_at   TEXT NOT NULL,
+    run          TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_iac_runs_partition
+    ON iac_runs (enclave_id, partition_id, started_at DESC);
+
+CREATE TABLE IF NOT EXISTS webhook_dead_letters (
+    seq       INTEGER PRIMARY KEY AUTOINCREMENT,
+    id        TEXT NOT NULL,
+    webhook   TEXT NOT NULL,
+    letter    TEXT NOT NULL,
+    failed_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    name       TEXT PRIMARY KEY,
+    token_hash TEXT NOT NULL UNIQUE,
+    token      TEXT NOT NULL
+);
+"#;
+
+/// Persistent state store backed by a SQLite database file.
+///
+/// Uses the same tables as [`PostgresStore`](crate::PostgresStore), so the file
+/// can be inspected with the `sqlite3` shell. The database runs in WAL mode:
+/// readers don't block the writer, and other processes can read while
+/// `nclav serve` is running.
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Open (or create) a SQLite database at `path` and run schema migrations.
+    ///
+    /// Parent directories are created automatically.
+    pub async fn open(path: &Path) -> Result<Self, StoreError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        let opts = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(Duration::from_secs(5));
+
+        let pool = SqlitePoolOptions::new()
+            .connect_with(opts)
+            .await
+            .map_err(|e| StoreError::Internal(format!("sqlite open: {e}")))?;
+        let store = Self { pool };
+        store.migrate().await?;
+        Ok(store)
+    }
+
+    /// Run all DDL migrations.  Safe to call on every startup — all statements
+    /// use `CREATE TABLE IF NOT EXISTS` / `CREATE INDEX IF NOT EXISTS`.
+    async fn migrate(&self) -> Result<(), StoreError> {
+        sqlx::raw_sql(MIGRATIONS)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(format!("migration: {e}")))?;
+        Ok(())
+    }
+}
+
+// ── Helper conversions ────────────────────────────────────────────────────────
+
+fn to_json<T: serde::Serialize>(v: &T) -> Result<String, StoreError> {
+    serde_json::to_string(v).map_err(StoreError::Serialization)
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(v: String) -> Result<T, StoreError> {
+    serde_json::from_str(&v).map_err(StoreError::Serialization)
+}
+
+// Extract the `enclave_id` string that should be stored alongside an AuditEvent
+// for indexed filtering.
+fn event_enclave_id(event: &AuditEvent) -> Option<String> {
+    event.enclave_id().map(|id| id.0.clone())
+}
+
+// ── StateStore implementation ─────────────────────────────────────────────────
+
+#[async_trait]
+impl StateStore for SqliteStore {
+    // ── Enclaves ──────────────────────────────────────────────────────────────
+
+    async fn get_enclave(&self, id: &EnclaveId) -> Result<Option<EnclaveState>, StoreError> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT state FROM enclaves WHERE id = ?1")
+                .bind(&id.0)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        row.map(|(v,)| from_json(v)).transpose()
+    }
+
+    async fn list_enclaves(&self) -> Result<Vec<EnclaveState>, StoreError> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT state FROM enclaves ORDER BY id")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    async fn upsert_enclave(&self, state: &EnclaveState) -> Result<(), StoreError> {
+        let json = to_json(state)?;
+        sqlx::query(
+            "INSERT INTO enclaves (id, state, updated_at)
+             VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+             ON CONFLICT (id) DO UPDATE SET state = excluded.state, updated_at = excluded.updated_at",
+        )
+        .bind(&state.desired.id.0)
+        .bind(&json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete_enclave(&self, id: &EnclaveId) -> Result<(), StoreError> {
+        sqlx::query("DELETE FROM enclaves WHERE id = ?1")
+            .bind(&id.0)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    // ── Partitions ────────────────────────────────────────────────────────────
+    //
+    // Partition state is stored nested inside EnclaveState (mirrors redb).
+    // These methods load the enclave, mutate the partition map, and re-upsert.
+
+    async fn upsert_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        state: &PartitionState,
+    ) -> Result<(), StoreError> {
+        let mut enc = self
+            .get_enclave(enclave_id)
+            .await?
+            .ok_or_else(|| StoreError::EnclaveNotFound(enclave_id.0.clone()))?;
+        enc.partitions.insert(state.desired.id.clone(), state.clone());
+        self.upsert_enclave(&enc).await
+    }
+
+    async fn delete_partition(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+    ) -> Result<(), StoreError> {
+        let mut enc = self
+            .get_enclave(enclave_id)
+            .await?
+            .ok_or_else(|| StoreError::EnclaveNotFound(enclave_id.0.clone()))?;
+        enc.partitions.remove(partition_id);
+        self.upsert_enclave(&enc).await
+    }
+
+    // ── Audit events ──────────────────────────────────────────────────────────
+
+    async fn append_event(&self, event: &AuditEvent) -> Result<(), StoreError> {
+        self.append_events(std::slice::from_ref(event)).await
+    }
+
+    async fn append_events(&self, events: &[AuditEvent]) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        for event in events {
+            sqlx::query("INSERT INTO audit_events (enclave_id, event) VALUES (?1, ?2)")
+                .bind(event_enclave_id(event))
+                .bind(to_json(event)?)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        tx.commit().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn query_events(&self, query: &EventQuery) -> Result<EventPage, StoreError> {
+        // Newest first, one row past the limit to tell whether an older page exists.
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT seq, event FROM audit_events
+             WHERE (?1 IS NULL OR enclave_id = ?1)
+               AND (?2 IS NULL OR json_extract(event, '$.partition_id') = ?2)
+               AND (?3 IS NULL OR json_extract(event, '$.kind') = ?3)
+               AND (?4 IS NULL OR json_extract(event, '$.run_id') = ?4)
+               AND (?5 IS NULL OR julianday(json_extract(event, '$.at')) >= julianday(?5))
+               AND (?6 IS NULL OR julianday(json_extract(event, '$.at')) < julianday(?6))
+               AND (?7 IS NULL OR seq < ?7)
+             ORDER BY seq DESC LIMIT ?8",
+        )
+        .bind(query.enclave_id.as_ref().map(|id| id.0.clone()))
+        .bind(query.partition_id.as_ref().map(|id| id.0.clone()))
+        .bind(query.kind.as_deref())
+        .bind(query.run_id.map(|id| id.to_string()))
+        .bind(query.since.map(|t| t.to_rfc3339()))
+        .bind(query.until.map(|t| t.to_rfc3339()))
+        .bind(query.before.map(|seq| seq as i64))
+        .bind(query.limit as i64 + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        let matching = rows
+            .into_iter()
+            .rev()
+            .map(|(seq, v)| Ok((seq as u64, from_json(v)?)))
+            .collect::<Result<Vec<_>, StoreError>>()?;
+        Ok(query.page(matching))
+    }
+
+    async fn prune_events(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<u64, StoreError> {
+        const EXPIRED: &str = "julianday(json_extract(event, '$.at')) < julianday(?1)";
+        if dry_run {
+            let (count,): (i64,) =
+                sqlx::query_as(&format!("SELECT COUNT(*) FROM audit_events WHERE {EXPIRED}"))
+                    .bind(cutoff.to_rfc3339())
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| StoreError::Internal(e.to_string()))?;
+            return Ok(count as u64);
+        }
+        let result = sqlx::query(&format!("DELETE FROM audit_events WHERE {EXPIRED}"))
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
+    // ── Terraform HTTP state backend ──────────────────────────────────────────
+
+    async fn get_tf_state(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        let row: Option<(Vec<u8>,)> =
+            sqlx::query_as("SELECT state FROM tf_state WHERE key = ?1")
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(row.map(|(b,)| b))
+    }
+
+    async fn put_tf_state(
+        &self,
+        key: &str,
+        state: Vec<u8>,
+        write: &TfStateWrite,
+    ) -> Result<TfStateVersion, StoreError> {
+        let mut tx = self.pool.begin().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+
+        if let Some(check) = &write.guard {
+            let held: Option<(String,)> = sqlx::query_as("SELECT lock_info FROM tf_locks WHERE key = ?1")
+                .bind(key)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+            let held: Option<serde_json::Value> = held.map(|(v,)| from_json(v)).transpose()?;
+            let latest: Option<(String,)> = sqlx::query_as(
+                "SELECT meta FROM tf_state_versions WHERE key = ?1 ORDER BY version DESC LIMIT 1",
+            )
+            .bind(key)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+            let latest: Option<TfStateVersion> = latest.map(|(v,)| from_json(v)).transpose()?;
+            let blob: Option<(Vec<u8>,)> = match latest {
+                Some(_) => None,
+                None => sqlx::query_as("SELECT state FROM tf_state WHERE key = ?1")
+                    .bind(key)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(|e| StoreError::Internal(e.to_string()))?,
+            };
+            let current = stored_header(latest.as_ref(), blob.as_ref().map(|(b,)| b.as_slice()));
+            check.check(held.as_ref(), current.as_ref(), &incoming_header(&state, write))?;
+        }
+
+        let (last,): (i64,) = sqlx::query_as(
+            "SELECT COALESCE(MAX(version), 0) FROM tf_state_versions WHERE key = ?1",
+        )
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        let version = new_version(last as u64 + 1, &state, write);
+
+        sqlx::query(
+            "INSERT INTO tf_state (key, state) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET state = excluded.state",
+        )
+        .bind(key)
+        .bind(&state)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO tf_state_versions (key, version, meta, state) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(key)
+        .bind(version.version as i64)
+        .bind(to_json(&version)?)
+        .bind(&state)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+
+        sqlx::query("DELETE FROM tf_state_versions WHERE key = ?1 AND version <= ?2")
+            .bind(key)
+            .bind(version.version as i64 - write.keep_versions.max(1) as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+
+        tx.commit().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(version)
+    }
+
+    async fn delete_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        for table in ["tf_state", "tf_state_versions"] {
+            sqlx::query(&format!("DELETE FROM {table} WHERE key = ?1"))
+                .bind(key)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        tx.commit().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_tf_state_versions(&self, key: &str) -> Result<Vec<TfStateVersion>, StoreError> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT meta FROM tf_state_versions WHERE key = ?1 ORDER BY version DESC",
+        )
+        .bind(key)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    async fn get_tf_state_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        let row: Option<(Vec<u8>,)> = sqlx::query_as(
+            "SELECT state FROM tf_state_versions WHERE key = ?1 AND version = ?2",
+        )
+        .bind(key)
+        .bind(version as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(row.map(|(b,)| b))
+    }
+
+    async fn rewrite_tf_state(
+        &self,
+        key: &str,
+        version: Option<u64>,
+        state: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let query = match version {
+            None => sqlx::query("UPDATE tf_state SET state = ?2 WHERE key = ?1")
+                .bind(key)
+                .bind(state),
+            Some(n) => sqlx::query(
+                "UPDATE tf_state_versions SET state = ?2 WHERE key = ?1 AND version = ?3",
+            )
+            .bind(key)
+            .bind(state)
+            .bind(n as i64),
+        };
+        query
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn lock_tf_state(
+        &self,
+        key: &str,
+        lock_info: serde_json::Value,
+    ) -> Result<(), StoreError> {
+        // Atomic insert — if the key already exists the INSERT is a no-op.
+        let result = sqlx::query(
+            "INSERT INTO tf_locks (key, lock_info) VALUES (?1, ?2)
+             ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(key)
+        .bind(to_json(&lock_info)?)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            // Lock already held — read the current holder.
+            let row: (String,) =
+                sqlx::query_as("SELECT lock_info FROM tf_locks WHERE key = ?1")
+                    .bind(key)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| StoreError::Internal(e.to_string()))?;
+            return Err(StoreError::LockConflict { holder: from_json(row.0)? });
+        }
+        Ok(())
+    }
+
+    async fn unlock_tf_state(&self, key: &str, lock_id: &str) -> Result<(), StoreError> {
+        sqlx::query(
+            "DELETE FROM tf_locks WHERE key = ?1 AND json_extract(lock_info, '$.ID') = ?2",
+        )
+        .bind(key)
+        .bind(lock_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn force_unlock_tf_state(&self, key: &str) -> Result<(), StoreError> {
+        sqlx::query("DELETE FROM tf_locks WHERE key = ?1")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_tf_state_keys(&self) -> Result<Vec<String>, StoreError> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT key FROM tf_state ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(rows.into_iter().map(|(k,)| k).collect())
+    }
+
+    async fn list_tf_locks(&self) -> Result<Vec<(String, serde_json::Value)>, StoreError> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT key, lock_info FROM tf_locks ORDER BY key")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(k, v)| Ok((k, from_json(v)?))).collect()
+    }
+
+    // ── IaC run logs ──────────────────────────────────────────────────────────
+
+    async fn upsert_iac_run(&self, run: &IacRun) -> Result<(), StoreError> {
+        let json = to_json(run)?;
+        sqlx::query(
+            "INSERT INTO iac_runs (run_id, enclave_id, partition_id, started_at, run)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (run_id) DO UPDATE SET run = excluded.run",
+        )
+        .bind(run.id.to_string())
+        .bind(&run.enclave_id.0)
+        .bind(&run.partition_id.0)
+        .bind(run.started_at.to_rfc3339_opts(SecondsFormat::Nanos, true))
+        .bind(&json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_iac_runs(
+        &self,
+        enclave_id: &EnclaveId,
+        partition_id: &PartitionId,
+    ) -> Result<Vec<IacRun>, StoreError> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT run FROM iac_runs
+             WHERE enclave_id = ?1 AND partition_id = ?2
+             ORDER BY started_at DESC
+             LIMIT 100",
+        )
+        .bind(&enclave_id.0)
+        .bind(&partition_id.0)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    async fn get_iac_run(&self, run_id: Uuid) -> Result<Option<IacRun>, StoreError> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT run FROM iac_runs WHERE run_id = ?1")
+                .bind(run_id.to_string())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        row.map(|(v,)| from_json(v)).transpose()
+    }
+
+    async fn list_all_iac_runs(&self) -> Result<Vec<IacRun>, StoreError> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT run FROM iac_runs ORDER BY started_at, run_id")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    async fn list_iac_run_summaries(&self) -> Result<Vec<IacRunSummary>, StoreError> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT json_remove(run, '$.log') FROM iac_runs ORDER BY started_at, run_id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    async fn delete_iac_runs(&self, run_ids: &[Uuid]) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        for id in run_ids {
+            sqlx::query("DELETE FROM iac_runs WHERE run_id = ?1")
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        }
+        tx.commit().await.map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn append_dead_letter(&self, letter: &DeadLetter) -> Result<(), StoreError> {
+        sqlx::query(
+            "INSERT INTO webhook_dead_letters (id, webhook, letter, failed_at) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(letter.id.to_string())
+        .bind(&letter.webhook)
+        .bind(to_json(letter)?)
+        .bind(letter.failed_at.to_rfc3339_opts(SecondsFormat::Nanos, true))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, StoreError> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT letter FROM webhook_dead_letters ORDER BY seq DESC LIMIT ?1")
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    // ── API tokens ────────────────────────────────────────────────────────────
+
+    async fn create_api_token(&self, token: &ApiToken) -> Result<(), StoreError> {
+        let result = sqlx::query(
+            "INSERT INTO api_tokens (name, token_hash, token) VALUES (?1, ?2, ?3)
+             ON CONFLICT (name) DO NOTHING",
+        )
+        .bind(&token.name)
+        .bind(&token.token_hash)
+        .bind(to_json(token)?)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StoreError::Internal(e.to_string()))?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::Conflict(format!("token '{}'", token.name)));
+        }
+        Ok(())
+    }
+
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, StoreError> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT token FROM api_tokens WHERE token_hash = ?1")
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| StoreError::Internal(e.to_string()))?;
+        row.map(|(v,)| from_json(v)).transpose()
+    }
+
+    async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, StoreError> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT token FROM api_tokens ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        rows.into_iter().map(|(v,)| from_json(v)).collect()
+    }
+
+    async fn delete_api_token(&self, name: &str) -> Result<bool, StoreError> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE name = ?1")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Internal(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance;
+    use crate::state::{EventContext, IacOperation, IacRunStatus};
+    use chrono::{TimeDelta, Utc};
+    use nclav_domain::*;
+    use tempfile::TempDir;
+
+    fn dummy_enclave(id: &str) -> EnclaveState {
+        EnclaveState::new(Enclave {
+            id: EnclaveId::new(id),
+            name: id.to_string(),
+            region: "local".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn dummy_run(enclave: &str, partition: &str, started_at: chrono::DateTime<Utc>) -> IacRun {
+        IacRun {
+            id: Uuid::new_v4(),
+            enclave_id: EnclaveId::new(enclave),
+            partition_id: PartitionId::new(partition),
+            operation: IacOperation::Provision,
+            started_at,
+            finished_at: None,
+            status: IacRunStatus::Succeeded,
+            exit_code: Some(0),
+            log: "ok".into(),
+            reconcile_run_id: None,
+            trace_id: None,
+        }
+    }
+
+    async fn open_store(dir: &TempDir) -> SqliteStore {
+        SqliteStore::open(&dir.path().join("state.db")).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn persistence_survives_reopen() {
+        let dir = TempDir::new().unwrap();
+        {
+            let store = open_store(&dir).await;
+            store.upsert_enclave(&dummy_enclave("persistent")).await.unwrap();
+            store.put_tf_state("k", b"tfstate".to_vec(), &TfStateWrite::default()).await.unwrap();
+        }
+        let store = open_store(&dir).await;
+        let got = store.get_enclave(&EnclaveId::new("persistent")).await.unwrap();
+        assert!(got.is_some(), "data should survive store reopen");
+        assert_eq!(store.get_tf_state("k").await.unwrap().unwrap(), b"tfstate");
+    }
+
+    #[tokio::test]
+    async fn uses_wal_journal() {
+        let dir = TempDir::new().unwrap();
+        let store = open_store(&dir).await;
+        let (mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(mode, "wal");
+    }
+
+    #[tokio::test]
+    async fn dead_letters_newest_first() {
+        let dir = TempDir::new().unwrap();
+        let store = open_store(&dir).await;
+        for webhook in ["chat", "pager", "audit"] {
+            let letter = DeadLetter {
+                id: Uuid::new_v4(),
+                webhook: webhook.into(),
+                url: "https://hooks.example.com/x".into(),
+                event: AuditEvent::EnclaveDeleted {
+                    id: Uuid::new_v4(),
+                    at: Utc::now(),
+                    context: EventContext::default(),
+                    enclave_id: EnclaveId::new("enc"),
+                },
+                attempts: 5,
+                last_error: "HTTP 500".into(),
+                failed_at: Utc::now(),
+            };
+            store.append_dead_letter(&letter).await.unwrap();
+        }
+        let letters = store.list_dead_letters(2).await.unwrap();
+        let names: Vec<_> = letters.iter().map(|l| l.webhook.as_str()).collect();
+        assert_eq!(names, ["audit", "pager"]);
+    }
+
+    #[tokio::test]
+    async fn prune_events_and_delete_iac_runs() {
+        let dir = TempDir::new().unwrap();
+        let store = open_store(&dir).await;
+        let now = Utc::now();
+        for age in [90, 30, 1] {
+            store
+                .append_event(&AuditEvent::EnclaveDeleted {
+                    id: Uuid::new_v4(),
+                    at: now - TimeDelta::days(age),
+                    context: EventContext::default(),
+                    enclave_id: EnclaveId::new("enc"),
+                })
+                .await
+                .unwrap();
+        }
+        let cutoff = now - TimeDelta::days(7);
+        assert_eq!(store.prune_events(cutoff, true).await.unwrap(), 2);
+        assert_eq!(store.list_events(None, 10).await.unwrap().len(), 3);
+        assert_eq!(store.prune_events(cutoff, false).await.unwrap(), 2);
+        assert_eq!(store.list_events(None, 10).await.unwrap().len(), 1);
+
+        let gone = dummy_run("enc", "part", now - TimeDelta::days(2));
+        let kept = dummy_run("enc", "part", now);
+        store.upsert_iac_run(&gone).await.unwrap();
+        store.upsert_iac_run(&kept).await.unwrap();
+        store.delete_iac_runs(&[gone.id, Uuid::new_v4()]).await.unwrap();
+        let ids: Vec<Uuid> = store.list_all_iac_runs().await.unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![kept.id]);
+        let summaries = store.list_iac_run_summaries().await.unwrap();
+        assert_eq!(summaries, vec![IacRunSummary::from(&kept)]);
+    }
+
+    #[tokio::test]
+    async fn enclaves() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_enclaves(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn partitions() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_partitions(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn events() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_events(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn tf_state() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_state(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn tf_state_history() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_state_history(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn tf_state_guard() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_state_guard(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn tf_locks() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_tf_locks(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn iac_runs() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_iac_runs(&open_store(&dir).await, "sqlite").await;
+    }
+
+    #[tokio::test]
+    async fn api_tokens() {
+        let dir = TempDir::new().unwrap();
+        conformance::check_api_tokens(&open_store(&dir).await, "sqlite").await;
+    }
+}
@@ -7,6 +7,8 @@ use serde_json::Value;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::tokens::Scope;
+
 /// Opaque driver handle — anything the driver returned from provision.
 pub type Handle = Value;
 
@@ -17,7 +19,8 @@ pub type Handle = Value;
 /// Transitions:
 ///   Pending → Provisioning → Active ↔ Updating
 ///   Provisioning | Updating → Error
-///   Active → Deleting → Deleted
+///   Active → Deleting → Deleted (purged)
+///   Deleting → Deleting (teardown failed; retried later)
 ///   Active → Degraded (from observe())
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -35,9 +38,9 @@ pub enum ProvisioningStatus {
     Degraded,
     /// Last driver call failed; `last_error` is populated.
     Error,
-    /// Driver teardown in-flight.
+    /// Driver teardown in-flight, or failed and awaiting retry (`last_error` is populated).
     Deleting,
-    /// Teardown confirmed; record retained briefly for audit.
+    /// Teardown confirmed; the record is purged right after.
     Deleted,
 }
 
@@ -123,6 +126,33 @@ impl ResourceMeta {
         self.generation += 1;
     }
 
+    /// Transition to Deleting before the first teardown step runs.
+    pub fn mark_deleting(&mut self) {
+        self.status = ProvisioningStatus::Deleting;
+        self.generation += 1;
+    }
+
+    /// Record a failed teardown step. The resource stays `Deleting` so the
+    /// teardown is retried rather than the record being dropped.
+    pub fn mark_teardown_failed(&mut self, now: DateTime<Utc>, message: String) {
+        self.status = ProvisioningStatus::Deleting;
+        self.last_error = Some(ResourceError { message, occurred_at: now });
+        self.generation += 1;
+    }
+
+    /// Transition to Deleted once every teardown step succeeded.
+    pub fn mark_deleted(&mut self, now: DateTime<Utc>) {
+        self.status = ProvisioningStatus::Deleted;
+        self.updated_at = Some(now);
+        self.last_error = None;
+        self.generation += 1;
+    }
+
+    /// Whether a teardown was started for this resource and it has not been purged yet.
+    pub fn is_tombstone(&self) -> bool {
+        matches!(self.status, ProvisioningStatus::Deleting | ProvisioningStatus::Deleted)
+    }
+
     /// Record a successful observe() call.
     pub fn mark_seen(&mut self, now: DateTime<Utc>, healthy: bool) {
         self.last_seen_at = Some(now);
@@ -173,7 +203,11 @@ pub struct PartitionState {
     /// Handle returned by the driver for this partition.
     pub partition_handle: Option<Handle>,
     /// Resolved key→value outputs produced by the driver.
-    pub resolved_outputs: HashMap<String, String>,
+    ///
+    /// Values are arbitrary JSON so that lists, maps, numbers and booleans
+    /// emitted by Terraform survive; state written by older versions (plain
+    /// strings) deserializes unchanged.
+    pub resolved_outputs: HashMap<String, Value>,
     /// Lifecycle and health metadata.
     pub meta: ResourceMeta,
 }
@@ -279,71 +313,434 @@ pub struct IacRun {
     pub log: String,
     /// The reconcile run that triggered this IaC run, if any.
     pub reconcile_run_id: Option<Uuid>,
+    /// Trace the run was part of, when the server exports traces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+/// An [`IacRun`] without its log, for scanning every run without loading
+/// every log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IacRunSummary {
+    pub id: Uuid,
+    pub enclave_id: EnclaveId,
+    pub partition_id: PartitionId,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: IacRunStatus,
+}
+
+impl From<&IacRun> for IacRunSummary {
+    fn from(run: &IacRun) -> Self {
+        Self {
+            id: run.id,
+            enclave_id: run.enclave_id.clone(),
+            partition_id: run.partition_id.clone(),
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+            status: run.status.clone(),
+        }
+    }
 }
 
 // ── AuditEvent ────────────────────────────────────────────────────────────────
 
+/// Where the action behind an audit event came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSource {
+    /// The nclav CLI.
+    Cli,
+    /// Any other API client, including Terraform's HTTP state backend.
+    Api,
+    /// The server acting on its own, e.g. expiring a stale lock.
+    Controller,
+}
+
+impl std::fmt::Display for EventSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            EventSource::Cli => "cli",
+            EventSource::Api => "api",
+            EventSource::Controller => "controller",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Who caused an audit event and what it was part of. Carried by every event;
+/// fields are absent on events recorded before they existed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventContext {
+    /// Identity of the authenticated credential that made the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<EventSource>,
+    /// The reconcile or destroy run the event belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<Uuid>,
+    /// ID of the API request that caused the event (`X-Request-Id`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Trace the action was part of, when the server exports traces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+impl EventContext {
+    /// Context for actions the server takes on its own, in the current trace.
+    pub fn controller() -> Self {
+        Self {
+            source: Some(EventSource::Controller),
+            trace_id: nclav_telemetry::trace::current_trace_id(),
+            ..Default::default()
+        }
+    }
+
+    /// This context, attributed to `run_id`.
+    pub fn with_run(&self, run_id: Uuid) -> Self {
+        Self { run_id: Some(run_id), ..self.clone() }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind")]
 pub enum AuditEvent {
     ReconcileStarted {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         dry_run: bool,
     },
     ReconcileCompleted {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         changes: usize,
         dry_run: bool,
     },
     EnclaveProvisioned {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         enclave_id: EnclaveId,
     },
     PartitionProvisioned {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         enclave_id: EnclaveId,
         partition_id: PartitionId,
     },
     ExportWired {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         enclave_id: EnclaveId,
         export_name: String,
     },
     ImportWired {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         importer_enclave: EnclaveId,
         export_name: String,
     },
     EnclaveError {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         enclave_id: EnclaveId,
         message: String,
     },
     PartitionError {
         id: Uuid,
         at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
         enclave_id: EnclaveId,
         partition_id: PartitionId,
         message: String,
     },
+    /// `DELETE /enclaves/{id}` or `DELETE /enclaves/{id}/partitions/{part}` was called.
+    DestroyRequested {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        /// Set when a single partition is being destroyed.
+        partition_id: Option<PartitionId>,
+        resources_only: bool,
+    },
+    /// An enclave was torn down cleanly and purged from the store.
+    EnclaveDeleted {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+    },
+    /// A partition was torn down cleanly and purged from the store.
+    PartitionDeleted {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+    },
+    /// An enclave teardown failed; the enclave is kept as a tombstone.
+    EnclaveTeardownFailed {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        message: String,
+    },
+    /// A partition teardown failed; the partition is kept as a tombstone.
+    PartitionTeardownFailed {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        message: String,
+    },
+    /// A Terraform state was written through the HTTP backend.
+    TfStateUploaded {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        /// The state version nclav assigned to the write.
+        version: u64,
+        serial: Option<u64>,
+        lineage: Option<String>,
+        /// The IaC run the write is attributed to.
+        iac_run_id: Option<Uuid>,
+    },
+    /// A retained Terraform state version was made current again.
+    TfStateRestored {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        /// The version restored from.
+        version: u64,
+    },
+    /// A Terraform state was deleted through the HTTP backend.
+    TfStateDeleted {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+    },
+    /// A Terraform state lock was released because the IaC run holding it had finished.
+    TfLockExpired {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        lock_id: String,
+        holder: Option<String>,
+        /// The finished IaC run that held the lock.
+        iac_run_id: Option<Uuid>,
+    },
+    /// A Terraform state lock was broken on request (`nclav iac unlock --force`).
+    TfLockForceUnlocked {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        enclave_id: EnclaveId,
+        partition_id: PartitionId,
+        lock_id: String,
+        holder: Option<String>,
+        /// The authenticated caller that broke the lock.
+        by: String,
+        /// Unverified note from the client, e.g. its `user@host`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+    /// An API token was created.
+    ApiTokenCreated {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        name: String,
+        scopes: Vec<Scope>,
+        /// Enclave allow-list; empty means all enclaves.
+        enclaves: Vec<EnclaveId>,
+    },
+    /// An API token was revoked.
+    ApiTokenRevoked {
+        id: Uuid,
+        at: DateTime<Utc>,
+        #[serde(flatten)]
+        context: EventContext,
+        name: String,
+    },
 }
 
 impl AuditEvent {
+    /// The variant name, as serialized in the `kind` field.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditEvent::ReconcileStarted { .. } => "ReconcileStarted",
+            AuditEvent::ReconcileCompleted { .. } => "ReconcileCompleted",
+            AuditEvent::EnclaveProvisioned { .. } => "EnclaveProvisioned",
+            AuditEvent::PartitionProvisioned { .. } => "PartitionProvisioned",
+            AuditEvent::ExportWired { .. } => "ExportWired",
+            AuditEvent::ImportWired { .. } => "ImportWired",
+            AuditEvent::EnclaveError { .. } => "EnclaveError",
+            AuditEvent::PartitionError { .. } => "PartitionError",
+            AuditEvent::DestroyRequested { .. } => "DestroyRequested",
+            AuditEvent::EnclaveDeleted { .. } => "EnclaveDeleted",
+            AuditEvent::PartitionDeleted { .. } => "PartitionDeleted",
+            AuditEvent::EnclaveTeardownFailed { .. } => "EnclaveTeardownFailed",
+            AuditEvent::PartitionTeardownFailed { .. } => "PartitionTeardownFailed",
+            AuditEvent::TfStateUploaded { .. } => "TfStateUploaded",
+            AuditEvent::TfStateRestored { .. } => "TfStateRestored",
+            AuditEvent::TfStateDeleted { .. } => "TfStateDeleted",
+            AuditEvent::TfLockExpired { .. } => "TfLockExpired",
+            AuditEvent::TfLockForceUnlocked { .. } => "TfLockForceUnlocked",
+            AuditEvent::ApiTokenCreated { .. } => "ApiTokenCreated",
+            AuditEvent::ApiTokenRevoked { .. } => "ApiTokenRevoked",
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            AuditEvent::ReconcileStarted { id, .. }
+            | AuditEvent::ReconcileCompleted { id, .. }
+            | AuditEvent::EnclaveProvisioned { id, .. }
+            | AuditEvent::PartitionProvisioned { id, .. }
+            | AuditEvent::ExportWired { id, .. }
+            | AuditEvent::ImportWired { id, .. }
+            | AuditEvent::EnclaveError { id, .. }
+            | AuditEvent::PartitionError { id, .. }
+            | AuditEvent::DestroyRequested { id, .. }
+            | AuditEvent::EnclaveDeleted { id, .. }
+            | AuditEvent::PartitionDeleted { id, .. }
+            | AuditEvent::EnclaveTeardownFailed { id, .. }
+            | AuditEvent::PartitionTeardownFailed { id, .. }
+            | AuditEvent::TfStateUploaded { id, .. }
+            | AuditEvent::TfStateRestored { id, .. }
+            | AuditEvent::TfStateDeleted { id, .. }
+            | AuditEvent::TfLockExpired { id, .. }
+            | AuditEvent::TfLockForceUnlocked { id, .. }
+            | AuditEvent::ApiTokenCreated { id, .. }
+            | AuditEvent::ApiTokenRevoked { id, .. } => *id,
+        }
+    }
+
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            AuditEvent::ReconcileStarted { at, .. }
+            | AuditEvent::ReconcileCompleted { at, .. }
+            | AuditEvent::EnclaveProvisioned { at, .. }
+            | AuditEvent::PartitionProvisioned { at, .. }
+            | AuditEvent::ExportWired { at, .. }
+            | AuditEvent::ImportWired { at, .. }
+            | AuditEvent::EnclaveError { at, .. }
+            | AuditEvent::PartitionError { at, .. }
+            | AuditEvent::DestroyRequested { at, .. }
+            | AuditEvent::EnclaveDeleted { at, .. }
+            | AuditEvent::PartitionDeleted { at, .. }
+            | AuditEvent::EnclaveTeardownFailed { at, .. }
+            | AuditEvent::PartitionTeardownFailed { at, .. }
+            | AuditEvent::TfStateUploaded { at, .. }
+            | AuditEvent::TfStateRestored { at, .. }
+            | AuditEvent::TfStateDeleted { at, .. }
+            | AuditEvent::TfLockExpired { at, .. }
+            | AuditEvent::TfLockForceUnlocked { at, .. }
+            | AuditEvent::ApiTokenCreated { at, .. }
+            | AuditEvent::ApiTokenRevoked { at, .. } => *at,
+        }
+    }
+
+    pub fn context(&self) -> &EventContext {
+        match self {
+            AuditEvent::ReconcileStarted { context, .. }
+            | AuditEvent::ReconcileCompleted { context, .. }
+            | AuditEvent::EnclaveProvisioned { context, .. }
+            | AuditEvent::PartitionProvisioned { context, .. }
+            | AuditEvent::ExportWired { context, .. }
+            | AuditEvent::ImportWired { context, .. }
+            | AuditEvent::EnclaveError { context, .. }
+            | AuditEvent::PartitionError { context, .. }
+            | AuditEvent::DestroyRequested { context, .. }
+            | AuditEvent::EnclaveDeleted { context, .. }
+            | AuditEvent::PartitionDeleted { context, .. }
+            | AuditEvent::EnclaveTeardownFailed { context, .. }
+            | AuditEvent::PartitionTeardownFailed { context, .. }
+            | AuditEvent::TfStateUploaded { context, .. }
+            | AuditEvent::TfStateRestored { context, .. }
+            | AuditEvent::TfStateDeleted { context, .. }
+            | AuditEvent::TfLockExpired { context, .. }
+            | AuditEvent::TfLockForceUnlocked { context, .. }
+            | AuditEvent::ApiTokenCreated { context, .. }
+            | AuditEvent::ApiTokenRevoked { context, .. } => context,
+        }
+    }
+
     pub fn enclave_id(&self) -> Option<&EnclaveId> {
         match self {
-            AuditEvent::EnclaveProvisioned { enclave_id, .. } => Some(enclave_id),
-            AuditEvent::PartitionProvisioned { enclave_id, .. } => Some(enclave_id),
-            AuditEvent::ExportWired { enclave_id, .. } => Some(enclave_id),
+            AuditEvent::EnclaveProvisioned { enclave_id, .. }
+            | AuditEvent::PartitionProvisioned { enclave_id, .. }
+            | AuditEvent::ExportWired { enclave_id, .. }
+            | AuditEvent::EnclaveError { enclave_id, .. }
+            | AuditEvent::PartitionError { enclave_id, .. }
+            | AuditEvent::DestroyRequested { enclave_id, .. }
+            | AuditEvent::EnclaveDeleted { enclave_id, .. }
+            | AuditEvent::PartitionDeleted { enclave_id, .. }
+            | AuditEvent::EnclaveTeardownFailed { enclave_id, .. }
+            | AuditEvent::PartitionTeardownFailed { enclave_id, .. }
+            | AuditEvent::TfStateUploaded { enclave_id, .. }
+            | AuditEvent::TfStateRestored { enclave_id, .. }
+            | AuditEvent::TfStateDeleted { enclave_id, .. }
+            | AuditEvent::TfLockExpired { enclave_id, .. }
+            | AuditEvent::TfLockForceUnlocked { enclave_id, .. } => Some(enclave_id),
             AuditEvent::ImportWired { importer_enclave, .. } => Some(importer_enclave),
-            AuditEvent::EnclaveError { enclave_id, .. } => Some(enclave_id),
-            AuditEvent::PartitionError { enclave_id, .. } => Some(enclave_id),
+            AuditEvent::ReconcileStarted { .. }
+            | AuditEvent::ReconcileCompleted { .. }
+            | AuditEvent::ApiTokenCreated { .. }
+            | AuditEvent::ApiTokenRevoked { .. } => None,
+        }
+    }
+
+    pub fn partition_id(&self) -> Option<&PartitionId> {
+        match self {
+            AuditEvent::PartitionProvisioned { partition_id, .. }
+            | AuditEvent::PartitionError { partition_id, .. }
+            | AuditEvent::PartitionDeleted { partition_id, .. }
+            | AuditEvent::PartitionTeardownFailed { partition_id, .. }
+            | AuditEvent::TfStateUploaded { partition_id, .. }
+            | AuditEvent::TfStateRestored { partition_id, .. }
+            | AuditEvent::TfStateDeleted { partition_id, .. }
+            | AuditEvent::TfLockExpired { partition_id, .. }
+            | AuditEvent::TfLockForceUnlocked { partition_id, .. } => Some(partition_id),
+            AuditEvent::DestroyRequested { partition_id, .. } => partition_id.as_ref(),
             _ => None,
         }
     }
@@ -1,13 +1,18 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use nclav_domain::{EnclaveId, PartitionId};
 use uuid::Uuid;
 
 use crate::error::StoreError;
-use crate::state::{AuditEvent, EnclaveState, IacRun, PartitionState};
+use crate::events::{DeadLetter, EventPage, EventQuery};
+use crate::state::{AuditEvent, EnclaveState, IacRun, IacRunSummary, PartitionState};
+use crate::tf_state::{TfStateVersion, TfStateWrite};
+use crate::tokens::ApiToken;
 
 #[async_trait]
 pub trait StateStore: Send + Sync + 'static {
     async fn get_enclave(&self, id: &EnclaveId) -> Result<Option<EnclaveState>, StoreError>;
+    /// Every enclave, sorted by ID.
     async fn list_enclaves(&self) -> Result<Vec<EnclaveState>, StoreError>;
     async fn upsert_enclave(&self, state: &EnclaveState) -> Result<(), StoreError>;
     async fn delete_enclave(&self, id: &EnclaveId) -> Result<(), StoreError>;
@@ -26,23 +31,64 @@ pub trait StateStore: Send + Sync + 'static {
 
     async fn append_event(&self, event: &AuditEvent) -> Result<(), StoreError>;
 
+    /// Append `events` in order, all or none. Used by archive import.
+    async fn append_events(&self, events: &[AuditEvent]) -> Result<(), StoreError>;
+
+    /// The most recent `limit` events, optionally for one enclave, oldest first.
     async fn list_events(
         &self,
         enclave_id: Option<&EnclaveId>,
         limit: u32,
-    ) -> Result<Vec<AuditEvent>, StoreError>;
+    ) -> Result<Vec<AuditEvent>, StoreError> {
+        let query = EventQuery { enclave_id: enclave_id.cloned(), limit, ..Default::default() };
+        Ok(self.query_events(&query).await?.events)
+    }
+
+    /// One page of the events matching `query`, oldest first.
+    async fn query_events(&self, query: &EventQuery) -> Result<EventPage, StoreError>;
+
+    /// Delete every event recorded before `cutoff` and return how many there
+    /// were. With `dry_run` nothing is deleted and the count is still returned.
+    async fn prune_events(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<u64, StoreError>;
 
     // ── Terraform HTTP state backend ──────────────────────────────────────────
 
     /// Fetch the raw Terraform state blob. Returns `None` if no state exists yet.
     async fn get_tf_state(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError>;
 
-    /// Persist the raw Terraform state blob (overwrites any existing state).
-    async fn put_tf_state(&self, key: &str, state: Vec<u8>) -> Result<(), StoreError>;
+    /// Persist the raw Terraform state blob as the current state and record it
+    /// as a new version, pruning the key's history to `write.keep_versions`.
+    async fn put_tf_state(
+        &self,
+        key: &str,
+        state: Vec<u8>,
+        write: &TfStateWrite,
+    ) -> Result<TfStateVersion, StoreError>;
 
-    /// Delete the Terraform state blob entirely (called after a successful destroy).
+    /// Delete the Terraform state blob and its version history entirely
+    /// (called after a successful destroy).
     async fn delete_tf_state(&self, key: &str) -> Result<(), StoreError>;
 
+    /// Retained versions of the Terraform state at `key`, newest first.
+    async fn list_tf_state_versions(&self, key: &str) -> Result<Vec<TfStateVersion>, StoreError>;
+
+    /// Fetch the blob of one retained version. `None` if it was never written or has been pruned.
+    async fn get_tf_state_version(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<Option<Vec<u8>>, StoreError>;
+
+    /// Overwrite stored bytes in place without recording a new version: the
+    /// current state when `version` is `None`, otherwise that retained version.
+    /// No-op if nothing is stored there. Used to re-encrypt state at rest.
+    async fn rewrite_tf_state(
+        &self,
+        key: &str,
+        version: Option<u64>,
+        state: Vec<u8>,
+    ) -> Result<(), StoreError>;
+
     /// Acquire an advisory lock on the Terraform state.
     /// Returns `Err(StoreError::LockConflict)` if already locked by a different holder.
     /// `lock_info` is the JSON body sent by Terraform's lock protocol.
@@ -55,6 +101,15 @@ pub trait StateStore: Send + Sync + 'static {
     /// Release the advisory lock. No-op if not locked or locked by a different ID.
     async fn unlock_tf_state(&self, key: &str, lock_id: &str) -> Result<(), StoreError>;
 
+    /// Release the advisory lock whoever holds it. No-op if not locked.
+    async fn force_unlock_tf_state(&self, key: &str) -> Result<(), StoreError>;
+
+    /// Every key with a stored Terraform state blob, sorted.
+    async fn list_tf_state_keys(&self) -> Result<Vec<String>, StoreError>;
+
+    /// Every held Terraform lock as `(key, lock_info)`, sorted by key.
+    async fn list_tf_locks(&self) -> Result<Vec<(String, serde_json::Value)>, StoreError>;
+
     // ── IaC run log ───────────────────────────────────────────────────────────
 
     /// Persist an IaC run record (insert or update by `run.id`).
@@ -69,4 +124,37 @@ pub trait StateStore: Send + Sync + 'static {
 
     /// Fetch a single IaC run by its UUID.
     async fn get_iac_run(&self, run_id: Uuid) -> Result<Option<IacRun>, StoreError>;
+
+    /// Every IaC run across all partitions, oldest first. Used for state export.
+    async fn list_all_iac_runs(&self) -> Result<Vec<IacRun>, StoreError>;
+
+    /// Every IaC run across all partitions without its log, oldest first.
+    /// Used for retention, which only needs run metadata.
+    async fn list_iac_run_summaries(&self) -> Result<Vec<IacRunSummary>, StoreError>;
+
+    /// Delete the given IaC runs and their logs. Unknown IDs are ignored.
+    async fn delete_iac_runs(&self, run_ids: &[Uuid]) -> Result<(), StoreError>;
+
+    // ── Webhook dead letters ──────────────────────────────────────────────────
+
+    /// Record a webhook delivery that ran out of retries.
+    async fn append_dead_letter(&self, letter: &DeadLetter) -> Result<(), StoreError>;
+
+    /// The most recent `limit` dead letters, newest first.
+    async fn list_dead_letters(&self, limit: u32) -> Result<Vec<DeadLetter>, StoreError>;
+
+    // ── API tokens ────────────────────────────────────────────────────────────
+
+    /// Store a new API token. Fails with [`StoreError::Conflict`] if a token
+    /// with the same name exists.
+    async fn create_api_token(&self, token: &ApiToken) -> Result<(), StoreError>;
+
+    /// The token whose secret hashes to `token_hash`.
+    async fn find_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, StoreError>;
+
+    /// Every API token, sorted by name.
+    async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, StoreError>;
+
+    /// Delete the named token. Returns whether it existed.
+    async fn delete_api_token(&self, name: &str) -> Result<bool, StoreError>;
 }
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use nclav_domain::{EnclaveId, PartitionId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::StoreError;
+use nclav_telemetry::metrics;
+use crate::state::{AuditEvent, EventContext, IacRunStatus};
+use crate::store::StateStore;
+
+/// Field nclav adds to the stored Terraform lock info to link the lock to the
+/// IaC run that was in progress for the partition when it was taken.
+/// Terraform ignores unknown fields, so the lock info stays valid for it.
+pub const LOCK_RUN_FIELD: &str = "NclavRunID";
+
+/// Field nclav adds to the stored Terraform lock info recording when the lock
+/// was granted, so the time it was held can be measured when it is released.
+pub const LOCK_ACQUIRED_FIELD: &str = "NclavAcquiredAt";
+
+/// Time since the RFC 3339 timestamp in `field` of `lock_info`; zero if the
+/// clocks disagree, `None` if the field is missing or unparseable.
+fn elapsed_since(lock_info: &Value, field: &str) -> Option<Duration> {
+    let at = DateTime::parse_from_rfc3339(lock_info.get(field)?.as_str()?).ok()?;
+    Some((Utc::now() - at.with_timezone(&Utc)).to_std().unwrap_or_default())
+}
+
+/// Record in the metrics how long Terraform waited for the lock it was just
+/// granted: Terraform keeps one lock info, with its `Created` time, across
+/// retries.
+pub fn observe_lock_granted(lock_info: &Value) {
+    if let Some(waited) = elapsed_since(lock_info, "Created") {
+        metrics().tf_lock_wait.observe(&[], waited);
+    }
+}
+
+/// Record in the metrics how long the lock described by `lock_info` was held,
+/// now that it has been `released` (`unlock`, `force_unlock` or `expired`).
+pub fn observe_lock_released(lock_info: &Value, released: &str) {
+    if let Some(held) = elapsed_since(lock_info, LOCK_ACQUIRED_FIELD) {
+        metrics().tf_lock_hold.observe(&[released], held);
+    }
+}
+
+/// A held Terraform state lock, as reported by `GET /terraform/locks`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TfLockStatus {
+    /// State key, `<enclave>/<partition>`.
+    pub key: String,
+    pub lock_id: String,
+    /// `Who` from the lock info (Terraform sets `user@host`).
+    pub holder: Option<String>,
+    /// `Operation` from the lock info, e.g. `OperationTypeApply`.
+    pub operation: Option<String>,
+    /// `Created` from the lock info, as Terraform sent it.
+    pub created: Option<String>,
+    pub run_id: Option<Uuid>,
+    /// Status of the linked run; `None` if no run is linked or it no longer exists.
+    pub run_status: Option<IacRunStatus>,
+    /// The linked run is no longer running, so nothing will release this lock.
+    pub stale: bool,
+}
+
+impl TfLockStatus {
+    /// Describe `lock_info` held on `key`, looking up its linked run.
+    pub async fn inspect(
+        store: &dyn StateStore,
+        key: &str,
+        lock_info: &Value,
+    ) -> Result<Self, StoreError> {
+        let field = |name: &str| lock_info.get(name).and_then(|v| v.as_str()).map(String::from);
+        let run_id = field(LOCK_RUN_FIELD).and_then(|s| Uuid::parse_str(&s).ok());
+        let run_status = match run_id {
+            Some(id) => store.get_iac_run(id).await?.map(|r| r.status),
+            None => None,
+        };
+        Ok(Self {
+            key: key.to_string(),
+            lock_id: field("ID").unwrap_or_default(),
+            holder: field("Who"),
+            operation: field("Operation"),
+            created: field("Created"),
+            run_id,
+            stale: run_id.is_some() && run_status != Some(IacRunStatus::Running),
+            run_status,
+        })
+    }
+}
+
+/// Split a state key into its enclave and partition.
+fn split_state_key(key: &str) -> (EnclaveId, PartitionId) {
+    let (enclave, partition) = key.split_once('/').unwrap_or((key, ""));
+    (EnclaveId::new(enclave), PartitionId::new(partition))
+}
+
+/// Release the lock on `key` if it is stale, recording a `TfLockExpired` audit
+/// event under `context`. Returns whether a lock was expired. Locks with no
+/// linked run are left alone.
+pub async fn expire_stale_lock(
+    store: &dyn StateStore,
+    key: &str,
+    context: &EventContext,
+) -> Result<bool, StoreError> {
+    let Some((_, info)) = store.list_tf_locks().await?.into_iter().find(|(k, _)| k == key) else {
+        return Ok(false);
+    };
+    let lock = TfLockStatus::inspect(store, key, &info).await?;
+    if !lock.stale {
+        return Ok(false);
+    }
+    // Unlocking by ID leaves a lock taken in the meantime untouched.
+    store.unlock_tf_state(key, &lock.lock_id).await?;
+    observe_lock_released(&info, "expired");
+    let (enclave_id, partition_id) = split_state_key(key);
+    store
+        .append_event(&AuditEvent::TfLockExpired {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            context: context.clone(),
+            enclave_id,
+            partition_id,
+            lock_id: lock.lock_id,
+            holder: lock.holder,
+            iac_run_id: lock.run_id,
+        })
+        .await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStore;
+    use crate::state::{IacOperation, IacRun};
+    use serde_json::json;
+
+    fn run(status: IacRunStatus) -> IacRun {
+        IacRun {
+            id: Uuid::new_v4(),
+            enclave_id: EnclaveId::new("enc"),
+            partition_id: PartitionId::new("db"),
+            operation: IacOperation::Provision,
+            started_at: Utc::now(),
+            finished_at: None,
+            status,
+            exit_code: None,
+            log: String::new(),
+            reconcile_run_id: None,
+            trace_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn expires_only_locks_whose_run_finished() {
+        let store = InMemoryStore::new();
+        let mut active = run(IacRunStatus::Running);
+        store.upsert_iac_run(&active).await.unwrap();
+        let info = json!({ "ID": "l1", "Who": "ci@runner", LOCK_RUN_FIELD: active.id.to_string() });
+        store.lock_tf_state("enc/db", info.clone()).await.unwrap();
+
+        let lock = TfLockStatus::inspect(&store, "enc/db", &info).await.unwrap();
+        assert_eq!(lock.holder.as_deref(), Some("ci@runner"));
+        assert!(!lock.stale);
+        assert!(!expire_stale_lock(&store, "enc/db", &EventContext::controller()).await.unwrap());
+
+        active.status = IacRunStatus::Failed;
+        store.upsert_iac_run(&active).await.unwrap();
+        assert!(expire_stale_lock(&store, "enc/db", &EventContext::controller()).await.unwrap());
+        assert!(store.list_tf_locks().await.unwrap().is_empty());
+        let events = store.list_events(None, 10).await.unwrap();
+        assert!(matches!(
+            &events[..],
+            [AuditEvent::TfLockExpired { lock_id, iac_run_id, .. }] if lock_id == "l1" && *iac_run_id == Some(active.id)
+        ));
+    }
+
+    #[tokio::test]
+    async fn leaves_unlinked_locks_alone() {
+        let store = InMemoryStore::new();
+        store.lock_tf_state("enc/db", json!({ "ID": "manual" })).await.unwrap();
+        assert!(!expire_stale_lock(&store, "enc/db", &EventContext::controller()).await.unwrap());
+        assert_eq!(store.list_tf_locks().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::StoreError;
+
+/// How many Terraform state versions are kept per key unless configured otherwise.
+pub const DEFAULT_TF_STATE_VERSIONS: usize = 10;
+
+/// One retained Terraform state write. The blob itself is fetched with
+/// [`StateStore::get_tf_state_version`](crate::StateStore::get_tf_state_version).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TfStateVersion {
+    /// Per-key sequence number assigned by nclav, starting at 1.
+    pub version: u64,
+    /// `serial` from the state file, if it parsed as Terraform state.
+    pub serial: Option<u64>,
+    /// `lineage` from the state file, if it parsed as Terraform state.
+    pub lineage: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// The IaC run that was in progress for the partition when the state was written.
+    pub run_id: Option<Uuid>,
+    /// Set when this version was created by restoring an older one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<u64>,
+    /// Blob size in bytes.
+    pub size: u64,
+}
+
+/// Provenance and retention for a [`StateStore::put_tf_state`](crate::StateStore::put_tf_state) call.
+#[derive(Debug, Clone)]
+pub struct TfStateWrite {
+    pub run_id: Option<Uuid>,
+    pub restored_from: Option<u64>,
+    /// Keep at most this many versions of the key, newest first. Never less than 1.
+    pub keep_versions: usize,
+    /// Header of the plaintext state when the blob being stored is ciphertext
+    /// (see [`EncryptedStore`](crate::EncryptedStore)). Read from the blob when `None`.
+    pub header: Option<TfStateHeader>,
+    /// Plaintext size in bytes, under the same rule as `header`.
+    pub size: Option<u64>,
+    /// Version number to record instead of the next one, when importing
+    /// history. Must be greater than every version already stored for the key.
+    pub version: Option<u64>,
+    /// Creation time to record instead of now, when importing history.
+    pub created_at: Option<DateTime<Utc>>,
+    /// Checks to run against the stored state and lock before writing, in the
+    /// same transaction as the write. Set for writes from Terraform's HTTP backend.
+    pub guard: Option<TfStateGuard>,
+}
+
+impl Default for TfStateWrite {
+    fn default() -> Self {
+        Self {
+            run_id: None,
+            restored_from: None,
+            keep_versions: DEFAULT_TF_STATE_VERSIONS,
+            header: None,
+            size: None,
+            version: None,
+            created_at: None,
+            guard: None,
+        }
+    }
+}
+
+/// Compare-and-put conditions for a [`TfStateWrite`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TfStateGuard {
+    /// The lock ID Terraform sent with the write (`?ID=`), if it holds a lock.
+    pub lock_id: Option<String>,
+}
+
+impl TfStateGuard {
+    /// Refuse the write unless `lock_id` matches `held_lock` (both absent is fine)
+    /// and `incoming` may replace `current` (see [`TfStateHeader::reject_successor`]).
+    pub(crate) fn check(
+        &self,
+        held_lock: Option<&serde_json::Value>,
+        current: Option<&TfStateHeader>,
+        incoming: &TfStateHeader,
+    ) -> Result<(), StoreError> {
+        match (held_lock, &self.lock_id) {
+            (Some(lock), id) if lock["ID"].as_str() != id.as_deref() => {
+                return Err(StoreError::LockConflict { holder: lock.clone() });
+            }
+            (None, Some(id)) => {
+                return Err(StoreError::StaleState(format!("lock '{}' is not held", id)));
+            }
+            _ => {}
+        }
+        match current.and_then(|c| c.reject_successor(incoming)) {
+            Some(reason) => Err(StoreError::StaleState(reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The `serial` and `lineage` fields of a Terraform state file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TfStateHeader {
+    pub serial: Option<u64>,
+    pub lineage: Option<String>,
+}
+
+impl TfStateHeader {
+    /// Read the header from a raw state blob. Blobs that aren't JSON objects
+    /// yield an empty header rather than an error.
+    pub fn parse(blob: &[u8]) -> Self {
+        let Ok(value) = serde_json::from_slice::<serde_json::Value>(blob) else {
+            return Self::default();
+        };
+        Self {
+            serial: value.get("serial").and_then(|v| v.as_u64()),
+            lineage: value.get("lineage").and_then(|v| v.as_str()).map(String::from),
+        }
+    }
+
+    /// Why `incoming` must not replace `self` as the stored state, if it mustn't.
+    ///
+    /// A write is rejected when both states carry a lineage and they differ, or
+    /// when both carry a serial and the incoming one is lower. An equal serial is
+    /// accepted: Terraform re-sends the same serial when persisting without changes.
+    pub fn reject_successor(&self, incoming: &TfStateHeader) -> Option<String> {
+        if let (Some(current), Some(new)) = (&self.lineage, &incoming.lineage) {
+            if current != new {
+                return Some(format!(
+                    "state lineage '{}' does not match stored lineage '{}'",
+                    new, current
+                ));
+            }
+        }
+        if let (Some(current), Some(new)) = (self.serial, incoming.serial) {
+            if new < current {
+                return Some(format!(
+                    "state serial {} is older than stored serial {}",
+                    new, current
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Header of a blob about to be written: `write.header`, else parsed from the blob.
+pub(crate) fn incoming_header(blob: &[u8], write: &TfStateWrite) -> TfStateHeader {
+    write.header.clone().unwrap_or_else(|| TfStateHeader::parse(blob))
+}
+
+/// Header of the stored state: from its newest version record when there is one,
+/// since the blob may be ciphertext, else parsed from the blob.
+pub(crate) fn stored_header(latest: Option<&TfStateVersion>, blob: Option<&[u8]>) -> Option<TfStateHeader> {
+    match latest {
+        Some(v) => Some(TfStateHeader { serial: v.serial, lineage: v.lineage.clone() }),
+        None => blob.map(TfStateHeader::parse),
+    }
+}
+
+/// Build the version record for a blob about to be stored as `next`, unless
+/// `write` names the version itself.
+pub(crate) fn new_version(next: u64, blob: &[u8], write: &TfStateWrite) -> TfStateVersion {
+    let header = incoming_header(blob, write);
+    TfStateVersion {
+        version: write.version.unwrap_or(next),
+        serial: header.serial,
+        lineage: header.lineage,
+        created_at: write.created_at.unwrap_or_else(Utc::now),
+        run_id: write.run_id,
+        restored_from: write.restored_from,
+        size: write.size.unwrap_or(blob.len() as u64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(serial: u64, lineage: &str) -> TfStateHeader {
+        TfStateHeader { serial: Some(serial), lineage: Some(lineage.into()) }
+    }
+
+    #[test]
+    fn parses_header_and_tolerates_non_json() {
+        let h = TfStateHeader::parse(br#"{"version":4,"serial":7,"lineage":"abc"}"#);
+        assert_eq!(h, header(7, "abc"));
+        assert_eq!(TfStateHeader::parse(b"not json"), TfStateHeader::default());
+    }
+
+    #[test]
+    fn rejects_other_lineage_and_older_serial() {
+        let stored = header(5, "abc");
+        assert!(stored.reject_successor(&header(5, "abc")).is_none());
+        assert!(stored.reject_successor(&header(6, "abc")).is_none());
+        assert!(stored.reject_successor(&header(4, "abc")).unwrap().contains("older"));
+        assert!(stored.reject_successor(&header(9, "xyz")).unwrap().contains("lineage"));
+        assert!(stored.reject_successor(&TfStateHeader::default()).is_none());
+    }
+
+    #[test]
+    fn guard_requires_matching_lock_and_successor() {
+        let lock = serde_json::json!({ "ID": "abc", "Who": "ci" });
+        let held = TfStateGuard { lock_id: Some("abc".into()) };
+        let none = TfStateGuard::default();
+        let stored = header(5, "l");
+
+        assert!(held.check(Some(&lock), Some(&stored), &header(6, "l")).is_ok());
+        assert!(none.check(None, None, &header(1, "l")).is_ok());
+        assert!(matches!(
+            none.check(Some(&lock), Some(&stored), &header(6, "l")),
+            Err(StoreError::LockConflict { holder }) if holder["Who"] == "ci"
+        ));
+        let other = TfStateGuard { lock_id: Some("xyz".into()) };
+        assert!(matches!(other.check(Some(&lock), None, &header(1, "l")), Err(StoreError::LockConflict { .. })));
+        assert!(matches!(held.check(None, None, &header(1, "l")), Err(StoreError::StaleState(_))));
+        assert!(matches!(
+            held.check(Some(&lock), Some(&stored), &header(4, "l")),
+            Err(StoreError::StaleState(m)) if m.contains("older")
+        ));
+    }
+}
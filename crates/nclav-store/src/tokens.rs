@@ -0,0 +1,204 @@
+//! API tokens and the short-lived tokens Terraform uses for the state backend.
+//!
+//! Named API tokens are stored as SHA-256 hashes; the secret is only returned
+//! once, when the token is created. State-backend tokens are not stored at
+//! all: they are HMACs of the state key and an expiry under the server's own
+//! token, so the server can check them without a lookup.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use nclav_domain::EnclaveId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Prefix of every API token secret, so leaked tokens are easy to grep for.
+pub const API_TOKEN_PREFIX: &str = "nclav_";
+
+/// Prefix of state-backend tokens minted by [`sign_state_token`].
+pub const STATE_TOKEN_PREFIX: &str = "nclav-tf.";
+
+/// How long a state-backend token stays valid. Longer than the 30-minute cap
+/// on a single Terraform command.
+pub const STATE_TOKEN_TTL: TimeDelta = TimeDelta::hours(1);
+
+/// What an API token may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Read-only endpoints.
+    Read,
+    /// `POST /reconcile/dry-run`.
+    Diff,
+    /// Reconcile, and write Terraform state and locks.
+    Apply,
+    /// Destroy enclaves and partitions, and delete Terraform state.
+    Destroy,
+    /// Everything, including token management, pruning and breaking locks.
+    Admin,
+}
+
+impl Scope {
+    /// Whether holding `self` permits an action that needs `required`.
+    /// `admin` grants everything, `apply` also grants `diff`, and every scope
+    /// grants `read`.
+    pub fn grants(self, required: Scope) -> bool {
+        self == required
+            || self == Scope::Admin
+            || required == Scope::Read
+            || (self == Scope::Apply && required == Scope::Diff)
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::Read => "read",
+            Scope::Diff => "diff",
+            Scope::Apply => "apply",
+            Scope::Destroy => "destroy",
+            Scope::Admin => "admin",
+        })
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Scope::Read),
+            "diff" => Ok(Scope::Diff),
+            "apply" => Ok(Scope::Apply),
+            "destroy" => Ok(Scope::Destroy),
+            "admin" => Ok(Scope::Admin),
+            other => Err(format!("unknown scope '{other}'")),
+        }
+    }
+}
+
+/// A named API token. Its name is the actor recorded on audit events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub name: String,
+    /// [`hash_token`] of the secret.
+    pub token_hash: String,
+    pub scopes: Vec<Scope>,
+    /// Enclaves the token may act on; empty means all of them.
+    #[serde(default)]
+    pub enclaves: Vec<EnclaveId>,
+    pub created_at: DateTime<Utc>,
+    /// Actor that created the token.
+    pub created_by: Option<String>,
+}
+
+impl ApiToken {
+    /// A new token and its secret. Only the hash of the secret is kept.
+    pub fn generate(
+        name: impl Into<String>,
+        scopes: Vec<Scope>,
+        enclaves: Vec<EnclaveId>,
+        created_by: Option<String>,
+    ) -> (Self, String) {
+        let secret = format!(
+            "{API_TOKEN_PREFIX}{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let token = Self {
+            name: name.into(),
+            token_hash: hash_token(&secret),
+            scopes,
+            enclaves,
+            created_at: Utc::now(),
+            created_by,
+        };
+        (token, secret)
+    }
+
+    /// Whether any of the token's scopes grants `required`.
+    pub fn grants(&self, required: Scope) -> bool {
+        self.scopes.iter().any(|s| s.grants(required))
+    }
+
+    /// Whether the token may act on `enclave`.
+    pub fn allows_enclave(&self, enclave: &EnclaveId) -> bool {
+        self.enclaves.is_empty() || self.enclaves.contains(enclave)
+    }
+}
+
+/// Hex SHA-256 of a token secret, as stored in [`ApiToken::token_hash`].
+pub fn hash_token(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// A token for Terraform's HTTP state backend, valid for the state and lock
+/// routes of `key` (`<enclave>/<partition>`) until `expires_at`.
+pub fn sign_state_token(server_token: &str, key: &str, expires_at: DateTime<Utc>) -> String {
+    let expires = expires_at.timestamp();
+    let mac = state_token_mac(server_token, key, expires).finalize().into_bytes();
+    let digest: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{STATE_TOKEN_PREFIX}{expires}.{digest}")
+}
+
+/// Whether `token` was signed by [`sign_state_token`] for `key` and has not
+/// expired at `now`.
+pub fn verify_state_token(server_token: &str, key: &str, token: &str, now: DateTime<Utc>) -> bool {
+    let Some(rest) = token.strip_prefix(STATE_TOKEN_PREFIX) else { return false };
+    let Some((expires, digest)) = rest.split_once('.') else { return false };
+    let Ok(expires) = expires.parse::<i64>() else { return false };
+    if expires <= now.timestamp() || !digest.is_ascii() || digest.len() % 2 != 0 {
+        return false;
+    }
+    let Ok(digest) = (0..digest.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digest[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+    else {
+        return false;
+    };
+    state_token_mac(server_token, key, expires).verify_slice(&digest).is_ok()
+}
+
+fn state_token_mac(server_token: &str, key: &str, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(server_token.as_bytes())
+        .expect("HMAC accepts any key length");
+    mac.update(format!("tf-state:{key}:{expires}").as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_grant_read_and_admin_grants_all() {
+        assert!(Scope::Destroy.grants(Scope::Read));
+        assert!(Scope::Apply.grants(Scope::Diff));
+        assert!(!Scope::Apply.grants(Scope::Destroy));
+        assert!(!Scope::Read.grants(Scope::Diff));
+        assert!(Scope::Admin.grants(Scope::Destroy));
+    }
+
+    #[test]
+    fn state_tokens_are_bound_to_key_secret_and_expiry() {
+        let now = Utc::now();
+        let token = sign_state_token("server", "enc/db", now + STATE_TOKEN_TTL);
+        assert!(verify_state_token("server", "enc/db", &token, now));
+        assert!(!verify_state_token("server", "enc/api", &token, now));
+        assert!(!verify_state_token("other", "enc/db", &token, now));
+        assert!(!verify_state_token("server", "enc/db", &token, now + TimeDelta::hours(2)));
+        assert!(!verify_state_token("server", "enc/db", "nclav-tf.1.zz", now));
+    }
+
+    #[test]
+    fn generated_tokens_store_only_the_hash() {
+        let (token, secret) = ApiToken::generate("ci", vec![Scope::Read], vec![], None);
+        assert!(secret.starts_with(API_TOKEN_PREFIX));
+        assert_eq!(token.token_hash, hash_token(&secret));
+        assert!(!token.token_hash.contains(&secret));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod metrics;
+pub mod trace;
+
+pub use metrics::metrics;
+pub use trace::TraceContext;
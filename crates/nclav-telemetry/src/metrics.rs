@@ -0,0 +1,312 @@
+//! Process-wide metrics, rendered in the Prometheus text format by
+//! `GET /metrics`.
+//!
+//! Counters and histograms are recorded where the work happens — the
+//! reconciler, the driver registry, the Terraform backend and the state lock
+//! handlers — through [`metrics()`]. Gauges describing stored state are set
+//! when they are computed.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// Buckets (seconds) for cloud API calls.
+const CALL_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Buckets (seconds) for reconcile runs, IaC runs and lock times.
+const RUN_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0];
+
+type Labels = Vec<String>;
+
+/// A counter with one series per label combination.
+pub struct CounterVec {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    values: Mutex<BTreeMap<Labels, u64>>,
+}
+
+impl CounterVec {
+    fn new(name: &'static str, help: &'static str, label_names: &'static [&'static str]) -> Self {
+        Self { name, help, label_names, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn inc(&self, labels: &[&str]) {
+        *self.values.lock().unwrap().entry(to_labels(labels)).or_default() += 1;
+    }
+
+    pub fn get(&self, labels: &[&str]) -> u64 {
+        self.values.lock().unwrap().get(&to_labels(labels)).copied().unwrap_or_default()
+    }
+
+    fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "counter");
+        for (labels, value) in self.values.lock().unwrap().iter() {
+            let _ = writeln!(out, "{}{} {}", self.name, label_set(self.label_names, labels, None), value);
+        }
+    }
+}
+
+/// A gauge with one series per label combination, replaced wholesale each
+/// time the value it describes is recomputed.
+pub struct GaugeVec {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    values: Mutex<BTreeMap<Labels, f64>>,
+}
+
+impl GaugeVec {
+    fn new(name: &'static str, help: &'static str, label_names: &'static [&'static str]) -> Self {
+        Self { name, help, label_names, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Replace every series with `series`.
+    pub fn replace<'a>(&self, series: impl IntoIterator<Item = (Vec<&'a str>, f64)>) {
+        *self.values.lock().unwrap() =
+            series.into_iter().map(|(labels, value)| (to_labels(&labels), value)).collect();
+    }
+
+    pub fn get(&self, labels: &[&str]) -> Option<f64> {
+        self.values.lock().unwrap().get(&to_labels(labels)).copied()
+    }
+
+    fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "gauge");
+        for (labels, value) in self.values.lock().unwrap().iter() {
+            let _ = writeln!(out, "{}{} {}", self.name, label_set(self.label_names, labels, None), value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct HistogramData {
+    /// Non-cumulative count per bucket; summed when rendered.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// A histogram of seconds with one series per label combination.
+pub struct HistogramVec {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    bounds: &'static [f64],
+    values: Mutex<BTreeMap<Labels, HistogramData>>,
+}
+
+impl HistogramVec {
+    fn new(
+        name: &'static str,
+        help: &'static str,
+        label_names: &'static [&'static str],
+        bounds: &'static [f64],
+    ) -> Self {
+        Self { name, help, label_names, bounds, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn observe(&self, labels: &[&str], value: Duration) {
+        let seconds = value.as_secs_f64();
+        let mut values = self.values.lock().unwrap();
+        let data = values.entry(to_labels(labels)).or_default();
+        data.buckets.resize(self.bounds.len(), 0);
+        if let Some(i) = self.bounds.iter().position(|b| seconds <= *b) {
+            data.buckets[i] += 1;
+        }
+        data.sum += seconds;
+        data.count += 1;
+    }
+
+    /// Number of observations in the series.
+    pub fn count(&self, labels: &[&str]) -> u64 {
+        self.values.lock().unwrap().get(&to_labels(labels)).map(|d| d.count).unwrap_or_default()
+    }
+
+    fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "histogram");
+        for (labels, data) in self.values.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (bound, n) in self.bounds.iter().zip(&data.buckets) {
+                cumulative += n;
+                let le = label_set(self.label_names, labels, Some(&bound.to_string()));
+                let _ = writeln!(out, "{}_bucket{} {}", self.name, le, cumulative);
+            }
+            let inf = label_set(self.label_names, labels, Some("+Inf"));
+            let _ = writeln!(out, "{}_bucket{} {}", self.name, inf, data.count);
+            let plain = label_set(self.label_names, labels, None);
+            let _ = writeln!(out, "{}_sum{} {}", self.name, plain, data.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, plain, data.count);
+        }
+    }
+}
+
+fn to_labels(labels: &[&str]) -> Labels {
+    labels.iter().map(|s| s.to_string()).collect()
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// `{a="1",b="2"}`, with `le` appended for histogram buckets; empty when
+/// there are no labels at all.
+fn label_set(names: &[&str], values: &[String], le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Every metric nclav exports.
+pub struct Metrics {
+    /// Reconcile runs by `outcome` (`success`, `partial` or `error`) and `dry_run`.
+    pub reconcile_duration: HistogramVec,
+    /// Driver calls by `driver` and `method`.
+    pub driver_call_duration: HistogramVec,
+    /// Failed driver calls by `driver` and `method`.
+    pub driver_call_errors: CounterVec,
+    /// Finished IaC runs by `operation` and `status`.
+    pub iac_run_duration: HistogramVec,
+    /// Finished IaC runs by `operation` and `exit_code`.
+    pub iac_runs: CounterVec,
+    /// Time from Terraform creating a lock request to the lock being granted.
+    pub tf_lock_wait: HistogramVec,
+    /// Time a state lock was held, by how it was `released`.
+    pub tf_lock_hold: HistogramVec,
+    /// Enclaves by `status`.
+    pub enclaves: GaugeVec,
+    /// Partitions by `status`.
+    pub partitions: GaugeVec,
+    /// Orphaned cloud resources found by the last scan, by `enclave`.
+    pub orphaned_resources: GaugeVec,
+    /// Unix time of the last orphan scan.
+    pub orphan_scan_timestamp: GaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            reconcile_duration: HistogramVec::new(
+                "nclav_reconcile_duration_seconds",
+                "Duration of reconcile runs.",
+                &["outcome", "dry_run"],
+                RUN_BUCKETS,
+            ),
+            driver_call_duration: HistogramVec::new(
+                "nclav_driver_call_duration_seconds",
+                "Latency of cloud driver calls.",
+                &["driver", "method"],
+                CALL_BUCKETS,
+            ),
+            driver_call_errors: CounterVec::new(
+                "nclav_driver_call_errors_total",
+                "Cloud driver calls that returned an error.",
+                &["driver", "method"],
+            ),
+            iac_run_duration: HistogramVec::new(
+                "nclav_iac_run_duration_seconds",
+                "Duration of finished IaC runs.",
+                &["operation", "status"],
+                RUN_BUCKETS,
+            ),
+            iac_runs: CounterVec::new(
+                "nclav_iac_runs_total",
+                "Finished IaC runs by exit code.",
+                &["operation", "exit_code"],
+            ),
+            tf_lock_wait: HistogramVec::new(
+                "nclav_tf_lock_wait_seconds",
+                "Time Terraform waited for a state lock.",
+                &[],
+                RUN_BUCKETS,
+            ),
+            tf_lock_hold: HistogramVec::new(
+                "nclav_tf_lock_hold_seconds",
+                "Time a Terraform state lock was held.",
+                &["released"],
+                RUN_BUCKETS,
+            ),
+            enclaves: GaugeVec::new("nclav_enclaves", "Enclaves by provisioning status.", &["status"]),
+            partitions: GaugeVec::new(
+                "nclav_partitions",
+                "Partitions by provisioning status.",
+                &["status"],
+            ),
+            orphaned_resources: GaugeVec::new(
+                "nclav_orphaned_resources",
+                "Orphaned cloud resources found by the last scan.",
+                &["enclave"],
+            ),
+            orphan_scan_timestamp: GaugeVec::new(
+                "nclav_orphan_scan_timestamp_seconds",
+                "Unix time of the last orphan scan.",
+                &[],
+            ),
+        }
+    }
+
+    /// All metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.reconcile_duration.render(&mut out);
+        self.driver_call_duration.render(&mut out);
+        self.driver_call_errors.render(&mut out);
+        self.iac_run_duration.render(&mut out);
+        self.iac_runs.render(&mut out);
+        self.tf_lock_wait.render(&mut out);
+        self.tf_lock_hold.render(&mut out);
+        self.enclaves.render(&mut out);
+        self.partitions.render(&mut out);
+        self.orphaned_resources.render(&mut out);
+        self.orphan_scan_timestamp.render(&mut out);
+        out
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The process-wide metrics.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_cumulative_histogram_buckets_and_escaped_labels() {
+        let metrics = Metrics::new();
+        let call = &["gcp", "provision_enclave"];
+        metrics.driver_call_duration.observe(call, Duration::from_millis(80));
+        metrics.driver_call_duration.observe(call, Duration::from_secs(4));
+        metrics.driver_call_errors.inc(call);
+        metrics.orphaned_resources.replace([(vec!["a\"b"], 2.0)]);
+
+        let text = metrics.render();
+        let labels = r#"driver="gcp",method="provision_enclave""#;
+        assert!(text.contains("# TYPE nclav_driver_call_duration_seconds histogram"));
+        assert!(text.contains(&format!("nclav_driver_call_duration_seconds_bucket{{{labels},le=\"0.1\"}} 1")));
+        assert!(text.contains(&format!("nclav_driver_call_duration_seconds_bucket{{{labels},le=\"5\"}} 2")));
+        assert!(text.contains(&format!("nclav_driver_call_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 2")));
+        assert!(text.contains(&format!("nclav_driver_call_duration_seconds_count{{{labels}}} 2")));
+        assert!(text.contains(&format!("nclav_driver_call_errors_total{{{labels}}} 1")));
+        assert!(text.contains(r#"nclav_orphaned_resources{enclave="a\"b"} 2"#));
+    }
+}
@@ -0,0 +1,97 @@
+//! W3C trace context of the current `tracing` span.
+//!
+//! When the server exports traces, its exporting layer attaches a
+//! [`TraceContext`] to every span it records. [`current`] reads it back so it
+//! can be propagated — as a `traceparent` header on cloud API requests, as
+//! `TRACEPARENT` in Terraform's environment — and recorded on IaC runs and
+//! audit events. Without an exporter there is no context and nothing is
+//! propagated.
+
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// Trace and span IDs of one span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+}
+
+impl TraceContext {
+    pub fn trace_id_hex(&self) -> String {
+        hex(&self.trace_id)
+    }
+
+    pub fn span_id_hex(&self) -> String {
+        hex(&self.span_id)
+    }
+
+    /// The `traceparent` header value naming this span as parent.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id_hex(), self.span_id_hex())
+    }
+
+    /// Parse a `traceparent` header value. Returns `None` for unknown
+    /// versions and all-zero IDs, which the spec defines as invalid.
+    pub fn parse_traceparent(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let (version, trace_id, span_id, _flags) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if version != "00" || parts.next().is_some() {
+            return None;
+        }
+        let trace_id: [u8; 16] = unhex(trace_id)?.try_into().ok()?;
+        let span_id: [u8; 8] = unhex(span_id)?.try_into().ok()?;
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+        Some(Self { trace_id, span_id })
+    }
+}
+
+/// Trace context of the innermost exported span the caller is in, if traces
+/// are being exported.
+pub fn current() -> Option<TraceContext> {
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            registry
+                .span(id)?
+                .scope()
+                .find_map(|span| span.extensions().get::<TraceContext>().copied())
+        })
+        .flatten()
+}
+
+/// Trace ID of the span the caller is in, as recorded on IaC runs and events.
+pub fn current_trace_id() -> Option<String> {
+    current().map(|c| c.trace_id_hex())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traceparent_round_trips_and_rejects_invalid_ids() {
+        let value = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::parse_traceparent(value).unwrap();
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.traceparent(), value);
+
+        assert!(TraceContext::parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse_traceparent("00-4bf92f35-00f067aa0ba902b7-01").is_none());
+        assert!(current().is_none());
+    }
+}